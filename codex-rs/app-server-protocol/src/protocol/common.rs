@@ -12,6 +12,7 @@ use crate::protocol::v2;
 use codex_protocol::ConversationId;
 use codex_protocol::parse_command::ParsedCommand;
 use codex_protocol::protocol::FileChange;
+use codex_protocol::protocol::McpInvocation;
 use codex_protocol::protocol::ReviewDecision;
 use codex_protocol::protocol::SandboxCommandAssessment;
 use paste::paste;
@@ -430,6 +431,8 @@ server_request_definitions! {
     ApplyPatchApproval,
     /// Request to exec a command.
     ExecCommandApproval,
+    /// Request to call an MCP tool.
+    McpToolCallApproval,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema, TS)]
@@ -459,6 +462,21 @@ pub struct ExecCommandApprovalParams {
     pub reason: Option<String>,
     pub risk: Option<SandboxCommandAssessment>,
     pub parsed_cmd: Vec<ParsedCommand>,
+    /// Command prefix saved when the client answers `approved_prefix` or
+    /// `approved_prefix_for_project`; absent when those decisions are not offered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proposed_prefix: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct McpToolCallApprovalParams {
+    pub conversation_id: ConversationId,
+    /// Use to correlate this with [codex_core::protocol::McpToolCallBeginEvent]
+    /// and [codex_core::protocol::McpToolCallEndEvent].
+    pub call_id: String,
+    pub invocation: McpInvocation,
+    pub reason: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema, TS)]
@@ -471,6 +489,11 @@ pub struct ApplyPatchApprovalResponse {
     pub decision: ReviewDecision,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema, TS)]
+pub struct McpToolCallApprovalResponse {
+    pub decision: ReviewDecision,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(rename_all = "camelCase")]
@@ -623,6 +646,7 @@ mod tests {
             parsed_cmd: vec![ParsedCommand::Unknown {
                 cmd: "echo hello".to_string(),
             }],
+            proposed_prefix: None,
         };
         let request = ServerRequest::ExecCommandApproval {
             request_id: RequestId::Integer(7),
@@ -58,6 +58,8 @@ use codex_app_server_protocol::LoginChatGptCompleteNotification;
 use codex_app_server_protocol::LoginChatGptResponse;
 use codex_app_server_protocol::LogoutAccountResponse;
 use codex_app_server_protocol::LogoutChatGptResponse;
+use codex_app_server_protocol::McpToolCallApprovalParams;
+use codex_app_server_protocol::McpToolCallApprovalResponse;
 use codex_app_server_protocol::ModelListParams;
 use codex_app_server_protocol::ModelListResponse;
 use codex_app_server_protocol::NewConversationParams;
@@ -127,6 +129,7 @@ use codex_core::protocol::ApplyPatchApprovalRequestEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
+use codex_core::protocol::McpToolCallApprovalRequestEvent;
 use codex_core::protocol::Op;
 use codex_core::protocol::ReviewDecision;
 use codex_core::read_head_for_summary;
@@ -2688,6 +2691,7 @@ async fn apply_bespoke_event_handling(
             reason,
             risk,
             parsed_cmd,
+            proposed_prefix,
         }) => {
             let params = ExecCommandApprovalParams {
                 conversation_id,
@@ -2697,6 +2701,7 @@ async fn apply_bespoke_event_handling(
                 reason,
                 risk,
                 parsed_cmd,
+                proposed_prefix,
             };
             let rx = outgoing
                 .send_request(ServerRequestPayload::ExecCommandApproval(params))
@@ -2707,6 +2712,24 @@ async fn apply_bespoke_event_handling(
                 on_exec_approval_response(event_id, rx, conversation).await;
             });
         }
+        EventMsg::McpToolCallApprovalRequest(McpToolCallApprovalRequestEvent {
+            call_id,
+            invocation,
+            reason,
+        }) => {
+            let params = McpToolCallApprovalParams {
+                conversation_id,
+                call_id,
+                invocation,
+                reason,
+            };
+            let rx = outgoing
+                .send_request(ServerRequestPayload::McpToolCallApproval(params))
+                .await;
+            tokio::spawn(async move {
+                on_mcp_tool_call_approval_response(event_id, rx, conversation).await;
+            });
+        }
         EventMsg::TokenCount(token_count_event) => {
             if let Some(rate_limits) = token_count_event.rate_limits {
                 outgoing
@@ -2851,6 +2874,35 @@ async fn on_exec_approval_response(
     }
 }
 
+async fn on_mcp_tool_call_approval_response(
+    event_id: String,
+    receiver: oneshot::Receiver<JsonRpcResult>,
+    conversation: Arc<CodexConversation>,
+) {
+    let decision = match receiver.await {
+        Ok(value) => serde_json::from_value::<McpToolCallApprovalResponse>(value)
+            .map(|response| response.decision)
+            .unwrap_or_else(|err| {
+                error!("failed to deserialize McpToolCallApprovalResponse: {err}");
+                ReviewDecision::Denied
+            }),
+        Err(err) => {
+            error!("request failed: {err:?}");
+            ReviewDecision::Denied
+        }
+    };
+
+    if let Err(err) = conversation
+        .submit(Op::McpToolCallApproval {
+            id: event_id,
+            decision,
+        })
+        .await
+    {
+        error!("failed to submit McpToolCallApproval: {err}");
+    }
+}
+
 async fn read_summary_from_rollout(
     path: &Path,
     fallback_provider: &str,
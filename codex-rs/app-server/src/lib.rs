@@ -1,18 +1,22 @@
 #![deny(clippy::print_stdout, clippy::print_stderr)]
 
 use codex_common::CliConfigOverrides;
+use codex_core::AuthManager;
+use codex_core::ConversationManager;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
 use opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge;
 use std::io::ErrorKind;
 use std::io::Result as IoResult;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use crate::message_processor::MessageProcessor;
 use crate::outgoing_message::OutgoingMessage;
 use crate::outgoing_message::OutgoingMessageSender;
 use codex_app_server_protocol::JSONRPCMessage;
 use codex_feedback::CodexFeedback;
+use codex_protocol::protocol::SessionSource;
 use tokio::io::AsyncBufReadExt;
 use tokio::io::AsyncWriteExt;
 use tokio::io::BufReader;
@@ -35,8 +39,6 @@ mod message_processor;
 mod models;
 mod outgoing_message;
 
-// Exportar MessageProcessor para uso externo (gateway)
-
 /// Size of the bounded channels used to communicate between tasks. The value
 /// is a balance between throughput and memory usage – 128 messages should be
 /// plenty for an interactive CLI.
@@ -47,8 +49,8 @@ pub async fn run_main(
     cli_config_overrides: CliConfigOverrides,
 ) -> IoResult<()> {
     // Set up channels.
-    let (incoming_tx, mut incoming_rx) = mpsc::channel::<JSONRPCMessage>(CHANNEL_CAPACITY);
-    let (outgoing_tx, mut outgoing_rx) = mpsc::unbounded_channel::<OutgoingMessage>();
+    let (incoming_tx, incoming_rx) = mpsc::channel::<JSONRPCMessage>(CHANNEL_CAPACITY);
+    let (outgoing_tx, mut outgoing_rx) = mpsc::unbounded_channel::<serde_json::Value>();
 
     // Task: read from stdin, push to `incoming_tx`.
     let stdin_reader_handle = tokio::spawn({
@@ -120,36 +122,18 @@ pub async fn run_main(
         .try_init();
 
     // Task: process incoming messages.
-    let processor_handle = tokio::spawn({
-        let outgoing_message_sender = OutgoingMessageSender::new(outgoing_tx);
-        let mut processor = MessageProcessor::new(
-            outgoing_message_sender,
-            codex_linux_sandbox_exe,
-            std::sync::Arc::new(config),
-            feedback.clone(),
-        );
-        async move {
-            while let Some(msg) = incoming_rx.recv().await {
-                match msg {
-                    JSONRPCMessage::Request(r) => processor.process_request(r).await,
-                    JSONRPCMessage::Response(r) => processor.process_response(r).await,
-                    JSONRPCMessage::Notification(n) => processor.process_notification(n).await,
-                    JSONRPCMessage::Error(e) => processor.process_error(e),
-                }
-            }
-
-            info!("processor task exited (channel closed)");
-        }
-    });
+    let processor_handle = tokio::spawn(serve_connection(
+        incoming_rx,
+        outgoing_tx,
+        codex_linux_sandbox_exe,
+        Arc::new(config),
+        feedback.clone(),
+    ));
 
     // Task: write outgoing messages to stdout.
     let stdout_writer_handle = tokio::spawn(async move {
         let mut stdout = io::stdout();
-        while let Some(outgoing_message) = outgoing_rx.recv().await {
-            let Ok(value) = serde_json::to_value(outgoing_message) else {
-                error!("Failed to convert OutgoingMessage to JSON value");
-                continue;
-            };
+        while let Some(value) = outgoing_rx.recv().await {
             match serde_json::to_string(&value) {
                 Ok(mut json) => {
                     json.push('\n');
@@ -172,3 +156,88 @@ pub async fn run_main(
 
     Ok(())
 }
+
+/// Serve one app-server client over an arbitrary transport.
+///
+/// Processes messages from `incoming_rx` until it closes and forwards every
+/// message for the client to `outgoing_tx` as JSON. [`run_main`] uses this
+/// over stdio; embedders such as the gateway run one per connection.
+pub async fn serve_connection(
+    incoming_rx: mpsc::Receiver<JSONRPCMessage>,
+    outgoing_tx: mpsc::UnboundedSender<serde_json::Value>,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+    config: Arc<Config>,
+    feedback: CodexFeedback,
+) {
+    let auth_manager = AuthManager::shared(
+        config.codex_home.clone(),
+        false,
+        config.cli_auth_credentials_store_mode,
+    );
+    let conversation_manager = Arc::new(ConversationManager::new(
+        auth_manager.clone(),
+        SessionSource::VSCode,
+    ));
+    serve_connection_with_manager(
+        incoming_rx,
+        outgoing_tx,
+        codex_linux_sandbox_exe,
+        config,
+        feedback,
+        auth_manager,
+        conversation_manager,
+    )
+    .await;
+}
+
+/// Like [`serve_connection`], with conversations held by the caller's
+/// `conversation_manager` so an embedder can reach them directly (for
+/// example to shut them down).
+pub async fn serve_connection_with_manager(
+    mut incoming_rx: mpsc::Receiver<JSONRPCMessage>,
+    outgoing_tx: mpsc::UnboundedSender<serde_json::Value>,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+    config: Arc<Config>,
+    feedback: CodexFeedback,
+    auth_manager: Arc<AuthManager>,
+    conversation_manager: Arc<ConversationManager>,
+) {
+    let (message_tx, mut message_rx) = mpsc::unbounded_channel::<OutgoingMessage>();
+
+    // Task: convert outgoing messages to JSON for the transport.
+    tokio::spawn(async move {
+        while let Some(outgoing_message) = message_rx.recv().await {
+            let Ok(value) = serde_json::to_value(outgoing_message) else {
+                error!("Failed to convert OutgoingMessage to JSON value");
+                continue;
+            };
+            if outgoing_tx.send(value).is_err() {
+                // Transport gone – nothing left to do.
+                break;
+            }
+        }
+    });
+
+    let mut processor = MessageProcessor::new(
+        OutgoingMessageSender::new(message_tx),
+        codex_linux_sandbox_exe,
+        config,
+        feedback,
+        auth_manager,
+        conversation_manager,
+    );
+    while let Some(msg) = incoming_rx.recv().await {
+        match msg {
+            JSONRPCMessage::Request(r) => processor.process_request(r).await,
+            JSONRPCMessage::Response(r) => processor.process_response(r).await,
+            JSONRPCMessage::Notification(n) => processor.process_notification(n).await,
+            JSONRPCMessage::Error(e) => processor.process_error(e),
+        }
+    }
+    info!("processor task exited (channel closed)");
+
+    // Dropping the processor cancels its conversation listeners; the
+    // forwarder ends once the last sender clone is gone or the transport
+    // stops accepting messages.
+    drop(processor);
+}
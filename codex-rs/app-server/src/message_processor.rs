@@ -18,7 +18,6 @@ use codex_core::config::Config;
 use codex_core::default_client::USER_AGENT_SUFFIX;
 use codex_core::default_client::get_codex_user_agent;
 use codex_feedback::CodexFeedback;
-use codex_protocol::protocol::SessionSource;
 use std::sync::Arc;
 
 pub struct MessageProcessor {
@@ -35,17 +34,10 @@ impl MessageProcessor {
         codex_linux_sandbox_exe: Option<PathBuf>,
         config: Arc<Config>,
         feedback: CodexFeedback,
+        auth_manager: Arc<AuthManager>,
+        conversation_manager: Arc<ConversationManager>,
     ) -> Self {
         let outgoing = Arc::new(outgoing);
-        let auth_manager = AuthManager::shared(
-            config.codex_home.clone(),
-            false,
-            config.cli_auth_credentials_store_mode,
-        );
-        let conversation_manager = Arc::new(ConversationManager::new(
-            auth_manager.clone(),
-            SessionSource::VSCode,
-        ));
         let codex_message_processor = CodexMessageProcessor::new(
             auth_manager,
             conversation_manager,
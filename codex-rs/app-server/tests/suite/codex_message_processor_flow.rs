@@ -21,7 +21,9 @@ use codex_app_server_protocol::SendUserTurnParams;
 use codex_app_server_protocol::SendUserTurnResponse;
 use codex_app_server_protocol::ServerRequest;
 use codex_core::protocol::AskForApproval;
+use codex_core::protocol::SandboxCommandAssessment;
 use codex_core::protocol::SandboxPolicy;
+use codex_core::protocol::SandboxRiskLevel;
 use codex_core::protocol_config_types::ReasoningEffort;
 use codex_core::protocol_config_types::ReasoningSummary;
 use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
@@ -274,10 +276,15 @@ async fn test_send_user_turn_changes_approval_policy_behavior() -> Result<()> {
             ],
             cwd: working_directory.clone(),
             reason: None,
-            risk: None,
+            risk: Some(SandboxCommandAssessment {
+                description: "Runs code with `python3`; its effects cannot be predicted offline."
+                    .to_string(),
+                risk_level: SandboxRiskLevel::Medium,
+            }),
             parsed_cmd: vec![ParsedCommand::Unknown {
                 cmd: "python3 -c 'print(42)'".to_string()
             }],
+            proposed_prefix: None,
         },
         params
     );
@@ -0,0 +1,113 @@
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use codex_common::CliConfigOverrides;
+use codex_core::approved_prefixes::ApprovedPrefixes;
+use codex_core::approved_prefixes::PrefixRule;
+use codex_core::config::find_codex_home;
+
+/// Manage the command prefixes saved by "always allow" approvals.
+///
+/// Subcommands:
+/// - `list`   — list saved rules (with `--json`)
+/// - `revoke` — delete a saved rule by its number in `list`
+#[derive(Debug, clap::Parser)]
+pub struct ApprovalsCli {
+    #[clap(flatten)]
+    pub config_overrides: CliConfigOverrides,
+
+    #[command(subcommand)]
+    pub subcommand: ApprovalsSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum ApprovalsSubcommand {
+    /// List saved "always allow" command prefixes.
+    List(ListArgs),
+
+    /// Revoke a saved command prefix.
+    Revoke(RevokeArgs),
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct ListArgs {
+    /// Output the saved rules as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct RevokeArgs {
+    /// Number of the rule as shown by `codex approvals list`.
+    #[arg(value_name = "NUMBER")]
+    pub number: usize,
+}
+
+impl ApprovalsCli {
+    pub async fn run(self) -> Result<()> {
+        let ApprovalsCli {
+            config_overrides,
+            subcommand,
+        } = self;
+        // Validate any provided overrides even though they are not currently applied.
+        config_overrides
+            .parse_overrides()
+            .map_err(anyhow::Error::msg)?;
+
+        match subcommand {
+            ApprovalsSubcommand::List(args) => run_list(args)?,
+            ApprovalsSubcommand::Revoke(args) => run_revoke(args)?,
+        }
+
+        Ok(())
+    }
+}
+
+fn load_rules() -> Result<ApprovedPrefixes> {
+    let codex_home = find_codex_home().context("failed to resolve CODEX_HOME")?;
+    ApprovedPrefixes::load(&codex_home)
+}
+
+fn run_list(list_args: ListArgs) -> Result<()> {
+    let rules = load_rules()?;
+
+    if list_args.json {
+        let output = serde_json::to_string_pretty(rules.rules())?;
+        println!("{output}");
+        return Ok(());
+    }
+
+    if rules.rules().is_empty() {
+        println!(
+            "No saved command approvals. Choose \"always allow\" when approving a command to add one."
+        );
+        return Ok(());
+    }
+
+    for (index, rule) in rules.rules().iter().enumerate() {
+        println!("{:>3}  {}", index + 1, describe(rule));
+    }
+    Ok(())
+}
+
+fn run_revoke(revoke_args: RevokeArgs) -> Result<()> {
+    let RevokeArgs { number } = revoke_args;
+    let mut rules = load_rules()?;
+    let Some(index) = number.checked_sub(1) else {
+        bail!("rule numbers start at 1; see `codex approvals list`");
+    };
+
+    match rules.remove(index)? {
+        Some(rule) => println!("Revoked {}.", describe(&rule)),
+        None => println!("No saved command approval number {number} found."),
+    }
+    Ok(())
+}
+
+fn describe(rule: &PrefixRule) -> String {
+    let prefix = rule.prefix.join(" ");
+    match &rule.project {
+        Some(project) => format!("{prefix} … (in {})", project.display()),
+        None => format!("{prefix} …"),
+    }
+}
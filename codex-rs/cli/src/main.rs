@@ -26,10 +26,12 @@ use owo_colors::OwoColorize;
 use std::path::PathBuf;
 use supports_color::Stream;
 
+mod approvals_cmd;
 mod mcp_cmd;
 #[cfg(not(windows))]
 mod wsl_paths;
 
+use crate::approvals_cmd::ApprovalsCli;
 use crate::mcp_cmd::McpCli;
 
 use codex_core::config::Config;
@@ -83,6 +85,9 @@ enum Subcommand {
     /// [experimental] Run the Codex MCP server (stdio transport).
     McpServer,
 
+    /// Manage command prefixes saved with "always allow" approvals.
+    Approvals(ApprovalsCli),
+
     /// [experimental] Run the app server or related tooling.
     AppServer(AppServerCommand),
 
@@ -431,6 +436,13 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
             prepend_config_flags(&mut mcp_cli.config_overrides, root_config_overrides.clone());
             mcp_cli.run().await?;
         }
+        Some(Subcommand::Approvals(mut approvals_cli)) => {
+            prepend_config_flags(
+                &mut approvals_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            approvals_cli.run().await?;
+        }
         Some(Subcommand::AppServer(app_server_cli)) => match app_server_cli.subcommand {
             None => {
                 codex_app_server::run_main(codex_linux_sandbox_exe, root_config_overrides).await?;
@@ -261,6 +261,8 @@ async fn run_add(config_overrides: &CliConfigOverrides, add_args: AddArgs) -> Re
         tool_timeout_sec: None,
         enabled_tools: None,
         disabled_tools: None,
+        approval: None,
+        tools: HashMap::new(),
     };
 
     servers.insert(name.clone(), new_entry);
@@ -0,0 +1,292 @@
+//! Command prefixes the user chose to always approve.
+//!
+//! Answering an exec approval with [`ReviewDecision::ApprovedPrefix`] or
+//! [`ReviewDecision::ApprovedPrefixForProject`] saves the request's proposed
+//! prefix (see [`proposed_prefix`]) to `CODEX_HOME/approved_prefixes.toml`.
+//! Later commands starting with a saved prefix are approved without asking,
+//! in every session. `codex approvals list` / `codex approvals revoke` manage
+//! the file.
+//!
+//! [`ReviewDecision::ApprovedPrefix`]: crate::protocol::ReviewDecision::ApprovedPrefix
+//! [`ReviewDecision::ApprovedPrefixForProject`]: crate::protocol::ReviewDecision::ApprovedPrefixForProject
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use serde::Deserialize;
+use serde::Serialize;
+use tempfile::NamedTempFile;
+
+use crate::bash::parse_shell_lc_plain_commands;
+use crate::git_info::get_git_repo_root;
+
+/// File under `CODEX_HOME` holding the saved rules.
+pub const APPROVED_PREFIXES_FILE: &str = "approved_prefixes.toml";
+
+/// Programs that run whatever command follows them; a prefix such as
+/// `sudo rm` or `env FOO=1` would approve far more than the user saw.
+const WRAPPER_PROGRAMS: &[&str] = &[
+    "bash", "sh", "zsh", "env", "sudo", "doas", "xargs", "nohup", "time", "timeout", "nice",
+    "exec", "eval",
+];
+
+/// One saved "always allow" rule.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrefixRule {
+    /// Leading words a command must start with, e.g. `["cargo", "test"]`.
+    pub prefix: Vec<String>,
+    /// When set, the rule only applies to commands run inside this directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<PathBuf>,
+}
+
+impl PrefixRule {
+    /// Whether `command`, run from `cwd`, starts with this rule's prefix.
+    fn matches(&self, command: &[String], cwd: &Path) -> bool {
+        !self.prefix.is_empty()
+            && command.starts_with(&self.prefix)
+            && self
+                .project
+                .as_deref()
+                .is_none_or(|project| cwd.starts_with(project))
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PrefixRulesFile {
+    #[serde(default, rename = "rule")]
+    rules: Vec<PrefixRule>,
+}
+
+/// The rules saved in `CODEX_HOME/approved_prefixes.toml`.
+#[derive(Debug, Clone)]
+pub struct ApprovedPrefixes {
+    codex_home: PathBuf,
+    rules: Vec<PrefixRule>,
+}
+
+impl ApprovedPrefixes {
+    /// Path of the rules file inside `codex_home`.
+    pub fn path(codex_home: &Path) -> PathBuf {
+        codex_home.join(APPROVED_PREFIXES_FILE)
+    }
+
+    /// Load the saved rules; a missing file means no rules.
+    pub fn load(codex_home: &Path) -> anyhow::Result<Self> {
+        let path = Self::path(codex_home);
+        let rules = match std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                toml::from_str::<PrefixRulesFile>(&contents)
+                    .with_context(|| format!("failed to parse {}", path.display()))?
+                    .rules
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to read {}", path.display()));
+            }
+        };
+        Ok(Self {
+            codex_home: codex_home.to_path_buf(),
+            rules,
+        })
+    }
+
+    /// An empty rule set that saves to `codex_home`.
+    pub fn empty(codex_home: &Path) -> Self {
+        Self {
+            codex_home: codex_home.to_path_buf(),
+            rules: Vec::new(),
+        }
+    }
+
+    pub fn rules(&self) -> &[PrefixRule] {
+        &self.rules
+    }
+
+    /// Whether a saved rule approves `command` run from `cwd`.
+    ///
+    /// `bash -lc` / `zsh -lc` scripts made only of plain commands are split
+    /// and approved only when every command matches a rule; other scripts
+    /// never match.
+    pub fn matches(&self, command: &[String], cwd: &Path) -> bool {
+        if self.rules.is_empty() {
+            return false;
+        }
+        let commands =
+            parse_shell_lc_plain_commands(command).unwrap_or_else(|| vec![command.to_vec()]);
+        !commands.is_empty()
+            && commands
+                .iter()
+                .all(|command| self.rules.iter().any(|rule| rule.matches(command, cwd)))
+    }
+
+    /// Save `rule`, merging with rules other sessions saved since this set
+    /// was loaded. Saving a rule that already exists is a no-op.
+    pub fn add(&mut self, rule: PrefixRule) -> anyhow::Result<()> {
+        let mut current = Self::load(&self.codex_home)?;
+        if !current.rules.contains(&rule) {
+            current.rules.push(rule);
+            current.save()?;
+        }
+        *self = current;
+        Ok(())
+    }
+
+    /// Remove the rule at `index` (as listed by [`Self::rules`]) and save.
+    pub fn remove(&mut self, index: usize) -> anyhow::Result<Option<PrefixRule>> {
+        if index >= self.rules.len() {
+            return Ok(None);
+        }
+        let rule = self.rules.remove(index);
+        self.save()?;
+        Ok(Some(rule))
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        std::fs::create_dir_all(&self.codex_home).with_context(|| {
+            format!(
+                "failed to create Codex home directory at {}",
+                self.codex_home.display()
+            )
+        })?;
+        let contents = toml::to_string(&PrefixRulesFile {
+            rules: self.rules.clone(),
+        })?;
+        let tmp = NamedTempFile::new_in(&self.codex_home)?;
+        std::fs::write(tmp.path(), contents).with_context(|| {
+            format!(
+                "failed to write temporary rules file at {}",
+                tmp.path().display()
+            )
+        })?;
+        tmp.persist(Self::path(&self.codex_home))?;
+        Ok(())
+    }
+}
+
+/// The prefix offered to the user when `command` needs approval: the program
+/// and its first argument, e.g. `cargo test` for `cargo test -p core`.
+///
+/// Returns `None` when no prefix would be both useful and safe to approve:
+/// scripts with several commands, commands whose first argument is a flag,
+/// and wrappers such as `sudo` or `env` that run arbitrary programs.
+pub fn proposed_prefix(command: &[String]) -> Option<Vec<String>> {
+    let command = match parse_shell_lc_plain_commands(command) {
+        Some(mut commands) if commands.len() == 1 => commands.pop()?,
+        Some(_) => return None,
+        None => command.to_vec(),
+    };
+    let [program, subcommand, ..] = command.as_slice() else {
+        return None;
+    };
+    let name = Path::new(program)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(program);
+    if WRAPPER_PROGRAMS.contains(&name) || subcommand.starts_with('-') {
+        return None;
+    }
+    Some(vec![program.clone(), subcommand.clone()])
+}
+
+/// The directory a project-scoped rule for `cwd` is limited to: the
+/// enclosing git repository, or `cwd` itself outside of one.
+pub fn project_root(cwd: &Path) -> PathBuf {
+    get_git_repo_root(cwd).unwrap_or_else(|| cwd.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn vec_str(items: &[&str]) -> Vec<String> {
+        items.iter().map(std::string::ToString::to_string).collect()
+    }
+
+    #[test]
+    fn proposes_program_and_subcommand() {
+        assert_eq!(
+            proposed_prefix(&vec_str(&["cargo", "test", "-p", "codex-core"])),
+            Some(vec_str(&["cargo", "test"]))
+        );
+        assert_eq!(
+            proposed_prefix(&vec_str(&["bash", "-lc", "pnpm run lint --fix"])),
+            Some(vec_str(&["pnpm", "run"]))
+        );
+        assert_eq!(proposed_prefix(&vec_str(&["ls", "-la"])), None);
+        assert_eq!(proposed_prefix(&vec_str(&["sudo", "rm", "-rf", "/"])), None);
+        assert_eq!(
+            proposed_prefix(&vec_str(&["bash", "-lc", "cargo fmt && cargo test"])),
+            None
+        );
+    }
+
+    #[test]
+    fn rules_round_trip_and_match() {
+        let home = tempfile::tempdir().expect("tempdir");
+        let project = home.path().join("project");
+        let mut rules = ApprovedPrefixes::load(home.path()).expect("load");
+        assert!(rules.rules().is_empty());
+
+        rules
+            .add(PrefixRule {
+                prefix: vec_str(&["cargo", "test"]),
+                project: None,
+            })
+            .expect("add");
+        rules
+            .add(PrefixRule {
+                prefix: vec_str(&["pnpm", "run"]),
+                project: Some(project.clone()),
+            })
+            .expect("add");
+        // Adding the same rule twice keeps one copy.
+        rules
+            .add(PrefixRule {
+                prefix: vec_str(&["cargo", "test"]),
+                project: None,
+            })
+            .expect("add");
+
+        let rules = ApprovedPrefixes::load(home.path()).expect("reload");
+        assert_eq!(rules.rules().len(), 2);
+
+        let elsewhere = home.path().join("other");
+        assert!(rules.matches(&vec_str(&["cargo", "test", "-p", "core"]), &elsewhere));
+        assert!(!rules.matches(&vec_str(&["cargo", "publish"]), &elsewhere));
+        assert!(!rules.matches(&vec_str(&["cargo"]), &elsewhere));
+        assert!(rules.matches(&vec_str(&["pnpm", "run", "build"]), &project.join("web")));
+        assert!(!rules.matches(&vec_str(&["pnpm", "run", "build"]), &elsewhere));
+        assert!(rules.matches(
+            &vec_str(&["bash", "-lc", "cargo test && pnpm run build"]),
+            &project
+        ));
+        assert!(!rules.matches(
+            &vec_str(&["bash", "-lc", "cargo test && rm -rf target"]),
+            &project
+        ));
+    }
+
+    #[test]
+    fn remove_rewrites_file() {
+        let home = tempfile::tempdir().expect("tempdir");
+        let mut rules = ApprovedPrefixes::load(home.path()).expect("load");
+        rules
+            .add(PrefixRule {
+                prefix: vec_str(&["cargo", "test"]),
+                project: None,
+            })
+            .expect("add");
+
+        assert_eq!(rules.remove(1).expect("remove"), None);
+        let removed = rules.remove(0).expect("remove");
+        assert_eq!(
+            removed.map(|rule| rule.prefix),
+            Some(vec_str(&["cargo", "test"]))
+        );
+        let rules = ApprovedPrefixes::load(home.path()).expect("reload");
+        assert!(rules.rules().is_empty());
+    }
+}
@@ -166,6 +166,147 @@ fn parse_plain_command_from_node(cmd: tree_sitter::Node, src: &str) -> Option<Ve
     Some(words)
 }
 
+/// Every command of a script, as found by [`parse_script_commands`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ScriptCommands {
+    /// All commands in source order, including those nested in subshells,
+    /// substitutions and control flow.
+    pub commands: Vec<ScriptCommand>,
+    /// For each pipeline (`a | b | c`), the words of each stage's command.
+    pub pipelines: Vec<Vec<Vec<String>>>,
+    /// Targets of output redirections (`>`, `>>`, `&>`, …).
+    pub output_redirects: Vec<String>,
+}
+
+/// One command of a script, see [`ScriptCommands`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptCommand {
+    /// Command name and arguments. Quotes are removed; expansions such as
+    /// `$HOME` or `$(…)` are kept verbatim.
+    pub words: Vec<String>,
+    /// Words of the commands run by `$(…)`, `` `…` `` or `<(…)` in the
+    /// arguments.
+    pub substitutions: Vec<Vec<String>>,
+}
+
+/// Collect every command of a parsed script, best effort.
+///
+/// Unlike [`try_parse_word_only_commands_sequence`], this never rejects a
+/// script: it is meant for checks that must see commands hidden in
+/// substitutions, subshells or redirected statements, and tolerates parse
+/// errors.
+pub fn parse_script_commands(tree: &Tree, src: &str) -> ScriptCommands {
+    const OUTPUT_REDIRECTS: &[&str] = &[">", ">>", "&>", "&>>", ">|"];
+
+    let mut script = ScriptCommands::default();
+    let root = tree.root_node();
+    let mut cursor = root.walk();
+    let mut stack = vec![root];
+    let mut command_nodes = Vec::new();
+    while let Some(node) = stack.pop() {
+        match node.kind() {
+            "command" => command_nodes.push(node),
+            "pipeline" => {
+                let mut stage_cursor = node.walk();
+                let stages = node
+                    .named_children(&mut stage_cursor)
+                    .filter_map(first_command)
+                    .map(|command| command_words(command, src))
+                    .collect();
+                script.pipelines.push(stages);
+            }
+            "file_redirect" => {
+                let mut op_cursor = node.walk();
+                let is_output = node
+                    .children(&mut op_cursor)
+                    .any(|child| !child.is_named() && OUTPUT_REDIRECTS.contains(&child.kind()));
+                if is_output && let Some(destination) = node.child_by_field_name("destination") {
+                    script.output_redirects.push(word_text(destination, src));
+                }
+            }
+            _ => {}
+        }
+        for child in node.children(&mut cursor) {
+            stack.push(child);
+        }
+    }
+
+    // Walk uses a stack (LIFO), so re-sort by position to restore source order.
+    command_nodes.sort_by_key(Node::start_byte);
+    script.commands = command_nodes
+        .into_iter()
+        .map(|node| ScriptCommand {
+            words: command_words(node, src),
+            substitutions: substitution_commands(node, src),
+        })
+        .collect();
+    script
+}
+
+/// The first `command` node at or below `node`, in source order.
+fn first_command(node: Node) -> Option<Node> {
+    if node.kind() == "command" {
+        return Some(node);
+    }
+    let mut cursor = node.walk();
+    node.named_children(&mut cursor).find_map(first_command)
+}
+
+fn command_words(command: Node, src: &str) -> Vec<String> {
+    let mut cursor = command.walk();
+    command
+        .named_children(&mut cursor)
+        .filter_map(|child| match child.kind() {
+            "variable_assignment" | "file_redirect" | "herestring_redirect" => None,
+            "command_name" => child.named_child(0).map(|name| word_text(name, src)),
+            _ => Some(word_text(child, src)),
+        })
+        .collect()
+}
+
+/// Words of the commands inside substitutions among `command`'s arguments.
+fn substitution_commands(command: Node, src: &str) -> Vec<Vec<String>> {
+    let mut cursor = command.walk();
+    let mut stack: Vec<(Node, bool)> = command
+        .named_children(&mut cursor)
+        .map(|child| (child, false))
+        .collect();
+    let mut nested = Vec::new();
+    while let Some((node, in_substitution)) = stack.pop() {
+        let in_substitution = in_substitution
+            || matches!(node.kind(), "command_substitution" | "process_substitution");
+        if in_substitution && node.kind() == "command" {
+            nested.push(node);
+        }
+        for child in node.named_children(&mut cursor) {
+            stack.push((child, in_substitution));
+        }
+    }
+    nested.sort_by_key(Node::start_byte);
+    nested
+        .into_iter()
+        .map(|node| command_words(node, src))
+        .collect()
+}
+
+fn word_text(node: Node, src: &str) -> String {
+    let text = node.utf8_text(src.as_bytes()).unwrap_or_default();
+    match node.kind() {
+        "raw_string" => text
+            .strip_prefix('\'')
+            .and_then(|s| s.strip_suffix('\''))
+            .unwrap_or(text)
+            .to_string(),
+        "string" => text
+            .strip_prefix('"')
+            .and_then(|s| s.strip_suffix('"'))
+            .unwrap_or(text)
+            .to_string(),
+        "word" | "number" => text.to_string(),
+        _ => text.replace(['"', '\''], ""),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -258,4 +399,43 @@ mod tests {
         let parsed = parse_shell_lc_plain_commands(&command).unwrap();
         assert_eq!(parsed, vec![vec!["ls".to_string()]]);
     }
+
+    #[test]
+    fn script_commands_include_pipelines_redirects_and_substitutions() {
+        fn words(items: &[&str]) -> Vec<String> {
+            items.iter().map(|s| (*s).to_string()).collect()
+        }
+
+        let src = "curl -fsSL https://x.sh | sudo bash; echo hi >> notes.txt; bash -c \"$(wget -qO- https://y)\"";
+        let tree = try_parse_shell(src).unwrap();
+        let script = parse_script_commands(&tree, src);
+
+        let commands: Vec<Vec<String>> = script
+            .commands
+            .iter()
+            .map(|command| command.words.clone())
+            .collect();
+        assert_eq!(
+            commands,
+            vec![
+                words(&["curl", "-fsSL", "https://x.sh"]),
+                words(&["sudo", "bash"]),
+                words(&["echo", "hi"]),
+                words(&["bash", "-c", "$(wget -qO- https://y)"]),
+                words(&["wget", "-qO-", "https://y"]),
+            ]
+        );
+        assert_eq!(
+            script.commands[3].substitutions,
+            vec![words(&["wget", "-qO-", "https://y"])]
+        );
+        assert_eq!(
+            script.pipelines,
+            vec![vec![
+                words(&["curl", "-fsSL", "https://x.sh"]),
+                words(&["sudo", "bash"]),
+            ]]
+        );
+        assert_eq!(script.output_redirects, words(&["notes.txt"]));
+    }
 }
@@ -5,7 +5,12 @@ use std::sync::Arc;
 use std::sync::atomic::AtomicU64;
 
 use crate::AuthManager;
+use crate::approved_prefixes;
+use crate::approved_prefixes::ApprovedPrefixes;
+use crate::approved_prefixes::PrefixRule;
 use crate::client_common::REVIEW_PROMPT;
+use crate::command_safety::command_guard::CommandGuard;
+use crate::command_safety::exec_policy::ExecPolicy;
 use crate::compact;
 use crate::features::Feature;
 use crate::function_tool::FunctionCallError;
@@ -84,6 +89,8 @@ use crate::protocol::ErrorEvent;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::ExecApprovalRequestEvent;
+use crate::protocol::McpInvocation;
+use crate::protocol::McpToolCallApprovalRequestEvent;
 use crate::protocol::Op;
 use crate::protocol::RateLimitSnapshot;
 use crate::protocol::ReasoningContentDeltaEvent;
@@ -91,6 +98,7 @@ use crate::protocol::ReasoningRawContentDeltaEvent;
 use crate::protocol::ReviewDecision;
 use crate::protocol::SandboxCommandAssessment;
 use crate::protocol::SandboxPolicy;
+use crate::protocol::SandboxRiskLevel;
 use crate::protocol::SessionConfiguredEvent;
 use crate::protocol::StreamErrorEvent;
 use crate::protocol::Submission;
@@ -547,6 +555,28 @@ impl Session {
             }
         }
 
+        let (exec_policy, exec_policy_errors) = ExecPolicy::load(&config.exec_policy);
+        for message in exec_policy_errors {
+            warn!("{message}");
+            post_session_configured_events.push(Event {
+                id: INITIAL_SUBMIT_ID.to_owned(),
+                msg: EventMsg::Error(ErrorEvent { message }),
+            });
+        }
+
+        let approved_prefixes = match ApprovedPrefixes::load(&config.codex_home) {
+            Ok(approved_prefixes) => approved_prefixes,
+            Err(err) => {
+                let message = format!("Ignoring saved command approvals: {err:#}");
+                warn!("{message}");
+                post_session_configured_events.push(Event {
+                    id: INITIAL_SUBMIT_ID.to_owned(),
+                    msg: EventMsg::Error(ErrorEvent { message }),
+                });
+                ApprovedPrefixes::empty(&config.codex_home)
+            }
+        };
+
         for (alias, feature) in session_configuration.features.legacy_feature_usages() {
             let canonical = feature.key();
             let summary = format!("`{alias}` is deprecated. Use `{canonical}` instead.");
@@ -600,6 +630,9 @@ impl Session {
             auth_manager: Arc::clone(&auth_manager),
             otel_event_manager,
             tool_approvals: Mutex::new(ApprovalStore::default()),
+            exec_policy,
+            approved_prefixes: Mutex::new(approved_prefixes),
+            command_guard: CommandGuard::new(config.command_guard.clone()),
         };
 
         let sess = Arc::new(Session {
@@ -828,6 +861,12 @@ impl Session {
         .await;
     }
 
+    /// Assess the risk of `command` for the approval prompt.
+    ///
+    /// The offline assessment is always available. Medium and high risk
+    /// commands are escalated to the model when
+    /// `experimental_sandbox_command_assessment` is enabled, falling back to
+    /// the offline result if the model does not answer.
     pub(crate) async fn assess_sandbox_command(
         &self,
         turn_context: &TurnContext,
@@ -835,6 +874,15 @@ impl Session {
         command: &[String],
         failure_message: Option<&str>,
     ) -> Option<SandboxCommandAssessment> {
+        let offline = crate::sandboxing::offline_assessment::assess_command(
+            command,
+            &turn_context.sandbox_policy,
+            &turn_context.cwd,
+            failure_message,
+        );
+        if offline.risk_level == SandboxRiskLevel::Low {
+            return Some(offline);
+        }
         let config = turn_context.client.config();
         let provider = turn_context.client.provider().clone();
         let auth_manager = Arc::clone(&self.services.auth_manager);
@@ -853,6 +901,7 @@ impl Session {
             failure_message,
         )
         .await
+        .or(Some(offline))
     }
 
     /// Emit an exec approval request event and await the user's decision.
@@ -860,6 +909,9 @@ impl Session {
     /// The request is keyed by `sub_id`/`call_id` so matching responses are delivered
     /// to the correct in-flight turn. If the task is aborted, this returns the
     /// default `ReviewDecision` (`Denied`).
+    ///
+    /// Commands matching a saved prefix rule are approved without asking, and
+    /// a prefix decision saves the proposed prefix as a new rule.
     pub async fn request_command_approval(
         &self,
         turn_context: &TurnContext,
@@ -868,6 +920,77 @@ impl Session {
         cwd: PathBuf,
         reason: Option<String>,
         risk: Option<SandboxCommandAssessment>,
+    ) -> ReviewDecision {
+        if self
+            .services
+            .approved_prefixes
+            .lock()
+            .await
+            .matches(&command, &cwd)
+        {
+            return ReviewDecision::Approved;
+        }
+
+        let proposed_prefix = approved_prefixes::proposed_prefix(&command);
+        let event = ExecApprovalRequestEvent {
+            call_id,
+            parsed_cmd: parse_command(&command),
+            command,
+            cwd: cwd.clone(),
+            reason,
+            risk,
+            proposed_prefix: proposed_prefix.clone(),
+        };
+        let decision = self.prompt_exec_approval(turn_context, event).await;
+
+        let project = match decision {
+            ReviewDecision::ApprovedPrefix => None,
+            ReviewDecision::ApprovedPrefixForProject => Some(approved_prefixes::project_root(&cwd)),
+            _ => return decision,
+        };
+        if let Some(prefix) = proposed_prefix {
+            let rule = PrefixRule { prefix, project };
+            let saved = self.services.approved_prefixes.lock().await.add(rule);
+            if let Err(err) = saved {
+                let message = format!("Failed to save command approval: {err:#}");
+                warn!("{message}");
+                self.send_event(turn_context, EventMsg::Warning(WarningEvent { message }))
+                    .await;
+            }
+        }
+        decision
+    }
+
+    /// Ask the user about a command the command guard flagged.
+    ///
+    /// Unlike [`Session::request_command_approval`], saved prefix rules are
+    /// ignored and no prefix is offered, so the user sees every such command.
+    pub(crate) async fn request_guarded_command_approval(
+        &self,
+        turn_context: &TurnContext,
+        call_id: String,
+        command: Vec<String>,
+        cwd: PathBuf,
+        reason: String,
+        risk: Option<SandboxCommandAssessment>,
+    ) -> ReviewDecision {
+        let event = ExecApprovalRequestEvent {
+            call_id,
+            parsed_cmd: parse_command(&command),
+            command,
+            cwd,
+            reason: Some(reason),
+            risk,
+            proposed_prefix: None,
+        };
+        self.prompt_exec_approval(turn_context, event).await
+    }
+
+    /// Send an exec approval request and wait for the decision.
+    async fn prompt_exec_approval(
+        &self,
+        turn_context: &TurnContext,
+        event: ExecApprovalRequestEvent,
     ) -> ReviewDecision {
         let sub_id = turn_context.sub_id.clone();
         // Add the tx_approve callback to the map before sending the request.
@@ -887,16 +1010,8 @@ impl Session {
             warn!("Overwriting existing pending approval for sub_id: {event_id}");
         }
 
-        let parsed_cmd = parse_command(&command);
-        let event = EventMsg::ExecApprovalRequest(ExecApprovalRequestEvent {
-            call_id,
-            command,
-            cwd,
-            reason,
-            risk,
-            parsed_cmd,
-        });
-        self.send_event(turn_context, event).await;
+        self.send_event(turn_context, EventMsg::ExecApprovalRequest(event))
+            .await;
         rx_approve.await.unwrap_or_default()
     }
 
@@ -936,6 +1051,44 @@ impl Session {
         rx_approve
     }
 
+    /// Emit an MCP tool call approval request event and await the user's decision.
+    ///
+    /// Like [`Session::request_command_approval`], the request is keyed by
+    /// `sub_id` and resolves to `Denied` if the task is aborted.
+    pub async fn request_mcp_tool_approval(
+        &self,
+        turn_context: &TurnContext,
+        call_id: String,
+        invocation: McpInvocation,
+        reason: Option<String>,
+    ) -> ReviewDecision {
+        let sub_id = turn_context.sub_id.clone();
+        // Add the tx_approve callback to the map before sending the request.
+        let (tx_approve, rx_approve) = oneshot::channel();
+        let event_id = sub_id.clone();
+        let prev_entry = {
+            let mut active = self.active_turn.lock().await;
+            match active.as_mut() {
+                Some(at) => {
+                    let mut ts = at.turn_state.lock().await;
+                    ts.insert_pending_approval(sub_id, tx_approve)
+                }
+                None => None,
+            }
+        };
+        if prev_entry.is_some() {
+            warn!("Overwriting existing pending approval for sub_id: {event_id}");
+        }
+
+        let event = EventMsg::McpToolCallApprovalRequest(McpToolCallApprovalRequestEvent {
+            call_id,
+            invocation,
+            reason,
+        });
+        self.send_event(turn_context, event).await;
+        rx_approve.await.unwrap_or_default()
+    }
+
     pub async fn notify_approval(&self, sub_id: &str, decision: ReviewDecision) {
         let entry = {
             let mut active = self.active_turn.lock().await;
@@ -1357,6 +1510,9 @@ async fn submission_loop(sess: Arc<Session>, config: Arc<Config>, rx_sub: Receiv
             Op::PatchApproval { id, decision } => {
                 handlers::patch_approval(&sess, id, decision).await;
             }
+            Op::McpToolCallApproval { id, decision } => {
+                handlers::mcp_tool_call_approval(&sess, id, decision).await;
+            }
             Op::AddToHistory { text } => {
                 handlers::add_to_history(&sess, &config, text).await;
             }
@@ -1523,6 +1679,15 @@ mod handlers {
         }
     }
 
+    pub async fn mcp_tool_call_approval(sess: &Arc<Session>, id: String, decision: ReviewDecision) {
+        match decision {
+            ReviewDecision::Abort => {
+                sess.interrupt_task().await;
+            }
+            other => sess.notify_approval(&id, other).await,
+        }
+    }
+
     pub async fn add_to_history(sess: &Arc<Session>, config: &Arc<Config>, text: String) {
         let id = sess.conversation_id;
         let config = Arc::clone(config);
@@ -2612,6 +2777,9 @@ mod tests {
             auth_manager: Arc::clone(&auth_manager),
             otel_event_manager: otel_event_manager.clone(),
             tool_approvals: Mutex::new(ApprovalStore::default()),
+            exec_policy: None,
+            approved_prefixes: Mutex::new(ApprovedPrefixes::empty(&config.codex_home)),
+            command_guard: CommandGuard::new(config.command_guard.clone()),
         };
 
         let turn_context = Session::make_turn_context(
@@ -2688,6 +2856,9 @@ mod tests {
             auth_manager: Arc::clone(&auth_manager),
             otel_event_manager: otel_event_manager.clone(),
             tool_approvals: Mutex::new(ApprovalStore::default()),
+            exec_policy: None,
+            approved_prefixes: Mutex::new(ApprovedPrefixes::empty(&config.codex_home)),
+            command_guard: CommandGuard::new(config.command_guard.clone()),
         };
 
         let turn_context = Arc::new(Session::make_turn_context(
@@ -3181,6 +3352,8 @@ mod tests {
                 tool_timeout_sec: None,
                 enabled_tools: None,
                 disabled_tools: None,
+                approval: None,
+                tools: HashMap::new(),
             },
             auth_status: McpAuthStatus::Unsupported,
         };
@@ -3225,6 +3398,8 @@ mod tests {
                 tool_timeout_sec: None,
                 enabled_tools: None,
                 disabled_tools: None,
+                approval: None,
+                tools: HashMap::new(),
             },
             auth_status: McpAuthStatus::Unsupported,
         };
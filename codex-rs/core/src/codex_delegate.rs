@@ -8,6 +8,7 @@ use codex_protocol::protocol::ApplyPatchApprovalRequestEvent;
 use codex_protocol::protocol::Event;
 use codex_protocol::protocol::EventMsg;
 use codex_protocol::protocol::ExecApprovalRequestEvent;
+use codex_protocol::protocol::McpToolCallApprovalRequestEvent;
 use codex_protocol::protocol::Op;
 use codex_protocol::protocol::SessionSource;
 use codex_protocol::protocol::SubAgentSource;
@@ -196,6 +197,20 @@ async fn forward_events(
                 )
                 .await;
             }
+            Event {
+                id,
+                msg: EventMsg::McpToolCallApprovalRequest(event),
+            } => {
+                handle_mcp_tool_call_approval(
+                    &codex,
+                    id,
+                    &parent_session,
+                    &parent_ctx,
+                    event,
+                    &cancel_token,
+                )
+                .await;
+            }
             other => {
                 let _ = tx_sub.send(other).await;
             }
@@ -275,6 +290,31 @@ async fn handle_patch_approval(
     let _ = codex.submit(Op::PatchApproval { id, decision }).await;
 }
 
+/// Handle an McpToolCallApprovalRequest by consulting the parent session and replying.
+async fn handle_mcp_tool_call_approval(
+    codex: &Codex,
+    id: String,
+    parent_session: &Session,
+    parent_ctx: &TurnContext,
+    event: McpToolCallApprovalRequestEvent,
+    cancel_token: &CancellationToken,
+) {
+    let approval_fut = parent_session.request_mcp_tool_approval(
+        parent_ctx,
+        parent_ctx.sub_id.clone(),
+        event.invocation,
+        event.reason,
+    );
+    let decision = await_approval_with_cancel(
+        approval_fut,
+        parent_session,
+        &parent_ctx.sub_id,
+        cancel_token,
+    )
+    .await;
+    let _ = codex.submit(Op::McpToolCallApproval { id, decision }).await;
+}
+
 /// Await an approval decision, aborting on cancellation.
 async fn await_approval_with_cancel<F>(
     fut: F,
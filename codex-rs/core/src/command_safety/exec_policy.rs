@@ -0,0 +1,236 @@
+use std::path::Path;
+
+use codex_execpolicy::ExecCall;
+use codex_execpolicy::MatchedExec;
+use codex_execpolicy::Policy;
+use codex_execpolicy::PolicyParser;
+
+use crate::bash::parse_shell_lc_plain_commands;
+use crate::config::types::ExecPolicyConfig;
+
+/// Outcome of checking a command against the configured exec policies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ExecPolicyDecision {
+    /// Every command in the invocation matched a `define_program` rule and
+    /// none of the matches may write files.
+    Allowed,
+    /// A policy explicitly forbids the command; carries the policy's reason.
+    Forbidden(String),
+    /// No policy has an opinion; fall back to the regular approval flow.
+    Unmatched,
+}
+
+/// The `codex-execpolicy` policies loaded for a session, in config order.
+pub(crate) struct ExecPolicy {
+    policies: Vec<Policy>,
+}
+
+impl ExecPolicy {
+    /// Load the policies listed in `config`.
+    ///
+    /// Returns `None` when no policy is configured. Files that cannot be read
+    /// or parsed are skipped and reported in the returned error messages so
+    /// the session can surface them to the user.
+    pub(crate) fn load(config: &ExecPolicyConfig) -> (Option<Self>, Vec<String>) {
+        if !config.is_enabled() {
+            return (None, Vec::new());
+        }
+
+        let mut policies = Vec::new();
+        let mut errors = Vec::new();
+        if config.include_default {
+            match codex_execpolicy::get_default_policy() {
+                Ok(policy) => policies.push(policy),
+                Err(err) => errors.push(format!("failed to parse default exec policy: {err}")),
+            }
+        }
+        for path in &config.files {
+            match parse_policy_file(path) {
+                Ok(policy) => policies.push(policy),
+                Err(err) => errors.push(err),
+            }
+        }
+        (Some(Self { policies }), errors)
+    }
+
+    /// Check a command as it would be passed to `execvp`.
+    ///
+    /// `bash -lc` / `zsh -lc` scripts made only of plain commands are split
+    /// and each command is checked: the script is allowed only when every
+    /// command matches, and forbidden as soon as one command is forbidden.
+    pub(crate) fn check(&self, command: &[String]) -> ExecPolicyDecision {
+        let commands =
+            parse_shell_lc_plain_commands(command).unwrap_or_else(|| vec![command.to_vec()]);
+        let mut all_matched = true;
+        for command in &commands {
+            match self.check_one(command) {
+                ExecPolicyDecision::Forbidden(reason) => {
+                    return ExecPolicyDecision::Forbidden(reason);
+                }
+                ExecPolicyDecision::Unmatched => all_matched = false,
+                ExecPolicyDecision::Allowed => {}
+            }
+        }
+        if all_matched && !commands.is_empty() {
+            ExecPolicyDecision::Allowed
+        } else {
+            ExecPolicyDecision::Unmatched
+        }
+    }
+
+    fn check_one(&self, command: &[String]) -> ExecPolicyDecision {
+        let Some((program, args)) = command.split_first() else {
+            return ExecPolicyDecision::Unmatched;
+        };
+        let exec_call = ExecCall {
+            program: program.clone(),
+            args: args.to_vec(),
+        };
+        let mut matched = false;
+        let mut might_write_files = false;
+        for policy in &self.policies {
+            match policy.check(&exec_call) {
+                Ok(MatchedExec::Forbidden { reason, .. }) => {
+                    return ExecPolicyDecision::Forbidden(reason);
+                }
+                Ok(MatchedExec::Match { exec }) => {
+                    matched = true;
+                    might_write_files |= exec.might_write_files();
+                }
+                // No rule for this program or these arguments in this policy.
+                Err(_) => {}
+            }
+        }
+        // A match that may write files (e.g. `ARG_WFILE`) only says the
+        // arguments are well formed, so leave it to the approval flow.
+        if matched && !might_write_files {
+            ExecPolicyDecision::Allowed
+        } else {
+            ExecPolicyDecision::Unmatched
+        }
+    }
+}
+
+fn parse_policy_file(path: &Path) -> Result<Policy, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|err| format!("failed to read exec policy {}: {err}", path.display()))?;
+    PolicyParser::new(&path.to_string_lossy(), &contents)
+        .parse()
+        .map_err(|err| format!("failed to parse exec policy {}: {err}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn vec_str(items: &[&str]) -> Vec<String> {
+        items.iter().map(std::string::ToString::to_string).collect()
+    }
+
+    fn load_policy(source: &str) -> ExecPolicy {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("team.policy");
+        std::fs::write(&path, source).expect("write policy");
+        let (policy, errors) = ExecPolicy::load(&ExecPolicyConfig {
+            files: vec![path],
+            include_default: false,
+        });
+        assert_eq!(errors, Vec::<String>::new());
+        policy.expect("policy configured")
+    }
+
+    const TEAM_POLICY: &str = r#"
+define_program(
+    program="make",
+    args=[ARG_OPAQUE_VALUE],
+)
+
+define_program(
+    program="deploy",
+    forbidden="deploys go through CI",
+)
+"#;
+
+    #[test]
+    fn not_configured_loads_nothing() {
+        let (policy, errors) = ExecPolicy::load(&ExecPolicyConfig::default());
+        assert!(policy.is_none());
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn matching_command_is_allowed() {
+        let policy = load_policy(TEAM_POLICY);
+        assert_eq!(
+            policy.check(&vec_str(&["make", "test"])),
+            ExecPolicyDecision::Allowed
+        );
+        assert_eq!(
+            policy.check(&vec_str(&["bash", "-lc", "make lint && make test"])),
+            ExecPolicyDecision::Allowed
+        );
+    }
+
+    #[test]
+    fn partially_matching_script_is_unmatched() {
+        let policy = load_policy(TEAM_POLICY);
+        assert_eq!(
+            policy.check(&vec_str(&["bash", "-lc", "make test && curl example.com"])),
+            ExecPolicyDecision::Unmatched
+        );
+        assert_eq!(
+            policy.check(&vec_str(&["cargo", "build"])),
+            ExecPolicyDecision::Unmatched
+        );
+    }
+
+    #[test]
+    fn matching_command_that_writes_files_is_unmatched() {
+        let policy = load_policy(
+            r#"
+define_program(
+    program="cp",
+    args=[ARG_RFILE, ARG_WFILE],
+)
+
+define_program(
+    program="cat",
+    args=[ARG_RFILE],
+)
+"#,
+        );
+        assert_eq!(
+            policy.check(&vec_str(&["cp", "a.txt", "b.txt"])),
+            ExecPolicyDecision::Unmatched
+        );
+        assert_eq!(
+            policy.check(&vec_str(&["bash", "-lc", "cat a.txt && cp a.txt b.txt"])),
+            ExecPolicyDecision::Unmatched
+        );
+        assert_eq!(
+            policy.check(&vec_str(&["cat", "a.txt"])),
+            ExecPolicyDecision::Allowed
+        );
+    }
+
+    #[test]
+    fn forbidden_command_is_rejected() {
+        let policy = load_policy(TEAM_POLICY);
+        assert_eq!(
+            policy.check(&vec_str(&["bash", "-lc", "make test && deploy"])),
+            ExecPolicyDecision::Forbidden("deploys go through CI".to_string())
+        );
+    }
+
+    #[test]
+    fn unreadable_file_is_reported() {
+        let (policy, errors) = ExecPolicy::load(&ExecPolicyConfig {
+            files: vec![Path::new("/nonexistent/team.policy").to_path_buf()],
+            include_default: true,
+        });
+        assert!(policy.is_some());
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("/nonexistent/team.policy"));
+    }
+}
@@ -1,3 +1,5 @@
+pub mod command_guard;
+pub mod exec_policy;
 pub mod is_dangerous_command;
 pub mod is_safe_command;
 #[cfg(target_os = "windows")]
@@ -157,6 +157,23 @@ mod document_helpers {
         {
             entry["disabled_tools"] = array_from_iter(disabled_tools.iter().cloned());
         }
+        if let Some(approval) = config.approval {
+            entry["approval"] = value(approval.as_str());
+        }
+        if !config.tools.is_empty() {
+            let mut tools: Vec<_> = config.tools.iter().collect();
+            tools.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let mut tools_table = new_implicit_table();
+            for (name, tool) in tools {
+                let mut tool_entry = TomlTable::new();
+                tool_entry.set_implicit(false);
+                if let Some(approval) = tool.approval {
+                    tool_entry["approval"] = value(approval.as_str());
+                }
+                tools_table.insert(name, TomlItem::Table(tool_entry));
+            }
+            entry["tools"] = TomlItem::Table(tools_table);
+        }
 
         TomlItem::Table(entry)
     }
@@ -554,8 +571,11 @@ impl ConfigEditsBuilder {
 mod tests {
     use super::*;
     use crate::config::types::McpServerTransportConfig;
+    use crate::config::types::McpToolApproval;
+    use crate::config::types::McpToolConfig;
     use codex_protocol::config_types::ReasoningEffort;
     use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
     use tempfile::tempdir;
     use tokio::runtime::Builder;
     use toml::Value as TomlValue;
@@ -849,6 +869,8 @@ hide_gpt5_1_migration_prompt = true
                 tool_timeout_sec: None,
                 enabled_tools: Some(vec!["one".to_string(), "two".to_string()]),
                 disabled_tools: None,
+                approval: None,
+                tools: HashMap::new(),
             },
         );
 
@@ -870,6 +892,13 @@ hide_gpt5_1_migration_prompt = true
                 tool_timeout_sec: None,
                 enabled_tools: None,
                 disabled_tools: Some(vec!["forbidden".to_string()]),
+                approval: Some(McpToolApproval::Always),
+                tools: HashMap::from([(
+                    "search".to_string(),
+                    McpToolConfig {
+                        approval: Some(McpToolApproval::Never),
+                    },
+                )]),
             },
         );
 
@@ -888,10 +917,14 @@ bearer_token_env_var = \"TOKEN\"
 enabled = false
 startup_timeout_sec = 5.0
 disabled_tools = [\"forbidden\"]
+approval = \"always\"
 
 [mcp_servers.http.http_headers]
 Z-Header = \"z\"
 
+[mcp_servers.http.tools.search]
+approval = \"never\"
+
 [mcp_servers.stdio]
 command = \"cmd\"
 args = [\"--flag\"]
@@ -1,5 +1,9 @@
 use crate::auth::AuthCredentialsStoreMode;
+use crate::config::types::CommandGuardConfig;
+use crate::config::types::CommandGuardConfigToml;
 use crate::config::types::DEFAULT_OTEL_ENVIRONMENT;
+use crate::config::types::ExecPolicyConfig;
+use crate::config::types::ExecPolicyConfigToml;
 use crate::config::types::History;
 use crate::config::types::McpServerConfig;
 use crate::config::types::Notice;
@@ -271,6 +275,12 @@ pub struct Config {
 
     /// OTEL configuration (exporter type, endpoint, headers, etc.).
     pub otel: crate::config::types::OtelConfig,
+
+    /// `codex-execpolicy` policy files used to auto-approve or forbid commands.
+    pub exec_policy: ExecPolicyConfig,
+
+    /// Rules that force an approval for (or refuse) catastrophic commands.
+    pub command_guard: CommandGuardConfig,
 }
 
 impl Config {
@@ -643,6 +653,12 @@ pub struct ConfigToml {
     /// OTEL configuration.
     pub otel: Option<crate::config::types::OtelConfigToml>,
 
+    /// `codex-execpolicy` policy files consulted before asking for approval.
+    pub exec_policy: Option<ExecPolicyConfigToml>,
+
+    /// Guard against catastrophic shell commands.
+    pub command_guard: Option<CommandGuardConfigToml>,
+
     /// Tracks whether the Windows onboarding screen has been acknowledged.
     pub windows_wsl_setup_acknowledged: Option<bool>,
 
@@ -992,6 +1008,31 @@ impl Config {
 
         let shell_environment_policy = cfg.shell_environment_policy.into();
 
+        let exec_policy = {
+            let t = cfg.exec_policy.unwrap_or_default();
+            let user_files = t
+                .files
+                .unwrap_or_default()
+                .into_iter()
+                .map(|path| codex_home.join(path));
+            // Only a trusted project may auto-approve commands for itself.
+            let project_files = if active_project.is_trusted() {
+                t.project_files.unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+            ExecPolicyConfig {
+                files: user_files
+                    .chain(
+                        project_files
+                            .into_iter()
+                            .map(|path| resolved_cwd.join(path)),
+                    )
+                    .collect(),
+                include_default: t.include_default.unwrap_or(false),
+            }
+        };
+
         let history = cfg.history.unwrap_or_default();
 
         let include_apply_patch_tool_flag = features.enabled(Feature::ApplyPatchFreeform);
@@ -1178,6 +1219,8 @@ impl Config {
                     exporter,
                 }
             },
+            exec_policy,
+            command_guard: cfg.command_guard.unwrap_or_default().into(),
         };
         Ok(config)
     }
@@ -1279,8 +1322,12 @@ mod tests {
     use crate::config::edit::ConfigEdit;
     use crate::config::edit::ConfigEditsBuilder;
     use crate::config::edit::apply_blocking;
+    use crate::config::types::CommandGuardAction;
+    use crate::config::types::CommandGuardRule;
     use crate::config::types::HistoryPersistence;
     use crate::config::types::McpServerTransportConfig;
+    use crate::config::types::McpToolApproval;
+    use crate::config::types::McpToolConfig;
     use crate::config::types::Notifications;
     use crate::features::Feature;
 
@@ -1581,6 +1628,90 @@ trust_level = "trusted"
         Ok(())
     }
 
+    #[test]
+    fn exec_policy_project_files_require_trusted_project() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let project = TempDir::new()?;
+        let exec_policy = ExecPolicyConfigToml {
+            files: Some(vec![PathBuf::from("exec.policy")]),
+            project_files: Some(vec![PathBuf::from(".codex/exec.policy")]),
+            include_default: None,
+        };
+        let overrides = || ConfigOverrides {
+            cwd: Some(project.path().to_path_buf()),
+            ..Default::default()
+        };
+
+        let untrusted = Config::load_from_base_config_with_overrides(
+            ConfigToml {
+                exec_policy: Some(exec_policy.clone()),
+                ..Default::default()
+            },
+            overrides(),
+            codex_home.path().to_path_buf(),
+        )?;
+        assert_eq!(
+            untrusted.exec_policy,
+            ExecPolicyConfig {
+                files: vec![codex_home.path().join("exec.policy")],
+                include_default: false,
+            }
+        );
+
+        let trusted = Config::load_from_base_config_with_overrides(
+            ConfigToml {
+                exec_policy: Some(exec_policy),
+                projects: Some(HashMap::from([(
+                    project.path().to_string_lossy().to_string(),
+                    ProjectConfig {
+                        trust_level: Some("trusted".to_string()),
+                    },
+                )])),
+                ..Default::default()
+            },
+            overrides(),
+            codex_home.path().to_path_buf(),
+        )?;
+        assert_eq!(
+            trusted.exec_policy.files,
+            vec![
+                codex_home.path().join("exec.policy"),
+                project.path().join(".codex/exec.policy"),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn command_guard_overrides_keep_defaults() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let cfg: ConfigToml = toml::from_str(
+            r#"
+[command_guard]
+action = "deny"
+disabled_rules = ["pipe_to_shell"]
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+        assert_eq!(
+            config.command_guard,
+            CommandGuardConfig {
+                action: CommandGuardAction::Deny,
+                protected_branches: vec!["main".to_string(), "master".to_string()],
+                disabled_rules: vec![CommandGuardRule::PipeToShell],
+            }
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn profile_legacy_toggles_override_base() -> std::io::Result<()> {
         let codex_home = TempDir::new()?;
@@ -1824,6 +1955,8 @@ trust_level = "trusted"
                 tool_timeout_sec: Some(Duration::from_secs(5)),
                 enabled_tools: None,
                 disabled_tools: None,
+                approval: None,
+                tools: HashMap::new(),
             },
         );
 
@@ -1970,6 +2103,8 @@ bearer_token = "secret"
                 tool_timeout_sec: None,
                 enabled_tools: None,
                 disabled_tools: None,
+                approval: None,
+                tools: HashMap::new(),
             },
         )]);
 
@@ -2038,6 +2173,8 @@ ZIG_VAR = "3"
                 tool_timeout_sec: None,
                 enabled_tools: None,
                 disabled_tools: None,
+                approval: None,
+                tools: HashMap::new(),
             },
         )]);
 
@@ -2086,6 +2223,8 @@ ZIG_VAR = "3"
                 tool_timeout_sec: None,
                 enabled_tools: None,
                 disabled_tools: None,
+                approval: None,
+                tools: HashMap::new(),
             },
         )]);
 
@@ -2132,6 +2271,8 @@ ZIG_VAR = "3"
                 tool_timeout_sec: None,
                 enabled_tools: None,
                 disabled_tools: None,
+                approval: None,
+                tools: HashMap::new(),
             },
         )]);
 
@@ -2194,6 +2335,8 @@ startup_timeout_sec = 2.0
                 tool_timeout_sec: None,
                 enabled_tools: None,
                 disabled_tools: None,
+                approval: None,
+                tools: HashMap::new(),
             },
         )]);
         apply_blocking(
@@ -2268,6 +2411,8 @@ X-Auth = "DOCS_AUTH"
                 tool_timeout_sec: None,
                 enabled_tools: None,
                 disabled_tools: None,
+                approval: None,
+                tools: HashMap::new(),
             },
         )]);
 
@@ -2295,6 +2440,8 @@ X-Auth = "DOCS_AUTH"
                 tool_timeout_sec: None,
                 enabled_tools: None,
                 disabled_tools: None,
+                approval: None,
+                tools: HashMap::new(),
             },
         );
         apply_blocking(
@@ -2360,6 +2507,8 @@ url = "https://example.com/mcp"
                     tool_timeout_sec: None,
                     enabled_tools: None,
                     disabled_tools: None,
+                    approval: None,
+                    tools: HashMap::new(),
                 },
             ),
             (
@@ -2377,6 +2526,8 @@ url = "https://example.com/mcp"
                     tool_timeout_sec: None,
                     enabled_tools: None,
                     disabled_tools: None,
+                    approval: None,
+                    tools: HashMap::new(),
                 },
             ),
         ]);
@@ -2457,6 +2608,8 @@ url = "https://example.com/mcp"
                 tool_timeout_sec: None,
                 enabled_tools: None,
                 disabled_tools: None,
+                approval: None,
+                tools: HashMap::new(),
             },
         )]);
 
@@ -2499,6 +2652,8 @@ url = "https://example.com/mcp"
                 tool_timeout_sec: None,
                 enabled_tools: Some(vec!["allowed".to_string()]),
                 disabled_tools: Some(vec!["blocked".to_string()]),
+                approval: None,
+                tools: HashMap::new(),
             },
         )]);
 
@@ -2527,6 +2682,52 @@ url = "https://example.com/mcp"
         Ok(())
     }
 
+    #[tokio::test]
+    async fn replace_mcp_servers_round_trips_tool_approvals() -> anyhow::Result<()> {
+        let codex_home = TempDir::new()?;
+
+        let servers = BTreeMap::from([(
+            "db".to_string(),
+            McpServerConfig {
+                transport: McpServerTransportConfig::Stdio {
+                    command: "db-server".to_string(),
+                    args: Vec::new(),
+                    env: None,
+                    env_vars: Vec::new(),
+                    cwd: None,
+                },
+                enabled: true,
+                startup_timeout_sec: None,
+                tool_timeout_sec: None,
+                enabled_tools: None,
+                disabled_tools: None,
+                approval: Some(McpToolApproval::Never),
+                tools: HashMap::from([(
+                    "drop_table".to_string(),
+                    McpToolConfig {
+                        approval: Some(McpToolApproval::Always),
+                    },
+                )]),
+            },
+        )]);
+
+        apply_blocking(
+            codex_home.path(),
+            None,
+            &[ConfigEdit::ReplaceMcpServers(servers.clone())],
+        )?;
+
+        let config_path = codex_home.path().join(CONFIG_TOML_FILE);
+        let serialized = std::fs::read_to_string(&config_path)?;
+        assert!(serialized.contains(r#"approval = "never""#));
+        assert!(serialized.contains("[mcp_servers.db.tools.drop_table]"));
+
+        let loaded = load_global_mcp_servers(codex_home.path()).await?;
+        assert_eq!(loaded.get("db"), servers.get("db"));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn set_model_updates_defaults() -> anyhow::Result<()> {
         let codex_home = TempDir::new()?;
@@ -2905,6 +3106,8 @@ model_verbosity = "high"
                 disable_paste_burst: false,
                 tui_notifications: Default::default(),
                 otel: OtelConfig::default(),
+                exec_policy: ExecPolicyConfig::default(),
+                command_guard: CommandGuardConfig::default(),
             },
             o3_profile_config
         );
@@ -2976,6 +3179,8 @@ model_verbosity = "high"
             disable_paste_burst: false,
             tui_notifications: Default::default(),
             otel: OtelConfig::default(),
+            exec_policy: ExecPolicyConfig::default(),
+            command_guard: CommandGuardConfig::default(),
         };
 
         assert_eq!(expected_gpt3_profile_config, gpt3_profile_config);
@@ -3062,6 +3267,8 @@ model_verbosity = "high"
             disable_paste_burst: false,
             tui_notifications: Default::default(),
             otel: OtelConfig::default(),
+            exec_policy: ExecPolicyConfig::default(),
+            command_guard: CommandGuardConfig::default(),
         };
 
         assert_eq!(expected_zdr_profile_config, zdr_profile_config);
@@ -3134,6 +3341,8 @@ model_verbosity = "high"
             disable_paste_burst: false,
             tui_notifications: Default::default(),
             otel: OtelConfig::default(),
+            exec_policy: ExecPolicyConfig::default(),
+            command_guard: CommandGuardConfig::default(),
         };
 
         assert_eq!(expected_gpt5_profile_config, gpt5_profile_config);
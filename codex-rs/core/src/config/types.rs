@@ -43,6 +43,46 @@ pub struct McpServerConfig {
     /// Explicit deny-list of tools. These tools will be removed after applying `enabled_tools`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub disabled_tools: Option<Vec<String>>,
+
+    /// When to ask before calling this server's tools. Defaults to `auto`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval: Option<McpToolApproval>,
+
+    /// Per-tool settings keyed by the tool name given by the server.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tools: HashMap<String, McpToolConfig>,
+}
+
+/// Settings for a single tool of an MCP server.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct McpToolConfig {
+    /// Overrides the server's `approval` for this tool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval: Option<McpToolApproval>,
+}
+
+/// When Codex asks the user before calling an MCP tool.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum McpToolApproval {
+    /// Decide from the tool's `readOnlyHint`/`destructiveHint` annotations
+    /// and the session's approval policy.
+    #[default]
+    Auto,
+    /// Always ask before calling the tool.
+    Always,
+    /// Never ask; the tool is trusted.
+    Never,
+}
+
+impl McpToolApproval {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Always => "always",
+            Self::Never => "never",
+        }
+    }
 }
 
 impl<'de> Deserialize<'de> for McpServerConfig {
@@ -84,6 +124,10 @@ impl<'de> Deserialize<'de> for McpServerConfig {
             enabled_tools: Option<Vec<String>>,
             #[serde(default)]
             disabled_tools: Option<Vec<String>>,
+            #[serde(default)]
+            approval: Option<McpToolApproval>,
+            #[serde(default)]
+            tools: HashMap<String, McpToolConfig>,
         }
 
         let mut raw = RawMcpServerConfig::deserialize(deserializer)?;
@@ -100,6 +144,8 @@ impl<'de> Deserialize<'de> for McpServerConfig {
         let enabled = raw.enabled.unwrap_or_else(default_enabled);
         let enabled_tools = raw.enabled_tools.clone();
         let disabled_tools = raw.disabled_tools.clone();
+        let approval = raw.approval;
+        let tools = std::mem::take(&mut raw.tools);
 
         fn throw_if_set<E, T>(transport: &str, field: &str, value: Option<&T>) -> Result<(), E>
         where
@@ -153,6 +199,8 @@ impl<'de> Deserialize<'de> for McpServerConfig {
             enabled,
             enabled_tools,
             disabled_tools,
+            approval,
+            tools,
         })
     }
 }
@@ -329,6 +377,119 @@ impl Default for OtelConfig {
     }
 }
 
+/// Exec policy settings loaded from the `[exec_policy]` table of config.toml.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ExecPolicyConfigToml {
+    /// User policy files. Relative paths are resolved against `CODEX_HOME`.
+    pub files: Option<Vec<PathBuf>>,
+
+    /// Project policy files. Relative paths are resolved against the session
+    /// cwd; these are only loaded when the project is trusted.
+    pub project_files: Option<Vec<PathBuf>>,
+
+    /// Also apply the policy bundled with `codex-execpolicy`. Defaults to `false`.
+    pub include_default: Option<bool>,
+}
+
+/// Effective exec policy settings: the policy files to load, in order.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExecPolicyConfig {
+    pub files: Vec<PathBuf>,
+    pub include_default: bool,
+}
+
+impl ExecPolicyConfig {
+    /// Whether any policy should be consulted at all.
+    pub fn is_enabled(&self) -> bool {
+        self.include_default || !self.files.is_empty()
+    }
+}
+
+/// Command guard settings loaded from the `[command_guard]` table of config.toml.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct CommandGuardConfigToml {
+    /// What to do with a guarded command. Defaults to `ask`.
+    pub action: Option<CommandGuardAction>,
+
+    /// Branches `git push --force` must not rewrite. Supports `*` and `?`
+    /// wildcards. Defaults to `["main", "master"]`.
+    pub protected_branches: Option<Vec<String>>,
+
+    /// Rules to turn off.
+    pub disabled_rules: Option<Vec<CommandGuardRule>>,
+}
+
+/// What the command guard does with a command one of its rules matches.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandGuardAction {
+    /// Ask the user every time, whatever the approval policy. Commands are
+    /// refused when the approval policy is `never`.
+    #[default]
+    Ask,
+    /// Refuse the command.
+    Deny,
+}
+
+/// A kind of catastrophic command the command guard looks for.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandGuardRule {
+    /// `rm -r` of a path outside the writable roots.
+    RecursiveDelete,
+    /// `git push --force` (or `--delete`) to a protected branch.
+    ForcePush,
+    /// `git reset --hard` / `git clean -f` in a repository with uncommitted work.
+    DiscardChanges,
+    /// `curl … | sh` and similar.
+    PipeToShell,
+    /// Writes to `~/.ssh` or shell start-up files such as `~/.bashrc`.
+    SensitiveFileWrite,
+}
+
+impl CommandGuardRule {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::RecursiveDelete => "recursive_delete",
+            Self::ForcePush => "force_push",
+            Self::DiscardChanges => "discard_changes",
+            Self::PipeToShell => "pipe_to_shell",
+            Self::SensitiveFileWrite => "sensitive_file_write",
+        }
+    }
+}
+
+/// Effective command guard settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandGuardConfig {
+    pub action: CommandGuardAction,
+    pub protected_branches: Vec<String>,
+    pub disabled_rules: Vec<CommandGuardRule>,
+}
+
+impl Default for CommandGuardConfig {
+    fn default() -> Self {
+        Self {
+            action: CommandGuardAction::default(),
+            protected_branches: vec!["main".to_string(), "master".to_string()],
+            disabled_rules: Vec::new(),
+        }
+    }
+}
+
+impl From<CommandGuardConfigToml> for CommandGuardConfig {
+    fn from(toml: CommandGuardConfigToml) -> Self {
+        let defaults = Self::default();
+        Self {
+            action: toml.action.unwrap_or(defaults.action),
+            protected_branches: toml
+                .protected_branches
+                .unwrap_or(defaults.protected_branches),
+            disabled_rules: toml.disabled_rules.unwrap_or(defaults.disabled_rules),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 #[serde(untagged)]
 pub enum Notifications {
@@ -595,6 +595,18 @@ pub async fn current_branch_name(cwd: &Path) -> Option<String> {
         .filter(|name| !name.is_empty())
 }
 
+/// Returns the `git status --porcelain` lines of the working tree, or `None`
+/// outside a repository.
+pub async fn working_tree_status(cwd: &Path) -> Option<Vec<String>> {
+    let out = run_git_command_with_timeout(&["status", "--porcelain"], cwd).await?;
+    if !out.status.success() {
+        return None;
+    }
+    String::from_utf8(out.stdout)
+        .ok()
+        .map(|s| s.lines().map(str::to_string).collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -6,6 +6,7 @@
 #![deny(clippy::print_stdout, clippy::print_stderr)]
 
 mod apply_patch;
+pub mod approved_prefixes;
 pub mod auth;
 pub mod bash;
 mod chat_completions;
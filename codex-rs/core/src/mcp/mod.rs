@@ -1 +1,25 @@
 pub mod auth;
+
+use std::collections::HashMap;
+
+use codex_rmcp_client::OAuthCredentialsStoreMode;
+
+use crate::config::types::McpServerConfig;
+use crate::mcp_connection_manager::McpConnectionManager;
+
+/// Start every enabled server in `mcp_servers` and return the start-up error
+/// of each one that failed, keyed by server name.
+///
+/// The clients are dropped before returning (stdio servers are killed on
+/// drop), so this only tells whether the servers can start; it is meant for
+/// health checks.
+pub async fn check_servers(
+    mcp_servers: HashMap<String, McpServerConfig>,
+    store_mode: OAuthCredentialsStoreMode,
+) -> anyhow::Result<HashMap<String, String>> {
+    let (_manager, errors) = McpConnectionManager::new(mcp_servers, store_mode).await?;
+    Ok(errors
+        .into_iter()
+        .map(|(name, err)| (name, format!("{err:#}")))
+        .collect())
+}
@@ -29,6 +29,7 @@ use mcp_types::ReadResourceResult;
 use mcp_types::Resource;
 use mcp_types::ResourceTemplate;
 use mcp_types::Tool;
+use mcp_types::ToolAnnotations;
 
 use serde_json::json;
 use sha1::Digest;
@@ -39,6 +40,7 @@ use tracing::warn;
 
 use crate::config::types::McpServerConfig;
 use crate::config::types::McpServerTransportConfig;
+use crate::config::types::McpToolApproval;
 
 /// Delimiter used to separate the server name from the tool name in a fully
 /// qualified tool name.
@@ -116,6 +118,9 @@ pub(crate) struct McpConnectionManager {
 
     /// Server-name -> configured tool filters.
     tool_filters: HashMap<String, ToolFilter>,
+
+    /// Server-name -> configured approval settings.
+    tool_approvals: HashMap<String, ToolApprovals>,
 }
 
 impl McpConnectionManager {
@@ -140,6 +145,7 @@ impl McpConnectionManager {
         let mut join_set = JoinSet::new();
         let mut errors = ClientStartErrors::new();
         let mut tool_filters: HashMap<String, ToolFilter> = HashMap::new();
+        let mut tool_approvals: HashMap<String, ToolApprovals> = HashMap::new();
 
         for (server_name, cfg) in mcp_servers {
             // Validate server name before spawning
@@ -151,6 +157,7 @@ impl McpConnectionManager {
                 continue;
             }
 
+            tool_approvals.insert(server_name.clone(), ToolApprovals::from_config(&cfg));
             if !cfg.enabled {
                 tool_filters.insert(server_name, ToolFilter::from_config(&cfg));
                 continue;
@@ -294,6 +301,7 @@ impl McpConnectionManager {
                 clients,
                 tools,
                 tool_filters,
+                tool_approvals,
             },
             errors,
         ))
@@ -520,6 +528,22 @@ impl McpConnectionManager {
             .with_context(|| format!("resources/read failed for `{server}` ({uri})"))
     }
 
+    /// Approval setting configured for the (server, tool) pair.
+    pub fn tool_approval(&self, server: &str, tool: &str) -> McpToolApproval {
+        self.tool_approvals
+            .get(server)
+            .map(|approvals| approvals.for_tool(tool))
+            .unwrap_or_default()
+    }
+
+    /// Annotations the server advertised for the (server, tool) pair, if any.
+    pub fn tool_annotations(&self, server: &str, tool: &str) -> Option<ToolAnnotations> {
+        self.tools
+            .values()
+            .find(|info| info.server_name == server && info.tool_name == tool)
+            .and_then(|info| info.tool.annotations.clone())
+    }
+
     pub fn parse_tool_name(&self, tool_name: &str) -> Option<(String, String)> {
         self.tools
             .get(tool_name)
@@ -562,6 +586,32 @@ impl ToolFilter {
     }
 }
 
+/// Approval settings for one server: the server-wide `approval` and the
+/// per-tool overrides from `[mcp_servers.<name>.tools.<tool>]`.
+#[derive(Default, Clone)]
+struct ToolApprovals {
+    server: McpToolApproval,
+    tools: HashMap<String, McpToolApproval>,
+}
+
+impl ToolApprovals {
+    fn from_config(cfg: &McpServerConfig) -> Self {
+        let tools = cfg
+            .tools
+            .iter()
+            .filter_map(|(name, tool)| tool.approval.map(|approval| (name.clone(), approval)))
+            .collect();
+        Self {
+            server: cfg.approval.unwrap_or_default(),
+            tools,
+        }
+    }
+
+    fn for_tool(&self, tool_name: &str) -> McpToolApproval {
+        self.tools.get(tool_name).copied().unwrap_or(self.server)
+    }
+}
+
 fn filter_tools(tools: Vec<ToolInfo>, filters: &HashMap<String, ToolFilter>) -> Vec<ToolInfo> {
     tools
         .into_iter()
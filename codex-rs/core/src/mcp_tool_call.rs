@@ -1,13 +1,19 @@
 use std::time::Instant;
 
+use mcp_types::ToolAnnotations;
+use serde::Serialize;
 use tracing::error;
 
 use crate::codex::Session;
 use crate::codex::TurnContext;
+use crate::config::types::McpToolApproval;
+use crate::protocol::AskForApproval;
 use crate::protocol::EventMsg;
 use crate::protocol::McpInvocation;
 use crate::protocol::McpToolCallBeginEvent;
 use crate::protocol::McpToolCallEndEvent;
+use crate::protocol::ReviewDecision;
+use crate::tools::sandboxing::with_cached_approval;
 use codex_protocol::models::FunctionCallOutputPayload;
 use codex_protocol::models::ResponseInputItem;
 
@@ -48,6 +54,17 @@ pub(crate) async fn handle_mcp_tool_call(
         arguments: arguments_value.clone(),
     };
 
+    if let Err(message) = ensure_approved(sess, turn_context, &call_id, &invocation).await {
+        return ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                content: message,
+                success: Some(false),
+                ..Default::default()
+            },
+        };
+    }
+
     let tool_call_begin_event = EventMsg::McpToolCallBegin(McpToolCallBeginEvent {
         call_id: call_id.clone(),
         invocation: invocation.clone(),
@@ -78,3 +95,232 @@ pub(crate) async fn handle_mcp_tool_call(
 async fn notify_mcp_tool_call_event(sess: &Session, turn_context: &TurnContext, event: EventMsg) {
     sess.send_event(turn_context, event).await;
 }
+
+/// Whether an MCP tool call may run without asking the user.
+#[derive(Debug, PartialEq, Eq)]
+enum ApprovalRequirement {
+    Skip,
+    Ask { reason: Option<String> },
+    Reject(String),
+}
+
+/// Key under which "approved for session" decisions are cached.
+#[derive(Serialize, Clone)]
+struct McpApprovalKey {
+    server: String,
+    tool: String,
+}
+
+/// Ask the user for approval when the tool's configuration, annotations and
+/// the turn's approval policy require it. Returns the message sent back to
+/// the model when the call must not run.
+async fn ensure_approved(
+    sess: &Session,
+    turn_context: &TurnContext,
+    call_id: &str,
+    invocation: &McpInvocation,
+) -> Result<(), String> {
+    let manager = &sess.services.mcp_connection_manager;
+    let requirement = approval_requirement(
+        manager.tool_approval(&invocation.server, &invocation.tool),
+        turn_context.approval_policy,
+        manager
+            .tool_annotations(&invocation.server, &invocation.tool)
+            .as_ref(),
+    );
+    let reason = match requirement {
+        ApprovalRequirement::Skip => return Ok(()),
+        ApprovalRequirement::Reject(message) => return Err(message),
+        ApprovalRequirement::Ask { reason } => reason,
+    };
+
+    let key = McpApprovalKey {
+        server: invocation.server.clone(),
+        tool: invocation.tool.clone(),
+    };
+    let decision = with_cached_approval(&sess.services, key, || {
+        sess.request_mcp_tool_approval(
+            turn_context,
+            call_id.to_string(),
+            invocation.clone(),
+            reason,
+        )
+    })
+    .await;
+    match decision {
+        ReviewDecision::Approved
+        | ReviewDecision::ApprovedForSession
+        | ReviewDecision::ApprovedPrefix
+        | ReviewDecision::ApprovedPrefixForProject => Ok(()),
+        ReviewDecision::Denied | ReviewDecision::Abort => Err("rejected by user".to_string()),
+    }
+}
+
+/// Decide whether a call needs approval.
+///
+/// `always`/`never` in the server or tool config win. Under `auto`, tools
+/// annotated `readOnlyHint` never prompt, tools that may be destructive
+/// prompt unless the policy is `never`, and other tools prompt only under
+/// `untrusted`. As in the MCP spec, a tool without `destructiveHint` may be
+/// destructive unless it is read-only.
+fn approval_requirement(
+    approval: McpToolApproval,
+    policy: AskForApproval,
+    annotations: Option<&ToolAnnotations>,
+) -> ApprovalRequirement {
+    let read_only = annotations.and_then(|a| a.read_only_hint) == Some(true);
+    let destructive_hint = annotations.and_then(|a| a.destructive_hint);
+    let destructive = !read_only && destructive_hint.unwrap_or(true);
+    let destructive_reason = if destructive_hint.is_some() {
+        "tool is marked destructive"
+    } else {
+        "tool is not marked read-only or non-destructive"
+    };
+    match (approval, policy) {
+        (McpToolApproval::Never, _) => ApprovalRequirement::Skip,
+        (McpToolApproval::Always, AskForApproval::Never) => ApprovalRequirement::Reject(
+            "tool requires approval, but the approval policy is `never`".to_string(),
+        ),
+        (McpToolApproval::Always, _) => ApprovalRequirement::Ask { reason: None },
+        (McpToolApproval::Auto, AskForApproval::Never) => ApprovalRequirement::Skip,
+        (McpToolApproval::Auto, _) if destructive => ApprovalRequirement::Ask {
+            reason: Some(destructive_reason.to_string()),
+        },
+        (McpToolApproval::Auto, AskForApproval::UnlessTrusted) if !read_only => {
+            ApprovalRequirement::Ask { reason: None }
+        }
+        (McpToolApproval::Auto, _) => ApprovalRequirement::Skip,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn annotations(read_only: Option<bool>, destructive: Option<bool>) -> ToolAnnotations {
+        ToolAnnotations {
+            destructive_hint: destructive,
+            idempotent_hint: None,
+            open_world_hint: None,
+            read_only_hint: read_only,
+            title: None,
+        }
+    }
+
+    #[test]
+    fn config_overrides_annotations() {
+        let read_only = annotations(Some(true), None);
+        assert_eq!(
+            approval_requirement(
+                McpToolApproval::Always,
+                AskForApproval::OnRequest,
+                Some(&read_only)
+            ),
+            ApprovalRequirement::Ask { reason: None }
+        );
+        let destructive = annotations(None, Some(true));
+        assert_eq!(
+            approval_requirement(
+                McpToolApproval::Never,
+                AskForApproval::UnlessTrusted,
+                Some(&destructive)
+            ),
+            ApprovalRequirement::Skip
+        );
+        assert!(matches!(
+            approval_requirement(McpToolApproval::Always, AskForApproval::Never, None),
+            ApprovalRequirement::Reject(_)
+        ));
+    }
+
+    #[test]
+    fn auto_follows_annotations_and_policy() {
+        let read_only = annotations(Some(true), Some(true));
+        let destructive = annotations(Some(false), Some(true));
+        let non_destructive = annotations(Some(false), Some(false));
+
+        for policy in [
+            AskForApproval::UnlessTrusted,
+            AskForApproval::OnFailure,
+            AskForApproval::OnRequest,
+            AskForApproval::Never,
+        ] {
+            assert_eq!(
+                approval_requirement(McpToolApproval::Auto, policy, Some(&read_only)),
+                ApprovalRequirement::Skip,
+                "read-only tool under {policy:?}"
+            );
+        }
+
+        assert_eq!(
+            approval_requirement(
+                McpToolApproval::Auto,
+                AskForApproval::OnRequest,
+                Some(&destructive)
+            ),
+            ApprovalRequirement::Ask {
+                reason: Some("tool is marked destructive".to_string())
+            }
+        );
+        assert_eq!(
+            approval_requirement(
+                McpToolApproval::Auto,
+                AskForApproval::Never,
+                Some(&destructive)
+            ),
+            ApprovalRequirement::Skip
+        );
+        assert_eq!(
+            approval_requirement(
+                McpToolApproval::Auto,
+                AskForApproval::OnRequest,
+                Some(&non_destructive)
+            ),
+            ApprovalRequirement::Skip
+        );
+        assert_eq!(
+            approval_requirement(
+                McpToolApproval::Auto,
+                AskForApproval::UnlessTrusted,
+                Some(&non_destructive)
+            ),
+            ApprovalRequirement::Ask { reason: None }
+        );
+    }
+
+    #[test]
+    fn auto_treats_unannotated_tools_as_destructive() {
+        let unannotated = annotations(None, None);
+        let not_read_only = annotations(Some(false), None);
+        let may_be_destructive = ApprovalRequirement::Ask {
+            reason: Some("tool is not marked read-only or non-destructive".to_string()),
+        };
+
+        for policy in [
+            AskForApproval::UnlessTrusted,
+            AskForApproval::OnFailure,
+            AskForApproval::OnRequest,
+        ] {
+            assert_eq!(
+                approval_requirement(McpToolApproval::Auto, policy, Some(&unannotated)),
+                may_be_destructive,
+                "unannotated tool under {policy:?}"
+            );
+            assert_eq!(
+                approval_requirement(McpToolApproval::Auto, policy, Some(&not_read_only)),
+                may_be_destructive,
+                "tool without destructiveHint under {policy:?}"
+            );
+            assert_eq!(
+                approval_requirement(McpToolApproval::Auto, policy, None),
+                may_be_destructive,
+                "tool without annotations under {policy:?}"
+            );
+        }
+        assert_eq!(
+            approval_requirement(McpToolApproval::Auto, AskForApproval::Never, None),
+            ApprovalRequirement::Skip
+        );
+    }
+}
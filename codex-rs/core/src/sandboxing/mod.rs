@@ -7,6 +7,7 @@ ready‑to‑spawn environment.
 */
 
 pub mod assessment;
+pub mod offline_assessment;
 
 use crate::exec::ExecToolCallOutput;
 use crate::exec::SandboxType;
@@ -0,0 +1,532 @@
+//! Offline, deterministic risk assessment for commands awaiting approval.
+//!
+//! Derives a [`SandboxRiskLevel`] and a short rationale from the parsed
+//! command, the sandbox policy, the paths it touches relative to the writable
+//! roots and whether it uses the network. No model is involved, so the result
+//! is instant and the same command always gets the same explanation. The
+//! model-based assessment in [`super::assessment`] is only consulted for
+//! medium and high risk commands, and only when
+//! `experimental_sandbox_command_assessment` is enabled.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_protocol::parse_command::ParsedCommand;
+
+use crate::bash::ScriptCommands;
+use crate::command_safety::command_guard::pipe_to_shell;
+use crate::command_safety::command_guard::program_name;
+use crate::command_safety::command_guard::resolve_path;
+use crate::command_safety::command_guard::script_commands;
+use crate::command_safety::command_guard::strip_wrappers;
+use crate::command_safety::command_guard::written_paths;
+use crate::is_safe_command::is_known_safe_command;
+use crate::parse_command::parse_command;
+use crate::protocol::SandboxCommandAssessment;
+use crate::protocol::SandboxPolicy;
+use crate::protocol::SandboxRiskLevel;
+
+/// At most this many findings are listed in the description.
+const MAX_FINDINGS: usize = 4;
+
+const NETWORK_PROGRAMS: &[&str] = &[
+    "curl", "wget", "ssh", "scp", "sftp", "rsync", "nc", "ftp", "telnet",
+];
+
+/// Package managers and the subcommands that download or install packages.
+const PACKAGE_MANAGERS: &[(&str, &[&str])] = &[
+    ("npm", &["install", "i", "ci", "add", "update"]),
+    ("pnpm", &["install", "i", "add", "update"]),
+    ("yarn", &["install", "add", "upgrade"]),
+    ("bun", &["install", "add"]),
+    ("pip", &["install"]),
+    ("pip3", &["install"]),
+    ("uv", &["add", "sync", "pip"]),
+    ("cargo", &["install", "add", "update", "fetch"]),
+    ("gem", &["install"]),
+    ("go", &["get", "install"]),
+    ("brew", &["install", "upgrade", "uninstall"]),
+    ("apt", &["install", "remove", "purge", "upgrade"]),
+    ("apt-get", &["install", "remove", "purge", "upgrade"]),
+    ("dnf", &["install", "remove", "upgrade"]),
+    ("yum", &["install", "remove", "update"]),
+];
+
+/// Programs that run arbitrary code given on the command line or in a file.
+const INTERPRETERS: &[&str] = &[
+    "python",
+    "python3",
+    "node",
+    "deno",
+    "ruby",
+    "perl",
+    "php",
+    "bash",
+    "sh",
+    "zsh",
+    "dash",
+    "pwsh",
+    "powershell",
+    "osascript",
+];
+
+/// Programs that manage disks, services or the machine itself.
+const SYSTEM_PROGRAMS: &[&str] = &[
+    "fdisk",
+    "parted",
+    "diskutil",
+    "shutdown",
+    "reboot",
+    "halt",
+    "poweroff",
+    "systemctl",
+    "launchctl",
+    "crontab",
+];
+
+/// Commands with no effect beyond their output.
+const HARMLESS_PROGRAMS: &[&str] = &[
+    "echo", "printf", "true", "false", "pwd", "export", "set", "sleep", "test", "[", "which",
+    "type", "date",
+];
+
+const GIT_READ_ONLY: &[&str] = &[
+    "status",
+    "log",
+    "diff",
+    "show",
+    "blame",
+    "grep",
+    "ls-files",
+    "rev-parse",
+    "describe",
+    "shortlog",
+    "config",
+];
+
+const GIT_NETWORK: &[&str] = &["clone", "fetch", "pull", "push", "ls-remote", "submodule"];
+
+/// Assess `command` as it would run from `cwd` under `sandbox_policy`.
+///
+/// `failure_message` is set when the command already failed in the sandbox
+/// and approval would run it again without one.
+pub(crate) fn assess_command(
+    command: &[String],
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+    failure_message: Option<&str>,
+) -> SandboxCommandAssessment {
+    let mut writable_roots: Vec<PathBuf> = sandbox_policy
+        .get_writable_roots_with_cwd(cwd)
+        .into_iter()
+        .map(|root| root.root)
+        .collect();
+    if writable_roots.is_empty() {
+        writable_roots.push(cwd.to_path_buf());
+    }
+    let mut assessor = Assessor {
+        writable_roots,
+        home: dirs::home_dir(),
+        network_blocked: !sandbox_policy.has_full_network_access(),
+        findings: Vec::new(),
+    };
+
+    let scripts = script_commands(command);
+    let parsed = parse_command(command);
+    let redirects = scripts
+        .iter()
+        .any(|script| !script.output_redirects.is_empty());
+    if is_known_safe_command(command)
+        || (!redirects && !parsed.is_empty() && parsed.iter().all(is_read_only))
+    {
+        assessor.add(
+            SandboxRiskLevel::Low,
+            "Only reads files, lists directories or searches text.".to_string(),
+        );
+    } else {
+        for script in &scripts {
+            assessor.assess_script(script, cwd);
+        }
+    }
+    if failure_message.is_some() {
+        assessor.add(
+            SandboxRiskLevel::Medium,
+            "It was blocked by the sandbox; approving runs it again without the sandbox."
+                .to_string(),
+        );
+    }
+    assessor.finish()
+}
+
+fn is_read_only(command: &ParsedCommand) -> bool {
+    matches!(
+        command,
+        ParsedCommand::Read { .. } | ParsedCommand::ListFiles { .. } | ParsedCommand::Search { .. }
+    )
+}
+
+struct Assessor {
+    writable_roots: Vec<PathBuf>,
+    home: Option<PathBuf>,
+    network_blocked: bool,
+    findings: Vec<(SandboxRiskLevel, String)>,
+}
+
+impl Assessor {
+    fn add(&mut self, risk: SandboxRiskLevel, note: String) {
+        if !self.findings.iter().any(|(_, existing)| *existing == note) {
+            self.findings.push((risk, note));
+        }
+    }
+
+    fn finish(mut self) -> SandboxCommandAssessment {
+        let risk_level = self
+            .findings
+            .iter()
+            .map(|(risk, _)| *risk)
+            .max()
+            .unwrap_or(SandboxRiskLevel::Low);
+        // Most severe findings first; the sort is stable so ties keep their order.
+        self.findings.sort_by(|(a, _), (b, _)| b.cmp(a));
+        let description = if self.findings.is_empty() {
+            "No writes, deletions or network access detected.".to_string()
+        } else {
+            self.findings
+                .into_iter()
+                .take(MAX_FINDINGS)
+                .map(|(_, note)| note)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        SandboxCommandAssessment {
+            description,
+            risk_level,
+        }
+    }
+
+    fn assess_script(&mut self, script: &ScriptCommands, cwd: &Path) {
+        if let Some(reason) = pipe_to_shell(script) {
+            self.add(SandboxRiskLevel::High, sentence(&reason));
+        }
+        for target in &script.output_redirects {
+            self.write(target, cwd);
+        }
+
+        // Follow `cd` so relative paths resolve where the command runs.
+        let mut cwd = cwd.to_path_buf();
+        for command in &script.commands {
+            let words = strip_wrappers(&command.words);
+            let Some((program, args)) = words.split_first() else {
+                continue;
+            };
+            let name = program_name(program);
+            let wrappers = &command.words[..command.words.len() - words.len()];
+            if let Some(wrapper) = wrappers
+                .iter()
+                .map(|word| program_name(word))
+                .find(|wrapper| matches!(*wrapper, "sudo" | "doas"))
+            {
+                self.add(
+                    SandboxRiskLevel::High,
+                    format!("Runs `{name}` with elevated privileges through `{wrapper}`."),
+                );
+            }
+
+            if name == "cd" {
+                if let Some(dir) = args
+                    .first()
+                    .and_then(|dir| resolve_path(dir, &cwd, self.home.as_deref()))
+                {
+                    cwd = dir;
+                }
+                continue;
+            }
+            if HARMLESS_PROGRAMS.contains(&name) || is_known_safe_command(words) {
+                continue;
+            }
+
+            match name {
+                "rm" | "rmdir" | "unlink" | "shred" => {
+                    self.delete(args, &cwd);
+                    continue;
+                }
+                "git" => self.git(args),
+                "mkdir" => {
+                    for target in args.iter().filter(|arg| !arg.starts_with('-')) {
+                        self.write(target, &cwd);
+                    }
+                }
+                _ if NETWORK_PROGRAMS.contains(&name) => self.network(name),
+                _ if SYSTEM_PROGRAMS.contains(&name) || name.starts_with("mkfs") => {
+                    self.add(
+                        SandboxRiskLevel::High,
+                        format!("`{name}` can destroy data or change how the system runs."),
+                    );
+                }
+                // Scripts passed to `bash -c` are assessed on their own.
+                "bash" | "sh" | "zsh" | "dash"
+                    if args
+                        .iter()
+                        .any(|arg| arg.starts_with('-') && arg.ends_with('c')) =>
+                {
+                    continue;
+                }
+                _ if INTERPRETERS.contains(&name) => {
+                    self.add(
+                        SandboxRiskLevel::Medium,
+                        format!(
+                            "Runs code with `{name}`; its effects cannot be predicted offline."
+                        ),
+                    );
+                }
+                _ => {
+                    if let Some(subcommand) = package_install(name, args) {
+                        self.add(
+                            SandboxRiskLevel::Medium,
+                            format!("Installs or updates packages with `{name} {subcommand}`."),
+                        );
+                        self.network(&format!("{name} {subcommand}"));
+                    } else if written_paths(words).is_empty() {
+                        self.add(
+                            SandboxRiskLevel::Medium,
+                            format!("Runs `{name}`; its effects cannot be predicted offline."),
+                        );
+                    }
+                }
+            }
+            for target in written_paths(words) {
+                self.write(target, &cwd);
+            }
+        }
+    }
+
+    fn delete(&mut self, args: &[String], cwd: &Path) {
+        let recursive = args.iter().any(|arg| {
+            arg == "--recursive"
+                || (arg.starts_with('-') && !arg.starts_with("--") && arg.contains(['r', 'R']))
+        });
+        for target in args.iter().filter(|arg| !arg.starts_with('-')) {
+            match resolve_path(target, cwd, self.home.as_deref()) {
+                None => self.add(
+                    SandboxRiskLevel::High,
+                    format!("Deletes `{target}`, whose location depends on a variable."),
+                ),
+                Some(path) if !self.is_writable(&path) => self.add(
+                    SandboxRiskLevel::High,
+                    format!("Deletes {} outside the writable roots.", path.display()),
+                ),
+                Some(_) if recursive => self.add(
+                    SandboxRiskLevel::Medium,
+                    format!("Deletes `{target}` and everything in it."),
+                ),
+                Some(_) => self.add(SandboxRiskLevel::Medium, format!("Deletes `{target}`.")),
+            }
+        }
+    }
+
+    fn write(&mut self, target: &str, cwd: &Path) {
+        if target.starts_with("/dev/") {
+            return;
+        }
+        match resolve_path(target, cwd, self.home.as_deref()) {
+            None => self.add(
+                SandboxRiskLevel::Medium,
+                format!("Writes to `{target}`, whose location depends on a variable."),
+            ),
+            Some(path) if !self.is_writable(&path) => self.add(
+                SandboxRiskLevel::High,
+                format!("Writes to {} outside the writable roots.", path.display()),
+            ),
+            Some(_) => self.add(SandboxRiskLevel::Medium, format!("Writes to `{target}`.")),
+        }
+    }
+
+    fn git(&mut self, args: &[String]) {
+        // Skip global options such as `-C <dir>` and `-c key=value`.
+        let mut args = args.iter();
+        let subcommand = loop {
+            match args.next().map(String::as_str) {
+                Some("-C" | "-c") => {
+                    args.next();
+                }
+                Some(arg) if arg.starts_with('-') => {}
+                Some(arg) => break arg,
+                None => return,
+            }
+        };
+        let args: Vec<&str> = args.map(String::as_str).collect();
+        let has_short = |flag: char| {
+            args.iter()
+                .any(|arg| arg.starts_with('-') && !arg.starts_with("--") && arg.contains(flag))
+        };
+
+        let destructive = match subcommand {
+            "reset" => args.contains(&"--hard"),
+            "clean" => has_short('f') || args.contains(&"--force"),
+            "push" => {
+                has_short('f')
+                    || args
+                        .iter()
+                        .any(|arg| arg.starts_with("--force") || arg.starts_with('+'))
+            }
+            "checkout" | "restore" => args.contains(&"--") || args.contains(&"."),
+            "branch" => args.contains(&"-D"),
+            "stash" => matches!(args.first(), Some(&"drop" | &"clear")),
+            _ => false,
+        };
+        if destructive {
+            self.add(
+                SandboxRiskLevel::High,
+                format!("`git {subcommand}` can discard uncommitted work or rewrite history."),
+            );
+        }
+        if GIT_NETWORK.contains(&subcommand) {
+            self.network(&format!("git {subcommand}"));
+        } else if !destructive && !GIT_READ_ONLY.contains(&subcommand) {
+            self.add(
+                SandboxRiskLevel::Medium,
+                format!("Modifies the git repository (`git {subcommand}`)."),
+            );
+        }
+    }
+
+    fn network(&mut self, label: &str) {
+        let note = if self.network_blocked {
+            format!("Needs network access (`{label}`), which the sandbox blocks.")
+        } else {
+            format!("Uses the network (`{label}`).")
+        };
+        self.add(SandboxRiskLevel::Medium, note);
+    }
+
+    fn is_writable(&self, path: &Path) -> bool {
+        self.writable_roots
+            .iter()
+            .any(|root| path.starts_with(root))
+    }
+}
+
+/// The subcommand of `name` that installs packages, if `args` starts with one.
+fn package_install<'a>(name: &str, args: &'a [String]) -> Option<&'a str> {
+    let (_, subcommands) = PACKAGE_MANAGERS
+        .iter()
+        .find(|(manager, _)| *manager == name)?;
+    let subcommand = args.iter().find(|arg| !arg.starts_with('-'))?;
+    subcommands
+        .contains(&subcommand.as_str())
+        .then_some(subcommand.as_str())
+}
+
+/// Capitalize `text` and end it with a period.
+fn sentence(text: &str) -> String {
+    let mut chars = text.chars();
+    let mut sentence: String = match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    };
+    if !sentence.ends_with('.') {
+        sentence.push('.');
+    }
+    sentence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn vec_str(items: &[&str]) -> Vec<String> {
+        items.iter().map(std::string::ToString::to_string).collect()
+    }
+
+    fn bash(script: &str) -> Vec<String> {
+        vec_str(&["bash", "-lc", script])
+    }
+
+    fn workspace_write() -> SandboxPolicy {
+        SandboxPolicy::WorkspaceWrite {
+            writable_roots: Vec::new(),
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        }
+    }
+
+    fn assess(command: &[String]) -> SandboxCommandAssessment {
+        assess_command(command, &workspace_write(), Path::new("/work/repo"), None)
+    }
+
+    #[test]
+    fn read_only_commands_are_low_risk() {
+        assert_eq!(
+            assess(&bash("cat README.md && rg TODO src")),
+            SandboxCommandAssessment {
+                description: "Only reads files, lists directories or searches text.".to_string(),
+                risk_level: SandboxRiskLevel::Low,
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_programs_are_medium_risk() {
+        assert_eq!(
+            assess(&vec_str(&["python3", "-c", "print(42)"])),
+            SandboxCommandAssessment {
+                description: "Runs code with `python3`; its effects cannot be predicted offline."
+                    .to_string(),
+                risk_level: SandboxRiskLevel::Medium,
+            }
+        );
+    }
+
+    #[test]
+    fn paths_are_checked_against_writable_roots() {
+        assert_eq!(
+            assess(&bash("rm -rf target && echo done > /etc/motd")),
+            SandboxCommandAssessment {
+                description: "Writes to /etc/motd outside the writable roots. Deletes `target` and everything in it."
+                    .to_string(),
+                risk_level: SandboxRiskLevel::High,
+            }
+        );
+    }
+
+    #[test]
+    fn network_intent_reflects_the_sandbox() {
+        let command = vec_str(&["npm", "install", "left-pad"]);
+        assert_eq!(
+            assess(&command),
+            SandboxCommandAssessment {
+                description: "Installs or updates packages with `npm install`. Needs network access (`npm install`), which the sandbox blocks."
+                    .to_string(),
+                risk_level: SandboxRiskLevel::Medium,
+            }
+        );
+        assert_eq!(
+            assess_command(
+                &command,
+                &SandboxPolicy::DangerFullAccess,
+                Path::new("/work/repo"),
+                Some("exit code 1"),
+            )
+            .description,
+            "Installs or updates packages with `npm install`. Uses the network (`npm install`). It was blocked by the sandbox; approving runs it again without the sandbox."
+        );
+    }
+
+    #[test]
+    fn git_history_rewrites_are_high_risk() {
+        assert_eq!(
+            assess(&bash("git fetch origin && git reset --hard origin/main")),
+            SandboxCommandAssessment {
+                description: "`git reset` can discard uncommitted work or rewrite history. Needs network access (`git fetch`), which the sandbox blocks."
+                    .to_string(),
+                risk_level: SandboxRiskLevel::High,
+            }
+        );
+        assert_eq!(
+            assess(&vec_str(&["sudo", "git", "init"])).description,
+            "Runs `git` with elevated privileges through `sudo`. Modifies the git repository (`git init`)."
+        );
+    }
+}
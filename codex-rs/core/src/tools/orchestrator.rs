@@ -2,9 +2,12 @@
 Module: orchestrator
 
 Central place for approvals + sandbox selection + retry semantics. Drives a
-simple sequence for any ToolRuntime: approval → select sandbox → attempt →
-retry without sandbox on denial (no re‑approval thanks to caching).
+simple sequence for any ToolRuntime: exec policy → command guard → approval →
+select sandbox → attempt → retry without sandbox on denial (no re‑approval
+thanks to caching).
 */
+use crate::command_safety::exec_policy::ExecPolicyDecision;
+use crate::config::types::CommandGuardAction;
 use crate::error::CodexErr;
 use crate::error::SandboxErr;
 use crate::error::get_error_message_ui;
@@ -47,13 +50,92 @@ impl ToolOrchestrator {
         let otel_ci = &tool_ctx.call_id;
         let otel_user = codex_otel::otel_event_manager::ToolDecisionSource::User;
         let otel_cfg = codex_otel::otel_event_manager::ToolDecisionSource::Config;
+        let otel_policy = codex_otel::otel_event_manager::ToolDecisionSource::ExecPolicy;
+        let otel_guard = codex_otel::otel_event_manager::ToolDecisionSource::CommandGuard;
 
-        // 1) Approval
+        // 0) Exec policy: forbidden commands never reach the sandbox; commands
+        // the policy allows skip the initial approval but still run sandboxed.
+        let policy_decision = match (
+            &tool_ctx.session.services.exec_policy,
+            req.sandbox_retry_data(),
+        ) {
+            (Some(exec_policy), Some(metadata)) => exec_policy.check(&metadata.command),
+            _ => ExecPolicyDecision::Unmatched,
+        };
+        if let ExecPolicyDecision::Forbidden(reason) = &policy_decision {
+            otel.tool_decision(otel_tn, otel_ci, ReviewDecision::Denied, otel_policy);
+            return Err(ToolError::Rejected(format!(
+                "forbidden by exec policy: {reason}"
+            )));
+        }
+
+        // 1) Command guard: catastrophic commands are refused, or put in front
+        // of the user whatever the approval policy, exec policy or saved
+        // approvals say.
+        let guard_hit = match req.sandbox_retry_data() {
+            Some(metadata) => {
+                let guard = &tool_ctx.session.services.command_guard;
+                let hit = guard
+                    .check(&metadata.command, &metadata.cwd, &turn_ctx.sandbox_policy)
+                    .await;
+                hit.map(|hit| (guard.action(), hit, metadata))
+            }
+            None => None,
+        };
+        if let Some((action, hit, _)) = &guard_hit
+            && (*action == CommandGuardAction::Deny || approval_policy == AskForApproval::Never)
+        {
+            otel.tool_decision(otel_tn, otel_ci, ReviewDecision::Denied, otel_guard);
+            return Err(ToolError::Rejected(format!(
+                "blocked by command guard ({}): {}",
+                hit.rule.as_str(),
+                hit.reason
+            )));
+        }
+
+        let policy_allowed = policy_decision == ExecPolicyDecision::Allowed
+            && !tool.wants_escalated_first_attempt(req);
+
+        // 2) Approval
         let needs_initial_approval =
             tool.wants_initial_approval(req, approval_policy, &turn_ctx.sandbox_policy);
         let mut already_approved = false;
 
-        if needs_initial_approval {
+        if let Some((_, hit, metadata)) = guard_hit {
+            let risk = tool_ctx
+                .session
+                .assess_sandbox_command(turn_ctx, &tool_ctx.call_id, &metadata.command, None)
+                .await;
+            let decision = tool_ctx
+                .session
+                .request_guarded_command_approval(
+                    turn_ctx,
+                    tool_ctx.call_id.clone(),
+                    metadata.command,
+                    metadata.cwd,
+                    format!("Command guard: {}", hit.reason),
+                    risk,
+                )
+                .await;
+
+            otel.tool_decision(otel_tn, otel_ci, decision, otel_user.clone());
+
+            match decision {
+                ReviewDecision::Denied | ReviewDecision::Abort => {
+                    return Err(ToolError::Rejected(format!(
+                        "rejected by user after a command guard warning: {}",
+                        hit.reason
+                    )));
+                }
+                ReviewDecision::Approved
+                | ReviewDecision::ApprovedForSession
+                | ReviewDecision::ApprovedPrefix
+                | ReviewDecision::ApprovedPrefixForProject => {}
+            }
+            already_approved = true;
+        } else if policy_allowed {
+            otel.tool_decision(otel_tn, otel_ci, ReviewDecision::Approved, otel_policy);
+        } else if needs_initial_approval {
             let mut risk = None;
 
             if let Some(metadata) = req.sandbox_retry_data() {
@@ -78,14 +160,17 @@ impl ToolOrchestrator {
                 ReviewDecision::Denied | ReviewDecision::Abort => {
                     return Err(ToolError::Rejected("rejected by user".to_string()));
                 }
-                ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {}
+                ReviewDecision::Approved
+                | ReviewDecision::ApprovedForSession
+                | ReviewDecision::ApprovedPrefix
+                | ReviewDecision::ApprovedPrefixForProject => {}
             }
             already_approved = true;
         } else {
             otel.tool_decision(otel_tn, otel_ci, ReviewDecision::Approved, otel_cfg);
         }
 
-        // 2) First attempt under the selected sandbox.
+        // 3) First attempt under the selected sandbox.
         let mut initial_sandbox = self
             .sandbox
             .select_initial(&turn_ctx.sandbox_policy, tool.sandbox_preference());
@@ -159,7 +244,10 @@ impl ToolOrchestrator {
                         ReviewDecision::Denied | ReviewDecision::Abort => {
                             return Err(ToolError::Rejected("rejected by user".to_string()));
                         }
-                        ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {}
+                        ReviewDecision::Approved
+                        | ReviewDecision::ApprovedForSession
+                        | ReviewDecision::ApprovedPrefix
+                        | ReviewDecision::ApprovedPrefixForProject => {}
                     }
                 }
 
@@ -101,6 +101,8 @@ async fn stdio_server_round_trip() -> anyhow::Result<()> {
                     tool_timeout_sec: None,
                     enabled_tools: None,
                     disabled_tools: None,
+                    approval: None,
+                    tools: HashMap::new(),
                 },
             );
         })
@@ -241,6 +243,8 @@ async fn stdio_image_responses_round_trip() -> anyhow::Result<()> {
                     tool_timeout_sec: None,
                     enabled_tools: None,
                     disabled_tools: None,
+                    approval: None,
+                    tools: HashMap::new(),
                 },
             );
         })
@@ -437,6 +441,8 @@ async fn stdio_image_completions_round_trip() -> anyhow::Result<()> {
                     tool_timeout_sec: None,
                     enabled_tools: None,
                     disabled_tools: None,
+                    approval: None,
+                    tools: HashMap::new(),
                 },
             );
         })
@@ -579,6 +585,8 @@ async fn stdio_server_propagates_whitelisted_env_vars() -> anyhow::Result<()> {
                     tool_timeout_sec: None,
                     enabled_tools: None,
                     disabled_tools: None,
+                    approval: None,
+                    tools: HashMap::new(),
                 },
             );
         })
@@ -730,6 +738,8 @@ async fn streamable_http_tool_call_round_trip() -> anyhow::Result<()> {
                     tool_timeout_sec: None,
                     enabled_tools: None,
                     disabled_tools: None,
+                    approval: None,
+                    tools: HashMap::new(),
                 },
             );
         })
@@ -913,6 +923,8 @@ async fn streamable_http_with_oauth_round_trip() -> anyhow::Result<()> {
                     tool_timeout_sec: None,
                     enabled_tools: None,
                     disabled_tools: None,
+                    approval: None,
+                    tools: HashMap::new(),
                 },
             );
         })
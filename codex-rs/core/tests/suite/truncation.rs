@@ -242,6 +242,8 @@ async fn mcp_tool_call_output_exceeds_limit_truncated_for_model() -> Result<()>
                 tool_timeout_sec: None,
                 enabled_tools: None,
                 disabled_tools: None,
+                approval: None,
+                tools: HashMap::new(),
             },
         );
     });
@@ -343,6 +345,8 @@ async fn mcp_image_output_preserves_image_and_no_text_summary() -> Result<()> {
                 tool_timeout_sec: None,
                 enabled_tools: None,
                 disabled_tools: None,
+                approval: None,
+                tools: HashMap::new(),
             },
         );
     });
@@ -23,6 +23,7 @@ use codex_core::protocol::AskForApproval;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::Op;
+use codex_core::protocol::ReviewDecision;
 use codex_core::protocol::SessionSource;
 use codex_ollama::DEFAULT_OSS_MODEL;
 use codex_protocol::config_types::SandboxMode;
@@ -351,6 +352,16 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         if matches!(event.msg, EventMsg::Error(_)) {
             error_seen = true;
         }
+        if let EventMsg::McpToolCallApprovalRequest(_) = &event.msg {
+            // There is nobody to ask in headless mode: decline the call and
+            // let the model carry on without it.
+            conversation
+                .submit(Op::McpToolCallApproval {
+                    id: event.id.clone(),
+                    decision: ReviewDecision::Denied,
+                })
+                .await?;
+        }
         let shutdown: CodexStatus = event_processor.process_event(event);
         match shutdown {
             CodexStatus::Running => continue,
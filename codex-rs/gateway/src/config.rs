@@ -1,11 +1,22 @@
 //! Configuration types for the Codex Gateway
 
+use codex_protocol::config_types::SandboxMode;
+use codex_protocol::protocol::AskForApproval;
 use serde::Deserialize;
 use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::time::Duration;
 
 /// Gateway configuration settings
+///
+/// Loaded from `gateway.toml` (see [`GatewayConfig::load`]); every field is
+/// optional in the file and environment variables override it.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GatewayConfig {
     /// Host to bind the server to
     pub host: String,
@@ -16,34 +27,87 @@ pub struct GatewayConfig {
     /// Timeout configurations
     pub timeouts: TimeoutConfig,
 
-    /// Maximum number of concurrent connections
+    /// Maximum number of open client connections; further connections wait
+    /// in the accept backlog
     pub max_connections: usize,
 
+    /// Maximum simultaneous `/exec` and `/ws` sessions per API key
+    pub max_sessions_per_key: usize,
+
+    /// Maximum background jobs (`/jobs`, webhooks) running at once
+    pub max_concurrent_jobs: usize,
+
+    /// Largest file or archive served by the session artifact endpoints, in
+    /// bytes; both are built in memory
+    pub max_download_size: u64,
+
     /// WebSocket configuration
     pub websocket: WebSocketConfig,
 
     /// Request body size limits configuration
     pub body_limits: BodyLimitsConfig,
+
+    /// Limits for `multipart/form-data` exec requests
+    pub uploads: UploadConfig,
+
+    /// Webhook signature and routing configuration
+    #[serde(default)]
+    pub webhooks: WebhookConfig,
+
+    /// Per-tenant workspace roots and sandbox/approval ceilings
+    #[serde(default)]
+    pub tenants: TenantConfig,
+
+    /// Serve HTTPS with this certificate instead of plain HTTP
+    pub tls: Option<TlsConfig>,
+
+    /// Browser origins allowed to call the gateway
+    pub cors: CorsConfig,
+
+    /// Where credentials are stored
+    pub auth: AuthConfig,
+
+    /// Append-only log of agent actions served on `/admin/audit`
+    pub audit: AuditConfig,
 }
 
 /// Timeout configuration
+///
+/// Durations are whole seconds in `gateway.toml`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TimeoutConfig {
     /// Request timeout duration
+    #[serde(with = "duration_secs")]
     pub request_timeout: Duration,
 
     /// Keep-alive timeout duration
+    #[serde(with = "duration_secs")]
     pub keep_alive_timeout: Duration,
 
     /// WebSocket ping interval
+    #[serde(with = "duration_secs")]
     pub websocket_ping_interval: Duration,
 
     /// WebSocket connection timeout
+    #[serde(with = "duration_secs")]
     pub websocket_timeout: Duration,
+
+    /// Interval between heartbeat comments on Server-Sent Events streams
+    #[serde(with = "duration_secs")]
+    pub sse_keep_alive_interval: Duration,
+
+    /// Time in-flight turns get to finish after SIGTERM before they are
+    /// interrupted and the gateway exits
+    #[serde(with = "duration_secs")]
+    pub shutdown_grace_period: Duration,
 }
 
 /// WebSocket-specific configuration
+///
+/// Applies to `/ws` and `/app-server`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct WebSocketConfig {
     /// Maximum message size in bytes
     pub max_message_size: usize,
@@ -51,15 +115,217 @@ pub struct WebSocketConfig {
     /// Maximum frame size in bytes
     pub max_frame_size: usize,
 
-    /// Enable compression
-    pub enable_compression: bool,
-
-    /// Maximum number of concurrent WebSocket connections
+    /// Maximum number of concurrent WebSocket connections; upgrades beyond
+    /// it are refused with 503
     pub max_connections: usize,
 }
 
+/// TLS certificate for the listener
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// PEM certificate chain, leaf first
+    pub cert: PathBuf,
+
+    /// PEM private key (PKCS#8, PKCS#1 or SEC1)
+    pub key: PathBuf,
+}
+
+/// Cross-origin access for browser clients
+///
+/// With no origins configured the gateway sends no CORS headers, so browsers
+/// refuse cross-origin calls. `"*"` allows every origin.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CorsConfig {
+    /// Origins such as `https://chat.openai.com`
+    pub allowed_origins: Vec<String>,
+
+    /// How long browsers may cache a preflight response
+    #[serde(with = "duration_secs_opt")]
+    pub max_age: Option<Duration>,
+}
+
+/// Credential storage
+///
+/// Unset paths default to files under `$CODEX_HOME/gateway`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// API key store (`api_keys.json`)
+    pub api_keys_file: Option<PathBuf>,
+
+    /// OAuth clients and tokens (`oauth.json`)
+    pub oauth_file: Option<PathBuf>,
+}
+
+/// Audit log of agent actions
+///
+/// The log is a JSONL file, `$CODEX_HOME/gateway/audit/audit.jsonl` unless
+/// `file` is set. Once it reaches `max_file_size` bytes it is rotated to
+/// `audit.jsonl.1`, older files shift up and the oldest beyond `max_files`
+/// is deleted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    /// Whether agent actions are recorded
+    pub enabled: bool,
+
+    /// Audit log file
+    pub file: Option<PathBuf>,
+
+    /// Size in bytes at which the log is rotated
+    pub max_file_size: u64,
+
+    /// Number of rotated files kept next to the live one
+    pub max_files: usize,
+}
+
+/// Limits for files uploaded with `multipart/form-data` exec requests
+///
+/// The route's body limit still caps the request as a whole.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UploadConfig {
+    /// Maximum size of one uploaded part in bytes
+    pub max_part_size: usize,
+
+    /// Maximum number of images and files per request
+    pub max_files: usize,
+}
+
+/// Webhook ingestion configuration
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    /// Shared secret used to verify webhook signatures
+    #[serde(skip_serializing)]
+    pub secret: Option<String>,
+
+    /// Maximum clock skew accepted for timestamped signatures
+    #[serde(with = "duration_secs")]
+    pub signature_tolerance: Duration,
+
+    /// Event type → prompt template routes, first match wins
+    pub routes: Vec<WebhookRoute>,
+
+    /// `key_id` that owns jobs queued by signed deliveries, so that key can
+    /// follow them at `/webhook/jobs/{id}`; without it they belong to no key
+    pub owner_key: Option<String>,
+}
+
+/// Maps a webhook event to the prompt the agent should run
+///
+/// `event` matches `<event>.<action>` first (e.g. `issue_comment.created`),
+/// then the bare event name; `*` matches anything. The prompt may reference
+/// payload fields as `{{issue.title}}`, plus `{{event}}` and `{{payload}}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookRoute {
+    pub event: String,
+    pub prompt: String,
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub sandbox_mode: Option<String>,
+}
+
+/// Workspace isolation for API keys
+///
+/// A key bound to a tenant (`ApiKeyInfo::tenant`) can only run turns whose
+/// `cwd` resolves inside that tenant's `workspace_root`, and only with a
+/// sandbox mode and approval policy at or below the tenant's maximum. Keys
+/// without a tenant, and signed webhooks, use `default_tenant` when set;
+/// otherwise they are capped by the host's Codex `config.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TenantConfig {
+    /// Tenant applied to callers that are not bound to one
+    pub default_tenant: Option<String>,
+
+    /// Tenant name → policy
+    pub tenants: HashMap<String, TenantPolicy>,
+}
+
+/// Limits applied to every turn a tenant starts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantPolicy {
+    /// Directory every `cwd` (and local image path) must resolve into
+    pub workspace_root: PathBuf,
+
+    /// Most permissive sandbox a request may ask for
+    #[serde(default = "default_tenant_sandbox_mode")]
+    pub max_sandbox_mode: SandboxMode,
+
+    /// Most permissive approval policy a request may ask for
+    #[serde(default)]
+    pub max_approval_policy: AskForApproval,
+
+    /// Whether `workspace-write` turns may use the network
+    #[serde(default)]
+    pub network_access: bool,
+}
+
+fn default_tenant_sandbox_mode() -> SandboxMode {
+    SandboxMode::WorkspaceWrite
+}
+
+impl TenantConfig {
+    /// Load tenants from the JSON file named by `GATEWAY_TENANTS_FILE`, if set
+    ///
+    /// ```json
+    /// {
+    ///   "default_tenant": "shared",
+    ///   "tenants": {
+    ///     "shared": { "workspace_root": "/srv/workspaces/shared", "max_sandbox_mode": "read-only" },
+    ///     "acme": { "workspace_root": "/srv/workspaces/acme", "max_approval_policy": "never" }
+    ///   }
+    /// }
+    /// ```
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Ok(path) = std::env::var("GATEWAY_TENANTS_FILE") else {
+            return Ok(None);
+        };
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|e| format!("failed to read tenants from {path}: {e}"))?;
+        let config: Self = serde_json::from_str(&contents)
+            .map_err(|e| format!("invalid tenants in {path}: {e}"))?;
+        config.validate()?;
+        Ok(Some(config))
+    }
+
+    /// Check that the default tenant exists and every root is absolute
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(default_tenant) = &self.default_tenant
+            && !self.tenants.contains_key(default_tenant)
+        {
+            return Err(format!("default_tenant '{default_tenant}' is not defined"));
+        }
+        for (name, policy) in &self.tenants {
+            if !policy.workspace_root.is_absolute() {
+                return Err(format!(
+                    "workspace_root of tenant '{name}' must be an absolute path"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for WebhookConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookConfig")
+            .field("secret", &self.secret.as_ref().map(|_| "<redacted>"))
+            .field("signature_tolerance", &self.signature_tolerance)
+            .field("routes", &self.routes)
+            .field("owner_key", &self.owner_key)
+            .finish()
+    }
+}
+
 /// Request body size limits configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct BodyLimitsConfig {
     /// Global default body size limit in bytes
     pub default_limit: usize,
@@ -73,9 +339,12 @@ pub struct BodyLimitsConfig {
     /// Health check body size limit in bytes (usually very small)
     pub health_limit: usize,
 
+    /// Path prefix → limit in bytes, overriding the limits above; the
+    /// longest matching prefix wins
+    pub routes: BTreeMap<String, usize>,
+
     /// Whether to enable body size limits (can be disabled for development)
     pub enabled: bool,
-    pub(crate) websocket_limit: usize,
 }
 
 impl Default for GatewayConfig {
@@ -85,8 +354,18 @@ impl Default for GatewayConfig {
             port: 8080,
             timeouts: TimeoutConfig::default(),
             max_connections: 10000,
+            max_sessions_per_key: 4,
+            max_concurrent_jobs: 2,
+            max_download_size: 64 * 1024 * 1024,
             websocket: WebSocketConfig::default(),
             body_limits: BodyLimitsConfig::default(),
+            uploads: UploadConfig::default(),
+            webhooks: WebhookConfig::default(),
+            tenants: TenantConfig::default(),
+            tls: None,
+            cors: CorsConfig::default(),
+            auth: AuthConfig::default(),
+            audit: AuditConfig::default(),
         }
     }
 }
@@ -98,6 +377,8 @@ impl Default for TimeoutConfig {
             keep_alive_timeout: Duration::from_secs(60),
             websocket_ping_interval: Duration::from_secs(30),
             websocket_timeout: Duration::from_secs(300),
+            sse_keep_alive_interval: Duration::from_secs(15),
+            shutdown_grace_period: Duration::from_secs(5),
         }
     }
 }
@@ -107,7 +388,6 @@ impl Default for WebSocketConfig {
         Self {
             max_message_size: 64 * 1024 * 1024, // 64MB
             max_frame_size: 16 * 1024 * 1024,   // 16MB
-            enable_compression: true,
             // Limite baseado em padrões de mercado e capacidade do servidor
             // nginx default: 1024, cloudflare: 10000, optamos por um meio termo robusto
             max_connections: 5000,
@@ -130,8 +410,7 @@ impl Default for BodyLimitsConfig {
             // Health: 1KB - endpoints de health são mínimos
             health_limit: 1024,
 
-            // Websocket: 1MB - suficiente para payloads de integração robustos (ex: GitHub webhooks com diffs grandes)
-            websocket_limit: 1024,
+            routes: BTreeMap::new(),
 
             // Habilitado por padrão para produção
             enabled: true,
@@ -139,29 +418,97 @@ impl Default for BodyLimitsConfig {
     }
 }
 
-impl BodyLimitsConfig {
-    /// Create body limits config from environment variables
-    pub fn from_env() -> Self {
-        let parse_size = |env_var: &str, default: usize| -> usize {
-            std::env::var(env_var)
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(default)
-        };
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            file: None,
+            max_file_size: 50 * 1024 * 1024,
+            max_files: 5,
+        }
+    }
+}
 
-        let enabled = std::env::var("GATEWAY_BODY_LIMITS_ENABLED")
-            .map(|v| v.parse().unwrap_or(true))
-            .unwrap_or(true);
+impl Default for UploadConfig {
+    fn default() -> Self {
+        Self {
+            max_part_size: 10 * 1024 * 1024,
+            max_files: 16,
+        }
+    }
+}
 
+impl Default for WebhookConfig {
+    fn default() -> Self {
         Self {
-            default_limit: parse_size("GATEWAY_BODY_LIMIT_DEFAULT", 2 * 1024 * 1024),
-            jsonrpc_limit: parse_size("GATEWAY_BODY_LIMIT_JSONRPC", 1024 * 1024),
-            webhook_limit: parse_size("GATEWAY_BODY_LIMIT_WEBHOOK", 10 * 1024 * 1024),
-            health_limit: parse_size("GATEWAY_BODY_LIMIT_HEALTH", 1024),
-            websocket_limit: parse_size("GATEWAY_BODY_LIMIT_WEBSOCKET", 1024 * 1024),
-            enabled,
+            secret: None,
+            signature_tolerance: Duration::from_secs(300),
+            routes: Vec::new(),
+            owner_key: None,
         }
     }
+}
+
+impl WebhookConfig {
+    /// Create webhook config from environment variables
+    pub fn from_env() -> Result<Self, String> {
+        let mut config = Self::default();
+        config.apply_env()?;
+        Ok(config)
+    }
+
+    /// Override settings from environment variables
+    ///
+    /// - `GATEWAY_WEBHOOK_SECRET`: shared secret for signature verification
+    /// - `GATEWAY_WEBHOOK_ROUTES_FILE`: JSON array of [`WebhookRoute`]s
+    /// - `GATEWAY_WEBHOOK_OWNER_KEY`: `key_id` that owns signed deliveries' jobs
+    pub fn apply_env(&mut self) -> Result<(), String> {
+        if let Some(secret) = std::env::var("GATEWAY_WEBHOOK_SECRET")
+            .ok()
+            .filter(|secret| !secret.is_empty())
+        {
+            self.secret = Some(secret);
+        }
+
+        if let Some(owner_key) = std::env::var("GATEWAY_WEBHOOK_OWNER_KEY")
+            .ok()
+            .filter(|owner_key| !owner_key.is_empty())
+        {
+            self.owner_key = Some(owner_key);
+        }
+
+        if let Ok(path) = std::env::var("GATEWAY_WEBHOOK_ROUTES_FILE") {
+            let contents = std::fs::read_to_string(&path)
+                .map_err(|e| format!("failed to read webhook routes from {path}: {e}"))?;
+            self.routes = serde_json::from_str(&contents)
+                .map_err(|e| format!("invalid webhook routes in {path}: {e}"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl BodyLimitsConfig {
+    /// Create body limits config from environment variables
+    pub fn from_env() -> Result<Self, String> {
+        let mut config = Self::default();
+        config.apply_env()?;
+        Ok(config)
+    }
+
+    /// Override limits from environment variables
+    ///
+    /// - `GATEWAY_BODY_LIMIT_DEFAULT`, `GATEWAY_BODY_LIMIT_JSONRPC`,
+    ///   `GATEWAY_BODY_LIMIT_WEBHOOK`, `GATEWAY_BODY_LIMIT_HEALTH`: bytes
+    /// - `GATEWAY_BODY_LIMITS_ENABLED`: `true`/`false`
+    pub fn apply_env(&mut self) -> Result<(), String> {
+        override_from_env("GATEWAY_BODY_LIMIT_DEFAULT", &mut self.default_limit)?;
+        override_from_env("GATEWAY_BODY_LIMIT_JSONRPC", &mut self.jsonrpc_limit)?;
+        override_from_env("GATEWAY_BODY_LIMIT_WEBHOOK", &mut self.webhook_limit)?;
+        override_from_env("GATEWAY_BODY_LIMIT_HEALTH", &mut self.health_limit)?;
+        override_from_env("GATEWAY_BODY_LIMITS_ENABLED", &mut self.enabled)?;
+        Ok(())
+    }
 
     /// Get the appropriate limit for a given endpoint path
     pub fn get_limit_for_path(&self, path: &str) -> usize {
@@ -169,6 +516,16 @@ impl BodyLimitsConfig {
             return usize::MAX; // No limit when disabled
         }
 
+        let route_limit = self
+            .routes
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, limit)| *limit);
+        if let Some(limit) = route_limit {
+            return limit;
+        }
+
         match path {
             p if p.starts_with("/health") => self.health_limit,
             p if p.starts_with("/jsonrpc") || p.starts_with("/rpc") => self.jsonrpc_limit,
@@ -178,33 +535,174 @@ impl BodyLimitsConfig {
     }
 }
 
+impl CorsConfig {
+    /// Check that every origin is `*` or a bare `http(s)://host[:port]`
+    pub fn validate(&self) -> Result<(), String> {
+        for origin in &self.allowed_origins {
+            if origin == "*" {
+                continue;
+            }
+            let url = url::Url::parse(origin)
+                .map_err(|e| format!("invalid CORS origin '{origin}': {e}"))?;
+            let bare = matches!(url.scheme(), "http" | "https")
+                && url.host_str().is_some()
+                && url.path() == "/"
+                && !origin.ends_with('/')
+                && url.query().is_none();
+            if !bare {
+                return Err(format!(
+                    "invalid CORS origin '{origin}': expected scheme://host[:port]"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
 impl GatewayConfig {
     /// Create a new config from environment variables
+    ///
+    /// Invalid values are logged and ignored; use [`GatewayConfig::load`] to
+    /// fail on them instead.
     pub fn from_env() -> Self {
-        let host = std::env::var("GATEWAY_HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
-        let port = std::env::var("GATEWAY_PORT")
-            .unwrap_or_else(|_| "8080".to_string())
-            .parse()
-            .unwrap_or(8080);
+        let mut config = Self::default();
+        if let Err(err) = config.apply_env() {
+            tracing::warn!("Ignoring invalid environment configuration: {err}");
+        }
+        config
+    }
 
-        // Body limits configuration from environment
-        let body_limits = BodyLimitsConfig::from_env();
+    /// Load `path` (when given), apply environment overrides and validate
+    ///
+    /// ```toml
+    /// host = "127.0.0.1"
+    /// port = 8443
+    /// max_connections = 2000
+    ///
+    /// [tls]
+    /// cert = "/etc/codex-gateway/cert.pem"
+    /// key = "/etc/codex-gateway/key.pem"
+    ///
+    /// [cors]
+    /// allowed_origins = ["https://chat.openai.com"]
+    ///
+    /// [body_limits.routes]
+    /// "/v1/chat/completions" = 4194304
+    ///
+    /// [websocket]
+    /// max_connections = 500
+    ///
+    /// [auth]
+    /// api_keys_file = "/var/lib/codex-gateway/api_keys.json"
+    /// ```
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        let mut config = match path {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        config.apply_env()?;
+        config.validate()?;
+        Ok(config)
+    }
 
-        // WebSocket configuration from environment
-        let mut websocket = WebSocketConfig::default();
-        if let Ok(max_conn_str) = std::env::var("GATEWAY_WEBSOCKET_MAX_CONNECTIONS")
-            && let Ok(max_conn) = max_conn_str.parse::<usize>()
-        {
-            websocket.max_connections = max_conn;
+    /// Parse a `gateway.toml` file
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        toml::from_str(&contents).map_err(|e| format!("invalid config in {}: {e}", path.display()))
+    }
+
+    /// Override settings from environment variables
+    ///
+    /// Only variables that are set are applied, so they take precedence over
+    /// the config file.
+    pub fn apply_env(&mut self) -> Result<(), String> {
+        if let Ok(host) = std::env::var("GATEWAY_HOST") {
+            self.host = host;
+        }
+        override_from_env("GATEWAY_PORT", &mut self.port)?;
+        // Cloud Run and similar platforms assign the port through PORT
+        override_from_env("PORT", &mut self.port)?;
+
+        let mut request_timeout_secs = self.timeouts.request_timeout.as_secs();
+        override_from_env("REQUEST_TIMEOUT_SECS", &mut request_timeout_secs)?;
+        self.timeouts.request_timeout = Duration::from_secs(request_timeout_secs);
+
+        let mut shutdown_grace_secs = self.timeouts.shutdown_grace_period.as_secs();
+        override_from_env("GATEWAY_SHUTDOWN_GRACE_SECS", &mut shutdown_grace_secs)?;
+        self.timeouts.shutdown_grace_period = Duration::from_secs(shutdown_grace_secs);
+
+        override_from_env("GATEWAY_MAX_CONNECTIONS", &mut self.max_connections)?;
+        override_from_env(
+            "GATEWAY_WEBSOCKET_MAX_CONNECTIONS",
+            &mut self.websocket.max_connections,
+        )?;
+        override_from_env(
+            "GATEWAY_MAX_SESSIONS_PER_KEY",
+            &mut self.max_sessions_per_key,
+        )?;
+        override_from_env("GATEWAY_MAX_CONCURRENT_JOBS", &mut self.max_concurrent_jobs)?;
+        override_from_env("GATEWAY_MAX_DOWNLOAD_SIZE", &mut self.max_download_size)?;
+
+        self.body_limits.apply_env()?;
+        override_from_env(
+            "GATEWAY_UPLOAD_MAX_PART_SIZE",
+            &mut self.uploads.max_part_size,
+        )?;
+        override_from_env("GATEWAY_UPLOAD_MAX_FILES", &mut self.uploads.max_files)?;
+        self.webhooks.apply_env()?;
+        if let Some(tenants) = TenantConfig::from_env()? {
+            self.tenants = tenants;
         }
 
-        Self {
-            host,
-            port,
-            body_limits,
-            websocket,
-            ..Default::default()
+        match (
+            std::env::var_os("GATEWAY_TLS_CERT"),
+            std::env::var_os("GATEWAY_TLS_KEY"),
+        ) {
+            (Some(cert), Some(key)) => {
+                self.tls = Some(TlsConfig {
+                    cert: PathBuf::from(cert),
+                    key: PathBuf::from(key),
+                });
+            }
+            (None, None) => {}
+            _ => return Err("GATEWAY_TLS_CERT and GATEWAY_TLS_KEY must be set together".into()),
+        }
+
+        if let Ok(origins) = std::env::var("GATEWAY_CORS_ALLOWED_ORIGINS") {
+            self.cors.allowed_origins = origins
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+
+        if let Some(path) = std::env::var_os("GATEWAY_API_KEYS_FILE") {
+            self.auth.api_keys_file = Some(PathBuf::from(path));
         }
+        if let Some(path) = std::env::var_os("GATEWAY_OAUTH_FILE") {
+            self.auth.oauth_file = Some(PathBuf::from(path));
+        }
+
+        override_from_env("GATEWAY_AUDIT_ENABLED", &mut self.audit.enabled)?;
+        if let Some(path) = std::env::var_os("GATEWAY_AUDIT_FILE") {
+            self.audit.file = Some(PathBuf::from(path));
+        }
+        override_from_env("GATEWAY_AUDIT_MAX_FILE_SIZE", &mut self.audit.max_file_size)?;
+        override_from_env("GATEWAY_AUDIT_MAX_FILES", &mut self.audit.max_files)?;
+
+        Ok(())
+    }
+
+    /// Check settings that would otherwise fail at runtime
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_connections == 0 {
+            return Err("max_connections must be at least 1".into());
+        }
+        self.tenants.validate()?;
+        self.cors.validate()?;
+        Ok(())
     }
 
     /// Get the full bind address
@@ -212,3 +710,144 @@ impl GatewayConfig {
         format!("{}:{}", self.host, self.port)
     }
 }
+
+/// Replace `target` with the parsed value of `name` when it is set
+fn override_from_env<T: FromStr>(name: &str, target: &mut T) -> Result<(), String>
+where
+    T::Err: std::fmt::Display,
+{
+    if let Ok(value) = std::env::var(name) {
+        *target = value
+            .trim()
+            .parse()
+            .map_err(|e| format!("invalid {name} value '{value}': {e}"))?;
+    }
+    Ok(())
+}
+
+/// [`Duration`]s as whole seconds
+mod duration_secs {
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serializer;
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_secs())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_secs)
+    }
+}
+
+/// Optional [`Duration`]s as whole seconds
+mod duration_secs_opt {
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serializer;
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&duration.as_secs()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Option::<u64>::deserialize(deserializer).map(|secs| secs.map(Duration::from_secs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gateway_toml() {
+        let config: GatewayConfig = toml::from_str(
+            r#"
+            host = "127.0.0.1"
+            port = 8443
+            max_connections = 2000
+
+            [timeouts]
+            request_timeout = 120
+            shutdown_grace_period = 20
+
+            [tls]
+            cert = "/etc/codex-gateway/cert.pem"
+            key = "/etc/codex-gateway/key.pem"
+
+            [cors]
+            allowed_origins = ["https://chat.openai.com"]
+            max_age = 600
+
+            [body_limits]
+            jsonrpc_limit = 4096
+
+            [body_limits.routes]
+            "/v1/chat" = 1000
+            "/v1/chat/completions" = 2000
+
+            [websocket]
+            max_connections = 10
+
+            [auth]
+            api_keys_file = "/var/lib/codex-gateway/api_keys.json"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.bind_address(), "127.0.0.1:8443");
+        assert_eq!(config.max_connections, 2000);
+        assert_eq!(config.timeouts.request_timeout, Duration::from_secs(120));
+        assert_eq!(
+            config.timeouts.shutdown_grace_period,
+            Duration::from_secs(20)
+        );
+        // Unset fields keep their defaults
+        assert_eq!(
+            config.timeouts.sse_keep_alive_interval,
+            TimeoutConfig::default().sse_keep_alive_interval
+        );
+        assert_eq!(
+            config.tls.map(|tls| tls.key),
+            Some(PathBuf::from("/etc/codex-gateway/key.pem"))
+        );
+        assert_eq!(config.cors.max_age, Some(Duration::from_secs(600)));
+        assert_eq!(config.websocket.max_connections, 10);
+        assert_eq!(
+            config.auth.api_keys_file,
+            Some(PathBuf::from("/var/lib/codex-gateway/api_keys.json"))
+        );
+
+        let limits = &config.body_limits;
+        assert_eq!(limits.get_limit_for_path("/jsonrpc"), 4096);
+        assert_eq!(limits.get_limit_for_path("/v1/chat/completions"), 2000);
+        assert_eq!(limits.get_limit_for_path("/v1/chat/other"), 1000);
+        assert_eq!(limits.get_limit_for_path("/exec"), limits.default_limit);
+    }
+
+    #[test]
+    fn test_cors_origins_are_validated() {
+        let cors = |origins: &[&str]| CorsConfig {
+            allowed_origins: origins.iter().map(|origin| origin.to_string()).collect(),
+            max_age: None,
+        };
+        assert!(
+            cors(&["*", "https://chat.openai.com", "http://localhost:3000"])
+                .validate()
+                .is_ok()
+        );
+        assert!(cors(&["chat.openai.com"]).validate().is_err());
+        assert!(cors(&["https://chat.openai.com/"]).validate().is_err());
+        assert!(cors(&["https://chat.openai.com/path"]).validate().is_err());
+    }
+}
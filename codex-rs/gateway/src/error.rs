@@ -1,5 +1,13 @@
 //! Error types for the Codex Gateway
-
+//!
+//! Every error response of the gateway's own routes carries an
+//! [`ErrorResponse`] body. The OpenAI-compatible facade and the OAuth
+//! endpoints keep the envelopes their clients expect instead.
+
+use axum::http::StatusCode;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
 use thiserror::Error;
 
 /// Errors that can occur in the gateway
@@ -37,6 +45,10 @@ pub enum GatewayError {
     #[error("Auth error: {0}")]
     Auth(String),
 
+    /// Authenticated caller is not allowed to do this
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     /// Timeout errors
     #[error("Timeout: {0}")]
     Timeout(String),
@@ -56,43 +68,147 @@ pub enum GatewayError {
         path: String,
     },
 
+    /// The response would exceed a configured size limit
+    #[error("Response too large: {0}")]
+    ResponseTooLarge(String),
+
     /// Invalid request error (malformed or invalid parameters)
     #[error("Invalid request: {0}")]
     InvalidRequest(String),
+
+    /// Requested resource does not exist
+    #[error("Not found: {0}")]
+    NotFound(String),
 }
 
 /// Result type alias for gateway operations
 pub type GatewayResult<T> = Result<T, GatewayError>;
 
-impl axum::response::IntoResponse for GatewayError {
+/// Machine-readable error code, derived from the HTTP status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidRequest,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    MethodNotAllowed,
+    Timeout,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    RateLimited,
+    Internal,
+    ServiceUnavailable,
+}
+
+impl ErrorCode {
+    /// Code for an error response with `status`
+    pub fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => Self::Unauthorized,
+            StatusCode::FORBIDDEN => Self::Forbidden,
+            StatusCode::NOT_FOUND => Self::NotFound,
+            StatusCode::METHOD_NOT_ALLOWED => Self::MethodNotAllowed,
+            StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => Self::Timeout,
+            StatusCode::PAYLOAD_TOO_LARGE => Self::PayloadTooLarge,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE => Self::UnsupportedMediaType,
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited,
+            StatusCode::SERVICE_UNAVAILABLE => Self::ServiceUnavailable,
+            status if status.is_client_error() => Self::InvalidRequest,
+            _ => Self::Internal,
+        }
+    }
+}
+
+/// JSON body of every gateway error response
+///
+/// ```json
+/// { "error": "Not found: session my-session", "code": "not_found", "status": 404 }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ErrorResponse {
+    /// Human-readable description
+    pub error: String,
+    /// Stable code to branch on
+    pub code: ErrorCode,
+    /// HTTP status, repeated for clients that only see the body
+    pub status: u16,
+}
+
+impl ErrorResponse {
+    pub fn new(status: StatusCode, error: impl Into<String>) -> Self {
+        Self {
+            error: error.into(),
+            code: ErrorCode::from_status(status),
+            status: status.as_u16(),
+        }
+    }
+}
+
+impl axum::response::IntoResponse for ErrorResponse {
     fn into_response(self) -> axum::response::Response {
-        use axum::Json;
-        use axum::http::StatusCode;
-
-        let (status, error_message) = match self {
-            GatewayError::Http(_) => (StatusCode::BAD_REQUEST, self.to_string()),
-            GatewayError::Json(_) => (StatusCode::BAD_REQUEST, self.to_string()),
-            GatewayError::WebSocket(_) => (StatusCode::BAD_REQUEST, self.to_string()),
-            GatewayError::ServerStart(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
-            GatewayError::Config(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
-            GatewayError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
-            GatewayError::ServiceUnavailable(_) => {
-                (StatusCode::SERVICE_UNAVAILABLE, self.to_string())
-            }
-            GatewayError::Auth(_) => (StatusCode::UNAUTHORIZED, self.to_string()),
-            GatewayError::Timeout(_) => (StatusCode::REQUEST_TIMEOUT, self.to_string()),
-            GatewayError::Generic(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
-            GatewayError::PayloadTooLarge { .. } => {
-                (StatusCode::PAYLOAD_TOO_LARGE, self.to_string())
-            }
-            GatewayError::InvalidRequest(_) => (StatusCode::BAD_REQUEST, self.to_string()),
-        };
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        (status, axum::Json(self)).into_response()
+    }
+}
 
-        let body = Json(serde_json::json!({
-            "error": error_message,
-            "status": status.as_u16()
-        }));
+impl GatewayError {
+    /// HTTP status the error is reported with
+    pub fn status(&self) -> StatusCode {
+        match self {
+            GatewayError::Http(_) => StatusCode::BAD_REQUEST,
+            GatewayError::Json(_) => StatusCode::BAD_REQUEST,
+            GatewayError::WebSocket(_) => StatusCode::BAD_REQUEST,
+            GatewayError::ServerStart(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            GatewayError::Config(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            GatewayError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            GatewayError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            GatewayError::Auth(_) => StatusCode::UNAUTHORIZED,
+            GatewayError::Forbidden(_) => StatusCode::FORBIDDEN,
+            GatewayError::Timeout(_) => StatusCode::REQUEST_TIMEOUT,
+            GatewayError::Generic(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            GatewayError::PayloadTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            GatewayError::ResponseTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            GatewayError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            GatewayError::NotFound(_) => StatusCode::NOT_FOUND,
+        }
+    }
+}
 
-        (status, body).into_response()
+impl axum::response::IntoResponse for GatewayError {
+    fn into_response(self) -> axum::response::Response {
+        ErrorResponse::new(self.status(), self.to_string()).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::response::IntoResponse;
+
+    #[tokio::test]
+    async fn test_error_envelope() -> Result<(), Box<dyn std::error::Error>> {
+        let response = GatewayError::NotFound("session s1".to_string()).into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        let body: ErrorResponse = serde_json::from_slice(&body)?;
+        assert_eq!(
+            body,
+            ErrorResponse {
+                error: "Not found: session s1".to_string(),
+                code: ErrorCode::NotFound,
+                status: 404,
+            }
+        );
+
+        assert_eq!(
+            ErrorCode::from_status(StatusCode::UNPROCESSABLE_ENTITY),
+            ErrorCode::InvalidRequest
+        );
+        assert_eq!(
+            ErrorCode::from_status(StatusCode::BAD_GATEWAY),
+            ErrorCode::Internal
+        );
+        Ok(())
     }
 }
@@ -0,0 +1,318 @@
+//! Admin handlers for API key and OAuth client management and the audit log
+//!
+//! These routes require a key with the `admin` scope. A fresh deployment can
+//! bootstrap access with the `GATEWAY_ADMIN_KEY` environment variable and
+//! then mint regular keys through `POST /admin/keys`.
+
+use crate::error::GatewayError;
+use crate::error::GatewayResult;
+use crate::middleware::api_key::ApiKeyInfo;
+use crate::middleware::api_key::KNOWN_SCOPES;
+use crate::middleware::api_key::SCOPE_AGENT;
+use crate::middleware::oauth::OAuthClient;
+use crate::services::audit::AuditFilter;
+use crate::state::AppState;
+use axum::extract::Path;
+use axum::extract::Query;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::Json;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use serde_json::json;
+use tracing::info;
+
+/// Default requests-per-minute for keys created without an explicit limit
+const DEFAULT_RATE_LIMIT: u32 = 100;
+
+/// Request body for `POST /admin/keys`
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    /// User/account the key belongs to
+    pub user_id: String,
+
+    /// Scopes to grant (defaults to `["agent"]`)
+    #[serde(default)]
+    pub scopes: Option<Vec<String>>,
+
+    /// Requests per minute (defaults to 100)
+    #[serde(default)]
+    pub rate_limit: Option<u32>,
+
+    /// Lifetime of the key in seconds; omit for a key that never expires
+    #[serde(default)]
+    pub expires_in_secs: Option<i64>,
+
+    /// Tenant whose workspace root and sandbox limits apply to the key
+    #[serde(default)]
+    pub tenant: Option<String>,
+}
+
+/// Response for `POST /admin/keys`
+///
+/// `api_key` is the only time the plaintext key is revealed.
+#[derive(Debug, Serialize)]
+pub struct CreateApiKeyResponse {
+    pub api_key: String,
+    #[serde(flatten)]
+    pub info: ApiKeyInfo,
+}
+
+/// POST /admin/keys - Create a new API key
+///
+/// ## Example Request
+///
+/// ```json
+/// {
+///   "user_id": "ci-bot",
+///   "scopes": ["agent"],
+///   "rate_limit": 60,
+///   "expires_in_secs": 2592000,
+///   "tenant": "acme"
+/// }
+/// ```
+pub async fn handle_create_key(
+    State(state): State<AppState>,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> GatewayResult<(StatusCode, Json<CreateApiKeyResponse>)> {
+    let scopes = request
+        .scopes
+        .unwrap_or_else(|| vec![SCOPE_AGENT.to_string()]);
+    validate_scopes(&scopes)?;
+
+    if request.user_id.trim().is_empty() {
+        return Err(GatewayError::InvalidRequest(
+            "user_id must not be empty".to_string(),
+        ));
+    }
+
+    let expires_at = match request.expires_in_secs {
+        Some(secs) if secs <= 0 => {
+            return Err(GatewayError::InvalidRequest(
+                "expires_in_secs must be positive".to_string(),
+            ));
+        }
+        Some(secs) => Some(Utc::now() + chrono::Duration::seconds(secs)),
+        None => None,
+    };
+
+    if let Some(tenant) = &request.tenant
+        && !state.config().tenants.tenants.contains_key(tenant)
+    {
+        return Err(GatewayError::InvalidRequest(format!(
+            "Unknown tenant '{tenant}'"
+        )));
+    }
+
+    let (api_key, info) = state
+        .api_keys
+        .create_key(
+            request.user_id,
+            scopes,
+            request.rate_limit.unwrap_or(DEFAULT_RATE_LIMIT),
+            expires_at,
+            request.tenant,
+        )
+        .await
+        .map_err(|e| GatewayError::Internal(format!("Failed to persist API key: {e}")))?;
+
+    info!(
+        "API key created: key_id={}, user_id={}, scopes={:?}, tenant={:?}",
+        info.key_id, info.user_id, info.scopes, info.tenant
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateApiKeyResponse { api_key, info }),
+    ))
+}
+
+/// GET /admin/keys - List key metadata (never the keys themselves)
+pub async fn handle_list_keys(
+    State(state): State<AppState>,
+) -> GatewayResult<(StatusCode, Json<Value>)> {
+    let keys = state.api_keys.list_keys().await;
+    Ok((StatusCode::OK, Json(json!({ "keys": keys }))))
+}
+
+/// DELETE /admin/keys/{key_id} - Revoke a key
+pub async fn handle_revoke_key(
+    State(state): State<AppState>,
+    Path(key_id): Path<String>,
+) -> GatewayResult<(StatusCode, Json<Value>)> {
+    let revoked = state
+        .api_keys
+        .revoke_key(&key_id)
+        .await
+        .map_err(|e| GatewayError::Internal(format!("Failed to persist API key: {e}")))?;
+
+    if !revoked {
+        return Err(GatewayError::NotFound(format!("API key {key_id}")));
+    }
+
+    info!("API key revoked: key_id={}", key_id);
+    Ok((
+        StatusCode::OK,
+        Json(json!({ "key_id": key_id, "revoked": true })),
+    ))
+}
+
+/// Request body for `POST /admin/oauth/clients`
+#[derive(Debug, Deserialize)]
+pub struct CreateOAuthClientRequest {
+    /// Display name
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Allowed redirect URIs (exact match)
+    pub redirect_uris: Vec<String>,
+
+    /// Issue a client secret (defaults to true); public clients must use PKCE
+    #[serde(default = "default_confidential")]
+    pub confidential: bool,
+}
+
+fn default_confidential() -> bool {
+    true
+}
+
+/// Response for `POST /admin/oauth/clients`
+///
+/// `client_secret` is the only time the plaintext secret is revealed.
+#[derive(Debug, Serialize)]
+pub struct CreateOAuthClientResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    #[serde(flatten)]
+    pub client: OAuthClient,
+}
+
+/// POST /admin/oauth/clients - Register an OAuth client
+///
+/// ## Example Request
+///
+/// ```json
+/// {
+///   "name": "ChatGPT Action",
+///   "redirect_uris": ["https://chat.openai.com/aip/g-123/oauth/callback"]
+/// }
+/// ```
+pub async fn handle_create_oauth_client(
+    State(state): State<AppState>,
+    Json(request): Json<CreateOAuthClientRequest>,
+) -> GatewayResult<(StatusCode, Json<CreateOAuthClientResponse>)> {
+    if request.redirect_uris.is_empty() {
+        return Err(GatewayError::InvalidRequest(
+            "redirect_uris must not be empty".to_string(),
+        ));
+    }
+    if let Some(invalid) = request
+        .redirect_uris
+        .iter()
+        .find(|uri| url::Url::parse(uri).is_err())
+    {
+        return Err(GatewayError::InvalidRequest(format!(
+            "Invalid redirect URI '{invalid}'"
+        )));
+    }
+
+    let (client, client_secret) = state
+        .oauth
+        .register_client(request.name, request.redirect_uris, request.confidential)
+        .await
+        .map_err(|e| GatewayError::Internal(format!("Failed to persist OAuth client: {e}")))?;
+
+    info!(
+        "OAuth client registered: client_id={}, public={}",
+        client.client_id,
+        client.is_public()
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateOAuthClientResponse {
+            client_secret,
+            client,
+        }),
+    ))
+}
+
+/// GET /admin/oauth/clients - List registered OAuth clients
+pub async fn handle_list_oauth_clients(
+    State(state): State<AppState>,
+) -> GatewayResult<(StatusCode, Json<Value>)> {
+    let clients = state.oauth.list_clients().await;
+    Ok((StatusCode::OK, Json(json!({ "clients": clients }))))
+}
+
+/// DELETE /admin/oauth/clients/{client_id} - Remove a client and its tokens
+pub async fn handle_remove_oauth_client(
+    State(state): State<AppState>,
+    Path(client_id): Path<String>,
+) -> GatewayResult<(StatusCode, Json<Value>)> {
+    let removed = state
+        .oauth
+        .remove_client(&client_id)
+        .await
+        .map_err(|e| GatewayError::Internal(format!("Failed to persist OAuth client: {e}")))?;
+
+    if !removed {
+        return Err(GatewayError::NotFound(format!("OAuth client {client_id}")));
+    }
+
+    info!("OAuth client removed: client_id={}", client_id);
+    Ok((
+        StatusCode::OK,
+        Json(json!({ "client_id": client_id, "removed": true })),
+    ))
+}
+
+/// GET /admin/audit - Query the audit log
+///
+/// Every query parameter is optional: `key_id`, `session_id`,
+/// `conversation_id`, `request_id`, `type` (e.g. `exec_command_end`),
+/// `since`/`until` (RFC 3339) and `limit` (default 1000). The newest matching
+/// records are returned, oldest first.
+pub async fn handle_query_audit(
+    State(state): State<AppState>,
+    Query(filter): Query<AuditFilter>,
+) -> GatewayResult<(StatusCode, Json<Value>)> {
+    let audit = state.codex_service.audit();
+    if !audit.is_enabled() {
+        return Err(GatewayError::NotFound("audit log is disabled".to_string()));
+    }
+    let records = audit
+        .query(&filter)
+        .await
+        .map_err(|e| GatewayError::Internal(format!("Failed to read audit log: {e}")))?;
+    Ok((StatusCode::OK, Json(json!({ "records": records }))))
+}
+
+fn validate_scopes(scopes: &[String]) -> GatewayResult<()> {
+    if let Some(unknown) = scopes
+        .iter()
+        .find(|scope| !KNOWN_SCOPES.contains(&scope.as_str()))
+    {
+        return Err(GatewayError::InvalidRequest(format!(
+            "Unknown scope '{unknown}', expected one of {KNOWN_SCOPES:?}"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_scopes() {
+        assert!(validate_scopes(&[]).is_ok());
+        assert!(validate_scopes(&["agent".to_string(), "admin".to_string()]).is_ok());
+        assert!(matches!(
+            validate_scopes(&["root".to_string()]),
+            Err(GatewayError::InvalidRequest(_))
+        ));
+    }
+}
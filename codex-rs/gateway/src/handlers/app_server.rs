@@ -0,0 +1,819 @@
+//! App-server protocol over WebSocket
+//!
+//! `GET /app-server` upgrades to a WebSocket that speaks the same JSON-RPC
+//! protocol as the `codex-app-server` binary on stdio: `initialize`,
+//! `thread/*`, `turn/*`, `item/*` notifications, account methods and the
+//! deprecated v1 methods. Each connection runs its own
+//! [`codex_app_server::serve_connection`]; one text frame carries one
+//! JSON-RPC message in each direction.
+//!
+//! ## Architecture
+//!
+//! ```text
+//! WebSocket text frame
+//!         ↓
+//! JSONRPCMessage ── requests checked against the caller's WorkspacePolicy
+//!         ↓
+//! MessageProcessor (one per connection)
+//!         ↓
+//! responses, notifications and server requests → WebSocket text frames
+//! ```
+//!
+//! ## Policy
+//!
+//! The connection's Codex config defaults to the caller's [`WorkspacePolicy`]
+//! (workspace root, sandbox and approval ceilings). Overrides carried by
+//! requests (`cwd`, `sandbox`, `sandboxPolicy`, `approvalPolicy`, search
+//! roots) are checked before the request reaches the processor, and
+//! free-form `config`/`profile` overrides are refused. `localImage` inputs
+//! make the processor read a host file, so like `/exec` image paths they are
+//! only accepted from loopback clients and must lie in the caller's
+//! workspace. Methods that change
+//! the host's credentials or `config.toml`, read its auth token or saved
+//! config, or upload its logs as feedback need the `admin` scope, and so do
+//! the methods that list, archive or resume recorded threads by id, since
+//! that history is shared by every key. Rejected
+//! requests are answered with a JSON-RPC error without reaching the server.
+//!
+//! ## Shutdown
+//!
+//! The processor's conversations live in a manager the gateway shares, so
+//! they drain like the gateway's own turns: once shutdown starts, `turn/start`
+//! and the v1 message methods are refused, running turns get the grace period
+//! and are then interrupted, every rollout is flushed with `Op::Shutdown`,
+//! and an interrupted turn is followed by a `codex/event/error` notification
+//! starting with "interrupted by shutdown".
+
+use crate::error::GatewayError;
+use crate::error::GatewayResult;
+use crate::handlers::websocket::limit_websocket;
+use crate::listener::PeerAddr;
+use crate::middleware::api_key::ApiKeyInfo;
+use crate::middleware::api_key::SCOPE_ADMIN;
+use crate::middleware::rate_limit::SessionPermit;
+use crate::services::drain::Drain;
+use crate::services::embedded_turns::EmbeddedTurns;
+use crate::services::embedded_turns::app_server_event;
+use crate::services::embedded_turns::app_server_notification;
+use crate::services::workspace::WorkspacePolicy;
+use crate::state::AppState;
+use axum::Extension;
+use axum::extract::ConnectInfo;
+use axum::extract::State;
+use axum::extract::WebSocketUpgrade;
+use axum::extract::ws::Message;
+use axum::extract::ws::WebSocket;
+use axum::response::Response;
+use codex_app_server_protocol::ClientRequest;
+use codex_app_server_protocol::InputItem;
+use codex_app_server_protocol::JSONRPCError;
+use codex_app_server_protocol::JSONRPCErrorError;
+use codex_app_server_protocol::JSONRPCMessage;
+use codex_app_server_protocol::JSONRPCRequest;
+use codex_app_server_protocol::NewConversationParams;
+use codex_app_server_protocol::SandboxMode as V2SandboxMode;
+use codex_app_server_protocol::UserInput as V2UserInput;
+use codex_core::AuthManager;
+use codex_core::ConversationManager;
+use codex_core::config::Config;
+use codex_feedback::CodexFeedback;
+use codex_protocol::ConversationId;
+use codex_protocol::config_types::SandboxMode;
+use codex_protocol::protocol::AskForApproval;
+use codex_protocol::protocol::SessionSource;
+use futures::SinkExt;
+use futures::StreamExt;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::debug;
+use tracing::info;
+use tracing::warn;
+
+/// Buffered client messages per connection before reads pause
+const INCOMING_CAPACITY: usize = 128;
+
+/// JSON-RPC error code for requests the gateway refuses
+const INVALID_REQUEST_ERROR_CODE: i64 = -32600;
+
+/// GET /app-server - Upgrade to an app-server protocol session
+pub async fn handle_app_server_upgrade(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
+    session_permit: Option<Extension<SessionPermit>>,
+    peer: Option<Extension<ConnectInfo<PeerAddr>>>,
+) -> GatewayResult<Response> {
+    info!("App-server WebSocket upgrade requested");
+    let local = peer.is_some_and(|Extension(ConnectInfo(peer))| peer.is_loopback());
+    let codex_config = state.codex_service.codex_config();
+    let policy = WorkspacePolicy::for_caller(state.config(), codex_config, caller.as_deref())?;
+    let defaults = policy.resolve(None, None, None)?;
+
+    let mut config = (**codex_config).clone();
+    config.cwd = defaults.cwd;
+    config.sandbox_policy = defaults.sandbox_policy;
+    config.approval_policy = defaults.approval_policy;
+
+    let guard = RequestGuard {
+        policy,
+        drain: state.codex_service.drain().clone(),
+        // Without authentication there is nobody to hold back
+        may_manage_host: caller
+            .as_deref()
+            .is_none_or(|key| key.has_scope(SCOPE_ADMIN)),
+        local,
+    };
+
+    let auth_manager = AuthManager::shared(
+        config.codex_home.clone(),
+        false,
+        config.cli_auth_credentials_store_mode,
+    );
+    let conversations = Arc::new(ConversationManager::new(
+        Arc::clone(&auth_manager),
+        SessionSource::VSCode,
+    ));
+    let turns = EmbeddedTurns::new(
+        state.codex_service.audit().clone(),
+        state.metrics.clone(),
+        state.codex_service.drain().clone(),
+        Arc::clone(&conversations),
+        caller.map(|Extension(key)| key.key_id),
+    );
+    let processor = Processor {
+        config: Arc::new(config),
+        auth_manager,
+        conversations,
+    };
+
+    let (ws, connection) = limit_websocket(&state, ws)?;
+    Ok(ws.on_upgrade(move |socket| async move {
+        let _session_permit = session_permit;
+        let _connection = connection;
+        handle_app_server_connection(socket, processor, guard, turns).await;
+    }))
+}
+
+/// What the connection's app-server processor runs with
+struct Processor {
+    config: Arc<Config>,
+    auth_manager: Arc<AuthManager>,
+    /// Shared with [`EmbeddedTurns`] so shutdown reaches the conversations
+    conversations: Arc<ConversationManager>,
+}
+
+async fn handle_app_server_connection(
+    socket: WebSocket,
+    processor: Processor,
+    guard: RequestGuard,
+    mut turns: EmbeddedTurns,
+) {
+    info!("App-server WebSocket connection established");
+
+    let (mut sender, mut receiver) = socket.split();
+    let (incoming_tx, incoming_rx) = mpsc::channel::<JSONRPCMessage>(INCOMING_CAPACITY);
+    let (outgoing_tx, mut outgoing_rx) = mpsc::unbounded_channel::<serde_json::Value>();
+
+    let codex_linux_sandbox_exe = processor.config.codex_linux_sandbox_exe.clone();
+    tokio::spawn(codex_app_server::serve_connection_with_manager(
+        incoming_rx,
+        outgoing_tx.clone(),
+        codex_linux_sandbox_exe,
+        processor.config,
+        CodexFeedback::new(),
+        processor.auth_manager,
+        processor.conversations,
+    ));
+
+    loop {
+        tokio::select! {
+            outgoing = outgoing_rx.recv() => {
+                let Some(value) = outgoing else {
+                    break;
+                };
+                let final_error = match app_server_event(&value) {
+                    Some((conversation_id, event)) => turns
+                        .observe(conversation_id, &event)
+                        .await
+                        .and_then(|error| app_server_notification(conversation_id, &error)),
+                    None => None,
+                };
+                if sender.send(Message::Text(value.to_string().into())).await.is_err() {
+                    warn!("Failed to send app-server message, closing connection");
+                    break;
+                }
+                if let Some(error) = final_error {
+                    let _ = sender.send(Message::Text(error.to_string().into())).await;
+                }
+            }
+            incoming = receiver.next() => {
+                let text = match incoming {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | None => {
+                        info!("App-server WebSocket closed by client");
+                        break;
+                    }
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => {
+                        warn!("App-server WebSocket error: {e}");
+                        break;
+                    }
+                };
+
+                let message = match serde_json::from_str::<JSONRPCMessage>(text.as_str()) {
+                    Ok(message) => message,
+                    Err(e) => {
+                        warn!("Failed to deserialize JSONRPCMessage: {e}");
+                        continue;
+                    }
+                };
+                let message = match message {
+                    JSONRPCMessage::Request(request) => {
+                        let id = request.id.clone();
+                        match guard.authorize(request) {
+                            Ok(request) => {
+                                if let Some((conversation_id, prompt)) = submitted_prompt(&request) {
+                                    turns.submitted(conversation_id, prompt);
+                                }
+                                JSONRPCMessage::Request(request)
+                            }
+                            Err(err) => {
+                                debug!("Refusing app-server request: {err}");
+                                let refusal = JSONRPCMessage::Error(JSONRPCError {
+                                    id,
+                                    error: JSONRPCErrorError {
+                                        code: INVALID_REQUEST_ERROR_CODE,
+                                        message: err.to_string(),
+                                        data: None,
+                                    },
+                                });
+                                if let Ok(value) = serde_json::to_value(refusal) {
+                                    let _ = outgoing_tx.send(value);
+                                }
+                                continue;
+                            }
+                        }
+                    }
+                    other => other,
+                };
+                if incoming_tx.send(message).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Dropping `incoming_tx` ends the processor and its conversations
+    info!("App-server WebSocket connection closed");
+}
+
+/// Conversation and prompt of a request that starts a turn
+fn submitted_prompt(request: &JSONRPCRequest) -> Option<(ConversationId, String)> {
+    let value = serde_json::to_value(request).ok()?;
+    let (conversation_id, texts) = match serde_json::from_value::<ClientRequest>(value).ok()? {
+        ClientRequest::TurnStart { params, .. } => {
+            let texts = params
+                .input
+                .into_iter()
+                .filter_map(|item| match item {
+                    V2UserInput::Text { text } => Some(text),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            (ConversationId::from_string(&params.thread_id).ok()?, texts)
+        }
+        ClientRequest::SendUserMessage { params, .. } => {
+            (params.conversation_id, input_texts(params.items))
+        }
+        ClientRequest::SendUserTurn { params, .. } => {
+            (params.conversation_id, input_texts(params.items))
+        }
+        _ => return None,
+    };
+    Some((conversation_id, texts.join("\n")))
+}
+
+fn input_texts(items: Vec<InputItem>) -> Vec<String> {
+    items
+        .into_iter()
+        .filter_map(|item| match item {
+            InputItem::Text { text } => Some(text),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Checks client requests against the connecting caller's limits
+struct RequestGuard {
+    policy: WorkspacePolicy,
+    /// Turns are refused once the gateway drains, even on open connections
+    drain: Drain,
+    /// Whether the caller may log the host in or out and edit its config
+    may_manage_host: bool,
+    /// Whether the client connected over loopback; only those may attach
+    /// images by host path
+    local: bool,
+}
+
+impl RequestGuard {
+    /// Validate `request`, returning it with paths resolved inside the workspace
+    ///
+    /// Requests the gateway cannot parse are passed through untouched so the
+    /// processor answers them with its usual error.
+    fn authorize(&self, request: JSONRPCRequest) -> GatewayResult<JSONRPCRequest> {
+        let parsed =
+            serde_json::to_value(&request).and_then(serde_json::from_value::<ClientRequest>);
+        let Ok(mut client_request) = parsed else {
+            return Ok(request);
+        };
+
+        match &mut client_request {
+            ClientRequest::ThreadStart { params, .. } => {
+                self.check_overrides(
+                    &mut params.cwd,
+                    params.sandbox.map(V2SandboxMode::to_core),
+                    params.approval_policy.map(|policy| policy.to_core()),
+                    params.config.as_ref(),
+                    None,
+                )?;
+            }
+            ClientRequest::ThreadResume { params, .. } => {
+                if let Some(path) = &params.path {
+                    params.path = Some(self.policy.check_path(path, false)?);
+                } else if params.history.is_none() {
+                    self.check_shared_history(&request.method)?;
+                }
+                self.check_overrides(
+                    &mut params.cwd,
+                    params.sandbox.map(V2SandboxMode::to_core),
+                    params.approval_policy.map(|policy| policy.to_core()),
+                    params.config.as_ref(),
+                    None,
+                )?;
+            }
+            ClientRequest::TurnStart { params, .. } => {
+                self.drain.check()?;
+                for item in &mut params.input {
+                    if let V2UserInput::LocalImage { path } = item {
+                        *path = self.check_local_image(path)?;
+                    }
+                }
+                if let Some(cwd) = &params.cwd {
+                    params.cwd = Some(self.policy.check_path(cwd, true)?);
+                }
+                if let Some(sandbox_policy) = &params.sandbox_policy {
+                    self.policy
+                        .check_sandbox_policy(&sandbox_policy.to_core())?;
+                }
+                self.policy.resolve(
+                    None,
+                    None,
+                    params.approval_policy.map(|policy| policy.to_core()),
+                )?;
+            }
+            ClientRequest::NewConversation { params, .. } => {
+                self.check_conversation_params(params)?;
+            }
+            ClientRequest::ResumeConversation { params, .. } => {
+                if let Some(path) = &params.path {
+                    params.path = Some(self.policy.check_path(path, false)?);
+                } else if params.conversation_id.is_some() {
+                    self.check_shared_history(&request.method)?;
+                }
+                if let Some(overrides) = &mut params.overrides {
+                    self.check_conversation_params(overrides)?;
+                }
+            }
+            ClientRequest::SendUserMessage { params, .. } => {
+                self.drain.check()?;
+                self.check_input_items(&mut params.items)?;
+            }
+            ClientRequest::SendUserTurn { params, .. } => {
+                self.drain.check()?;
+                self.check_input_items(&mut params.items)?;
+                params.cwd = self.policy.check_path(&params.cwd, true)?;
+                self.policy.check_sandbox_policy(&params.sandbox_policy)?;
+                self.policy
+                    .resolve(None, None, Some(params.approval_policy))?;
+            }
+            ClientRequest::ExecOneOffCommand { params, .. } => {
+                if let Some(cwd) = &params.cwd {
+                    params.cwd = Some(self.policy.check_path(cwd, true)?);
+                }
+                if let Some(sandbox_policy) = &params.sandbox_policy {
+                    self.policy.check_sandbox_policy(sandbox_policy)?;
+                }
+            }
+            ClientRequest::GitDiffToRemote { params, .. } => {
+                params.cwd = self.policy.check_path(&params.cwd, true)?;
+            }
+            ClientRequest::FuzzyFileSearch { params, .. } => {
+                for root in &mut params.roots {
+                    let resolved = self.policy.check_path(Path::new(root.as_str()), true)?;
+                    *root = resolved.to_string_lossy().into_owned();
+                }
+            }
+            ClientRequest::LoginAccount { .. }
+            | ClientRequest::CancelLoginAccount { .. }
+            | ClientRequest::LogoutAccount { .. }
+            | ClientRequest::LoginApiKey { .. }
+            | ClientRequest::LoginChatGpt { .. }
+            | ClientRequest::CancelLoginChatGpt { .. }
+            | ClientRequest::LogoutChatGpt { .. }
+            | ClientRequest::SetDefaultModel { .. } => {
+                self.check_host_access(&request.method, "changes the gateway host")?;
+            }
+            ClientRequest::GetAuthStatus { params, .. } => {
+                // The bare status is what `account/read` returns anyway
+                if params.include_token == Some(true) || params.refresh_token == Some(true) {
+                    self.check_host_access(&request.method, "reads the host's credentials")?;
+                }
+            }
+            ClientRequest::GetUserSavedConfig { .. } => {
+                self.check_host_access(&request.method, "reads the host's config.toml")?;
+            }
+            ClientRequest::FeedbackUpload { .. } => {
+                self.check_host_access(&request.method, "uploads the host's logs")?;
+            }
+            ClientRequest::ThreadList { .. }
+            | ClientRequest::ThreadArchive { .. }
+            | ClientRequest::ListConversations { .. }
+            | ClientRequest::ArchiveConversation { .. }
+            | ClientRequest::GetConversationSummary { .. } => {
+                self.check_shared_history(&request.method)?;
+            }
+            _ => {}
+        }
+
+        let value = serde_json::to_value(&client_request)
+            .map_err(|e| GatewayError::Internal(format!("Failed to encode request: {e}")))?;
+        serde_json::from_value(value)
+            .map_err(|e| GatewayError::Internal(format!("Failed to encode request: {e}")))
+    }
+
+    fn check_input_items(&self, items: &mut [InputItem]) -> GatewayResult<()> {
+        for item in items {
+            if let InputItem::LocalImage { path } = item {
+                *path = self.check_local_image(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The processor reads image paths on the host, so like `/exec` images
+    /// they are only taken from loopback clients, and only inside the
+    /// caller's workspace
+    fn check_local_image(&self, path: &Path) -> GatewayResult<PathBuf> {
+        if !self.local {
+            return Err(GatewayError::Forbidden(format!(
+                "image path {} refused: host paths are only accepted from loopback clients; \
+                 send the image as a data URL instead",
+                path.display()
+            )));
+        }
+        self.policy.check_path(path, false)
+    }
+
+    /// Refuse `method`, which `action` on the host, unless the caller
+    /// manages the host
+    fn check_host_access(&self, method: &str, action: &str) -> GatewayResult<()> {
+        if self.may_manage_host {
+            return Ok(());
+        }
+        Err(GatewayError::Forbidden(format!(
+            "{method} {action} and requires the '{SCOPE_ADMIN}' scope"
+        )))
+    }
+
+    /// Thread history lives in the gateway's shared `CODEX_HOME` and is not
+    /// scoped to keys, so only host managers may list or reopen it by id
+    fn check_shared_history(&self, method: &str) -> GatewayResult<()> {
+        if self.may_manage_host {
+            return Ok(());
+        }
+        Err(GatewayError::Forbidden(format!(
+            "{method} reads the gateway's shared thread history and requires the '{SCOPE_ADMIN}' scope"
+        )))
+    }
+
+    fn check_conversation_params(&self, params: &mut NewConversationParams) -> GatewayResult<()> {
+        self.check_overrides(
+            &mut params.cwd,
+            params.sandbox,
+            params.approval_policy,
+            params.config.as_ref(),
+            params.profile.as_deref(),
+        )
+    }
+
+    /// Shared checks for the thread and conversation start parameters
+    fn check_overrides(
+        &self,
+        cwd: &mut Option<String>,
+        sandbox: Option<SandboxMode>,
+        approval_policy: Option<AskForApproval>,
+        config: Option<&HashMap<String, serde_json::Value>>,
+        profile: Option<&str>,
+    ) -> GatewayResult<()> {
+        if config.is_some_and(|config| !config.is_empty()) {
+            return Err(GatewayError::Forbidden(
+                "config overrides are not accepted by the gateway".to_string(),
+            ));
+        }
+        if profile.is_some() {
+            return Err(GatewayError::Forbidden(
+                "config profiles are not accepted by the gateway".to_string(),
+            ));
+        }
+
+        let sandbox = sandbox.map(|mode| mode.to_string());
+        let settings = self.policy.resolve(
+            cwd.as_deref().map(Path::new),
+            sandbox.as_deref(),
+            approval_policy,
+        )?;
+        if cwd.is_some() {
+            *cwd = Some(settings.cwd.to_string_lossy().into_owned());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GatewayConfig;
+    use crate::config::TenantPolicy;
+    use codex_core::config::ConfigOverrides;
+    use codex_core::config::ConfigToml;
+    use serde_json::json;
+
+    fn tenant_guard(root: &Path, may_manage_host: bool) -> RequestGuard {
+        key_guard(root, "key_001", "acme", may_manage_host)
+    }
+
+    fn key_guard(root: &Path, key_id: &str, tenant: &str, may_manage_host: bool) -> RequestGuard {
+        let mut config = GatewayConfig::default();
+        config.tenants.tenants.insert(
+            tenant.to_string(),
+            serde_json::from_value::<TenantPolicy>(json!({ "workspace_root": root })).unwrap(),
+        );
+        let caller: ApiKeyInfo = serde_json::from_value(json!({
+            "key_id": key_id,
+            "user_id": "user",
+            "rate_limit": 60,
+            "active": true,
+            "tenant": tenant,
+        }))
+        .unwrap();
+        let codex_home = tempfile::tempdir().unwrap();
+        let codex_config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )
+        .unwrap();
+        RequestGuard {
+            policy: WorkspacePolicy::for_caller(&config, &codex_config, Some(&caller)).unwrap(),
+            drain: Drain::new(),
+            may_manage_host,
+            local: false,
+        }
+    }
+
+    fn request(method: &str, params: serde_json::Value) -> JSONRPCRequest {
+        serde_json::from_value(json!({"id": 1, "method": method, "params": params})).unwrap()
+    }
+
+    #[test]
+    fn test_thread_start_cwd_is_confined() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("acme/repo")).unwrap();
+        let guard = tenant_guard(&dir.path().join("acme"), false);
+
+        let authorized = guard
+            .authorize(request("thread/start", json!({"cwd": "repo"})))
+            .unwrap();
+        let cwd = authorized.params.unwrap()["cwd"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(cwd.ends_with("repo"));
+        assert!(Path::new(&cwd).is_absolute());
+
+        assert!(
+            guard
+                .authorize(request("thread/start", json!({"cwd": "../"})))
+                .is_err()
+        );
+        assert!(
+            guard
+                .authorize(request(
+                    "thread/start",
+                    json!({"sandbox": "dangerFullAccess"})
+                ))
+                .is_err()
+        );
+        assert!(
+            guard
+                .authorize(request(
+                    "thread/start",
+                    json!({"config": {"sandbox_mode": "danger-full-access"}})
+                ))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_account_changes_need_admin() {
+        let dir = tempfile::tempdir().unwrap();
+        let guard = tenant_guard(dir.path(), false);
+        assert!(
+            guard
+                .authorize(request("account/logout", json!(null)))
+                .is_err()
+        );
+        assert!(guard.authorize(request("account/read", json!({}))).is_ok());
+
+        let admin = tenant_guard(dir.path(), true);
+        assert!(
+            admin
+                .authorize(request("account/logout", json!(null)))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_host_images_need_loopback_and_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("acme");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("shot.png"), "").unwrap();
+        std::fs::write(dir.path().join("secret.png"), "").unwrap();
+        let thread_id = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+        let turn_start = |path: &str| {
+            request(
+                "turn/start",
+                json!({"threadId": thread_id, "input": [{"type": "localImage", "path": path}]}),
+            )
+        };
+        let send_message = |path: &str| {
+            request(
+                "sendUserMessage",
+                json!({
+                    "conversationId": thread_id,
+                    "items": [{"type": "localImage", "data": {"path": path}}],
+                }),
+            )
+        };
+
+        let remote_peer = PeerAddr("203.0.113.7:51000".parse().unwrap());
+        let mut guard = tenant_guard(&root, false);
+        guard.local = remote_peer.is_loopback();
+        for request in [turn_start("shot.png"), send_message("shot.png")] {
+            let err = guard.authorize(request).unwrap_err();
+            assert!(matches!(err, GatewayError::Forbidden(_)), "{err}");
+        }
+
+        let local_peer = PeerAddr("127.0.0.1:51000".parse().unwrap());
+        guard.local = local_peer.is_loopback();
+        let authorized = guard.authorize(turn_start("shot.png")).unwrap();
+        let path = authorized.params.unwrap()["input"][0]["path"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(Path::new(&path).is_absolute());
+        assert!(path.ends_with("shot.png"));
+        assert!(guard.authorize(send_message("shot.png")).is_ok());
+
+        let outside = dir.path().join("secret.png");
+        let outside = outside.to_str().unwrap();
+        assert!(guard.authorize(turn_start(outside)).is_err());
+        assert!(guard.authorize(send_message(outside)).is_err());
+    }
+
+    #[test]
+    fn test_auth_token_needs_admin() {
+        let dir = tempfile::tempdir().unwrap();
+        let guard = tenant_guard(dir.path(), false);
+        let with_token = || request("getAuthStatus", json!({"includeToken": true}));
+        let err = guard.authorize(with_token()).unwrap_err();
+        assert!(matches!(err, GatewayError::Forbidden(_)), "{err}");
+        assert!(
+            guard
+                .authorize(request("getAuthStatus", json!({"refreshToken": true})))
+                .is_err()
+        );
+        assert!(
+            guard
+                .authorize(request("getAuthStatus", json!({"includeToken": false})))
+                .is_ok()
+        );
+
+        let admin = tenant_guard(dir.path(), true);
+        assert!(admin.authorize(with_token()).is_ok());
+    }
+
+    #[test]
+    fn test_saved_config_needs_admin() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = tenant_guard(dir.path(), false)
+            .authorize(request("getUserSavedConfig", json!(null)))
+            .unwrap_err();
+        assert!(matches!(err, GatewayError::Forbidden(_)), "{err}");
+        assert!(
+            tenant_guard(dir.path(), true)
+                .authorize(request("getUserSavedConfig", json!(null)))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_feedback_upload_needs_admin() {
+        let dir = tempfile::tempdir().unwrap();
+        let feedback = || {
+            request(
+                "feedback/upload",
+                json!({"classification": "bug", "includeLogs": true}),
+            )
+        };
+        let err = tenant_guard(dir.path(), false)
+            .authorize(feedback())
+            .unwrap_err();
+        assert!(matches!(err, GatewayError::Forbidden(_)), "{err}");
+        assert!(tenant_guard(dir.path(), true).authorize(feedback()).is_ok());
+    }
+
+    #[test]
+    fn test_shared_thread_history_needs_admin() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("acme")).unwrap();
+        std::fs::create_dir_all(dir.path().join("globex")).unwrap();
+        let acme = key_guard(&dir.path().join("acme"), "key_001", "acme", false);
+        let globex = key_guard(&dir.path().join("globex"), "key_002", "globex", false);
+
+        // A thread id key_001 learnt about must not be reachable from key_002,
+        // and neither key may enumerate the shared history
+        let thread_id = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+        let history_requests = [
+            request("thread/list", json!({})),
+            request("thread/resume", json!({"threadId": thread_id})),
+            request("thread/archive", json!({"threadId": thread_id})),
+            request("listConversations", json!({})),
+            request("resumeConversation", json!({"conversationId": thread_id})),
+            request(
+                "archiveConversation",
+                json!({"conversationId": thread_id, "rolloutPath": "/tmp/rollout.jsonl"}),
+            ),
+            request(
+                "getConversationSummary",
+                json!({"conversationId": thread_id}),
+            ),
+        ];
+        for guard in [&acme, &globex] {
+            for history_request in &history_requests {
+                let err = guard.authorize(history_request.clone()).unwrap_err();
+                assert!(matches!(err, GatewayError::Forbidden(_)), "{err}");
+            }
+        }
+
+        let admin = tenant_guard(&dir.path().join("acme"), true);
+        for history_request in history_requests {
+            assert!(admin.authorize(history_request).is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_turns_are_refused_while_draining() {
+        let dir = tempfile::tempdir().unwrap();
+        let guard = tenant_guard(dir.path(), false);
+        let thread_id = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+        let turn_start = || {
+            request(
+                "turn/start",
+                json!({"threadId": thread_id, "input": [{"type": "text", "text": "hi"}]}),
+            )
+        };
+        let send_message = || {
+            request(
+                "sendUserMessage",
+                json!({"conversationId": thread_id, "items": [{"type": "text", "data": {"text": "hi"}}]}),
+            )
+        };
+        assert!(guard.authorize(turn_start()).is_ok());
+
+        guard.drain.run(std::time::Duration::ZERO).await;
+        assert!(matches!(
+            guard.authorize(turn_start()),
+            Err(GatewayError::ServiceUnavailable(_))
+        ));
+        assert!(matches!(
+            guard.authorize(send_message()),
+            Err(GatewayError::ServiceUnavailable(_))
+        ));
+        assert!(guard.authorize(request("account/read", json!({}))).is_ok());
+    }
+}
@@ -30,22 +30,39 @@
 //! - **Image Support**: Handles base64 data URIs
 //! - **Output Schema**: Supports JSON schema validation
 //! - **Resumable**: Can resume conversations via session_id
+//! - **Tenant Isolation**: `cwd`, sandbox and approval overrides are checked
+//!   against the caller's [`WorkspacePolicy`]
 
 use crate::error::GatewayError;
 use crate::error::GatewayResult;
+use crate::handlers::upload::ExecPayload;
+use crate::handlers::upload::Uploads;
+use crate::middleware::api_key::ApiKeyInfo;
+use crate::services::audit::AuditContext;
+use crate::services::drain::interrupted_message;
+use crate::services::workspace::TurnSettings;
+use crate::services::workspace::WorkspacePolicy;
 use crate::state::AppState;
+use axum::Extension;
 use axum::extract::State;
 use axum::http::StatusCode;
 use axum::response::Json;
 use codex_exec::event_processor_with_jsonl_output::EventProcessorWithJsonOutput;
+use codex_exec::exec_events::ThreadErrorEvent;
 use codex_exec::exec_events::ThreadEvent;
+use codex_exec::exec_events::TurnFailedEvent;
+use codex_protocol::ConversationId;
+use codex_protocol::protocol::AskForApproval;
 use codex_protocol::protocol::EventMsg;
 use codex_protocol::protocol::Op;
 use codex_protocol::user_input::UserInput;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::Value;
+use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 use tokio::sync::mpsc;
 use tracing::debug;
 use tracing::error;
@@ -55,7 +72,7 @@ use tracing::warn;
 /// Request structure for exec endpoint
 ///
 /// Accepts a prompt and optional parameters for customizing the execution.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct ExecRequest {
     /// User prompt to execute
     pub prompt: String,
@@ -66,6 +83,7 @@ pub struct ExecRequest {
 
     /// Optional images (base64 data URIs or local paths)
     /// Format: "data:image/png;base64,iVBORw0KGg..." or "/path/to/image.png"
+    /// Local paths are only accepted from loopback clients
     #[serde(default)]
     pub images: Vec<String>,
 
@@ -74,7 +92,7 @@ pub struct ExecRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub output_schema: Option<Value>,
 
-    /// Current working directory override
+    /// Current working directory override, relative to the tenant's workspace root
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cwd: Option<PathBuf>,
 
@@ -83,14 +101,24 @@ pub struct ExecRequest {
     pub model: Option<String>,
 
     /// Sandbox mode override ("read-only", "workspace-write", "danger-full-access")
+    /// Can only tighten the caller's allowed sandbox
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sandbox_mode: Option<String>,
+
+    /// Approval policy override ("untrusted", "on-failure", "on-request", "never")
+    /// Can only tighten the caller's allowed approval policy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_policy: Option<AskForApproval>,
+
+    /// Images and workspace files sent as `multipart/form-data` parts
+    #[serde(skip)]
+    pub uploads: Uploads,
 }
 
 /// Response structure for exec endpoint
 ///
 /// Contains the conversation ID, all events, and final status.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ExecResponse {
     /// Conversation ID for tracking and resuming
     pub conversation_id: String,
@@ -107,7 +135,7 @@ pub struct ExecResponse {
 }
 
 /// Request structure for resume endpoint
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct ResumeRequest {
     /// Conversation ID to resume
     pub conversation_id: String,
@@ -117,7 +145,7 @@ pub struct ResumeRequest {
 }
 
 /// Response structure for resume endpoint
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ResumeResponse {
     /// Conversation ID that was resumed
     pub conversation_id: String,
@@ -151,6 +179,9 @@ pub struct ResumeResponse {
 /// }
 /// ```
 ///
+/// The same fields, plus images and files, can be sent as
+/// `multipart/form-data`; see [`crate::handlers::upload`].
+///
 /// ## Example Response
 ///
 /// ```json
@@ -167,7 +198,8 @@ pub struct ResumeResponse {
 /// ```
 pub async fn handle_exec(
     State(state): State<AppState>,
-    Json(request): Json<ExecRequest>,
+    caller: Option<Extension<ApiKeyInfo>>,
+    ExecPayload(request): ExecPayload,
 ) -> GatewayResult<(StatusCode, Json<ExecResponse>)> {
     info!(
         "Exec request received: prompt_len={}, session_id={:?}",
@@ -175,10 +207,92 @@ pub async fn handle_exec(
         request.session_id
     );
 
+    let settings = resolve_turn_settings(&state, caller.as_deref(), &request)?;
+    let (conversation_id, mut rx) = start_exec_turn(
+        &state,
+        request,
+        caller.as_deref().map(|key| key.key_id.as_str()),
+        settings,
+    )
+    .await?;
+
+    // Collect all events from background task
+    let mut events = Vec::new();
+    while let Some(event) = rx.recv().await {
+        events.push(event);
+    }
+
+    // Determine final status
+    let status = determine_status(&events);
+    let error = if status == "error" {
+        events.iter().find_map(|e| match e {
+            ThreadEvent::Error(err) => Some(err.message.clone()),
+            _ => None,
+        })
+    } else {
+        None
+    };
+
+    let response = ExecResponse {
+        conversation_id: conversation_id.to_string(),
+        events: events.clone(),
+        status: status.to_string(),
+        error,
+    };
+
+    info!(
+        "Exec completed: conversation_id={}, status={}, events={}",
+        conversation_id,
+        status,
+        response.events.len()
+    );
+
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Check an [`ExecRequest`]'s overrides and local images against the caller's policy
+///
+/// Run before the turn is started (or queued) so violations surface as a 403
+/// on the originating request.
+pub(crate) fn resolve_turn_settings(
+    state: &AppState,
+    caller: Option<&ApiKeyInfo>,
+    request: &ExecRequest,
+) -> GatewayResult<TurnSettings> {
+    let policy =
+        WorkspacePolicy::for_caller(state.config(), state.codex_service.codex_config(), caller)?;
+    for image in request
+        .images
+        .iter()
+        .filter(|img| !img.starts_with("data:"))
+    {
+        policy.check_path(Path::new(image), false)?;
+    }
+    policy.resolve(
+        request.cwd.as_deref(),
+        request.sandbox_mode.as_deref(),
+        request.approval_policy,
+    )
+}
+
+/// Start a turn for an [`ExecRequest`] and return the live ThreadEvent feed
+///
+/// Shared by the buffered `POST /exec` handler, the SSE variant in
+/// [`crate::handlers::exec_stream`] and the job queue. `settings` come from
+/// [`resolve_turn_settings`]; `owner` is the submitting key, which a named
+/// session must belong to. The returned receiver is closed once the turn
+/// reaches a terminal event; dropping it earlier interrupts the turn with
+/// `Op::Interrupt` at its next event.
+pub(crate) async fn start_exec_turn(
+    state: &AppState,
+    mut request: ExecRequest,
+    owner: Option<&str>,
+    settings: TurnSettings,
+) -> GatewayResult<(ConversationId, mpsc::UnboundedReceiver<ThreadEvent>)> {
     // 1. Get or create conversation
     let conversation_id = state
         .codex_service
-        .get_or_create_conversation(request.session_id.as_deref())
+        .get_or_create_conversation(request.session_id.as_deref(), owner)
         .await?;
 
     debug!("Using conversation_id: {}", conversation_id);
@@ -191,6 +305,10 @@ pub async fn handle_exec(
             .await
             .map_err(|e| GatewayError::Internal(format!("Failed to get conversation: {e}")))?
     };
+    let turn = state
+        .codex_service
+        .drain()
+        .track(Arc::clone(&conversation))?;
 
     // 3. Prepare UserInputs from request
     let user_inputs = prepare_user_inputs(&request)?;
@@ -198,28 +316,71 @@ pub async fn handle_exec(
 
     // 4. Get config for Op::UserTurn params
     let config = state.codex_service.codex_config();
-    let cwd = request.cwd.unwrap_or_else(|| config.cwd.clone());
+    let cwd = settings.cwd;
+
+    // Uploaded files land in the workspace; uploaded images must outlive the turn
+    request.uploads.write_files(&cwd).await?;
+    let upload_dir = request.uploads.take_image_dir();
     let model = request.model.unwrap_or_else(|| config.model.clone());
 
     // 5. Create channel for event collection
-    let (tx, mut rx) = mpsc::unbounded_channel::<ThreadEvent>();
+    let (tx, rx) = mpsc::unbounded_channel::<ThreadEvent>();
 
     // 6. Spawn background task to process events using REAL EventProcessorWithJsonOutput
     let conversation_clone = conversation.clone();
+    let metrics = state.metrics.clone();
+    let drain = state.codex_service.drain().clone();
+    let session_id = request.session_id.clone();
+    let mut recorder = state
+        .codex_service
+        .artifacts()
+        .start_turn(request.session_id.as_deref(), cwd.clone());
+    let mut auditor = state
+        .codex_service
+        .audit()
+        .start_turn(
+            AuditContext::new(owner, request.session_id.as_deref(), conversation_id),
+            &request.prompt,
+        )
+        .await;
     tokio::spawn(async move {
+        let _turn = turn;
+        let _upload_dir = upload_dir;
         let mut processor = EventProcessorWithJsonOutput::new(None);
+        let mut abandoned = false;
 
         loop {
             match conversation_clone.next_event().await {
                 Ok(event) => {
                     debug!("Processing event: {:?}", event.msg);
+                    let usage = metrics.observe_event(conversation_id, &event.msg);
+                    auditor.observe(&event, usage.as_ref()).await;
+                    if let Some(recorder) = recorder.as_mut() {
+                        recorder.observe(&event.msg);
+                    }
 
                     // Use REAL EventProcessorWithJsonOutput to convert Codex events → ThreadEvents
-                    let thread_events = processor.collect_thread_events(&event);
-                    for te in thread_events {
-                        if tx.send(te).is_err() {
-                            error!("Failed to send event to channel (receiver dropped)");
-                            break;
+                    let mut thread_events = processor.collect_thread_events(&event);
+                    if matches!(event.msg, EventMsg::TurnAborted(_)) && drain.is_draining() {
+                        thread_events.push(ThreadEvent::TurnFailed(TurnFailedEvent {
+                            error: ThreadErrorEvent {
+                                message: interrupted_message(
+                                    conversation_id,
+                                    session_id.as_deref(),
+                                ),
+                            },
+                        }));
+                    }
+                    // Nobody is listening any more: stop the agent instead of
+                    // letting it run to completion, but keep auditing the events
+                    // that lead up to the abort
+                    if !abandoned && thread_events.into_iter().any(|te| tx.send(te).is_err()) {
+                        abandoned = true;
+                        warn!(
+                            "Event receiver dropped, interrupting conversation {conversation_id}"
+                        );
+                        if let Err(e) = conversation_clone.submit(Op::Interrupt).await {
+                            error!("Failed to interrupt abandoned turn: {e}");
                         }
                     }
 
@@ -238,6 +399,10 @@ pub async fn handle_exec(
                 }
             }
         }
+
+        if let Some(recorder) = recorder {
+            recorder.finish().await;
+        }
     });
 
     // 7. Submit Op::UserTurn with all config parameters
@@ -246,8 +411,8 @@ pub async fn handle_exec(
         .submit(Op::UserTurn {
             items: user_inputs,
             cwd,
-            approval_policy: config.approval_policy,
-            sandbox_policy: config.sandbox_policy.clone(),
+            approval_policy: settings.approval_policy,
+            sandbox_policy: settings.sandbox_policy,
             model,
             effort: config.model_reasoning_effort,
             summary: config.model_reasoning_summary,
@@ -256,38 +421,7 @@ pub async fn handle_exec(
         .await
         .map_err(|e| GatewayError::Internal(format!("Failed to submit user turn: {e}")))?;
 
-    // 8. Collect all events from background task
-    let mut events = Vec::new();
-    while let Some(event) = rx.recv().await {
-        events.push(event);
-    }
-
-    // 9. Determine final status
-    let status = determine_status(&events);
-    let error = if status == "error" {
-        events.iter().find_map(|e| match e {
-            ThreadEvent::Error(err) => Some(err.message.clone()),
-            _ => None,
-        })
-    } else {
-        None
-    };
-
-    let response = ExecResponse {
-        conversation_id: conversation_id.to_string(),
-        events: events.clone(),
-        status: status.to_string(),
-        error,
-    };
-
-    info!(
-        "Exec completed: conversation_id={}, status={}, events={}",
-        conversation_id,
-        status,
-        response.events.len()
-    );
-
-    Ok((StatusCode::OK, Json(response)))
+    Ok((conversation_id, rx))
 }
 
 /// POST /exec/resume - Resume a previous conversation
@@ -315,6 +449,7 @@ pub async fn handle_exec(
 /// ```
 pub async fn handle_exec_resume(
     State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
     Json(request): Json<ResumeRequest>,
 ) -> GatewayResult<(StatusCode, Json<ResumeResponse>)> {
     info!(
@@ -325,7 +460,11 @@ pub async fn handle_exec_resume(
     // Resume the conversation
     let conversation_id = state
         .codex_service
-        .resume_conversation(&request.conversation_id, &request.session_id)
+        .resume_conversation(
+            &request.conversation_id,
+            &request.session_id,
+            caller.as_deref().map(|key| key.key_id.as_str()),
+        )
         .await?;
 
     let response = ResumeResponse {
@@ -350,7 +489,7 @@ pub async fn handle_exec_resume(
 /// Converts prompt and images into UserInput enum variants:
 /// - Text prompts → UserInput::Text
 /// - Base64 data URIs → UserInput::Image
-/// - Local paths → UserInput::LocalImage
+/// - Local paths and uploaded images → UserInput::LocalImage
 fn prepare_user_inputs(request: &ExecRequest) -> GatewayResult<Vec<UserInput>> {
     let mut inputs = Vec::new();
 
@@ -373,6 +512,9 @@ fn prepare_user_inputs(request: &ExecRequest) -> GatewayResult<Vec<UserInput>> {
             inputs.push(UserInput::LocalImage { path });
         }
     }
+    for path in &request.uploads.images {
+        inputs.push(UserInput::LocalImage { path: path.clone() });
+    }
 
     // Add text prompt last
     inputs.push(UserInput::Text {
@@ -423,9 +565,11 @@ mod tests {
             cwd: None,
             model: None,
             sandbox_mode: None,
+            approval_policy: None,
+            uploads: Uploads::default(),
         };
 
-        let result = handle_exec(State(state), Json(request)).await;
+        let result = handle_exec(State(state), None, ExecPayload(request)).await;
 
         // Should succeed (or fail gracefully with proper error)
         assert!(result.is_ok() || matches!(result, Err(GatewayError::Internal(_))));
@@ -443,6 +587,8 @@ mod tests {
             cwd: None,
             model: None,
             sandbox_mode: None,
+            approval_policy: None,
+            uploads: Uploads::default(),
         };
 
         let inputs = prepare_user_inputs(&request).unwrap();
@@ -460,6 +606,8 @@ mod tests {
             cwd: None,
             model: None,
             sandbox_mode: None,
+            approval_policy: None,
+            uploads: Uploads::default(),
         };
 
         let inputs = prepare_user_inputs(&request).unwrap();
@@ -0,0 +1,277 @@
+//! Server-Sent Events variant of the exec endpoint
+//!
+//! `POST /exec/stream` (or `POST /exec` with `Accept: text/event-stream`)
+//! runs the same turn as the buffered handler but emits every `ThreadEvent`
+//! as an SSE frame as soon as it is produced, so HTTP-only clients can follow
+//! long agent runs without hitting the request timeout.
+//!
+//! ## Wire format
+//!
+//! ```text
+//! event: turn.started
+//! data: {"type":"turn.started"}
+//!
+//! : heartbeat
+//!
+//! event: item.completed
+//! data: {"type":"item.completed","item":{...}}
+//!
+//! event: turn.completed
+//! data: {"type":"turn.completed","usage":{...}}
+//! ```
+//!
+//! The conversation id is returned in the `X-Codex-Conversation-Id` response
+//! header. The stream always ends with a `turn.completed` or `turn.failed`
+//! frame, even when the underlying event loop stops without one.
+//!
+//! A client that disconnects abandons the turn: its session slot is released
+//! and the turn is interrupted instead of running on with nobody listening.
+
+use crate::error::GatewayResult;
+use crate::handlers::exec::handle_exec;
+use crate::handlers::exec::resolve_turn_settings;
+use crate::handlers::exec::start_exec_turn;
+use crate::handlers::upload::ExecPayload;
+use crate::middleware::api_key::ApiKeyInfo;
+use crate::middleware::rate_limit::SessionPermit;
+use crate::state::AppState;
+use axum::Extension;
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::http::HeaderValue;
+use axum::http::header::ACCEPT;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum::response::sse::Event;
+use axum::response::sse::KeepAlive;
+use axum::response::sse::Sse;
+use codex_exec::exec_events::ThreadErrorEvent;
+use codex_exec::exec_events::ThreadEvent;
+use codex_exec::exec_events::TurnFailedEvent;
+use tokio::sync::mpsc;
+use tracing::info;
+use tracing::warn;
+
+/// Header carrying the conversation id on streaming responses
+pub const CONVERSATION_ID_HEADER: &str = "x-codex-conversation-id";
+
+/// POST /exec - Dispatch to the buffered or streaming handler based on `Accept`
+pub async fn handle_exec_negotiated(
+    State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
+    session_permit: Option<Extension<SessionPermit>>,
+    headers: HeaderMap,
+    payload: ExecPayload,
+) -> GatewayResult<Response> {
+    if accepts_event_stream(&headers) {
+        handle_exec_stream(State(state), caller, session_permit, payload).await
+    } else {
+        handle_exec(State(state), caller, payload)
+            .await
+            .map(IntoResponse::into_response)
+    }
+}
+
+/// POST /exec/stream - Execute prompt and stream ThreadEvents as SSE frames
+///
+/// The caller's session slot, if any, is held until the stream ends.
+pub async fn handle_exec_stream(
+    State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
+    session_permit: Option<Extension<SessionPermit>>,
+    ExecPayload(request): ExecPayload,
+) -> GatewayResult<Response> {
+    info!(
+        "Exec stream request received: prompt_len={}, session_id={:?}",
+        request.prompt.len(),
+        request.session_id
+    );
+
+    let keep_alive_interval = state.config().timeouts.sse_keep_alive_interval;
+    let settings = resolve_turn_settings(&state, caller.as_deref(), &request)?;
+    let (conversation_id, rx) = start_exec_turn(
+        &state,
+        request,
+        caller.as_deref().map(|key| key.key_id.as_str()),
+        settings,
+    )
+    .await?;
+
+    let mut stream_state = StreamState::new(rx);
+    stream_state._session_permit = session_permit.map(|Extension(permit)| permit);
+    let stream = futures::stream::unfold(stream_state, |mut st| async move {
+        let event = st.next_event().await?;
+        Some((to_sse_event(&event), st))
+    });
+
+    let sse = Sse::new(stream).keep_alive(
+        KeepAlive::new()
+            .interval(keep_alive_interval)
+            .text("heartbeat"),
+    );
+
+    let mut response = sse.into_response();
+    if let Ok(value) = HeaderValue::from_str(&conversation_id.to_string()) {
+        response.headers_mut().insert(CONVERSATION_ID_HEADER, value);
+    }
+    Ok(response)
+}
+
+/// Whether the client asked for `text/event-stream`
+fn accepts_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media| {
+            media
+                .split(';')
+                .next()
+                .is_some_and(|m| m.trim().eq_ignore_ascii_case("text/event-stream"))
+        })
+}
+
+/// Tracks the event feed so a terminal frame is always emitted
+struct StreamState {
+    rx: mpsc::UnboundedReceiver<ThreadEvent>,
+    terminal_seen: bool,
+    last_error: Option<String>,
+    done: bool,
+    /// Keeps the per-key session slot busy while the stream is alive
+    _session_permit: Option<SessionPermit>,
+}
+
+impl StreamState {
+    fn new(rx: mpsc::UnboundedReceiver<ThreadEvent>) -> Self {
+        Self {
+            rx,
+            terminal_seen: false,
+            last_error: None,
+            done: false,
+            _session_permit: None,
+        }
+    }
+
+    /// Next event to forward, synthesizing `turn.failed` if the feed closes early
+    async fn next_event(&mut self) -> Option<ThreadEvent> {
+        if self.done {
+            return None;
+        }
+
+        match self.rx.recv().await {
+            Some(event) => {
+                match &event {
+                    ThreadEvent::TurnCompleted(_) | ThreadEvent::TurnFailed(_) => {
+                        self.terminal_seen = true;
+                    }
+                    ThreadEvent::Error(err) => {
+                        self.last_error = Some(err.message.clone());
+                    }
+                    _ => {}
+                }
+                Some(event)
+            }
+            None => {
+                self.done = true;
+                if self.terminal_seen {
+                    return None;
+                }
+                warn!("Exec stream ended without a terminal event, emitting turn.failed");
+                let message = self
+                    .last_error
+                    .take()
+                    .unwrap_or_else(|| "turn ended without a completion event".to_string());
+                Some(ThreadEvent::TurnFailed(TurnFailedEvent {
+                    error: ThreadErrorEvent { message },
+                }))
+            }
+        }
+    }
+}
+
+/// SSE event name for a ThreadEvent (matches its JSON `type` tag)
+fn thread_event_name(event: &ThreadEvent) -> &'static str {
+    match event {
+        ThreadEvent::ThreadStarted(_) => "thread.started",
+        ThreadEvent::TurnStarted(_) => "turn.started",
+        ThreadEvent::TurnCompleted(_) => "turn.completed",
+        ThreadEvent::TurnFailed(_) => "turn.failed",
+        ThreadEvent::ItemStarted(_) => "item.started",
+        ThreadEvent::ItemUpdated(_) => "item.updated",
+        ThreadEvent::ItemCompleted(_) => "item.completed",
+        ThreadEvent::Error(_) => "error",
+    }
+}
+
+fn to_sse_event(event: &ThreadEvent) -> Result<Event, axum::Error> {
+    Event::default()
+        .event(thread_event_name(event))
+        .json_data(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_exec::exec_events::TurnCompletedEvent;
+    use codex_exec::exec_events::TurnStartedEvent;
+
+    #[test]
+    fn test_accepts_event_stream() {
+        let mut headers = HeaderMap::new();
+        assert!(!accepts_event_stream(&headers));
+
+        headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+        assert!(!accepts_event_stream(&headers));
+
+        headers.insert(
+            ACCEPT,
+            HeaderValue::from_static("application/json, text/event-stream;q=0.9"),
+        );
+        assert!(accepts_event_stream(&headers));
+    }
+
+    #[tokio::test]
+    async fn test_stream_state_passes_through_terminal_event() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tx.send(ThreadEvent::TurnStarted(TurnStartedEvent::default()))
+            .unwrap();
+        tx.send(ThreadEvent::TurnCompleted(TurnCompletedEvent {
+            usage: Default::default(),
+        }))
+        .unwrap();
+        drop(tx);
+
+        let mut state = StreamState::new(rx);
+        assert!(matches!(
+            state.next_event().await,
+            Some(ThreadEvent::TurnStarted(_))
+        ));
+        assert!(matches!(
+            state.next_event().await,
+            Some(ThreadEvent::TurnCompleted(_))
+        ));
+        assert!(state.next_event().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_state_synthesizes_turn_failed() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tx.send(ThreadEvent::Error(ThreadErrorEvent {
+            message: "boom".to_string(),
+        }))
+        .unwrap();
+        drop(tx);
+
+        let mut state = StreamState::new(rx);
+        assert!(matches!(
+            state.next_event().await,
+            Some(ThreadEvent::Error(_))
+        ));
+        match state.next_event().await {
+            Some(ThreadEvent::TurnFailed(failed)) => assert_eq!(failed.error.message, "boom"),
+            other => panic!("expected turn.failed, got {other:?}"),
+        }
+        assert!(state.next_event().await.is_none());
+    }
+}
@@ -10,7 +10,9 @@ use serde_json::json;
 
 /// Health check endpoint
 ///
-/// Returns a simple JSON response indicating the service is healthy
+/// Returns a simple JSON response indicating the service is healthy. Once
+/// shutdown has started it answers 503 with `"status": "draining"`, so load
+/// balancers stop routing new work here while in-flight turns finish.
 ///
 /// ## Response
 ///
@@ -20,10 +22,19 @@ use serde_json::json;
 /// }
 /// ```
 pub async fn health_check(
-    State(_state): State<AppState>,
+    State(state): State<AppState>,
 ) -> GatewayResult<(StatusCode, Json<Value>)> {
     tracing::debug!("Health check requested");
 
+    let drain = state.codex_service.drain();
+    if drain.is_draining() {
+        let response = json!({
+            "status": "draining",
+            "in_flight_turns": drain.in_flight()
+        });
+        return Ok((StatusCode::SERVICE_UNAVAILABLE, Json(response)));
+    }
+
     let response = json!({
         "status": "healthy"
     });
@@ -31,6 +42,56 @@ pub async fn health_check(
     Ok((StatusCode::OK, Json(response)))
 }
 
+/// GET /health/live - liveness probe
+///
+/// Answers 200 as long as the process serves requests, draining included;
+/// restart the instance only when this fails.
+pub async fn health_live() -> Json<Value> {
+    Json(json!({ "status": "alive" }))
+}
+
+/// GET /health/ready - readiness probe
+///
+/// Runs the dependency checks of [`Readiness`](crate::services::Readiness)
+/// and answers 200 when none failed, 503 otherwise (and while draining).
+///
+/// ## Response
+///
+/// ```json
+/// {
+///   "status": "ready",
+///   "checks": [
+///     { "name": "config", "status": "ok", "latency_ms": 3, "detail": "..." },
+///     { "name": "mcp_servers", "status": "skipped", "latency_ms": 0 }
+///   ]
+/// }
+/// ```
+pub async fn health_ready(State(state): State<AppState>) -> (StatusCode, Json<Value>) {
+    if state.codex_service.drain().is_draining() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "status": "draining", "checks": [] })),
+        );
+    }
+
+    let report = state
+        .readiness
+        .check(state.codex_service.codex_config())
+        .await;
+    let (status, label) = if report.ready {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "not_ready")
+    };
+    if !report.ready {
+        tracing::warn!("Readiness check failed: {:?}", report.checks);
+    }
+    (
+        status,
+        Json(json!({ "status": label, "checks": report.checks })),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -49,4 +110,50 @@ mod tests {
         assert_eq!(value["status"], "healthy");
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_health_ready_reports_every_check() -> Result<(), Box<dyn std::error::Error>> {
+        let state = AppState::new(GatewayConfig::default()).await?;
+        let (status, json_response) = health_ready(State(state)).await;
+
+        let value = json_response.0;
+        let names: Vec<&str> = value["checks"]
+            .as_array()
+            .ok_or("checks is not an array")?
+            .iter()
+            .filter_map(|check| check["name"].as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "config",
+                "auth",
+                "model_provider",
+                "codex_home",
+                "mcp_servers"
+            ]
+        );
+        let expected = if value["status"] == "ready" {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        assert_eq!(status, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_health_check_draining() -> Result<(), Box<dyn std::error::Error>> {
+        let state = AppState::new(GatewayConfig::default()).await?;
+        state
+            .codex_service
+            .drain()
+            .run(std::time::Duration::ZERO)
+            .await;
+
+        let (status, json_response) = health_check(State(state)).await?;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(json_response.0["status"], "draining");
+        Ok(())
+    }
 }
@@ -0,0 +1,176 @@
+//! Fire-and-forget exec jobs
+//!
+//! `POST /jobs` accepts the same body as `POST /exec` but returns as soon as
+//! the turn is queued. Progress is polled through `GET /jobs/{id}` and
+//! `GET /jobs/{id}/events?after=N`; both are backed by the on-disk
+//! [`crate::services::JobQueue`] and survive a gateway restart. Jobs are only
+//! visible to the key that submitted them.
+
+use crate::error::GatewayError;
+use crate::error::GatewayResult;
+use crate::handlers::exec::resolve_turn_settings;
+use crate::handlers::upload::ExecPayload;
+use crate::middleware::api_key::ApiKeyInfo;
+use crate::services::job_queue::Job;
+use crate::state::AppState;
+use axum::Extension;
+use axum::extract::Path;
+use axum::extract::Query;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::Json;
+use codex_exec::exec_events::ThreadEvent;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::info;
+
+/// Response for `POST /jobs`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CreateJobResponse {
+    pub job_id: String,
+    pub status_url: String,
+    pub events_url: String,
+}
+
+/// Query parameters for `GET /jobs/{id}/events`
+#[derive(Debug, Default, Deserialize)]
+pub struct JobEventsQuery {
+    /// Number of events the caller already has
+    #[serde(default)]
+    pub after: usize,
+}
+
+/// Response for `GET /jobs/{id}/events`
+#[derive(Debug, Serialize)]
+pub struct JobEventsResponse {
+    pub job: Job,
+    pub events: Vec<ThreadEvent>,
+    /// Value to pass as `after` on the next poll
+    pub next: usize,
+}
+
+/// POST /jobs - Queue an exec turn and return its job id
+///
+/// ## Example Response
+///
+/// ```json
+/// {
+///   "job_id": "550e8400-e29b-41d4-a716-446655440000",
+///   "status_url": "/jobs/550e8400-e29b-41d4-a716-446655440000",
+///   "events_url": "/jobs/550e8400-e29b-41d4-a716-446655440000/events"
+/// }
+/// ```
+pub async fn handle_create_job(
+    State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
+    ExecPayload(request): ExecPayload,
+) -> GatewayResult<(StatusCode, Json<CreateJobResponse>)> {
+    info!(
+        "Job request received: prompt_len={}, session_id={:?}",
+        request.prompt.len(),
+        request.session_id
+    );
+
+    let settings = resolve_turn_settings(&state, caller.as_deref(), &request)?;
+    let job_id = state
+        .jobs
+        .submit(
+            state.clone(),
+            "api".to_string(),
+            caller.as_deref().map(|key| key.key_id.clone()),
+            request,
+            settings,
+        )
+        .await
+        .map_err(|e| GatewayError::Internal(format!("Failed to queue job: {e}")))?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(CreateJobResponse {
+            status_url: format!("/jobs/{job_id}"),
+            events_url: format!("/jobs/{job_id}/events"),
+            job_id,
+        }),
+    ))
+}
+
+/// GET /jobs/{id} - Job status and final result
+pub async fn handle_get_job(
+    State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
+    Path(job_id): Path<String>,
+) -> GatewayResult<(StatusCode, Json<Job>)> {
+    match state.jobs.get(&job_id, owner(caller.as_deref())).await {
+        Some(job) => Ok((StatusCode::OK, Json(job))),
+        None => Err(GatewayError::NotFound(format!("Job {job_id}"))),
+    }
+}
+
+/// GET /jobs/{id}/events?after=N - ThreadEvents recorded after the first N
+pub async fn handle_job_events(
+    State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
+    Path(job_id): Path<String>,
+    Query(query): Query<JobEventsQuery>,
+) -> GatewayResult<(StatusCode, Json<JobEventsResponse>)> {
+    let owner = owner(caller.as_deref());
+    // Read the status first so a finished job never reports missing events
+    let job = state
+        .jobs
+        .get(&job_id, owner)
+        .await
+        .ok_or_else(|| GatewayError::NotFound(format!("Job {job_id}")))?;
+    let events = state.jobs.events(&job_id, owner, query.after).await?;
+    let next = query.after + events.len();
+
+    Ok((
+        StatusCode::OK,
+        Json(JobEventsResponse { job, events, next }),
+    ))
+}
+
+/// DELETE /jobs/{id} - Cancel a queued or running job
+pub async fn handle_cancel_job(
+    State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
+    Path(job_id): Path<String>,
+) -> GatewayResult<(StatusCode, Json<Job>)> {
+    let job = state
+        .jobs
+        .cancel(&state, &job_id, owner(caller.as_deref()))
+        .await?;
+    Ok((StatusCode::OK, Json(job)))
+}
+
+/// Key a job must belong to for `caller` to see it
+fn owner(caller: Option<&ApiKeyInfo>) -> Option<&str> {
+    caller.map(|key| key.key_id.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GatewayConfig;
+
+    #[tokio::test]
+    async fn test_unknown_job() -> Result<(), Box<dyn std::error::Error>> {
+        let state = AppState::new(GatewayConfig::default()).await?;
+
+        let result = handle_get_job(State(state.clone()), None, Path("missing".to_string())).await;
+        assert!(matches!(result, Err(GatewayError::NotFound(_))));
+
+        let result = handle_job_events(
+            State(state.clone()),
+            None,
+            Path("missing".to_string()),
+            Query(JobEventsQuery::default()),
+        )
+        .await;
+        assert!(matches!(result, Err(GatewayError::NotFound(_))));
+
+        let result = handle_cancel_job(State(state), None, Path("missing".to_string())).await;
+        assert!(matches!(result, Err(GatewayError::NotFound(_))));
+        Ok(())
+    }
+}
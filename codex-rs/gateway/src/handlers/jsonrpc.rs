@@ -1,8 +1,10 @@
 //! JSON-RPC handler
 
+use axum::Extension;
 use axum::extract::State;
 use axum::http::StatusCode;
 use axum::response::Json;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::Value;
@@ -12,10 +14,13 @@ use tracing::error;
 use tracing::info;
 
 use crate::error::GatewayResult;
+use crate::middleware::api_key::ApiKeyInfo;
 use crate::services::CodexService;
+use crate::services::workspace::TurnSettings;
+use crate::services::workspace::WorkspacePolicy;
 use crate::state::AppState;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct JsonRpcRequest {
     pub jsonrpc: String,
     pub method: String,
@@ -23,7 +28,7 @@ pub struct JsonRpcRequest {
     pub id: Option<Value>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct JsonRpcResponse {
     pub jsonrpc: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -33,7 +38,7 @@ pub struct JsonRpcResponse {
     pub id: Option<Value>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct JsonRpcError {
     pub code: i32,
     pub message: String,
@@ -84,9 +89,11 @@ impl JsonRpcResponse {
 ///
 /// ## Response
 ///
-/// Returns a JSON-RPC 2.0 formatted response.
+/// Returns a JSON-RPC 2.0 formatted response. Prompts run in the caller's
+/// workspace (see [`WorkspacePolicy`]).
 pub async fn handle_jsonrpc(
     State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
     Json(request): Json<JsonRpcRequest>,
 ) -> GatewayResult<(StatusCode, Json<JsonRpcResponse>)> {
     info!(
@@ -108,19 +115,27 @@ pub async fn handle_jsonrpc(
 
     // Get CodexService from state
     let codex_service = &state.codex_service;
+    // Named sessions are scoped to the key that created them
+    let owner = caller.as_deref().map(|key| key.key_id.as_str());
 
     let response = match request.method.as_str() {
         "conversation.prompt" => {
             info!("Processing conversation.prompt request");
-            process_execute(codex_service, &request).await
+            let settings = WorkspacePolicy::for_caller(
+                state.config(),
+                codex_service.codex_config(),
+                caller.as_deref(),
+            )?
+            .resolve(None, None, None)?;
+            process_execute(codex_service, &request, owner, settings).await
         }
         "conversation.status" => {
             info!("Processing conversation.status request");
-            process_status(codex_service, &request).await
+            process_status(codex_service, &request, owner).await
         }
         "conversation.cancel" => {
             info!("Processing conversation.cancel request");
-            process_cancel(codex_service, &request).await
+            process_cancel(codex_service, &request, owner).await
         }
         _ => {
             error!("Unknown method: {}", request.method);
@@ -143,7 +158,12 @@ pub async fn handle_jsonrpc(
 }
 
 /// Process execute request - main AI prompt processing
-async fn process_execute(service: &CodexService, request: &JsonRpcRequest) -> JsonRpcResponse {
+async fn process_execute(
+    service: &CodexService,
+    request: &JsonRpcRequest,
+    owner: Option<&str>,
+    settings: TurnSettings,
+) -> JsonRpcResponse {
     let params = match &request.params {
         Some(p) => p,
         None => {
@@ -172,7 +192,10 @@ async fn process_execute(service: &CodexService, request: &JsonRpcRequest) -> Js
 
     let session_id = params.get("session_id").and_then(|v| v.as_str());
 
-    match service.execute_prompt(prompt, session_id).await {
+    match service
+        .execute_prompt(prompt, session_id, owner, settings)
+        .await
+    {
         Ok(result) => JsonRpcResponse::success(request.id.clone(), result),
         Err(e) => {
             error!("Execute failed: {}", e);
@@ -182,7 +205,11 @@ async fn process_execute(service: &CodexService, request: &JsonRpcRequest) -> Js
 }
 
 /// Process status request - get processing status
-async fn process_status(service: &CodexService, request: &JsonRpcRequest) -> JsonRpcResponse {
+async fn process_status(
+    service: &CodexService,
+    request: &JsonRpcRequest,
+    owner: Option<&str>,
+) -> JsonRpcResponse {
     let params = match &request.params {
         Some(p) => p,
         None => {
@@ -203,7 +230,7 @@ async fn process_status(service: &CodexService, request: &JsonRpcRequest) -> Jso
         }
     };
 
-    match service.get_session_status(session_id).await {
+    match service.get_session_status(session_id, owner).await {
         Ok(Some(status)) => JsonRpcResponse::success(request.id.clone(), json!(status)),
         Ok(None) => JsonRpcResponse::success(
             request.id.clone(),
@@ -217,7 +244,11 @@ async fn process_status(service: &CodexService, request: &JsonRpcRequest) -> Jso
 }
 
 /// Process cancel request - cancel ongoing processing
-async fn process_cancel(service: &CodexService, request: &JsonRpcRequest) -> JsonRpcResponse {
+async fn process_cancel(
+    service: &CodexService,
+    request: &JsonRpcRequest,
+    owner: Option<&str>,
+) -> JsonRpcResponse {
     let params = match &request.params {
         Some(p) => p,
         None => {
@@ -238,7 +269,7 @@ async fn process_cancel(service: &CodexService, request: &JsonRpcRequest) -> Jso
         }
     };
 
-    match service.cancel_session(session_id).await {
+    match service.cancel_session(session_id, owner).await {
         Ok(Some(conversation_id)) => {
             info!(
                 "Cancelled session {} mapped to conversation {}",
@@ -280,7 +311,7 @@ mod tests {
             id: Some(json!(1)),
         };
 
-        let result = handle_jsonrpc(State(state), Json(request)).await;
+        let result = handle_jsonrpc(State(state), None, Json(request)).await;
 
         assert!(result.is_ok());
         let (status, json_response) = result.unwrap();
@@ -305,7 +336,7 @@ mod tests {
             id: Some(json!(2)),
         };
 
-        let result = handle_jsonrpc(State(state), Json(request)).await;
+        let result = handle_jsonrpc(State(state), None, Json(request)).await;
 
         assert!(result.is_ok());
         let (status, json_response) = result.unwrap();
@@ -333,7 +364,7 @@ mod tests {
             id: Some(json!(3)),
         };
 
-        let result = handle_jsonrpc(State(state), Json(request)).await;
+        let result = handle_jsonrpc(State(state), None, Json(request)).await;
 
         assert!(result.is_ok());
         let (status, json_response) = result.unwrap();
@@ -361,7 +392,7 @@ mod tests {
             id: Some(json!(4)),
         };
 
-        let result = handle_jsonrpc(State(state), Json(request)).await;
+        let result = handle_jsonrpc(State(state), None, Json(request)).await;
 
         assert!(result.is_ok());
         let (status, json_response) = result.unwrap();
@@ -385,7 +416,7 @@ mod tests {
             id: Some(json!(5)),
         };
 
-        let result = handle_jsonrpc(State(state), Json(request)).await;
+        let result = handle_jsonrpc(State(state), None, Json(request)).await;
 
         assert!(result.is_ok());
         let (status, json_response) = result.unwrap();
@@ -409,7 +440,7 @@ mod tests {
             id: Some(json!(6)),
         };
 
-        let result = handle_jsonrpc(State(state), Json(request)).await;
+        let result = handle_jsonrpc(State(state), None, Json(request)).await;
 
         assert!(result.is_ok());
         let (status, json_response) = result.unwrap();
@@ -432,7 +463,7 @@ mod tests {
             id: Some(json!(7)),
         };
 
-        let result = handle_jsonrpc(State(state), Json(request)).await;
+        let result = handle_jsonrpc(State(state), None, Json(request)).await;
 
         assert!(result.is_ok());
         let (status, json_response) = result.unwrap();
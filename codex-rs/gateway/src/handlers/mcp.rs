@@ -0,0 +1,388 @@
+//! MCP server over Streamable HTTP
+//!
+//! `/mcp` serves the `codex` and `codex-reply` tools of `codex-mcp-server`
+//! with the MCP Streamable HTTP transport, so remote MCP clients can call
+//! Codex without spawning a local process:
+//!
+//! - `POST /mcp` carries one JSON-RPC message (or a batch). An `initialize`
+//!   request without a session starts one and the response carries its id in
+//!   `Mcp-Session-Id`; every later request must send that header back.
+//!   Requests are answered with a `text/event-stream` that carries the
+//!   response plus the `codex/event` notifications and approval
+//!   elicitations of that call; notifications and responses alone get
+//!   `202 Accepted`.
+//! - `GET /mcp` opens the session's standalone SSE stream for server messages
+//!   that no open POST can carry.
+//! - `DELETE /mcp` ends the session.
+//!
+//! Sessions belong to the API key that opened them. Arguments of `codex`
+//! tool calls are checked against the key's [`WorkspacePolicy`] and pinned to
+//! the resolved `cwd`, sandbox and approval policy, because the tool loads
+//! its own config from `CODEX_HOME`.
+
+use crate::error::GatewayError;
+use crate::error::GatewayResult;
+use crate::middleware::api_key::ApiKeyInfo;
+use crate::middleware::rate_limit::SessionPermit;
+use crate::services::embedded_turns::EmbeddedTurns;
+use crate::services::mcp_sessions::MessageStream;
+use crate::services::workspace::TurnSettings;
+use crate::services::workspace::WorkspacePolicy;
+use crate::state::AppState;
+use axum::Extension;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::http::HeaderValue;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum::response::sse::Event;
+use axum::response::sse::KeepAlive;
+use axum::response::sse::Sse;
+use codex_core::AuthManager;
+use codex_core::ConversationManager;
+use codex_mcp_server::CodexToolCallParam;
+use codex_protocol::protocol::AskForApproval;
+use codex_protocol::protocol::SandboxPolicy;
+use codex_protocol::protocol::SessionSource;
+use mcp_types::JSONRPCError;
+use mcp_types::JSONRPCErrorError;
+use mcp_types::JSONRPCMessage;
+use mcp_types::JSONRPCRequest;
+use serde_json::Value;
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::debug;
+use tracing::info;
+
+/// Header carrying the MCP session id
+pub const MCP_SESSION_ID_HEADER: &str = "mcp-session-id";
+
+/// JSON-RPC error code for requests the gateway refuses
+const INVALID_REQUEST_ERROR_CODE: i64 = -32600;
+
+/// POST /mcp - Send JSON-RPC messages to an MCP session
+pub async fn handle_mcp_post(
+    State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
+    session_permit: Option<Extension<SessionPermit>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> GatewayResult<Response> {
+    let messages = parse_messages(&body)?;
+    let policy = WorkspacePolicy::for_caller(
+        state.config(),
+        state.codex_service.codex_config(),
+        caller.as_deref(),
+    )?;
+    let owner = caller.as_deref().map(|key| key.key_id.clone());
+
+    let session = match session_id(&headers) {
+        Some(id) => state
+            .mcp_sessions
+            .get(id, owner.as_deref())
+            .await
+            .ok_or_else(|| GatewayError::NotFound(format!("MCP session {id} not found")))?,
+        None => {
+            let initializes = messages.iter().any(|message| {
+                matches!(message, JSONRPCMessage::Request(request) if request.method == "initialize")
+            });
+            if !initializes {
+                return Err(GatewayError::InvalidRequest(format!(
+                    "Missing {MCP_SESSION_ID_HEADER} header; start a session with 'initialize'"
+                )));
+            }
+            let config = state.codex_service.codex_config();
+            let auth_manager = AuthManager::shared(
+                config.codex_home.clone(),
+                false,
+                config.cli_auth_credentials_store_mode,
+            );
+            let conversations =
+                Arc::new(ConversationManager::new(auth_manager, SessionSource::Mcp));
+            let turns = EmbeddedTurns::new(
+                state.codex_service.audit().clone(),
+                state.metrics.clone(),
+                state.codex_service.drain().clone(),
+                Arc::clone(&conversations),
+                owner.clone(),
+            );
+            state
+                .mcp_sessions
+                .create(
+                    owner,
+                    config.codex_linux_sandbox_exe.clone(),
+                    conversations,
+                    turns,
+                )
+                .await
+        }
+    };
+
+    let mut forward = Vec::with_capacity(messages.len());
+    let mut requests = Vec::new();
+    let mut refusals = Vec::new();
+    for message in messages {
+        match message {
+            JSONRPCMessage::Request(request) => match authorize(&policy, request) {
+                Ok(request) => {
+                    requests.push(request.id.clone());
+                    forward.push(JSONRPCMessage::Request(request));
+                }
+                Err(refusal) => refusals.push(refusal),
+            },
+            other => forward.push(other),
+        }
+    }
+
+    let session_header = HeaderValue::from_str(session.id())
+        .map_err(|e| GatewayError::Internal(format!("Invalid MCP session id: {e}")))?;
+    let mut response = match session.send(forward, requests, refusals).await {
+        Some(stream) => {
+            let permit = session_permit.map(|Extension(permit)| permit);
+            sse_response(stream, permit)
+        }
+        None => StatusCode::ACCEPTED.into_response(),
+    };
+    response
+        .headers_mut()
+        .insert(MCP_SESSION_ID_HEADER, session_header);
+    Ok(response)
+}
+
+/// GET /mcp - Standalone SSE stream for server-initiated messages
+pub async fn handle_mcp_get(
+    State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
+    headers: HeaderMap,
+) -> GatewayResult<Response> {
+    let id = session_id(&headers).ok_or_else(|| {
+        GatewayError::InvalidRequest(format!("Missing {MCP_SESSION_ID_HEADER} header"))
+    })?;
+    let owner = caller.as_deref().map(|key| key.key_id.as_str());
+    let session = state
+        .mcp_sessions
+        .get(id, owner)
+        .await
+        .ok_or_else(|| GatewayError::NotFound(format!("MCP session {id} not found")))?;
+    Ok(sse_response(session.listen().await, None))
+}
+
+/// DELETE /mcp - End an MCP session
+pub async fn handle_mcp_delete(
+    State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
+    headers: HeaderMap,
+) -> GatewayResult<StatusCode> {
+    let id = session_id(&headers).ok_or_else(|| {
+        GatewayError::InvalidRequest(format!("Missing {MCP_SESSION_ID_HEADER} header"))
+    })?;
+    let owner = caller.as_deref().map(|key| key.key_id.as_str());
+    if !state.mcp_sessions.remove(id, owner).await {
+        return Err(GatewayError::NotFound(format!(
+            "MCP session {id} not found"
+        )));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn session_id(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(MCP_SESSION_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+}
+
+/// A single JSON-RPC message or a batch of them
+fn parse_messages(body: &[u8]) -> GatewayResult<Vec<JSONRPCMessage>> {
+    let value: Value = serde_json::from_slice(body)
+        .map_err(|e| GatewayError::InvalidRequest(format!("Invalid JSON body: {e}")))?;
+    let parsed = match value {
+        Value::Array(batch) => batch.into_iter().map(serde_json::from_value).collect(),
+        single => serde_json::from_value(single).map(|message| vec![message]),
+    };
+    parsed.map_err(|e| GatewayError::InvalidRequest(format!("Invalid JSON-RPC message: {e}")))
+}
+
+fn sse_response(stream: MessageStream, session_permit: Option<SessionPermit>) -> Response {
+    let frames = futures::stream::unfold(
+        (stream, session_permit),
+        |(mut stream, permit)| async move {
+            let message = stream.recv().await?;
+            let data = serde_json::to_string(&message).unwrap_or_default();
+            Some((
+                Ok::<_, axum::Error>(Event::default().event("message").data(data)),
+                (stream, permit),
+            ))
+        },
+    );
+    Sse::new(frames)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// Check a client request, pinning `codex` tool calls to the caller's policy
+///
+/// Refused requests come back as the JSON-RPC error to send to the client.
+fn authorize(
+    policy: &WorkspacePolicy,
+    mut request: JSONRPCRequest,
+) -> Result<JSONRPCRequest, JSONRPCMessage> {
+    let is_codex_call = request.method == "tools/call"
+        && request
+            .params
+            .as_ref()
+            .and_then(|params| params.get("name"))
+            .and_then(Value::as_str)
+            == Some("codex");
+    if !is_codex_call {
+        return Ok(request);
+    }
+
+    let arguments = request
+        .params
+        .as_mut()
+        .and_then(|params| params.get_mut("arguments"));
+    let result = match arguments {
+        Some(arguments) => pin_codex_arguments(policy, arguments),
+        // The tool answers missing arguments with its own error
+        None => Ok(()),
+    };
+    match result {
+        Ok(()) => Ok(request),
+        Err(err) => {
+            debug!("Refusing MCP tool call: {err}");
+            Err(JSONRPCMessage::Error(JSONRPCError {
+                jsonrpc: mcp_types::JSONRPC_VERSION.to_string(),
+                id: request.id,
+                error: JSONRPCErrorError {
+                    code: INVALID_REQUEST_ERROR_CODE,
+                    message: err.to_string(),
+                    data: None,
+                },
+            }))
+        }
+    }
+}
+
+/// Validate `codex` tool arguments and write the resolved settings back
+fn pin_codex_arguments(policy: &WorkspacePolicy, arguments: &mut Value) -> GatewayResult<()> {
+    let Ok(param) = serde_json::from_value::<CodexToolCallParam>(arguments.clone()) else {
+        // Malformed arguments are reported by the tool itself
+        return Ok(());
+    };
+    if param
+        .config
+        .as_ref()
+        .is_some_and(|config| !config.is_empty())
+    {
+        return Err(GatewayError::Forbidden(
+            "config overrides are not accepted by the gateway".to_string(),
+        ));
+    }
+    if param.profile.is_some() {
+        return Err(GatewayError::Forbidden(
+            "config profiles are not accepted by the gateway".to_string(),
+        ));
+    }
+
+    let sandbox = param
+        .sandbox
+        .map(|mode| codex_protocol::config_types::SandboxMode::from(mode).to_string());
+    let settings = policy.resolve(
+        param.cwd.as_deref().map(Path::new),
+        sandbox.as_deref(),
+        param.approval_policy.map(AskForApproval::from),
+    )?;
+    info!(
+        "Pinning MCP codex call to cwd={}, approval_policy={}",
+        settings.cwd.display(),
+        settings.approval_policy
+    );
+
+    if let Value::Object(arguments) = arguments {
+        for (key, value) in pinned_arguments(&settings) {
+            arguments.insert(key.to_string(), value);
+        }
+    }
+    Ok(())
+}
+
+/// `codex` tool arguments that reproduce `settings`
+fn pinned_arguments(settings: &TurnSettings) -> Vec<(&'static str, Value)> {
+    let mut pinned = vec![
+        ("cwd", json!(settings.cwd)),
+        ("approval-policy", json!(settings.approval_policy)),
+    ];
+    match &settings.sandbox_policy {
+        SandboxPolicy::ReadOnly => pinned.push(("sandbox", json!("read-only"))),
+        SandboxPolicy::DangerFullAccess => pinned.push(("sandbox", json!("danger-full-access"))),
+        SandboxPolicy::WorkspaceWrite {
+            writable_roots,
+            network_access,
+            ..
+        } => {
+            pinned.push(("sandbox", json!("workspace-write")));
+            pinned.push((
+                "config",
+                json!({
+                    "sandbox_workspace_write.writable_roots": writable_roots,
+                    "sandbox_workspace_write.network_access": network_access,
+                }),
+            ));
+        }
+    }
+    pinned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_single_and_batch() {
+        let single = br#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#;
+        assert_eq!(parse_messages(single).unwrap().len(), 1);
+
+        let batch = br#"[{"jsonrpc":"2.0","method":"notifications/initialized"},
+                         {"jsonrpc":"2.0","id":2,"method":"tools/list"}]"#;
+        assert_eq!(parse_messages(batch).unwrap().len(), 2);
+
+        assert!(matches!(
+            parse_messages(b"not json"),
+            Err(GatewayError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_pinned_arguments_match_settings() {
+        let settings = TurnSettings {
+            cwd: "/srv/acme".into(),
+            sandbox_policy: SandboxPolicy::WorkspaceWrite {
+                writable_roots: Vec::new(),
+                network_access: false,
+                exclude_tmpdir_env_var: false,
+                exclude_slash_tmp: false,
+            },
+            approval_policy: AskForApproval::OnRequest,
+        };
+        let mut arguments = json!({ "prompt": "hi" });
+        if let Value::Object(map) = &mut arguments {
+            for (key, value) in pinned_arguments(&settings) {
+                map.insert(key.to_string(), value);
+            }
+        }
+
+        let param: CodexToolCallParam = serde_json::from_value(arguments).unwrap();
+        assert_eq!(param.cwd.as_deref(), Some("/srv/acme"));
+        assert_eq!(
+            param.approval_policy.map(AskForApproval::from),
+            Some(AskForApproval::OnRequest)
+        );
+        assert_eq!(
+            param.config.unwrap()["sandbox_workspace_write.network_access"],
+            json!(false)
+        );
+    }
+}
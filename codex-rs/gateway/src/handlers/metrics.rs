@@ -0,0 +1,64 @@
+//! Prometheus metrics handler
+
+use crate::services::metrics::MetricsSnapshot;
+use crate::state::AppState;
+use axum::extract::State;
+use axum::http::HeaderValue;
+use axum::http::StatusCode;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::response::Response;
+
+/// Content type of the Prometheus text exposition format
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Metrics endpoint
+///
+/// Exposes request, connection, turn, token and auth counters in the
+/// Prometheus text format. Like `/health`, it does not require an API key.
+///
+/// ## Response
+///
+/// ```text
+/// # HELP codex_gateway_http_requests_total HTTP requests by method, route and status
+/// # TYPE codex_gateway_http_requests_total counter
+/// codex_gateway_http_requests_total{method="POST",route="/exec",status="200"} 12
+/// ...
+/// ```
+pub async fn handle_metrics(State(state): State<AppState>) -> Response {
+    let active_conversations = state.codex_service.loaded_conversation_count().await;
+    let body = state.metrics.render(MetricsSnapshot {
+        active_conversations,
+        max_websocket_connections: state.config().websocket.max_connections,
+    });
+
+    let mut response = (StatusCode::OK, body).into_response();
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static(PROMETHEUS_CONTENT_TYPE),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GatewayConfig;
+
+    #[tokio::test]
+    async fn test_metrics_endpoint() -> Result<(), Box<dyn std::error::Error>> {
+        let state = AppState::new(GatewayConfig::default()).await?;
+        let response = handle_metrics(State(state)).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[CONTENT_TYPE],
+            HeaderValue::from_static(PROMETHEUS_CONTENT_TYPE)
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        let body = String::from_utf8(body.to_vec())?;
+        assert!(body.contains("codex_gateway_websocket_connections_max 5000"));
+        assert!(body.contains("codex_gateway_active_conversations 0"));
+        Ok(())
+    }
+}
@@ -1,15 +1,35 @@
 //! HTTP handlers for the Codex Gateway
 
+pub mod admin;
+pub mod app_server;
 pub mod exec;
+pub mod exec_stream;
 pub mod health;
+pub mod jobs;
 pub mod jsonrpc;
+pub mod mcp;
+pub mod metrics;
 pub mod oauth;
+pub mod openai;
+pub mod openapi;
+pub mod sessions;
+pub mod upload;
 pub mod webhook;
 pub mod websocket;
 
+pub use admin::*;
+pub use app_server::*;
 pub use exec::*;
+pub use exec_stream::*;
 pub use health::*;
+pub use jobs::*;
 pub use jsonrpc::*;
+pub use mcp::*;
+pub use metrics::*;
 pub use oauth::*;
+pub use openai::*;
+pub use openapi::*;
+pub use sessions::*;
+pub use upload::*;
 pub use webhook::*;
 pub use websocket::*;
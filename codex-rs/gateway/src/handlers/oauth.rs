@@ -1,247 +1,627 @@
 //! OAuth 2.0 handlers for GPT Actions
 //!
-//! This module implements OAuth 2.0 authorization code flow for ChatGPT GPT Actions.
-
+//! This module implements the OAuth 2.0 authorization code flow (RFC 6749)
+//! with PKCE (RFC 7636) for ChatGPT GPT Actions and other registered clients:
+//!
+//! 1. `GET /oauth/authorize` validates the client and redirect URI and shows
+//!    a consent form asking for the user's gateway API key.
+//! 2. `POST /oauth/authorize` checks the key and redirects back to the client
+//!    with a single-use authorization code.
+//! 3. `POST /oauth/token` exchanges the code (or a refresh token) for an
+//!    access token that is accepted as `Authorization: Bearer` on every
+//!    endpoint that takes an `X-API-Key`.
+//!
+//! Clients are registered through `/admin/oauth/clients` or the
+//! `OAUTH_CLIENT_ID`/`OAUTH_CLIENT_SECRET`/`OAUTH_REDIRECT_URIS` environment
+//! variables. State lives in [`crate::middleware::oauth::OAuthStore`].
+
+use crate::middleware::api_key::SCOPE_AGENT;
+use crate::middleware::oauth::OAuthClient;
+use crate::middleware::oauth::OAuthError;
+use crate::middleware::oauth::OAuthStore;
+use crate::middleware::oauth::parse_scopes;
+use crate::state::AppState;
 use axum::Json;
-use axum::extract::Request;
+use axum::body::Bytes;
+use axum::extract::Query;
 use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::http::HeaderValue;
 use axum::http::StatusCode;
+use axum::http::header::AUTHORIZATION;
+use axum::http::header::CACHE_CONTROL;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::header::PRAGMA;
+use axum::response::Html;
 use axum::response::IntoResponse;
 use axum::response::Redirect;
 use axum::response::Response;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::Utc;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
+use serde::de::DeserializeOwned;
 use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use uuid::Uuid;
-
-use crate::state::AppState;
-
-/// In-memory storage for OAuth tokens (in production, use Redis or database)
-#[derive(Debug, Clone)]
-pub struct OAuthStore {
-    /// Maps authorization code -> access token
-    codes: Arc<RwLock<HashMap<String, String>>>,
-    /// Maps access token -> user info
-    tokens: Arc<RwLock<HashMap<String, UserInfo>>>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UserInfo {
-    pub user_id: String,
-    pub email: Option<String>,
-    pub created_at: chrono::DateTime<chrono::Utc>,
-}
-
-impl OAuthStore {
-    pub fn new() -> Self {
-        Self {
-            codes: Arc::new(RwLock::new(HashMap::new())),
-            tokens: Arc::new(RwLock::new(HashMap::new())),
-        }
-    }
-
-    pub async fn create_authorization_code(&self, user_info: UserInfo) -> String {
-        let code = Uuid::new_v4().to_string();
-        let token = Uuid::new_v4().to_string();
-
-        let mut codes = self.codes.write().await;
-        let mut tokens = self.tokens.write().await;
-
-        codes.insert(code.clone(), token.clone());
-        tokens.insert(token, user_info);
-
-        code
-    }
-
-    pub async fn exchange_code(&self, code: &str) -> Option<String> {
-        let mut codes = self.codes.write().await;
-        codes.remove(code)
-    }
-
-    pub async fn validate_token(&self, token: &str) -> Option<UserInfo> {
-        let tokens = self.tokens.read().await;
-        tokens.get(token).cloned()
-    }
-}
-
-impl Default for OAuthStore {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+use tracing::info;
+use tracing::warn;
 
 /// Query parameters for OAuth authorization endpoint
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct AuthorizeQuery {
+    pub response_type: String,
     pub client_id: String,
     pub redirect_uri: String,
-    pub state: String,
-    pub response_type: String,
+    #[serde(default)]
+    pub state: Option<String>,
+    #[serde(default)]
+    pub scope: Option<String>,
+    #[serde(default)]
+    pub code_challenge: Option<String>,
+    #[serde(default)]
+    pub code_challenge_method: Option<String>,
 }
 
-/// Request body for token exchange
+/// Consent form submitted to `POST /oauth/authorize`
 #[derive(Debug, Deserialize)]
+pub struct AuthorizeForm {
+    #[serde(flatten)]
+    pub request: AuthorizeQuery,
+    pub api_key: String,
+}
+
+/// Request body for token exchange (form-encoded or JSON)
+#[derive(Debug, Default, Deserialize, JsonSchema)]
 pub struct TokenRequest {
     pub grant_type: String,
-    pub client_id: String,
-    pub client_secret: String,
-    pub code: String,
-    pub redirect_uri: String,
+    #[serde(default)]
+    pub client_id: Option<String>,
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    #[serde(default)]
+    pub code: Option<String>,
+    #[serde(default)]
+    pub redirect_uri: Option<String>,
+    #[serde(default)]
+    pub code_verifier: Option<String>,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub scope: Option<String>,
 }
 
 /// Response for token endpoint
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct TokenResponse {
     pub access_token: String,
     pub token_type: String,
     pub expires_in: i64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub refresh_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+/// Why an authorization request was refused
+#[derive(Debug)]
+enum AuthorizeError {
+    /// The client or redirect URI cannot be trusted: show an error page
+    Untrusted(String),
+    /// Report back to the client's redirect URI (RFC 6749 section 4.1.2.1)
+    Redirect {
+        error: &'static str,
+        description: String,
+    },
 }
 
 /// OAuth authorization endpoint
 /// GET /oauth/authorize
-pub async fn handle_oauth_authorize(State(_state): State<AppState>, req: Request) -> Response {
-    // Extract query parameters manually
-    let query = req.uri().query().unwrap_or("");
-    let params: HashMap<String, String> = url::form_urlencoded::parse(query.as_bytes())
-        .into_owned()
-        .collect();
-
-    // Validate parameters
-    let response_type = params.get("response_type");
-    let redirect_uri = params.get("redirect_uri");
-    let state = params.get("state");
-    let client_id = params.get("client_id");
-
-    if response_type != Some(&"code".to_string()) {
-        return (
-            StatusCode::BAD_REQUEST,
-            "Invalid response_type. Must be 'code'",
-        )
-            .into_response();
+pub async fn handle_oauth_authorize(
+    State(state): State<AppState>,
+    Query(query): Query<AuthorizeQuery>,
+) -> Response {
+    match validate_authorization_request(&state.oauth, &query).await {
+        Ok(client) => consent_page(&client, &query, None, StatusCode::OK),
+        Err(err) => authorize_error_response(&query, err),
     }
+}
 
-    let redirect_uri = match redirect_uri {
-        Some(uri) => uri,
-        None => return (StatusCode::BAD_REQUEST, "Missing redirect_uri").into_response(),
+/// Consent form submission
+/// POST /oauth/authorize
+pub async fn handle_oauth_authorize_submit(State(state): State<AppState>, body: Bytes) -> Response {
+    let form: AuthorizeForm = match parse_form(&body) {
+        Ok(form) => form,
+        Err(err) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid authorization form: {err}"),
+            )
+                .into_response();
+        }
     };
+    let query = form.request;
 
-    let state = match state {
-        Some(s) => s,
-        None => return (StatusCode::BAD_REQUEST, "Missing state").into_response(),
+    let client = match validate_authorization_request(&state.oauth, &query).await {
+        Ok(client) => client,
+        Err(err) => return authorize_error_response(&query, err),
     };
 
-    // Validate client_id (optional but recommended)
-    if client_id.is_none() {
-        return (StatusCode::BAD_REQUEST, "Missing client_id").into_response();
-    }
-
-    // In a real implementation, you would:
-    // 1. Show a login page
-    // 2. Authenticate the user
-    // 3. Ask for consent
-    // 4. Generate authorization code
-
-    // For this example, we'll auto-approve and create a dummy user
-    let oauth_store = OAuthStore::new();
-    let user_info = UserInfo {
-        user_id: Uuid::new_v4().to_string(),
-        email: Some("user@example.com".to_string()),
-        created_at: chrono::Utc::now(),
+    let key_info = match state.api_keys.validate_key(form.api_key.trim()).await {
+        Some(info) if info.active && !info.is_expired(Utc::now()) => info,
+        _ => {
+            warn!(
+                "OAuth authorization with invalid API key: client_id={}",
+                client.client_id
+            );
+            return consent_page(
+                &client,
+                &query,
+                Some("Invalid, inactive or expired API key."),
+                StatusCode::UNAUTHORIZED,
+            );
+        }
     };
 
-    let code = oauth_store.create_authorization_code(user_info).await;
+    // Tokens may carry at most the scopes of the key that authorized them
+    let scopes = query
+        .scope
+        .as_deref()
+        .map(parse_scopes)
+        .unwrap_or_else(|| vec![SCOPE_AGENT.to_string()]);
+    if let Some(scope) = scopes.iter().find(|scope| !key_info.has_scope(scope)) {
+        return authorize_error_response(
+            &query,
+            AuthorizeError::Redirect {
+                error: "invalid_scope",
+                description: format!("API key does not grant scope '{scope}'"),
+            },
+        );
+    }
+
+    let code = state
+        .oauth
+        .create_authorization_code(
+            &client.client_id,
+            &query.redirect_uri,
+            &key_info.key_id,
+            scopes,
+            query.code_challenge.clone(),
+        )
+        .await;
 
-    // Redirect back to ChatGPT with the code
-    let redirect_url = format!("{redirect_uri}?code={code}&state={state}");
+    info!(
+        "OAuth authorization granted: client_id={}, key_id={}",
+        client.client_id, key_info.key_id
+    );
 
-    Redirect::to(&redirect_url).into_response()
+    let mut params = vec![("code", code.as_str())];
+    if let Some(state) = &query.state {
+        params.push(("state", state));
+    }
+    redirect_with(&query.redirect_uri, &params)
 }
 
 /// OAuth token endpoint
 /// POST /oauth/token
+///
+/// Accepts `application/x-www-form-urlencoded` (standard) or JSON bodies,
+/// with client credentials in the body or an HTTP Basic `Authorization`
+/// header. Supports the `authorization_code` and `refresh_token` grants.
 pub async fn handle_oauth_token(
-    State(_state): State<AppState>,
-    Json(request): Json<TokenRequest>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
 ) -> Response {
-    // Validate grant type
-    if request.grant_type != "authorization_code" {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({
-                "error": "unsupported_grant_type",
-                "error_description": "Only authorization_code is supported"
-            })),
-        )
-            .into_response();
-    }
-
-    // Validate client credentials
-    // In production, verify client_id and client_secret against your database
-    let valid_client_id =
-        std::env::var("OAUTH_CLIENT_ID").unwrap_or_else(|_| "codex-gateway-client".to_string());
-    let valid_client_secret =
-        std::env::var("OAUTH_CLIENT_SECRET").unwrap_or_else(|_| "secret-key-here".to_string());
+    let is_json = headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    let parsed = if is_json {
+        serde_json::from_slice::<TokenRequest>(&body)
+    } else {
+        parse_form::<TokenRequest>(&body)
+    };
+    let mut request = match parsed {
+        Ok(request) => request,
+        Err(err) => {
+            return oauth_error(
+                StatusCode::BAD_REQUEST,
+                "invalid_request",
+                &format!("Malformed token request: {err}"),
+            );
+        }
+    };
 
-    if request.client_id != valid_client_id || request.client_secret != valid_client_secret {
-        return (
-            StatusCode::UNAUTHORIZED,
-            Json(serde_json::json!({
-                "error": "invalid_client",
-                "error_description": "Invalid client credentials"
-            })),
-        )
-            .into_response();
+    if let Some((client_id, client_secret)) = basic_credentials(&headers) {
+        request.client_id = Some(client_id);
+        request.client_secret = Some(client_secret);
     }
+    let Some(client_id) = request.client_id.as_deref() else {
+        return oauth_error(
+            StatusCode::UNAUTHORIZED,
+            "invalid_client",
+            "Missing client_id",
+        );
+    };
+    let client_secret = request.client_secret.as_deref();
+
+    let result = match request.grant_type.as_str() {
+        "authorization_code" => {
+            let (Some(code), Some(redirect_uri)) = (&request.code, &request.redirect_uri) else {
+                return oauth_error(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_request",
+                    "code and redirect_uri are required",
+                );
+            };
+            state
+                .oauth
+                .exchange_code(
+                    client_id,
+                    client_secret,
+                    code,
+                    redirect_uri,
+                    request.code_verifier.as_deref(),
+                )
+                .await
+        }
+        "refresh_token" => {
+            let Some(refresh_token) = &request.refresh_token else {
+                return oauth_error(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_request",
+                    "refresh_token is required",
+                );
+            };
+            state
+                .oauth
+                .refresh(
+                    client_id,
+                    client_secret,
+                    refresh_token,
+                    request.scope.as_deref(),
+                )
+                .await
+        }
+        _ => {
+            return oauth_error(
+                StatusCode::BAD_REQUEST,
+                "unsupported_grant_type",
+                "Only authorization_code and refresh_token are supported",
+            );
+        }
+    };
 
-    // Exchange code for token
-    let oauth_store = OAuthStore::new();
-    let access_token = match oauth_store.exchange_code(&request.code).await {
-        Some(token) => token,
-        None => {
-            return (
+    let tokens = match result {
+        Ok(tokens) => tokens,
+        Err(OAuthError::InvalidRequest(description)) => {
+            return oauth_error(StatusCode::BAD_REQUEST, "invalid_request", &description);
+        }
+        Err(OAuthError::InvalidClient) => {
+            return oauth_error(
+                StatusCode::UNAUTHORIZED,
+                "invalid_client",
+                "Invalid client credentials",
+            );
+        }
+        Err(OAuthError::InvalidGrant(description)) => {
+            return oauth_error(StatusCode::BAD_REQUEST, "invalid_grant", &description);
+        }
+        Err(OAuthError::InvalidScope) => {
+            return oauth_error(
                 StatusCode::BAD_REQUEST,
-                Json(serde_json::json!({
-                    "error": "invalid_grant",
-                    "error_description": "Invalid or expired authorization code"
-                })),
-            )
-                .into_response();
+                "invalid_scope",
+                "Requested scope exceeds the original grant",
+            );
+        }
+        Err(OAuthError::Io(err)) => {
+            warn!("Failed to persist OAuth tokens: {}", err);
+            return oauth_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "server_error",
+                "Failed to persist tokens",
+            );
         }
     };
 
-    // Return token response
     let response = TokenResponse {
-        access_token,
+        access_token: tokens.access_token,
         token_type: "bearer".to_string(),
-        expires_in: 3600, // 1 hour
-        refresh_token: None,
+        expires_in: tokens.expires_in,
+        refresh_token: Some(tokens.refresh_token),
+        scope: Some(tokens.scopes.join(" ")),
+    };
+
+    no_store((StatusCode::OK, Json(response)).into_response())
+}
+
+/// Check client, redirect URI, response type and PKCE parameters
+async fn validate_authorization_request(
+    oauth: &OAuthStore,
+    query: &AuthorizeQuery,
+) -> Result<OAuthClient, AuthorizeError> {
+    let Some(client) = oauth.get_client(&query.client_id).await else {
+        return Err(AuthorizeError::Untrusted(format!(
+            "Unknown client_id '{}'",
+            query.client_id
+        )));
     };
+    if !client.allows_redirect_uri(&query.redirect_uri) {
+        return Err(AuthorizeError::Untrusted(
+            "redirect_uri is not registered for this client".to_string(),
+        ));
+    }
+
+    if query.response_type != "code" {
+        return Err(AuthorizeError::Redirect {
+            error: "unsupported_response_type",
+            description: "response_type must be 'code'".to_string(),
+        });
+    }
+
+    match (
+        &query.code_challenge,
+        query.code_challenge_method.as_deref(),
+    ) {
+        (Some(_), Some("S256")) => {}
+        (Some(_), _) => {
+            return Err(AuthorizeError::Redirect {
+                error: "invalid_request",
+                description: "code_challenge_method must be 'S256'".to_string(),
+            });
+        }
+        (None, _) if client.is_public() => {
+            return Err(AuthorizeError::Redirect {
+                error: "invalid_request",
+                description: "code_challenge is required for public clients".to_string(),
+            });
+        }
+        (None, _) => {}
+    }
+
+    Ok(client)
+}
+
+fn authorize_error_response(query: &AuthorizeQuery, err: AuthorizeError) -> Response {
+    match err {
+        AuthorizeError::Untrusted(message) => {
+            warn!("Rejected OAuth authorization request: {}", message);
+            (StatusCode::BAD_REQUEST, message).into_response()
+        }
+        AuthorizeError::Redirect { error, description } => {
+            let mut params = vec![
+                ("error", error),
+                ("error_description", description.as_str()),
+            ];
+            if let Some(state) = &query.state {
+                params.push(("state", state));
+            }
+            redirect_with(&query.redirect_uri, &params)
+        }
+    }
+}
+
+/// Redirect to `base` with `params` appended to its query string
+fn redirect_with(base: &str, params: &[(&str, &str)]) -> Response {
+    match url::Url::parse(base) {
+        Ok(mut url) => {
+            url.query_pairs_mut().extend_pairs(params);
+            Redirect::to(url.as_str()).into_response()
+        }
+        Err(_) => (StatusCode::BAD_REQUEST, "Invalid redirect_uri").into_response(),
+    }
+}
+
+/// Minimal consent page that collects the user's API key
+fn consent_page(
+    client: &OAuthClient,
+    query: &AuthorizeQuery,
+    error: Option<&str>,
+    status: StatusCode,
+) -> Response {
+    let hidden = [
+        ("response_type", Some(query.response_type.as_str())),
+        ("client_id", Some(query.client_id.as_str())),
+        ("redirect_uri", Some(query.redirect_uri.as_str())),
+        ("state", query.state.as_deref()),
+        ("scope", query.scope.as_deref()),
+        ("code_challenge", query.code_challenge.as_deref()),
+        (
+            "code_challenge_method",
+            query.code_challenge_method.as_deref(),
+        ),
+    ]
+    .iter()
+    .filter_map(|(name, value)| {
+        value.map(|value| {
+            format!(
+                r#"<input type="hidden" name="{name}" value="{}">"#,
+                escape_html(value)
+            )
+        })
+    })
+    .collect::<Vec<_>>()
+    .join("\n      ");
+
+    let client_name = escape_html(client.name.as_deref().unwrap_or(&client.client_id));
+    let error = error
+        .map(|message| format!(r#"<p class="error">{}</p>"#, escape_html(message)))
+        .unwrap_or_default();
+
+    let page = format!(
+        r#"<!DOCTYPE html>
+<html>
+  <head><meta charset="utf-8"><title>Authorize {client_name}</title></head>
+  <body>
+    <h1>Authorize {client_name}</h1>
+    <p>{client_name} is requesting access to Codex Gateway on your behalf.</p>
+    {error}
+    <form method="post" action="/oauth/authorize">
+      {hidden}
+      <label>API key <input type="password" name="api_key" autocomplete="off" required></label>
+      <button type="submit">Authorize</button>
+    </form>
+  </body>
+</html>
+"#
+    );
+
+    (status, Html(page)).into_response()
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Decode an `application/x-www-form-urlencoded` body into `T`
+fn parse_form<T: DeserializeOwned>(body: &[u8]) -> Result<T, serde_json::Error> {
+    let fields: HashMap<String, String> = url::form_urlencoded::parse(body).into_owned().collect();
+    serde_json::from_value(serde_json::to_value(fields)?)
+}
+
+/// Client credentials from an HTTP Basic `Authorization` header
+fn basic_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let encoded = headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Basic ")?;
+    let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (client_id, client_secret) = decoded.split_once(':')?;
+    Some((client_id.to_string(), client_secret.to_string()))
+}
 
-    (StatusCode::OK, Json(response)).into_response()
+fn oauth_error(status: StatusCode, error: &str, description: &str) -> Response {
+    no_store(
+        (
+            status,
+            Json(serde_json::json!({
+                "error": error,
+                "error_description": description
+            })),
+        )
+            .into_response(),
+    )
+}
+
+/// Token responses must not be cached (RFC 6749 section 5.1)
+fn no_store(mut response: Response) -> Response {
+    let headers = response.headers_mut();
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    headers.insert(PRAGMA, HeaderValue::from_static("no-cache"));
+    response
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use axum::http::header::LOCATION;
+
+    const REDIRECT_URI: &str = "https://chat.openai.com/aip/g-123/oauth/callback";
+
+    fn query(client_id: &str) -> AuthorizeQuery {
+        AuthorizeQuery {
+            response_type: "code".to_string(),
+            client_id: client_id.to_string(),
+            redirect_uri: REDIRECT_URI.to_string(),
+            state: Some("xyz".to_string()),
+            scope: None,
+            code_challenge: None,
+            code_challenge_method: None,
+        }
+    }
 
     #[tokio::test]
-    async fn test_oauth_store() {
-        let store = OAuthStore::new();
+    async fn test_validate_authorization_request() -> Result<(), Box<dyn std::error::Error>> {
+        let oauth = OAuthStore::new();
+        let (confidential, _) = oauth
+            .register_client(None, vec![REDIRECT_URI.to_string()], true)
+            .await?;
+        let (public, _) = oauth
+            .register_client(None, vec![REDIRECT_URI.to_string()], false)
+            .await?;
+
+        assert!(
+            validate_authorization_request(&oauth, &query(&confidential.client_id))
+                .await
+                .is_ok()
+        );
+        assert!(matches!(
+            validate_authorization_request(&oauth, &query("unknown")).await,
+            Err(AuthorizeError::Untrusted(_))
+        ));
+
+        let mut bad_redirect = query(&confidential.client_id);
+        bad_redirect.redirect_uri = "https://evil.example.com/callback".to_string();
+        assert!(matches!(
+            validate_authorization_request(&oauth, &bad_redirect).await,
+            Err(AuthorizeError::Untrusted(_))
+        ));
+
+        // Public clients need S256 PKCE
+        let mut pkce = query(&public.client_id);
+        assert!(matches!(
+            validate_authorization_request(&oauth, &pkce).await,
+            Err(AuthorizeError::Redirect { .. })
+        ));
+        pkce.code_challenge = Some("E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM".to_string());
+        pkce.code_challenge_method = Some("plain".to_string());
+        assert!(matches!(
+            validate_authorization_request(&oauth, &pkce).await,
+            Err(AuthorizeError::Redirect { .. })
+        ));
+        pkce.code_challenge_method = Some("S256".to_string());
+        assert!(validate_authorization_request(&oauth, &pkce).await.is_ok());
+        Ok(())
+    }
 
-        let user_info = UserInfo {
-            user_id: "test-user".to_string(),
-            email: Some("test@example.com".to_string()),
-            created_at: chrono::Utc::now(),
-        };
+    #[test]
+    fn test_redirect_error_keeps_state() {
+        let response = authorize_error_response(
+            &query("client"),
+            AuthorizeError::Redirect {
+                error: "invalid_scope",
+                description: "nope".to_string(),
+            },
+        );
+        let location = response.headers()[LOCATION].to_str().unwrap_or_default();
+        assert_eq!(
+            location,
+            format!("{REDIRECT_URI}?error=invalid_scope&error_description=nope&state=xyz")
+        );
+    }
 
-        let code = store.create_authorization_code(user_info.clone()).await;
-        let token = store.exchange_code(&code).await.unwrap();
+    #[test]
+    fn test_token_request_parsing() {
+        let request: TokenRequest =
+            parse_form(b"grant_type=authorization_code&code=abc&redirect_uri=https%3A%2F%2Fx")
+                .unwrap();
+        assert_eq!(request.grant_type, "authorization_code");
+        assert_eq!(request.code.as_deref(), Some("abc"));
+        assert_eq!(request.redirect_uri.as_deref(), Some("https://x"));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Basic {}", STANDARD.encode("client:s3cr:et"))).unwrap(),
+        );
+        assert_eq!(
+            basic_credentials(&headers),
+            Some(("client".to_string(), "s3cr:et".to_string()))
+        );
+    }
 
-        let retrieved = store.validate_token(&token).await.unwrap();
-        assert_eq!(retrieved.user_id, "test-user");
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html(r#""><script>alert('x')</script>"#),
+            "&quot;&gt;&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;"
+        );
     }
 }
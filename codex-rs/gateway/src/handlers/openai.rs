@@ -0,0 +1,758 @@
+//! OpenAI-compatible facade
+//!
+//! `POST /v1/chat/completions` and `GET /v1/models` let clients written
+//! against the OpenAI Chat Completions API drive the full Codex agent. Each
+//! request becomes one Codex turn started through
+//! [`crate::services::CodexService::start_turn`]; the agent runs its tools
+//! server-side and only its messages come back as the assistant reply.
+//!
+//! ## Mapping
+//!
+//! - Without `session_id` every request starts a fresh conversation and the
+//!   whole `messages` array is rendered into the prompt: system/developer
+//!   messages first, earlier turns as a transcript, the last user message
+//!   last.
+//! - With `session_id` (a gateway extension, sent via `extra_body` in the
+//!   SDKs) the Codex conversation keeps the history, so only the messages
+//!   after the last assistant message are submitted.
+//! - `AgentMessageDelta` events stream as `chat.completion.chunk` frames;
+//!   the tokens each `TokenCount` event adds are summed into `usage` (the
+//!   repeats core sends with rate-limit updates add nothing).
+//!
+//! There is no client to answer approval requests, so any the agent raises
+//! are denied. Errors use the OpenAI `{"error": {...}}` envelope.
+
+use crate::error::GatewayError;
+use crate::handlers::exec_stream::CONVERSATION_ID_HEADER;
+use crate::middleware::api_key::ApiKeyInfo;
+use crate::middleware::rate_limit::SessionPermit;
+use crate::services::Drain;
+use crate::services::Metrics;
+use crate::services::audit::ApprovalKind;
+use crate::services::audit::AuditContext;
+use crate::services::audit::AuditEvent;
+use crate::services::audit::TurnAuditor;
+use crate::services::drain::TurnGuard;
+use crate::services::drain::interrupted_message;
+use crate::services::workspace::WorkspacePolicy;
+use crate::state::AppState;
+use axum::Extension;
+use axum::Json;
+use axum::extract::State;
+use axum::http::HeaderValue;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum::response::sse::Event;
+use axum::response::sse::KeepAlive;
+use axum::response::sse::Sse;
+use chrono::Utc;
+use codex_common::model_presets::builtin_model_presets;
+use codex_core::CodexConversation;
+use codex_protocol::ConversationId;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::Op;
+use codex_protocol::protocol::ReviewDecision;
+use codex_protocol::protocol::TokenUsage;
+use codex_protocol::user_input::UserInput;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Model name that selects the gateway's configured model
+pub const DEFAULT_MODEL_ALIAS: &str = "codex";
+
+/// Request body for `POST /v1/chat/completions`
+///
+/// Sampling parameters (`temperature`, `tools`, ...) are accepted and ignored;
+/// the agent decides how to answer and which of its own tools to use.
+#[derive(Debug, Deserialize)]
+pub struct ChatCompletionRequest {
+    /// Model override; `codex` or omitted uses the configured model
+    #[serde(default)]
+    pub model: Option<String>,
+    pub messages: Vec<ChatMessage>,
+    #[serde(default)]
+    pub stream: bool,
+    #[serde(default)]
+    pub stream_options: Option<StreamOptions>,
+    /// Gateway extension: continue a Codex session across requests
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StreamOptions {
+    #[serde(default)]
+    pub include_usage: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    #[serde(default)]
+    pub content: Option<MessageContent>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text {
+        text: String,
+    },
+    ImageUrl {
+        image_url: ImageUrl,
+    },
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImageUrl {
+    pub url: String,
+}
+
+/// `usage` object of a completion
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ChatUsage {
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub total_tokens: i64,
+    pub prompt_tokens_details: PromptTokensDetails,
+    pub completion_tokens_details: CompletionTokensDetails,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PromptTokensDetails {
+    pub cached_tokens: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CompletionTokensDetails {
+    pub reasoning_tokens: i64,
+}
+
+impl ChatUsage {
+    fn add(&mut self, usage: &TokenUsage) {
+        self.prompt_tokens += usage.input_tokens;
+        self.completion_tokens += usage.output_tokens;
+        self.total_tokens += usage.input_tokens + usage.output_tokens;
+        self.prompt_tokens_details.cached_tokens += usage.cached_input_tokens;
+        self.completion_tokens_details.reasoning_tokens += usage.reasoning_output_tokens;
+    }
+}
+
+/// GET /v1/models - Models accepted by `/v1/chat/completions`
+pub async fn handle_list_models(State(state): State<AppState>) -> Json<Value> {
+    let created = Utc::now().timestamp();
+    let configured = state.codex_service.codex_config().model.clone();
+
+    let mut ids = vec![DEFAULT_MODEL_ALIAS.to_string(), configured];
+    for preset in builtin_model_presets(None) {
+        if !ids.iter().any(|id| id == preset.model) {
+            ids.push(preset.model.to_string());
+        }
+    }
+
+    let data: Vec<Value> = ids
+        .into_iter()
+        .map(|id| {
+            json!({
+                "id": id,
+                "object": "model",
+                "created": created,
+                "owned_by": "codex-gateway",
+            })
+        })
+        .collect();
+    Json(json!({ "object": "list", "data": data }))
+}
+
+/// POST /v1/chat/completions - Run an agent turn as a chat completion
+///
+/// ## Example Request
+///
+/// ```json
+/// {
+///   "model": "codex",
+///   "messages": [
+///     {"role": "system", "content": "Answer briefly."},
+///     {"role": "user", "content": "Which files changed in the last commit?"}
+///   ],
+///   "stream": true
+/// }
+/// ```
+pub async fn handle_chat_completions(
+    State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
+    session_permit: Option<Extension<SessionPermit>>,
+    Json(request): Json<ChatCompletionRequest>,
+) -> Response {
+    let owner = caller.as_deref().map(|key| key.key_id.as_str());
+    let resume = match &request.session_id {
+        Some(session_id) => state.codex_service.session_exists(session_id, owner).await,
+        None => false,
+    };
+    let (prompt, images) = match render_prompt(&request.messages, resume) {
+        Ok(rendered) => rendered,
+        Err(message) => return openai_error(StatusCode::BAD_REQUEST, &message),
+    };
+
+    let settings = match WorkspacePolicy::for_caller(
+        state.config(),
+        state.codex_service.codex_config(),
+        caller.as_deref(),
+    )
+    .and_then(|policy| policy.resolve(None, None, None))
+    {
+        Ok(settings) => settings,
+        Err(err) => return gateway_error(err),
+    };
+
+    let model = request
+        .model
+        .filter(|model| !model.is_empty() && model != DEFAULT_MODEL_ALIAS)
+        .unwrap_or_else(|| state.codex_service.codex_config().model.clone());
+
+    let mut items: Vec<UserInput> = images
+        .into_iter()
+        .map(|image_url| UserInput::Image { image_url })
+        .collect();
+    items.push(UserInput::Text {
+        text: prompt.clone(),
+    });
+
+    info!(
+        "Chat completion request: model={}, messages={}, stream={}, session_id={:?}",
+        model,
+        request.messages.len(),
+        request.stream,
+        request.session_id
+    );
+
+    let (conversation_id, conversation, guard) = match state
+        .codex_service
+        .start_turn(
+            request.session_id.as_deref(),
+            owner,
+            items,
+            settings,
+            Some(model.clone()),
+        )
+        .await
+    {
+        Ok(started) => started,
+        Err(err) => return gateway_error(err),
+    };
+
+    let auditor = state
+        .codex_service
+        .audit()
+        .start_turn(
+            AuditContext::new(owner, request.session_id.as_deref(), conversation_id),
+            &prompt,
+        )
+        .await;
+    let turn = ChatTurn {
+        conversation_id,
+        conversation,
+        metrics: state.metrics.clone(),
+        auditor,
+        drain: state.codex_service.drain().clone(),
+        shutdown_message: interrupted_message(conversation_id, request.session_id.as_deref()),
+        _guard: guard,
+        usage: ChatUsage::default(),
+        in_message: false,
+        emitted: false,
+        finished: false,
+    };
+    let completion_id = format!("chatcmpl-{}", Uuid::new_v4().simple());
+    let mut response = if request.stream {
+        let include_usage = request
+            .stream_options
+            .is_some_and(|options| options.include_usage);
+        let stream = ChunkStream {
+            turn,
+            completion_id,
+            model,
+            created: Utc::now().timestamp(),
+            include_usage,
+            phase: ChunkPhase::Role,
+            _session_permit: session_permit.map(|Extension(permit)| permit),
+        };
+        stream_response(stream)
+    } else {
+        complete(turn, completion_id, model).await
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&conversation_id.to_string()) {
+        response.headers_mut().insert(CONVERSATION_ID_HEADER, value);
+    }
+    response
+}
+
+/// Render `messages` into one Codex prompt plus image URLs
+///
+/// When `resume` is set the Codex conversation already holds the history, so
+/// only the messages after the last assistant reply are used.
+fn render_prompt(messages: &[ChatMessage], resume: bool) -> Result<(String, Vec<String>), String> {
+    let Some(last) = messages.last() else {
+        return Err("messages must not be empty".to_string());
+    };
+    if last.role != "user" {
+        return Err("the last message must have role 'user'".to_string());
+    }
+
+    let mut images = Vec::new();
+    let mut text_of = |message: &ChatMessage| -> String {
+        match &message.content {
+            None => String::new(),
+            Some(MessageContent::Text(text)) => text.clone(),
+            Some(MessageContent::Parts(parts)) => {
+                let mut texts = Vec::new();
+                for part in parts {
+                    match part {
+                        ContentPart::Text { text } => texts.push(text.clone()),
+                        ContentPart::ImageUrl { image_url } if message.role == "user" => {
+                            images.push(image_url.url.clone());
+                        }
+                        ContentPart::ImageUrl { .. } | ContentPart::Unsupported => {}
+                    }
+                }
+                texts.join("\n")
+            }
+        }
+    };
+
+    if resume {
+        let start = messages
+            .iter()
+            .rposition(|message| message.role == "assistant")
+            .map_or(0, |index| index + 1);
+        let prompt = messages[start..]
+            .iter()
+            .map(&mut text_of)
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        return Ok((prompt, images));
+    }
+
+    let mut instructions = Vec::new();
+    let mut transcript = Vec::new();
+    for message in &messages[..messages.len() - 1] {
+        let text = text_of(message);
+        if text.is_empty() {
+            continue;
+        }
+        match message.role.as_str() {
+            "system" | "developer" => instructions.push(text),
+            role => transcript.push(format!("{role}: {text}")),
+        }
+    }
+    let question = text_of(last);
+
+    let mut sections = Vec::new();
+    if !instructions.is_empty() {
+        sections.push(instructions.join("\n\n"));
+    }
+    if !transcript.is_empty() {
+        sections.push(format!("Conversation so far:\n{}", transcript.join("\n")));
+    }
+    sections.push(question);
+    Ok((sections.join("\n\n"), images))
+}
+
+/// Progress of the agent turn behind one completion
+enum TurnUpdate {
+    /// Text to append to the assistant message
+    Content(String),
+    /// The turn finished successfully
+    Done,
+    /// The turn failed or was aborted
+    Failed(String),
+}
+
+/// Reads the Codex event feed of one turn
+struct ChatTurn {
+    conversation_id: ConversationId,
+    conversation: Arc<CodexConversation>,
+    metrics: Metrics,
+    auditor: TurnAuditor,
+    drain: Drain,
+    /// Failure reported if a shutdown interrupts the turn
+    shutdown_message: String,
+    _guard: TurnGuard,
+    usage: ChatUsage,
+    /// Whether the current agent message has streamed any delta
+    in_message: bool,
+    /// Whether any agent message was emitted, to separate consecutive ones
+    emitted: bool,
+    finished: bool,
+}
+
+impl ChatTurn {
+    async fn next(&mut self) -> TurnUpdate {
+        loop {
+            let event = match self.conversation.next_event().await {
+                Ok(event) => event,
+                Err(err) => {
+                    self.finished = true;
+                    return TurnUpdate::Failed(format!("Failed to read agent events: {err}"));
+                }
+            };
+            let usage = self.metrics.observe_event(self.conversation_id, &event.msg);
+            self.auditor.observe(&event, usage.as_ref()).await;
+            if let Some(usage) = &usage {
+                self.usage.add(usage);
+            }
+
+            match event.msg {
+                EventMsg::AgentMessageDelta(delta) => {
+                    let mut text = String::new();
+                    if !self.in_message && self.emitted {
+                        text.push_str("\n\n");
+                    }
+                    self.in_message = true;
+                    self.emitted = true;
+                    text.push_str(&delta.delta);
+                    return TurnUpdate::Content(text);
+                }
+                EventMsg::AgentMessage(message) => {
+                    // Already streamed as deltas
+                    if std::mem::take(&mut self.in_message) {
+                        continue;
+                    }
+                    let separator = if self.emitted { "\n\n" } else { "" };
+                    self.emitted = true;
+                    return TurnUpdate::Content(format!("{separator}{}", message.message));
+                }
+                EventMsg::ExecApprovalRequest(_) => {
+                    self.deny(ApprovalKind::Exec, event.id).await;
+                }
+                EventMsg::ApplyPatchApprovalRequest(_) => {
+                    self.deny(ApprovalKind::Patch, event.id).await;
+                }
+                EventMsg::McpToolCallApprovalRequest(_) => {
+                    self.deny(ApprovalKind::McpToolCall, event.id).await;
+                }
+                EventMsg::TaskComplete(_) => {
+                    self.finished = true;
+                    return TurnUpdate::Done;
+                }
+                EventMsg::Error(error) => {
+                    self.finished = true;
+                    return TurnUpdate::Failed(error.message);
+                }
+                EventMsg::TurnAborted(_) if self.drain.is_draining() => {
+                    self.finished = true;
+                    return TurnUpdate::Failed(self.shutdown_message.clone());
+                }
+                EventMsg::TurnAborted(_) => {
+                    self.finished = true;
+                    return TurnUpdate::Failed("turn aborted".to_string());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    async fn deny(&self, kind: ApprovalKind, id: String) {
+        warn!("Denying approval request: no client can answer it on /v1/chat/completions");
+        self.auditor
+            .record(AuditEvent::ApprovalDecision {
+                approval_id: id.clone(),
+                kind,
+                decision: ReviewDecision::Denied,
+                reason: Some("no client can answer approvals on /v1/chat/completions".to_string()),
+            })
+            .await;
+        let op = match kind {
+            ApprovalKind::Exec => Op::ExecApproval {
+                id,
+                decision: ReviewDecision::Denied,
+            },
+            ApprovalKind::Patch => Op::PatchApproval {
+                id,
+                decision: ReviewDecision::Denied,
+            },
+            ApprovalKind::McpToolCall => Op::McpToolCallApproval {
+                id,
+                decision: ReviewDecision::Denied,
+            },
+        };
+        if let Err(err) = self.conversation.submit(op).await {
+            warn!("Failed to deny approval request: {err}");
+        }
+    }
+}
+
+impl Drop for ChatTurn {
+    /// Stop the agent when the client goes away mid-turn
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        let conversation = Arc::clone(&self.conversation);
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(async move {
+                if let Err(err) = conversation.submit(Op::Interrupt).await {
+                    warn!("Failed to interrupt abandoned chat completion: {err}");
+                }
+            });
+        }
+    }
+}
+
+/// Run the turn to completion and build a `chat.completion` response
+async fn complete(mut turn: ChatTurn, completion_id: String, model: String) -> Response {
+    let mut content = String::new();
+    loop {
+        match turn.next().await {
+            TurnUpdate::Content(text) => content.push_str(&text),
+            TurnUpdate::Done => break,
+            TurnUpdate::Failed(message) => {
+                return openai_error(StatusCode::INTERNAL_SERVER_ERROR, &message);
+            }
+        }
+    }
+
+    let body = json!({
+        "id": completion_id,
+        "object": "chat.completion",
+        "created": Utc::now().timestamp(),
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": {"role": "assistant", "content": content},
+            "finish_reason": "stop",
+        }],
+        "usage": turn.usage,
+    });
+    (StatusCode::OK, Json(body)).into_response()
+}
+
+/// Where a chunk stream is in its sequence of frames
+enum ChunkPhase {
+    /// First frame announces the assistant role
+    Role,
+    Content,
+    /// Turn finished; the usage frame (if requested) and `[DONE]` remain
+    Usage,
+    Done,
+    Closed,
+}
+
+/// State for a streamed completion
+struct ChunkStream {
+    turn: ChatTurn,
+    completion_id: String,
+    model: String,
+    created: i64,
+    include_usage: bool,
+    phase: ChunkPhase,
+    /// Keeps the per-key session slot busy while the stream is alive
+    _session_permit: Option<SessionPermit>,
+}
+
+impl ChunkStream {
+    fn chunk(&self, delta: Value, finish_reason: Option<&str>) -> Value {
+        json!({
+            "id": self.completion_id,
+            "object": "chat.completion.chunk",
+            "created": self.created,
+            "model": self.model,
+            "choices": [{
+                "index": 0,
+                "delta": delta,
+                "finish_reason": finish_reason,
+            }],
+        })
+    }
+
+    /// Next SSE payload, or `None` once `[DONE]` was sent
+    async fn next_frame(&mut self) -> Option<String> {
+        match self.phase {
+            ChunkPhase::Role => {
+                self.phase = ChunkPhase::Content;
+                Some(
+                    self.chunk(json!({"role": "assistant", "content": ""}), None)
+                        .to_string(),
+                )
+            }
+            ChunkPhase::Content => match self.turn.next().await {
+                TurnUpdate::Content(text) => {
+                    Some(self.chunk(json!({"content": text}), None).to_string())
+                }
+                TurnUpdate::Done => {
+                    self.phase = ChunkPhase::Usage;
+                    Some(self.chunk(json!({}), Some("stop")).to_string())
+                }
+                TurnUpdate::Failed(message) => {
+                    self.phase = ChunkPhase::Done;
+                    Some(json!({"error": {"message": message, "type": "server_error"}}).to_string())
+                }
+            },
+            ChunkPhase::Usage => {
+                self.phase = ChunkPhase::Done;
+                if self.include_usage {
+                    Some(
+                        json!({
+                            "id": self.completion_id,
+                            "object": "chat.completion.chunk",
+                            "created": self.created,
+                            "model": self.model,
+                            "choices": [],
+                            "usage": self.turn.usage,
+                        })
+                        .to_string(),
+                    )
+                } else {
+                    self.phase = ChunkPhase::Closed;
+                    Some("[DONE]".to_string())
+                }
+            }
+            ChunkPhase::Done => {
+                self.phase = ChunkPhase::Closed;
+                Some("[DONE]".to_string())
+            }
+            ChunkPhase::Closed => None,
+        }
+    }
+}
+
+fn stream_response(stream: ChunkStream) -> Response {
+    let frames = futures::stream::unfold(stream, |mut stream| async move {
+        let frame = stream.next_frame().await?;
+        Some((Ok::<_, axum::Error>(Event::default().data(frame)), stream))
+    });
+    Sse::new(frames)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// Error in the OpenAI `{"error": {...}}` envelope
+fn openai_error(status: StatusCode, message: &str) -> Response {
+    let error_type = match status {
+        StatusCode::BAD_REQUEST | StatusCode::NOT_FOUND => "invalid_request_error",
+        StatusCode::UNAUTHORIZED => "authentication_error",
+        StatusCode::FORBIDDEN => "permission_error",
+        _ => "server_error",
+    };
+    (
+        status,
+        Json(json!({
+            "error": {
+                "message": message,
+                "type": error_type,
+                "param": null,
+                "code": null,
+            }
+        })),
+    )
+        .into_response()
+}
+
+fn gateway_error(err: GatewayError) -> Response {
+    let status = match &err {
+        GatewayError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+        GatewayError::NotFound(_) => StatusCode::NOT_FOUND,
+        GatewayError::Auth(_) => StatusCode::UNAUTHORIZED,
+        GatewayError::Forbidden(_) => StatusCode::FORBIDDEN,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    openai_error(status, &err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: &str) -> ChatMessage {
+        ChatMessage {
+            role: role.to_string(),
+            content: Some(MessageContent::Text(content.to_string())),
+        }
+    }
+
+    #[test]
+    fn test_render_prompt_new_conversation() {
+        let messages = vec![
+            message("system", "Be brief."),
+            message("user", "hi"),
+            message("assistant", "hello"),
+            message("user", "list files"),
+        ];
+        let (prompt, images) = render_prompt(&messages, false).unwrap();
+        assert_eq!(
+            prompt,
+            "Be brief.\n\nConversation so far:\nuser: hi\nassistant: hello\n\nlist files"
+        );
+        assert!(images.is_empty());
+    }
+
+    #[test]
+    fn test_render_prompt_resumed_conversation() {
+        let messages = vec![
+            message("user", "hi"),
+            message("assistant", "hello"),
+            message("user", "list files"),
+        ];
+        let (prompt, _) = render_prompt(&messages, true).unwrap();
+        assert_eq!(prompt, "list files");
+
+        assert!(render_prompt(&[], false).is_err());
+        assert!(render_prompt(&[message("assistant", "x")], false).is_err());
+    }
+
+    #[test]
+    fn test_parse_content_parts() {
+        let request: ChatCompletionRequest = serde_json::from_value(json!({
+            "model": "codex",
+            "messages": [{
+                "role": "user",
+                "content": [
+                    {"type": "text", "text": "what is this?"},
+                    {"type": "image_url", "image_url": {"url": "data:image/png;base64,AAAA"}},
+                    {"type": "input_audio", "input_audio": {}}
+                ]
+            }],
+            "temperature": 0.2
+        }))
+        .unwrap();
+        let (prompt, images) = render_prompt(&request.messages, false).unwrap();
+        assert_eq!(prompt, "what is this?");
+        assert_eq!(images, vec!["data:image/png;base64,AAAA".to_string()]);
+    }
+
+    #[test]
+    fn test_usage_accumulates() {
+        let mut usage = ChatUsage::default();
+        let step = TokenUsage {
+            input_tokens: 100,
+            cached_input_tokens: 40,
+            output_tokens: 20,
+            reasoning_output_tokens: 5,
+            total_tokens: 120,
+        };
+        usage.add(&step);
+        usage.add(&step);
+        assert_eq!(usage.prompt_tokens, 200);
+        assert_eq!(usage.completion_tokens, 40);
+        assert_eq!(usage.total_tokens, 240);
+        assert_eq!(usage.prompt_tokens_details.cached_tokens, 80);
+        assert_eq!(usage.completion_tokens_details.reasoning_tokens, 10);
+    }
+}
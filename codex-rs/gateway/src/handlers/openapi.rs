@@ -0,0 +1,391 @@
+//! OpenAPI 3.1 description of the REST surface
+//!
+//! `GET /openapi.json` (no auth required) serves a document whose schemas are
+//! generated from the Rust request/response types with `schemars`, so GPT
+//! Actions and client generators stay in sync with the handlers. Paths and
+//! operations are listed here by hand; when a route's types change, its
+//! schema follows automatically.
+
+use crate::error::ErrorResponse;
+use crate::handlers::exec::ExecRequest;
+use crate::handlers::exec::ExecResponse;
+use crate::handlers::exec::ResumeRequest;
+use crate::handlers::exec::ResumeResponse;
+use crate::handlers::jobs::CreateJobResponse;
+use crate::handlers::jsonrpc::JsonRpcRequest;
+use crate::handlers::jsonrpc::JsonRpcResponse;
+use crate::handlers::oauth::TokenRequest;
+use crate::handlers::oauth::TokenResponse;
+use crate::handlers::webhook::WebhookResponse;
+use crate::state::AppState;
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::http::header::HOST;
+use axum::response::Json;
+use codex_exec::exec_events::ThreadEvent;
+use schemars::JsonSchema;
+use schemars::r#gen::SchemaGenerator;
+use schemars::r#gen::SchemaSettings;
+use serde_json::Value;
+use serde_json::json;
+
+/// Where generated schemas live in the document
+const SCHEMAS_PATH: &str = "#/components/schemas/";
+
+/// GET /openapi.json - OpenAPI 3.1 document of the gateway
+///
+/// The `servers` entry points at the host the document was fetched from,
+/// over HTTPS when TLS is configured or a proxy reports `X-Forwarded-Proto:
+/// https`.
+pub async fn handle_openapi(State(state): State<AppState>, headers: HeaderMap) -> Json<Value> {
+    let host = headers.get(HOST).and_then(|value| value.to_str().ok());
+    let https = state.config().tls.is_some()
+        || headers
+            .get("x-forwarded-proto")
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|proto| proto.eq_ignore_ascii_case("https"));
+    let server = host.map(|host| {
+        let scheme = if https { "https" } else { "http" };
+        format!("{scheme}://{host}")
+    });
+    Json(openapi_document(server.as_deref()))
+}
+
+/// Build the document, optionally with a `servers` entry for `server_url`
+pub fn openapi_document(server_url: Option<&str>) -> Value {
+    let mut generator = SchemaSettings::draft2019_09()
+        .with(|settings| {
+            settings.definitions_path = SCHEMAS_PATH.to_string();
+            settings.meta_schema = None;
+        })
+        .into_generator();
+
+    let exec_request = schema_ref::<ExecRequest>(&mut generator);
+    let exec_response = schema_ref::<ExecResponse>(&mut generator);
+    let thread_event = schema_ref::<ThreadEvent>(&mut generator);
+    let resume_request = schema_ref::<ResumeRequest>(&mut generator);
+    let resume_response = schema_ref::<ResumeResponse>(&mut generator);
+    let create_job_response = schema_ref::<CreateJobResponse>(&mut generator);
+    let jsonrpc_request = schema_ref::<JsonRpcRequest>(&mut generator);
+    let jsonrpc_response = schema_ref::<JsonRpcResponse>(&mut generator);
+    let webhook_response = schema_ref::<WebhookResponse>(&mut generator);
+    let token_request = schema_ref::<TokenRequest>(&mut generator);
+    let token_response = schema_ref::<TokenResponse>(&mut generator);
+    let error_response = schema_ref::<ErrorResponse>(&mut generator);
+
+    let schemas: serde_json::Map<String, Value> = generator
+        .take_definitions()
+        .into_iter()
+        .map(|(name, schema)| (name, serde_json::to_value(schema).unwrap_or(Value::Null)))
+        .collect();
+
+    // Exec requests are JSON, or multipart with the same JSON in a `request` part
+    let exec_body = json!({
+        "required": true,
+        "content": {
+            "application/json": { "schema": exec_request },
+            "multipart/form-data": {
+                "schema": {
+                    "type": "object",
+                    "properties": {
+                        "request": exec_request,
+                        "prompt": { "type": "string" },
+                        "image": {
+                            "type": "array",
+                            "items": { "type": "string", "contentMediaType": "image/*" }
+                        },
+                        "file": {
+                            "type": "array",
+                            "items": {
+                                "type": "string",
+                                "contentMediaType": "application/octet-stream"
+                            }
+                        }
+                    }
+                },
+                "encoding": { "request": { "contentType": "application/json" } }
+            }
+        }
+    });
+    let event_stream = json!({
+        "description": "One `ThreadEvent` per SSE `data:` frame",
+        "content": { "text/event-stream": { "schema": thread_event } }
+    });
+    let error = json!({ "$ref": "#/components/responses/Error" });
+
+    let paths = json!({
+        "/exec": {
+            "post": {
+                "operationId": "exec",
+                "summary": "Run a prompt and return every event of the turn",
+                "description": "Send `Accept: text/event-stream` to stream the events instead.",
+                "requestBody": exec_body,
+                "responses": {
+                    "200": {
+                        "description": "The finished turn",
+                        "content": {
+                            "application/json": { "schema": exec_response },
+                            "text/event-stream": { "schema": thread_event }
+                        }
+                    },
+                    "default": error
+                }
+            }
+        },
+        "/exec/stream": {
+            "post": {
+                "operationId": "execStream",
+                "summary": "Run a prompt and stream its events as Server-Sent Events",
+                "requestBody": exec_body,
+                "responses": { "200": event_stream, "default": error }
+            }
+        },
+        "/exec/resume": {
+            "post": {
+                "operationId": "execResume",
+                "summary": "Bind a stored conversation to a session id",
+                "requestBody": {
+                    "required": true,
+                    "content": { "application/json": { "schema": resume_request } }
+                },
+                "responses": {
+                    "200": {
+                        "description": "The conversation was resumed",
+                        "content": { "application/json": { "schema": resume_response } }
+                    },
+                    "default": error
+                }
+            }
+        },
+        "/jobs": {
+            "post": {
+                "operationId": "createJob",
+                "summary": "Queue a prompt to run in the background",
+                "requestBody": exec_body,
+                "responses": {
+                    "202": {
+                        "description": "The job was queued",
+                        "content": { "application/json": { "schema": create_job_response } }
+                    },
+                    "default": error
+                }
+            }
+        },
+        "/jsonrpc": {
+            "post": {
+                "operationId": "jsonrpc",
+                "summary": "JSON-RPC 2.0 (`conversation.prompt`, `conversation.status`, `conversation.cancel`)",
+                "requestBody": {
+                    "required": true,
+                    "content": { "application/json": { "schema": jsonrpc_request } }
+                },
+                "responses": {
+                    "200": {
+                        "description": "JSON-RPC result or error",
+                        "content": { "application/json": { "schema": jsonrpc_response } }
+                    },
+                    "default": error
+                }
+            }
+        },
+        "/webhook": {
+            "post": {
+                "operationId": "webhook",
+                "summary": "Receive a signed webhook delivery and queue its routed prompt",
+                "security": [{}, { "apiKey": [] }],
+                "requestBody": {
+                    "required": true,
+                    "content": { "application/json": { "schema": {} } }
+                },
+                "responses": {
+                    "202": {
+                        "description": "The delivery was queued or ignored",
+                        "content": { "application/json": { "schema": webhook_response } }
+                    },
+                    "default": error
+                }
+            }
+        },
+        "/oauth/authorize": {
+            "get": {
+                "operationId": "oauthAuthorize",
+                "summary": "OAuth 2.0 authorization endpoint (consent page)",
+                "security": [],
+                "parameters": [
+                    query_parameter("response_type", true),
+                    query_parameter("client_id", true),
+                    query_parameter("redirect_uri", true),
+                    query_parameter("state", false),
+                    query_parameter("scope", false),
+                    query_parameter("code_challenge", false),
+                    query_parameter("code_challenge_method", false)
+                ],
+                "responses": {
+                    "200": {
+                        "description": "Consent page",
+                        "content": { "text/html": { "schema": { "type": "string" } } }
+                    },
+                    "302": { "description": "Redirect back to the client with an error" },
+                    "400": {
+                        "description": "Untrusted client or redirect URI",
+                        "content": { "text/html": { "schema": { "type": "string" } } }
+                    }
+                }
+            }
+        },
+        "/oauth/token": {
+            "post": {
+                "operationId": "oauthToken",
+                "summary": "OAuth 2.0 token endpoint",
+                "security": [],
+                "requestBody": {
+                    "required": true,
+                    "content": {
+                        "application/x-www-form-urlencoded": { "schema": token_request },
+                        "application/json": { "schema": token_request }
+                    }
+                },
+                "responses": {
+                    "200": {
+                        "description": "Issued tokens",
+                        "content": { "application/json": { "schema": token_response } }
+                    },
+                    "400": {
+                        "description": "RFC 6749 error (`invalid_grant`, `invalid_client`, ...)",
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "properties": {
+                                        "error": { "type": "string" },
+                                        "error_description": { "type": "string" }
+                                    },
+                                    "required": ["error"]
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    let mut document = json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "Codex Gateway",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "HTTP gateway to the Codex agent."
+        },
+        "security": [
+            { "apiKey": [] },
+            { "bearer": [] },
+            { "oauth2": ["agent"] }
+        ],
+        "paths": paths,
+        "components": {
+            "schemas": schemas,
+            "responses": {
+                "Error": {
+                    "description": "Error",
+                    "content": { "application/json": { "schema": error_response } }
+                }
+            },
+            "securitySchemes": {
+                "apiKey": { "type": "apiKey", "in": "header", "name": "X-API-Key" },
+                "bearer": { "type": "http", "scheme": "bearer" },
+                "oauth2": {
+                    "type": "oauth2",
+                    "flows": {
+                        "authorizationCode": {
+                            "authorizationUrl": "/oauth/authorize",
+                            "tokenUrl": "/oauth/token",
+                            "refreshUrl": "/oauth/token",
+                            "scopes": {
+                                "agent": "Run the agent",
+                                "admin": "Manage keys and clients"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    });
+    if let Some(url) = server_url {
+        document["servers"] = json!([{ "url": url }]);
+    }
+    document
+}
+
+/// Schema of `T` as a reference into `components/schemas`
+fn schema_ref<T: JsonSchema>(generator: &mut SchemaGenerator) -> Value {
+    serde_json::to_value(generator.subschema_for::<T>()).unwrap_or(Value::Null)
+}
+
+fn query_parameter(name: &str, required: bool) -> Value {
+    json!({
+        "name": name,
+        "in": "query",
+        "required": required,
+        "schema": { "type": "string" }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every `$ref` in `value`
+    fn refs<'a>(value: &'a Value, out: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    match (key.as_str(), value) {
+                        ("$ref", Value::String(target)) => out.push(target),
+                        _ => refs(value, out),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|item| refs(item, out)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_document_references_resolve() {
+        let document = openapi_document(Some("https://gateway.example"));
+        assert_eq!(document["openapi"], "3.1.0");
+        assert_eq!(document["servers"][0]["url"], "https://gateway.example");
+        for path in [
+            "/exec",
+            "/exec/resume",
+            "/jsonrpc",
+            "/webhook",
+            "/oauth/token",
+        ] {
+            assert!(document["paths"][path].is_object(), "{path}");
+        }
+
+        let schemas = &document["components"]["schemas"];
+        for name in [
+            "ExecRequest",
+            "ExecResponse",
+            "ThreadEvent",
+            "ErrorResponse",
+        ] {
+            assert!(schemas[name].is_object(), "{name}");
+        }
+        // Multipart parts are not part of the JSON body
+        assert!(schemas["ExecRequest"]["properties"]["uploads"].is_null());
+
+        let mut targets = Vec::new();
+        refs(&document, &mut targets);
+        assert!(!targets.is_empty());
+        for target in targets {
+            let resolved = target
+                .strip_prefix("#/")
+                .map(|pointer| document.pointer(&format!("/{pointer}")));
+            assert!(matches!(resolved, Some(Some(_))), "dangling {target}");
+        }
+    }
+}
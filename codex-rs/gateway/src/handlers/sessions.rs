@@ -0,0 +1,431 @@
+//! Session management
+//!
+//! `GET /sessions` lists the sessions created by the calling key and
+//! `DELETE /sessions/{id}` closes one. Sessions are recorded by
+//! [`crate::services::CodexService`] in the durable session registry, so the
+//! list includes sessions that have not been used since the last restart.
+//!
+//! The artifact endpoints expose what a session's turns changed, as recorded
+//! by [`crate::services::ArtifactStore`]:
+//!
+//! - `GET /sessions/{id}/diff` - unified diff, ready for `git apply`
+//! - `GET /sessions/{id}/files` - changed files; `?path=` downloads one file
+//!   from the session workspace
+//! - `GET /sessions/{id}/archive` - changed files as a `.tar.gz`
+//!
+//! Each takes `?scope=last_turn` (the default) or `?scope=session`. Files are
+//! served from the `cwd` of the session's latest turn and must stay inside it
+//! and inside the caller's tenant workspace. Downloads and archives are built
+//! in memory, so both are refused with `413` above `max_download_size`.
+
+use crate::error::GatewayError;
+use crate::error::GatewayResult;
+use crate::middleware::api_key::ApiKeyInfo;
+use crate::services::artifacts::SessionArtifacts;
+use crate::services::artifacts::build_archive;
+use crate::services::session_registry::SessionRecord;
+use crate::services::workspace::WorkspacePolicy;
+use crate::state::AppState;
+use axum::Extension;
+use axum::extract::Path;
+use axum::extract::Query;
+use axum::extract::State;
+use axum::http::HeaderValue;
+use axum::http::StatusCode;
+use axum::http::header::CONTENT_DISPOSITION;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::response::Json;
+use axum::response::Response;
+use serde::Deserialize;
+use serde::Serialize;
+use std::path::PathBuf;
+use tokio::io::AsyncReadExt;
+use tracing::debug;
+use tracing::info;
+
+/// Response for `GET /sessions`
+#[derive(Debug, Serialize)]
+pub struct SessionListResponse {
+    pub sessions: Vec<SessionRecord>,
+}
+
+/// GET /sessions - List the caller's sessions, most recently used first
+///
+/// ## Example Response
+///
+/// ```json
+/// {
+///   "sessions": [
+///     {
+///       "session_id": "my-session",
+///       "conversation_id": "550e8400-e29b-41d4-a716-446655440000",
+///       "rollout_path": "/home/user/.codex/sessions/2025/01/01/rollout-....jsonl",
+///       "owner": "key_001",
+///       "created_at": "2025-01-01T12:00:00Z",
+///       "last_used_at": "2025-01-01T12:30:00Z"
+///     }
+///   ]
+/// }
+/// ```
+pub async fn handle_list_sessions(
+    State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
+) -> Json<SessionListResponse> {
+    let owner = caller.as_deref().map(|key| key.key_id.as_str());
+    let sessions = state.codex_service.list_sessions(owner).await;
+    Json(SessionListResponse { sessions })
+}
+
+/// DELETE /sessions/{id} - Close a session and forget it
+///
+/// The conversation's rollout stays on disk and can still be resumed with
+/// `POST /exec/resume`. Sessions of other keys are reported as not found.
+pub async fn handle_delete_session(
+    State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
+    Path(session_id): Path<String>,
+) -> GatewayResult<StatusCode> {
+    let owner = caller.as_deref().map(|key| key.key_id.as_str());
+    match state
+        .codex_service
+        .cancel_session(&session_id, owner)
+        .await?
+    {
+        Some(conversation_id) => {
+            info!("Session {session_id} deleted: conversation_id={conversation_id}");
+            Ok(StatusCode::NO_CONTENT)
+        }
+        None => Err(GatewayError::NotFound(format!("Session {session_id}"))),
+    }
+}
+
+/// Which turns an artifact request covers
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactScope {
+    /// The latest recorded turn
+    #[default]
+    LastTurn,
+    /// Every recorded turn of the session
+    Session,
+}
+
+/// Query parameters for the artifact endpoints
+#[derive(Debug, Default, Deserialize)]
+pub struct ArtifactQuery {
+    #[serde(default)]
+    pub scope: ArtifactScope,
+    /// File to download, relative to the session workspace (`/files` only)
+    pub path: Option<PathBuf>,
+}
+
+/// Response for `GET /sessions/{id}/files` without `path`
+#[derive(Debug, Serialize)]
+pub struct ChangedFilesResponse {
+    /// Session workspace the paths are relative to
+    pub cwd: PathBuf,
+    pub files: Vec<PathBuf>,
+}
+
+/// GET /sessions/{id}/diff - Unified diff of the last turn or the whole session
+///
+/// Answers `204 No Content` when the selected turns changed nothing, so the
+/// body can be piped straight into `git apply`:
+///
+/// ```text
+/// curl -H "X-API-Key: $KEY" "$GATEWAY/sessions/my-session/diff?scope=session" | git apply
+/// ```
+pub async fn handle_session_diff(
+    State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
+    Path(session_id): Path<String>,
+    Query(query): Query<ArtifactQuery>,
+) -> GatewayResult<Response> {
+    let artifacts = session_artifacts(&state, caller.as_deref(), &session_id).await?;
+    let diff = artifacts
+        .map(|artifacts| artifacts.diff(query.scope == ArtifactScope::Session))
+        .unwrap_or_default();
+    if diff.is_empty() {
+        return Ok(StatusCode::NO_CONTENT.into_response());
+    }
+
+    let mut response = (StatusCode::OK, diff).into_response();
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("text/x-diff; charset=utf-8"),
+    );
+    Ok(response)
+}
+
+/// GET /sessions/{id}/files - List changed files, or download one with `?path=`
+///
+/// ## Example Response
+///
+/// ```json
+/// {
+///   "cwd": "/srv/workspaces/acme/repo",
+///   "files": ["src/lib.rs", "tests/smoke.rs"]
+/// }
+/// ```
+///
+/// Any file in the session workspace can be downloaded, changed or not.
+pub async fn handle_session_files(
+    State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
+    Path(session_id): Path<String>,
+    Query(query): Query<ArtifactQuery>,
+) -> GatewayResult<Response> {
+    let artifacts = session_artifacts(&state, caller.as_deref(), &session_id)
+        .await?
+        .ok_or_else(|| no_turns(&session_id))?;
+    let workspace = Workspace::for_session(&state, caller.as_deref(), &artifacts)?;
+
+    let Some(path) = query.path else {
+        let files = artifacts
+            .changed_files(query.scope == ArtifactScope::Session)
+            .into_iter()
+            .map(|file| match file.strip_prefix(&workspace.cwd) {
+                Ok(relative) => relative.to_path_buf(),
+                Err(_) => file,
+            })
+            .collect();
+        return Ok(Json(ChangedFilesResponse {
+            cwd: workspace.cwd,
+            files,
+        })
+        .into_response());
+    };
+
+    let file = workspace.resolve(&path).ok_or_else(|| {
+        GatewayError::NotFound(format!(
+            "{} is not a file in the workspace of session {session_id}",
+            path.display()
+        ))
+    })?;
+    let contents = read_capped(&file, state.config().max_download_size).await?;
+    let name = file
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Ok(attachment(contents, "application/octet-stream", &name))
+}
+
+/// GET /sessions/{id}/archive - Changed files of the last turn or the whole
+/// session as a gzip-compressed tarball
+///
+/// Entries are named relative to the session workspace. Files that were
+/// deleted afterwards or lie outside the workspace are left out.
+pub async fn handle_session_archive(
+    State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
+    Path(session_id): Path<String>,
+    Query(query): Query<ArtifactQuery>,
+) -> GatewayResult<Response> {
+    let artifacts = session_artifacts(&state, caller.as_deref(), &session_id)
+        .await?
+        .ok_or_else(|| no_turns(&session_id))?;
+    let workspace = Workspace::for_session(&state, caller.as_deref(), &artifacts)?;
+
+    let max_size = state.config().max_download_size;
+    let mut entries = Vec::new();
+    let mut total_size = 0u64;
+    for file in artifacts.changed_files(query.scope == ArtifactScope::Session) {
+        match workspace.resolve(&file) {
+            Some(resolved) => {
+                let name = resolved
+                    .strip_prefix(&workspace.cwd)
+                    .map(|name| name.to_path_buf())
+                    .unwrap_or_default();
+                total_size += std::fs::metadata(&resolved).map_or(0, |meta| meta.len());
+                check_download_size(total_size, max_size, "archive")?;
+                entries.push((resolved, name));
+            }
+            None => debug!("Leaving {} out of the archive", file.display()),
+        }
+    }
+
+    let archive = tokio::task::spawn_blocking(move || build_archive(&entries))
+        .await
+        .map_err(|e| GatewayError::Internal(format!("archive task failed: {e}")))?
+        .map_err(|e| GatewayError::Internal(format!("failed to build archive: {e}")))?;
+    let name = format!("{}.tar.gz", sanitize_file_name(&session_id));
+    Ok(attachment(archive, "application/gzip", &name))
+}
+
+/// Artifacts of a session owned by `caller`
+///
+/// `Ok(None)` means the session exists but none of its turns was recorded.
+async fn session_artifacts(
+    state: &AppState,
+    caller: Option<&ApiKeyInfo>,
+    session_id: &str,
+) -> GatewayResult<Option<SessionArtifacts>> {
+    let owner = caller.map(|key| key.key_id.as_str());
+    if !state.codex_service.session_exists(session_id, owner).await {
+        return Err(GatewayError::NotFound(format!("Session {session_id}")));
+    }
+    Ok(state.codex_service.artifacts().get(session_id).await)
+}
+
+fn no_turns(session_id: &str) -> GatewayError {
+    GatewayError::NotFound(format!("Session {session_id} has no recorded turns"))
+}
+
+/// Directory files of a session are served from
+struct Workspace {
+    /// Canonical `cwd` of the session's latest turn
+    cwd: PathBuf,
+    policy: WorkspacePolicy,
+}
+
+impl Workspace {
+    fn for_session(
+        state: &AppState,
+        caller: Option<&ApiKeyInfo>,
+        artifacts: &SessionArtifacts,
+    ) -> GatewayResult<Self> {
+        let policy = WorkspacePolicy::for_caller(
+            state.config(),
+            state.codex_service.codex_config(),
+            caller,
+        )?;
+        let cwd = artifacts
+            .cwd()
+            .and_then(|cwd| std::fs::canonicalize(cwd).ok())
+            .ok_or_else(|| {
+                GatewayError::NotFound(format!(
+                    "workspace of session {} is no longer available",
+                    artifacts.session_id
+                ))
+            })?;
+        // The tenant may have been moved since the turn ran
+        let cwd = policy.check_path(&cwd, true)?;
+        Ok(Self { cwd, policy })
+    }
+
+    /// Canonical path of a regular file inside the workspace
+    ///
+    /// Missing files and files outside the workspace (symlinks included) are
+    /// both `None`, so callers cannot probe the host.
+    fn resolve(&self, path: &std::path::Path) -> Option<PathBuf> {
+        let resolved = std::fs::canonicalize(self.cwd.join(path)).ok()?;
+        if !resolved.starts_with(&self.cwd) || !resolved.is_file() {
+            return None;
+        }
+        self.policy.check_path(&resolved, false).ok()
+    }
+}
+
+/// Contents of `file`, refused once they exceed `max_size` bytes
+async fn read_capped(file: &std::path::Path, max_size: u64) -> GatewayResult<Vec<u8>> {
+    let read_error = |e: std::io::Error| {
+        GatewayError::Internal(format!("failed to read {}: {e}", file.display()))
+    };
+    let mut contents = Vec::new();
+    tokio::fs::File::open(file)
+        .await
+        .map_err(read_error)?
+        .take(max_size.saturating_add(1))
+        .read_to_end(&mut contents)
+        .await
+        .map_err(read_error)?;
+    check_download_size(contents.len() as u64, max_size, &file.display().to_string())?;
+    Ok(contents)
+}
+
+fn check_download_size(size: u64, max_size: u64, what: &str) -> GatewayResult<()> {
+    if size <= max_size {
+        return Ok(());
+    }
+    Err(GatewayError::ResponseTooLarge(format!(
+        "{what} exceeds the {max_size} byte download limit"
+    )))
+}
+
+/// Response carrying a file download
+fn attachment(body: Vec<u8>, content_type: &'static str, file_name: &str) -> Response {
+    let mut response = (StatusCode::OK, body).into_response();
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    let disposition = format!("attachment; filename=\"{}\"", sanitize_file_name(file_name));
+    if let Ok(disposition) = HeaderValue::from_str(&disposition) {
+        headers.insert(CONTENT_DISPOSITION, disposition);
+    }
+    response
+}
+
+/// Keep a name safe for a quoted `Content-Disposition` filename
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GatewayConfig;
+    use codex_core::config::Config;
+    use codex_core::config::ConfigOverrides;
+    use codex_core::config::ConfigToml;
+
+    #[tokio::test]
+    async fn test_delete_unknown_session() -> Result<(), Box<dyn std::error::Error>> {
+        let state = AppState::new(GatewayConfig::default()).await?;
+        let result = handle_delete_session(State(state), None, Path("missing".to_string())).await;
+        assert!(matches!(result, Err(GatewayError::NotFound(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_workspace_files_stay_inside_cwd() {
+        let dir = tempfile::tempdir().unwrap();
+        let cwd = dir.path().join("repo");
+        std::fs::create_dir_all(cwd.join("src")).unwrap();
+        std::fs::write(cwd.join("src/lib.rs"), "").unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "").unwrap();
+        let cwd = std::fs::canonicalize(cwd).unwrap();
+        let codex_config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            dir.path().to_path_buf(),
+        )
+        .unwrap();
+        let workspace = Workspace {
+            policy: WorkspacePolicy::for_caller(&GatewayConfig::default(), &codex_config, None)
+                .unwrap(),
+            cwd: cwd.clone(),
+        };
+
+        assert_eq!(
+            workspace.resolve(std::path::Path::new("src/lib.rs")),
+            Some(cwd.join("src/lib.rs"))
+        );
+        for path in ["../secret.txt", "src", "missing.rs"] {
+            assert_eq!(
+                workspace.resolve(std::path::Path::new(path)),
+                None,
+                "{path}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_downloads_are_capped() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("big.bin");
+        std::fs::write(&file, [0u8; 10]).unwrap();
+
+        assert_eq!(read_capped(&file, 10).await.unwrap().len(), 10);
+        let err = read_capped(&file, 9).await.unwrap_err();
+        assert!(matches!(err, GatewayError::ResponseTooLarge(_)), "{err}");
+        assert_eq!(err.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
@@ -0,0 +1,514 @@
+//! `multipart/form-data` bodies for the exec endpoints
+//!
+//! `POST /exec`, `POST /exec/stream` and `POST /jobs` take their body through
+//! [`ExecPayload`], which accepts the usual JSON [`ExecRequest`] or a form:
+//!
+//! - `request`: the JSON request (or `prompt` as a plain text field)
+//! - `image`: an image for the turn, saved in a per-request temp dir and sent
+//!   as `UserInput::LocalImage`; the dir is removed when the turn ends
+//! - `file`: a file written into the turn's `cwd` before the turn starts,
+//!   at the part's filename (which may name subdirectories)
+//!
+//! ```text
+//! curl -F 'request={"prompt":"describe the chart"};type=application/json' \
+//!      -F image=@chart.png -F file=@data.csv "$GATEWAY/exec"
+//! ```
+//!
+//! Each part is capped at [`crate::config::UploadConfig::max_part_size`] and
+//! a request at `max_files` uploads; the route's body limit still applies to
+//! the whole request. Host paths in `images` are only honored for clients
+//! connecting from loopback; remote callers must upload images instead.
+
+use crate::config::UploadConfig;
+use crate::error::GatewayError;
+use crate::error::GatewayResult;
+use crate::handlers::exec::ExecRequest;
+use crate::listener::PeerAddr;
+use crate::state::AppState;
+use axum::Json;
+use axum::body::Bytes;
+use axum::extract::ConnectInfo;
+use axum::extract::FromRequest;
+use axum::extract::Multipart;
+use axum::extract::Request;
+use axum::extract::multipart::Field;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use tempfile::TempDir;
+use tokio::io::AsyncWriteExt;
+use tracing::debug;
+
+/// Files received with a multipart exec request
+#[derive(Debug, Default)]
+pub struct Uploads {
+    /// Per-request directory holding the images; removed when dropped
+    image_dir: Option<TempDir>,
+    /// Uploaded images, in request order
+    pub images: Vec<PathBuf>,
+    /// Workspace files as (path relative to the turn's `cwd`, contents)
+    files: Vec<(PathBuf, Bytes)>,
+}
+
+impl Uploads {
+    /// Write the uploaded workspace files under `cwd`
+    ///
+    /// Paths were checked to be relative and free of `..` when the request
+    /// was parsed. Here each existing ancestor is resolved before anything is
+    /// created, so a symlink can only lead to a directory inside `cwd`, and a
+    /// target that is itself a symlink (even a dangling one) is refused. The
+    /// contents go to a fresh temp file that is renamed over the target, so
+    /// the write never follows a link swapped in meanwhile.
+    pub async fn write_files(&self, cwd: &Path) -> GatewayResult<()> {
+        if self.files.is_empty() {
+            return Ok(());
+        }
+        let root = tokio::fs::canonicalize(cwd).await.map_err(|e| {
+            GatewayError::Internal(format!("cwd {} unavailable: {e}", cwd.display()))
+        })?;
+
+        for (relative, contents) in &self.files {
+            let Some(file_name) = relative.file_name() else {
+                return Err(outside_workspace(relative));
+            };
+            let parent = match relative.parent() {
+                Some(parent) => workspace_dir(&root, parent, relative).await?,
+                None => root.clone(),
+            };
+            let target = parent.join(file_name);
+            match tokio::fs::symlink_metadata(&target).await {
+                Ok(metadata) if metadata.is_symlink() => {
+                    return Err(GatewayError::Forbidden(format!(
+                        "upload {} would replace a symlink",
+                        relative.display()
+                    )));
+                }
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(write_error(relative, e)),
+            }
+
+            let temp = parent.join(format!(".codex-upload-{}", uuid::Uuid::new_v4()));
+            if let Err(e) = write_new_file(&temp, contents).await {
+                let _ = tokio::fs::remove_file(&temp).await;
+                return Err(write_error(relative, e));
+            }
+            if let Err(e) = tokio::fs::rename(&temp, &target).await {
+                let _ = tokio::fs::remove_file(&temp).await;
+                return Err(write_error(relative, e));
+            }
+            debug!(
+                "Wrote upload {} ({} bytes)",
+                target.display(),
+                contents.len()
+            );
+        }
+        Ok(())
+    }
+
+    /// Hand over the image directory so it can outlive the request
+    pub fn take_image_dir(&mut self) -> Option<TempDir> {
+        self.image_dir.take()
+    }
+
+    fn count(&self) -> usize {
+        self.images.len() + self.files.len()
+    }
+}
+
+/// Resolve `dir` (relative to `root`) one component at a time
+///
+/// Missing directories are created; existing symlinks are followed only when
+/// they resolve inside `root`.
+async fn workspace_dir(root: &Path, dir: &Path, relative: &Path) -> GatewayResult<PathBuf> {
+    let mut resolved = root.to_path_buf();
+    for component in dir.components() {
+        let next = resolved.join(component);
+        match tokio::fs::symlink_metadata(&next).await {
+            Ok(metadata) if metadata.is_symlink() => {
+                let target = tokio::fs::canonicalize(&next)
+                    .await
+                    .map_err(|e| write_error(relative, e))?;
+                if !target.starts_with(root) {
+                    return Err(outside_workspace(relative));
+                }
+                resolved = target;
+            }
+            Ok(_) => resolved = next,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                tokio::fs::create_dir(&next)
+                    .await
+                    .map_err(|e| write_error(relative, e))?;
+                resolved = next;
+            }
+            Err(e) => return Err(write_error(relative, e)),
+        }
+    }
+    Ok(resolved)
+}
+
+/// Write `contents` to a file that must not exist yet
+async fn write_new_file(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .await?;
+    file.write_all(contents).await?;
+    file.flush().await
+}
+
+fn outside_workspace(relative: &Path) -> GatewayError {
+    GatewayError::Forbidden(format!(
+        "upload {} would be written outside the workspace",
+        relative.display()
+    ))
+}
+
+fn write_error(relative: &Path, err: std::io::Error) -> GatewayError {
+    GatewayError::Internal(format!(
+        "failed to write upload {}: {err}",
+        relative.display()
+    ))
+}
+
+/// Exec request body, sent as JSON or as `multipart/form-data`
+#[derive(Debug)]
+pub struct ExecPayload(pub ExecRequest);
+
+impl From<ExecRequest> for ExecPayload {
+    fn from(request: ExecRequest) -> Self {
+        Self(request)
+    }
+}
+
+impl FromRequest<AppState> for ExecPayload {
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &AppState) -> Result<Self, Self::Rejection> {
+        let local = request
+            .extensions()
+            .get::<ConnectInfo<PeerAddr>>()
+            .is_some_and(|ConnectInfo(peer)| peer.is_loopback());
+        let path = request.uri().path().to_string();
+        let is_multipart = request
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("multipart/form-data"));
+
+        let request = if is_multipart {
+            let multipart = Multipart::from_request(request, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            parse_multipart(multipart, &state.config().uploads, &path).await?
+        } else {
+            let Json(request) = Json::<ExecRequest>::from_request(request, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            request
+        };
+
+        check_host_images(&request.images, local).map_err(IntoResponse::into_response)?;
+        Ok(Self(request))
+    }
+}
+
+/// Refuse host image paths (anything but `data:` URIs) from remote clients
+pub(crate) fn check_host_images(images: &[String], local: bool) -> GatewayResult<()> {
+    if !local && let Some(image) = images.iter().find(|img| !img.starts_with("data:")) {
+        return Err(GatewayError::Forbidden(format!(
+            "image path {image} refused: host paths are only accepted from loopback clients; \
+             upload the image as a multipart part or a data URI"
+        )));
+    }
+    Ok(())
+}
+
+async fn parse_multipart(
+    mut multipart: Multipart,
+    limits: &UploadConfig,
+    path: &str,
+) -> Result<ExecRequest, Response> {
+    let mut request: Option<ExecRequest> = None;
+    let mut uploads = Uploads::default();
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(IntoResponse::into_response)?
+    {
+        let name = field.name().unwrap_or_default().to_string();
+        match name.as_str() {
+            "request" | "prompt" if request.is_some() => {
+                return Err(invalid("only one `request` or `prompt` part is allowed"));
+            }
+            "request" => {
+                let body = read_part(field, limits, path).await?;
+                let parsed = serde_json::from_slice(&body)
+                    .map_err(|e| invalid(&format!("invalid `request` part: {e}")))?;
+                request = Some(parsed);
+            }
+            "prompt" => {
+                let body = read_part(field, limits, path).await?;
+                let prompt = String::from_utf8(body.to_vec())
+                    .map_err(|_| invalid("`prompt` part must be UTF-8 text"))?;
+                request = Some(
+                    serde_json::from_value(serde_json::json!({ "prompt": prompt }))
+                        .map_err(|e| invalid(&format!("invalid `prompt` part: {e}")))?,
+                );
+            }
+            "image" | "file" => {
+                if uploads.count() >= limits.max_files {
+                    return Err(invalid(&format!(
+                        "at most {} files may be uploaded per request",
+                        limits.max_files
+                    )));
+                }
+                let file_name = field.file_name().map(str::to_string);
+                let body = read_part(field, limits, path).await?;
+                if name == "image" {
+                    save_image(&mut uploads, file_name.as_deref(), body)
+                        .await
+                        .map_err(IntoResponse::into_response)?;
+                } else {
+                    let relative = file_name
+                        .as_deref()
+                        .and_then(workspace_relative_path)
+                        .ok_or_else(|| {
+                            invalid("`file` parts need a relative filename without `..`")
+                        })?;
+                    uploads.files.push((relative, body));
+                }
+            }
+            other => return Err(invalid(&format!("unexpected multipart part `{other}`"))),
+        }
+    }
+
+    let mut request = request.ok_or_else(|| invalid("missing `request` or `prompt` part"))?;
+    request.uploads = uploads;
+    Ok(request)
+}
+
+/// Read one part, refusing it once it exceeds the per-part limit
+async fn read_part(
+    mut field: Field<'_>,
+    limits: &UploadConfig,
+    path: &str,
+) -> Result<Bytes, Response> {
+    let mut body = Vec::new();
+    while let Some(chunk) = field.chunk().await.map_err(IntoResponse::into_response)? {
+        if body.len() + chunk.len() > limits.max_part_size {
+            return Err(GatewayError::PayloadTooLarge {
+                max_size: limits.max_part_size,
+                actual_size: None,
+                path: path.to_string(),
+            }
+            .into_response());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(Bytes::from(body))
+}
+
+/// Store an image in the request's temp dir under a generated name
+///
+/// Only the extension of the client's filename is kept, so the file type can
+/// still be recognised.
+async fn save_image(
+    uploads: &mut Uploads,
+    file_name: Option<&str>,
+    body: Bytes,
+) -> GatewayResult<()> {
+    let dir = match &uploads.image_dir {
+        Some(dir) => dir.path().to_path_buf(),
+        None => {
+            let dir = tempfile::Builder::new()
+                .prefix("codex-gateway-upload-")
+                .tempdir()
+                .map_err(|e| GatewayError::Internal(format!("failed to create upload dir: {e}")))?;
+            let path = dir.path().to_path_buf();
+            uploads.image_dir = Some(dir);
+            path
+        }
+    };
+    let extension = file_name
+        .and_then(|name| Path::new(name).extension())
+        .and_then(|ext| ext.to_str())
+        .filter(|ext| ext.chars().all(|c| c.is_ascii_alphanumeric()))
+        .unwrap_or("bin");
+    let path = dir.join(format!("image-{}.{extension}", uploads.images.len()));
+    tokio::fs::write(&path, &body)
+        .await
+        .map_err(|e| GatewayError::Internal(format!("failed to save uploaded image: {e}")))?;
+    uploads.images.push(path);
+    Ok(())
+}
+
+/// `name` as a path below the workspace, or `None` if it could leave it
+fn workspace_relative_path(name: &str) -> Option<PathBuf> {
+    let path = Path::new(name);
+    let relative = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    (relative && path.components().next().is_some()).then(|| path.to_path_buf())
+}
+
+fn invalid(message: &str) -> Response {
+    GatewayError::InvalidRequest(message.to_string()).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GatewayConfig;
+    use axum::body::Body;
+    use axum::http::StatusCode;
+
+    fn multipart_request(parts: &[(&str, Option<&str>, &str)]) -> Request {
+        let mut body = String::new();
+        for (name, file_name, contents) in parts {
+            body.push_str("--XBOUNDARY\r\n");
+            match file_name {
+                Some(file_name) => body.push_str(&format!(
+                    "Content-Disposition: form-data; name=\"{name}\"; filename=\"{file_name}\"\r\n\r\n"
+                )),
+                None => body.push_str(&format!(
+                    "Content-Disposition: form-data; name=\"{name}\"\r\n\r\n"
+                )),
+            }
+            body.push_str(contents);
+            body.push_str("\r\n");
+        }
+        body.push_str("--XBOUNDARY--\r\n");
+        Request::post("/exec")
+            .header(CONTENT_TYPE, "multipart/form-data; boundary=XBOUNDARY")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_multipart_uploads() -> Result<(), Box<dyn std::error::Error>> {
+        let state = AppState::new(GatewayConfig::default()).await?;
+        let request = multipart_request(&[
+            (
+                "request",
+                None,
+                r#"{"prompt": "describe", "session_id": "s1"}"#,
+            ),
+            ("image", Some("chart.png"), "png-bytes"),
+            ("file", Some("data/input.csv"), "a,b\n"),
+        ]);
+        let ExecPayload(mut request) = ExecPayload::from_request(request, &state)
+            .await
+            .map_err(|response| format!("rejected: {}", response.status()))?;
+        assert_eq!(request.prompt, "describe");
+        assert_eq!(request.uploads.images.len(), 1);
+        assert_eq!(std::fs::read(&request.uploads.images[0])?, b"png-bytes");
+
+        let cwd = tempfile::tempdir()?;
+        request.uploads.write_files(cwd.path()).await?;
+        assert_eq!(std::fs::read(cwd.path().join("data/input.csv"))?, b"a,b\n");
+
+        let image_dir = request.uploads.take_image_dir().ok_or("no image dir")?;
+        let image_path = image_dir.path().to_path_buf();
+        drop(image_dir);
+        assert!(!image_path.exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_uploads_are_checked() -> Result<(), Box<dyn std::error::Error>> {
+        let mut config = GatewayConfig::default();
+        config.uploads.max_part_size = 8;
+        let state = AppState::new(config).await?;
+
+        for (parts, status) in [
+            (
+                vec![("prompt", None, "hi"), ("file", Some("../escape.txt"), "x")],
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                vec![
+                    ("prompt", None, "hi"),
+                    ("image", Some("big.png"), "123456789"),
+                ],
+                StatusCode::PAYLOAD_TOO_LARGE,
+            ),
+            (vec![("image", Some("a.png"), "x")], StatusCode::BAD_REQUEST),
+        ] {
+            let rejection = ExecPayload::from_request(multipart_request(&parts), &state)
+                .await
+                .err()
+                .ok_or("accepted")?;
+            assert_eq!(rejection.status(), status);
+        }
+
+        // Requests without connection info count as remote
+        let request = Request::post("/exec")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"prompt": "hi", "images": ["/etc/passwd"]}"#))?;
+        let rejection = ExecPayload::from_request(request, &state)
+            .await
+            .err()
+            .ok_or("accepted")?;
+        assert_eq!(rejection.status(), StatusCode::FORBIDDEN);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_files_cannot_escape_through_symlinks() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let cwd = dir.path().join("repo");
+        std::fs::create_dir_all(&cwd)?;
+        std::fs::create_dir_all(dir.path().join("outside"))?;
+        std::os::unix::fs::symlink(dir.path().join("outside"), cwd.join("link"))?;
+
+        let uploads = Uploads {
+            files: vec![(PathBuf::from("link/x.txt"), Bytes::from_static(b"x"))],
+            ..Uploads::default()
+        };
+        assert!(matches!(
+            uploads.write_files(&cwd).await,
+            Err(GatewayError::Forbidden(_))
+        ));
+        assert!(!dir.path().join("outside/x.txt").exists());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_files_do_not_follow_dangling_symlinks() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let dir = tempfile::tempdir()?;
+        let cwd = dir.path().join("repo");
+        std::fs::create_dir_all(&cwd)?;
+        std::fs::create_dir_all(dir.path().join("outside"))?;
+        std::os::unix::fs::symlink(dir.path().join("outside/new.txt"), cwd.join("new.txt"))?;
+        std::os::unix::fs::symlink(dir.path().join("outside/sub"), cwd.join("sub"))?;
+
+        for relative in ["new.txt", "sub/x.txt"] {
+            let uploads = Uploads {
+                files: vec![(PathBuf::from(relative), Bytes::from_static(b"x"))],
+                ..Uploads::default()
+            };
+            assert!(uploads.write_files(&cwd).await.is_err(), "{relative}");
+        }
+        assert!(!dir.path().join("outside/new.txt").exists());
+        assert!(!dir.path().join("outside/sub").exists());
+
+        // Regular files are still replaced in place
+        std::fs::write(cwd.join("plain.txt"), b"old")?;
+        let uploads = Uploads {
+            files: vec![(PathBuf::from("plain.txt"), Bytes::from_static(b"new"))],
+            ..Uploads::default()
+        };
+        uploads.write_files(&cwd).await?;
+        assert_eq!(std::fs::read(cwd.join("plain.txt"))?, b"new");
+        Ok(())
+    }
+}
@@ -1,70 +1,484 @@
 //! Webhook handler
+//!
+//! `POST /webhook` turns external events (GitHub pushes, issue comments, CI
+//! notifications, ...) into background agent runs:
+//!
+//! 1. The request is authenticated with an HMAC-SHA256 signature over the raw
+//!    body, using the shared secret from `GATEWAY_WEBHOOK_SECRET`. Two schemes
+//!    are accepted:
+//!    - GitHub: `X-Hub-Signature-256: sha256=<hex>` over the body.
+//!    - Generic: `X-Webhook-Timestamp: <unix secs>` and
+//!      `X-Webhook-Signature: sha256=<hex>` over `<timestamp>.<body>`; the
+//!      timestamp must be within the configured tolerance.
+//!
+//!    Callers presenting a valid `X-API-Key` may skip the signature.
+//! 2. The event type (`X-GitHub-Event`, `X-Webhook-Event` or the payload's
+//!    `type` field) is matched against the configured [`WebhookRoute`]s.
+//! 3. The route's prompt template is rendered with the payload and queued on
+//!    the [`crate::services::JobQueue`]. The job can be polled at
+//!    `GET /webhook/jobs/{id}`, an alias of `GET /jobs/{id}`, by the key that
+//!    sent it, or by `webhooks.owner_key` for signed deliveries.
 
+use crate::config::WebhookConfig;
+use crate::config::WebhookRoute;
+use crate::error::GatewayError;
 use crate::error::GatewayResult;
+use crate::handlers::exec::ExecRequest;
+use crate::handlers::exec::resolve_turn_settings;
+use crate::handlers::upload::Uploads;
+use crate::middleware::api_key::ApiKeyInfo;
 use crate::state::AppState;
+use axum::Extension;
+use axum::body::Bytes;
 use axum::extract::State;
+use axum::http::HeaderMap;
 use axum::http::StatusCode;
 use axum::response::Json;
+use chrono::Utc;
+use hmac::Hmac;
+use hmac::Mac;
+use schemars::JsonSchema;
+use serde::Serialize;
 use serde_json::Value;
-use serde_json::json;
+use sha2::Sha256;
+use tracing::info;
+use tracing::warn;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const GITHUB_SIGNATURE_HEADER: &str = "x-hub-signature-256";
+const GITHUB_EVENT_HEADER: &str = "x-github-event";
+const GITHUB_DELIVERY_HEADER: &str = "x-github-delivery";
+const SIGNATURE_HEADER: &str = "x-webhook-signature";
+const TIMESTAMP_HEADER: &str = "x-webhook-timestamp";
+const EVENT_HEADER: &str = "x-webhook-event";
+
+/// Response for `POST /webhook`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct WebhookResponse {
+    /// `queued` when a route matched, `ignored` otherwise
+    pub status: String,
+    /// Event name, qualified with the action (`issue_comment.created`)
+    pub event: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub events_url: Option<String>,
+}
 
 /// Webhook endpoint
 ///
-/// Accepts generic webhook payloads and processes them asynchronously.
-/// This is a placeholder implementation that logs the payload and returns an acknowledgment.
-///
-/// Future iterations will implement:
-/// - Webhook signature verification
-/// - Event type routing
-/// - Asynchronous processing queues
-/// - Retry mechanisms
-/// - Dead letter queues
-///
 /// ## Request
 ///
-/// Accepts any JSON payload.
+/// Any JSON payload, signed as described in the module docs.
 ///
 /// ## Response
 ///
-/// Returns HTTP 202 Accepted with acknowledgment.
+/// HTTP 202 Accepted. When a route matches, the body carries the job id:
+///
+/// ```json
+/// {
+///   "status": "queued",
+///   "event": "issue_comment.created",
+///   "job_id": "550e8400-e29b-41d4-a716-446655440000",
+///   "status_url": "/webhook/jobs/550e8400-e29b-41d4-a716-446655440000",
+///   "events_url": "/jobs/550e8400-e29b-41d4-a716-446655440000/events"
+/// }
+/// ```
+///
+/// Events without a matching route are acknowledged with `"status": "ignored"`.
 pub async fn handle_webhook(
-    State(_state): State<AppState>,
-    Json(payload): Json<Value>,
-) -> GatewayResult<(StatusCode, Json<Value>)> {
-    tracing::info!("Webhook received");
-    tracing::debug!("Webhook payload: {}", payload);
+    State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> GatewayResult<(StatusCode, Json<WebhookResponse>)> {
+    let webhooks = &state.config().webhooks;
+    verify_request(
+        webhooks,
+        caller.is_some(),
+        &headers,
+        &body,
+        Utc::now().timestamp(),
+    )
+    .inspect_err(|_| state.metrics.record_auth_rejection("signature"))?;
+
+    let payload: Value = serde_json::from_slice(&body).map_err(|e| {
+        GatewayError::InvalidRequest(format!("Webhook body is not valid JSON: {e}"))
+    })?;
+
+    let Some(event) = event_type(&headers, &payload) else {
+        return Err(GatewayError::InvalidRequest(
+            "Webhook event type missing (X-GitHub-Event, X-Webhook-Event or \"type\")".to_string(),
+        ));
+    };
+    let action = payload.get("action").and_then(Value::as_str);
+    let qualified_event = match action {
+        Some(action) => format!("{event}.{action}"),
+        None => event.clone(),
+    };
 
-    // Log webhook details for debugging
-    if let Some(event_type) = payload.get("type").and_then(|v| v.as_str()) {
-        tracing::info!("Webhook event type: {}", event_type);
+    info!(
+        "Webhook received: event={}, delivery={:?}",
+        qualified_event,
+        headers
+            .get(GITHUB_DELIVERY_HEADER)
+            .and_then(|v| v.to_str().ok())
+    );
+
+    let Some(route) = find_route(&webhooks.routes, &event, action) else {
+        info!("No webhook route for event {}, ignoring", qualified_event);
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(WebhookResponse {
+                status: "ignored".to_string(),
+                message: Some(format!("No route configured for event '{qualified_event}'")),
+                event: qualified_event,
+                job_id: None,
+                status_url: None,
+                events_url: None,
+            }),
+        ));
+    };
+
+    let request = ExecRequest {
+        prompt: render_prompt(&route.prompt, &qualified_event, &payload),
+        session_id: None,
+        images: Vec::new(),
+        output_schema: None,
+        cwd: route.cwd.clone(),
+        model: route.model.clone(),
+        sandbox_mode: route.sandbox_mode.clone(),
+        approval_policy: None,
+        uploads: Uploads::default(),
+    };
+    // Signed deliveries have no key and run under the default tenant; their
+    // job belongs to the configured owner key
+    let settings = resolve_turn_settings(&state, caller.as_deref(), &request)?;
+    let owner = match caller.as_deref() {
+        Some(key) => Some(key.key_id.clone()),
+        None => webhooks.owner_key.clone(),
+    };
+    let job_id = state
+        .jobs
+        .submit(
+            state.clone(),
+            format!("webhook:{qualified_event}"),
+            owner,
+            request,
+            settings,
+        )
+        .await
+        .map_err(|e| GatewayError::Internal(format!("Failed to queue webhook job: {e}")))?;
+
+    info!(
+        "Webhook job queued: job_id={}, event={}",
+        job_id, qualified_event
+    );
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(WebhookResponse {
+            status: "queued".to_string(),
+            event: qualified_event,
+            message: None,
+            status_url: Some(format!("/webhook/jobs/{job_id}")),
+            events_url: Some(format!("/jobs/{job_id}/events")),
+            job_id: Some(job_id),
+        }),
+    ))
+}
+
+/// Check the request signature, or accept an API-key-authenticated caller
+fn verify_request(
+    config: &WebhookConfig,
+    has_api_key: bool,
+    headers: &HeaderMap,
+    body: &[u8],
+    now: i64,
+) -> GatewayResult<()> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    let Some(secret) = config.secret.as_deref() else {
+        if has_api_key {
+            return Ok(());
+        }
+        return Err(GatewayError::Auth(
+            "Webhook signatures are not configured; provide an X-API-Key".to_string(),
+        ));
+    };
+
+    if let Some(signature) = header(GITHUB_SIGNATURE_HEADER) {
+        return verify_signature(secret, &[body], signature);
     }
 
-    if let Some(source) = payload.get("source").and_then(|v| v.as_str()) {
-        tracing::info!("Webhook source: {}", source);
+    if let Some(signature) = header(SIGNATURE_HEADER) {
+        let timestamp = header(TIMESTAMP_HEADER)
+            .ok_or_else(|| GatewayError::Auth(format!("Missing {TIMESTAMP_HEADER} header")))?;
+        let sent_at: i64 = timestamp
+            .parse()
+            .map_err(|_| GatewayError::Auth(format!("Invalid {TIMESTAMP_HEADER} header")))?;
+        if now.abs_diff(sent_at) > config.signature_tolerance.as_secs() {
+            return Err(GatewayError::Auth(
+                "Webhook timestamp outside the allowed window".to_string(),
+            ));
+        }
+        return verify_signature(secret, &[timestamp.as_bytes(), b".", body], signature);
     }
 
-    // Placeholder: In future iterations, this will:
-    // 1. Validate webhook signature
-    // 2. Route to appropriate service based on event type
-    // 3. Queue for async processing
-    // 4. Return appropriate response based on processing requirements
+    if has_api_key {
+        return Ok(());
+    }
+    Err(GatewayError::Auth("Missing webhook signature".to_string()))
+}
+
+/// Compare a `sha256=<hex>` signature against the HMAC of `message`
+fn verify_signature(secret: &str, message: &[&[u8]], signature: &str) -> GatewayResult<()> {
+    let provided = signature
+        .strip_prefix("sha256=")
+        .unwrap_or(signature)
+        .trim();
+    let mac = hmac_sha256(secret.as_bytes(), message)?;
+    match hex_decode(provided) {
+        Some(provided) if mac.verify_slice(&provided).is_ok() => Ok(()),
+        _ => {
+            warn!("Webhook signature mismatch");
+            Err(GatewayError::Auth("Invalid webhook signature".to_string()))
+        }
+    }
+}
+
+/// HMAC-SHA256 over the concatenation of `message` parts
+fn hmac_sha256(key: &[u8], message: &[&[u8]]) -> GatewayResult<HmacSha256> {
+    let mut mac = HmacSha256::new_from_slice(key)
+        .map_err(|e| GatewayError::Internal(format!("Invalid webhook secret: {e}")))?;
+    for part in message {
+        mac.update(part);
+    }
+    Ok(mac)
+}
+
+/// Bytes of a hex string, or `None` if it is not valid hex
+fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    let digits = hex
+        .chars()
+        .map(|c| c.to_digit(16).and_then(|d| u8::try_from(d).ok()))
+        .collect::<Option<Vec<u8>>>()?;
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+    Some(
+        digits
+            .chunks_exact(2)
+            .map(|pair| (pair[0] << 4) | pair[1])
+            .collect(),
+    )
+}
+
+/// Event name from the provider header, falling back to the payload `type`
+fn event_type(headers: &HeaderMap, payload: &Value) -> Option<String> {
+    [GITHUB_EVENT_HEADER, EVENT_HEADER]
+        .iter()
+        .find_map(|name| headers.get(*name).and_then(|v| v.to_str().ok()))
+        .or_else(|| payload.get("type").and_then(Value::as_str))
+        .map(str::to_string)
+}
+
+/// First route matching `<event>.<action>`, `<event>` or `*`
+fn find_route<'a>(
+    routes: &'a [WebhookRoute],
+    event: &str,
+    action: Option<&str>,
+) -> Option<&'a WebhookRoute> {
+    routes.iter().find(|route| {
+        route.event == "*"
+            || route.event == event
+            || action.is_some_and(|action| {
+                route
+                    .event
+                    .strip_prefix(event)
+                    .and_then(|rest| rest.strip_prefix('.'))
+                    == Some(action)
+            })
+    })
+}
+
+/// Substitute `{{path.to.field}}`, `{{event}}` and `{{payload}}` in `template`
+///
+/// Missing fields render as an empty string; non-string values render as JSON.
+fn render_prompt(template: &str, event: &str, payload: &Value) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+
+        let key = rest[start + 2..start + 2 + len].trim();
+        match key {
+            "event" => rendered.push_str(event),
+            "payload" => {
+                rendered.push_str(&serde_json::to_string_pretty(payload).unwrap_or_default())
+            }
+            path => match lookup(payload, path) {
+                Some(Value::String(s)) => rendered.push_str(s),
+                Some(Value::Null) | None => {}
+                Some(value) => rendered.push_str(&value.to_string()),
+            },
+        }
 
-    let response = json!({
-        "status": "accepted",
-        "message": "Webhook received and queued for processing",
-        "timestamp": chrono::Utc::now().to_rfc3339()
-    });
+        rest = &rest[start + 2 + len + 2..];
+    }
 
-    Ok((StatusCode::ACCEPTED, Json(response)))
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Resolve a dotted path (`commits.0.message`) inside a JSON value
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(value, |current, segment| match current {
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => current.get(segment),
+        })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::GatewayConfig;
+    use crate::handlers::jobs::handle_cancel_job;
+    use crate::handlers::jobs::handle_get_job;
+    use axum::extract::Path;
+    use axum::http::HeaderValue;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn caller() -> ApiKeyInfo {
+        ApiKeyInfo {
+            key_id: "test".to_string(),
+            user_id: "test".to_string(),
+            rate_limit: 0,
+            active: true,
+            scopes: vec!["agent".to_string()],
+            created_at: None,
+            expires_at: None,
+            max_concurrent_sessions: None,
+            tenant: None,
+        }
+    }
+
+    fn signed_config() -> WebhookConfig {
+        WebhookConfig {
+            secret: Some("It's a Secret to Everybody".to_string()),
+            signature_tolerance: Duration::from_secs(300),
+            ..Default::default()
+        }
+    }
+
+    fn hex_signature(key: &[u8], message: &[&[u8]]) -> String {
+        let mac = hmac_sha256(key, message).unwrap().finalize().into_bytes();
+        mac.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn test_hmac_sha256_rfc4231_vector() {
+        assert_eq!(
+            hex_signature(b"Jefe", &[b"what do ya want ", b"for nothing?"]),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(hex_decode("0aFf"), Some(vec![0x0a, 0xff]));
+        assert_eq!(hex_decode("0a f"), None);
+        assert_eq!(hex_decode("abc"), None);
+        assert_eq!(hex_decode("+f"), None);
+    }
+
+    #[test]
+    fn test_verify_github_signature() {
+        // Example from GitHub's "Validating webhook deliveries" documentation
+        let config = signed_config();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            GITHUB_SIGNATURE_HEADER,
+            HeaderValue::from_static(
+                "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17",
+            ),
+        );
+        assert!(verify_request(&config, false, &headers, b"Hello, World!", 0).is_ok());
+        assert!(matches!(
+            verify_request(&config, true, &headers, b"Hello, World?", 0),
+            Err(GatewayError::Auth(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_generic_signature_checks_timestamp() {
+        let config = signed_config();
+        let body = br#"{"type":"deploy"}"#;
+        let signature = hex_signature(b"It's a Secret to Everybody", &[b"1700000000", b".", body]);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(TIMESTAMP_HEADER, HeaderValue::from_static("1700000000"));
+        headers.insert(
+            SIGNATURE_HEADER,
+            HeaderValue::from_str(&format!("sha256={signature}")).unwrap(),
+        );
+
+        assert!(verify_request(&config, false, &headers, body, 1_700_000_100).is_ok());
+        assert!(verify_request(&config, false, &headers, body, 1_700_001_000).is_err());
+    }
+
+    #[test]
+    fn test_unsigned_requests_need_api_key() {
+        let headers = HeaderMap::new();
+        assert!(verify_request(&signed_config(), false, &headers, b"{}", 0).is_err());
+        assert!(verify_request(&signed_config(), true, &headers, b"{}", 0).is_ok());
+        assert!(verify_request(&WebhookConfig::default(), false, &headers, b"{}", 0).is_err());
+    }
+
+    #[test]
+    fn test_find_route_prefers_order_and_action() {
+        let route = |event: &str| WebhookRoute {
+            event: event.to_string(),
+            prompt: event.to_string(),
+            cwd: None,
+            model: None,
+            sandbox_mode: None,
+        };
+        let routes = vec![route("issue_comment.created"), route("push"), route("*")];
+
+        let found = |event, action| find_route(&routes, event, action).map(|r| r.event.as_str());
+        assert_eq!(
+            found("issue_comment", Some("created")),
+            Some("issue_comment.created")
+        );
+        assert_eq!(found("issue_comment", Some("deleted")), Some("*"));
+        assert_eq!(found("push", None), Some("push"));
+        assert!(find_route(&routes[..2], "ping", None).is_none());
+    }
+
+    #[test]
+    fn test_render_prompt() {
+        let payload = json!({
+            "issue": {"title": "Crash on start", "number": 42},
+            "commits": [{"message": "fix"}]
+        });
+        let rendered = render_prompt(
+            "[{{event}}] #{{ issue.number }} {{issue.title}}: {{commits.0.message}}{{missing}} {{open",
+            "issues.opened",
+            &payload,
+        );
+        assert_eq!(rendered, "[issues.opened] #42 Crash on start: fix {{open");
+    }
 
     #[tokio::test]
-    async fn test_webhook_handler() -> Result<(), Box<dyn std::error::Error>> {
+    async fn test_webhook_without_route_is_ignored() -> Result<(), Box<dyn std::error::Error>> {
         let state = AppState::new(GatewayConfig::default()).await?;
 
         let webhook_payload = json!({
@@ -75,46 +489,116 @@ mod tests {
             }
         });
 
-        let result = handle_webhook(State(state), Json(webhook_payload)).await;
+        let (status, json_response) = handle_webhook(
+            State(state),
+            Some(Extension(caller())),
+            HeaderMap::new(),
+            Bytes::from(webhook_payload.to_string()),
+        )
+        .await?;
 
-        assert!(result.is_ok());
-        let (status, json_response) = result.unwrap();
         assert_eq!(status, StatusCode::ACCEPTED);
-
-        let value = json_response.0;
-        assert_eq!(value["status"], "accepted");
-        assert!(value["message"].is_string());
-        assert!(value["timestamp"].is_string());
+        let response = json_response.0;
+        assert_eq!(response.status, "ignored");
+        assert_eq!(response.event, "test_event");
+        assert!(response.message.is_some());
+        assert!(response.job_id.is_none());
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_webhook_with_minimal_payload() -> Result<(), Box<dyn std::error::Error>> {
+    async fn test_webhook_rejects_unauthenticated_payload() -> Result<(), Box<dyn std::error::Error>>
+    {
         let state = AppState::new(GatewayConfig::default()).await?;
 
-        let minimal_payload = json!({
-            "data": "minimal"
-        });
-
-        let result = handle_webhook(State(state), Json(minimal_payload)).await;
+        let result = handle_webhook(
+            State(state),
+            None,
+            HeaderMap::new(),
+            Bytes::from_static(b"{\"type\":\"test_event\"}"),
+        )
+        .await;
 
-        assert!(result.is_ok());
-        let (status, _) = result.unwrap();
-        assert_eq!(status, StatusCode::ACCEPTED);
+        assert!(matches!(result, Err(GatewayError::Auth(_))));
         Ok(())
     }
 
     #[tokio::test]
-    async fn test_webhook_with_empty_payload() -> Result<(), Box<dyn std::error::Error>> {
+    async fn test_webhook_without_event_type() -> Result<(), Box<dyn std::error::Error>> {
         let state = AppState::new(GatewayConfig::default()).await?;
 
-        let empty_payload = json!({});
+        let result = handle_webhook(
+            State(state),
+            Some(Extension(caller())),
+            HeaderMap::new(),
+            Bytes::from_static(b"{\"data\":\"minimal\"}"),
+        )
+        .await;
 
-        let result = handle_webhook(State(state), Json(empty_payload)).await;
+        assert!(matches!(result, Err(GatewayError::InvalidRequest(_))));
+        Ok(())
+    }
 
-        assert!(result.is_ok());
-        let (status, _) = result.unwrap();
+    #[tokio::test]
+    async fn test_signed_webhook_job_belongs_to_owner_key() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let mut config = GatewayConfig::default();
+        config.webhooks = WebhookConfig {
+            routes: vec![WebhookRoute {
+                event: "*".to_string(),
+                prompt: "Handle {{event}}".to_string(),
+                cwd: None,
+                model: None,
+                sandbox_mode: None,
+            }],
+            owner_key: Some("hooks".to_string()),
+            ..signed_config()
+        };
+        let state = AppState::new(config).await?;
+
+        let body = br#"{"type":"deploy"}"#;
+        let timestamp = Utc::now().timestamp().to_string();
+        let signature = hex_signature(
+            b"It's a Secret to Everybody",
+            &[timestamp.as_bytes(), b".", body],
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(TIMESTAMP_HEADER, HeaderValue::from_str(&timestamp)?);
+        headers.insert(
+            SIGNATURE_HEADER,
+            HeaderValue::from_str(&format!("sha256={signature}"))?,
+        );
+
+        let (status, Json(response)) = handle_webhook(
+            State(state.clone()),
+            None,
+            headers,
+            Bytes::from_static(body),
+        )
+        .await?;
         assert_eq!(status, StatusCode::ACCEPTED);
+        let job_id = response.job_id.ok_or("no job queued")?;
+
+        let owner = ApiKeyInfo {
+            key_id: "hooks".to_string(),
+            ..caller()
+        };
+        let job = handle_get_job(
+            State(state.clone()),
+            Some(Extension(owner.clone())),
+            Path(job_id.clone()),
+        )
+        .await;
+        assert!(job.is_ok());
+        let other = handle_get_job(
+            State(state.clone()),
+            Some(Extension(caller())),
+            Path(job_id.clone()),
+        )
+        .await;
+        assert!(matches!(other, Err(GatewayError::NotFound(_))));
+
+        let _ = handle_cancel_job(State(state), Some(Extension(owner)), Path(job_id)).await;
         Ok(())
     }
 }
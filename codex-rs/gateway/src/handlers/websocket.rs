@@ -21,18 +21,58 @@
 //! {"type": "item.completed", ...}
 //! {"type": "turn.completed", ...}
 //! ```
+//!
+//! ## Approvals
+//!
+//! When the session runs under an approval policy other than `never`, the
+//! agent may pause for a decision. Pending requests are surfaced as
+//! `exec_approval_request` / `patch_approval_request` /
+//! `mcp_tool_call_approval_request` messages carrying an `id` and a
+//! `conversation_id`, and the client answers with `exec_approval` /
+//! `patch_approval` / `mcp_tool_call_approval` using the same `id` and
+//! `conversation_id` and a `ReviewDecision` (`approved`,
+//! `approved_for_session`, `denied` or `abort`; exec approvals also accept
+//! `approved_prefix` / `approved_prefix_for_project`). Exec streams run in the
+//! background so approval replies can arrive while a turn is in flight.
+//!
+//! `cwd`, `sandbox_mode`, `approval_policy` and local image paths are checked
+//! against the connecting key's [`WorkspacePolicy`] before each turn starts.
+//! As for `POST /exec`, host image paths are only accepted from loopback
+//! clients; remote clients must send images as `data:` URIs.
 
+use crate::error::GatewayError;
 use crate::error::GatewayResult;
+use crate::handlers::upload::check_host_images;
+use crate::listener::PeerAddr;
+use crate::middleware::api_key::ApiKeyInfo;
+use crate::middleware::rate_limit::SessionPermit;
+use crate::services::audit::ApprovalKind;
+use crate::services::audit::AuditContext;
+use crate::services::audit::AuditEvent;
+use crate::services::drain::interrupted_message;
+use crate::services::metrics::WebSocketGuard;
+use crate::services::workspace::TurnSettings;
+use crate::services::workspace::WorkspacePolicy;
 use crate::state::AppState;
+use axum::Extension;
+use axum::extract::ConnectInfo;
 use axum::extract::State;
 use axum::extract::WebSocketUpgrade;
 use axum::extract::ws::Message;
 use axum::extract::ws::WebSocket;
 use axum::response::Response;
+use codex_core::CodexConversation;
 use codex_exec::event_processor_with_jsonl_output::EventProcessorWithJsonOutput;
+use codex_exec::exec_events::ThreadErrorEvent;
 use codex_exec::exec_events::ThreadEvent;
+use codex_exec::exec_events::TurnFailedEvent;
+use codex_protocol::approvals::ApplyPatchApprovalRequestEvent;
+use codex_protocol::approvals::ExecApprovalRequestEvent;
+use codex_protocol::approvals::McpToolCallApprovalRequestEvent;
+use codex_protocol::protocol::AskForApproval;
 use codex_protocol::protocol::EventMsg;
 use codex_protocol::protocol::Op;
+use codex_protocol::protocol::ReviewDecision;
 use codex_protocol::user_input::UserInput;
 use futures::SinkExt;
 use futures::StreamExt;
@@ -40,6 +80,8 @@ use futures::stream::SplitSink;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -66,9 +108,31 @@ enum WebSocketRequest {
         cwd: Option<PathBuf>,
         #[serde(skip_serializing_if = "Option::is_none")]
         model: Option<String>,
+        #[serde(default)]
+        sandbox_mode: Option<String>,
+        #[serde(default)]
+        approval_policy: Option<AskForApproval>,
     },
     /// Interrupt current execution
     Interrupt { session_id: String },
+    /// Answer a pending `exec_approval_request`
+    ExecApproval {
+        conversation_id: String,
+        id: String,
+        decision: ReviewDecision,
+    },
+    /// Answer a pending `patch_approval_request`
+    PatchApproval {
+        conversation_id: String,
+        id: String,
+        decision: ReviewDecision,
+    },
+    /// Answer a pending `mcp_tool_call_approval_request`
+    McpToolCallApproval {
+        conversation_id: String,
+        id: String,
+        decision: ReviewDecision,
+    },
     /// Ping for keep-alive
     Ping,
 }
@@ -79,6 +143,24 @@ enum WebSocketRequest {
 enum WebSocketResponse {
     /// JSONL event from exec (matches ThreadEvent from codex-exec)
     Event { event: Box<ThreadEvent> },
+    /// The agent is waiting for approval to run a command
+    ExecApprovalRequest {
+        id: String,
+        conversation_id: String,
+        request: Box<ExecApprovalRequestEvent>,
+    },
+    /// The agent is waiting for approval to apply a patch
+    PatchApprovalRequest {
+        id: String,
+        conversation_id: String,
+        request: Box<ApplyPatchApprovalRequestEvent>,
+    },
+    /// The agent is waiting for approval to call an MCP tool
+    McpToolCallApprovalRequest {
+        id: String,
+        conversation_id: String,
+        request: Box<McpToolCallApprovalRequestEvent>,
+    },
     /// Acknowledgment of command
     Ack { message: String },
     /// Error message
@@ -87,27 +169,162 @@ enum WebSocketResponse {
     Pong,
 }
 
+/// Approval request awaiting a client decision
+struct PendingApproval<C = Arc<CodexConversation>> {
+    kind: ApprovalKind,
+    conversation: C,
+    /// Turn the request belongs to, for auditing the decision
+    audit: AuditContext,
+}
+
+/// Approval requests awaiting a decision on one connection
+///
+/// Core numbers request ids per conversation, so two conversations on the
+/// same socket can both have a request `"1"`; entries are keyed by
+/// `(conversation_id, id)`.
+struct ApprovalTable<C = Arc<CodexConversation>> {
+    entries: HashMap<(String, String), PendingApproval<C>>,
+}
+
+impl<C> ApprovalTable<C> {
+    fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, conversation_id: String, id: String, pending: PendingApproval<C>) {
+        self.entries.insert((conversation_id, id), pending);
+    }
+
+    /// Remove and return the pending request answered by a client reply
+    fn take(
+        &mut self,
+        kind: ApprovalKind,
+        conversation_id: &str,
+        id: &str,
+    ) -> anyhow::Result<PendingApproval<C>> {
+        let key = (conversation_id.to_string(), id.to_string());
+        match self.entries.remove(&key) {
+            Some(pending) if pending.kind == kind => Ok(pending),
+            Some(pending) => {
+                self.entries.insert(key, pending);
+                Err(anyhow::anyhow!(
+                    "Approval request {id} is not a {kind:?} approval"
+                ))
+            }
+            None => Err(anyhow::anyhow!(
+                "No pending approval request with id {id} for conversation {conversation_id}"
+            )),
+        }
+    }
+
+    /// Drop every request belonging to `conversation_id`
+    fn remove_conversation(&mut self, conversation_id: &str) {
+        self.entries
+            .retain(|(conversation, _), _| conversation != conversation_id);
+    }
+
+    /// Remove every request, returning the request ids
+    fn drain(&mut self) -> Vec<(String, PendingApproval<C>)> {
+        self.entries
+            .drain()
+            .map(|((_, id), pending)| (id, pending))
+            .collect()
+    }
+}
+
+/// Pending approvals for one connection
+type PendingApprovals = Arc<Mutex<ApprovalTable>>;
+
+type WsSender = Arc<Mutex<SplitSink<WebSocket, Message>>>;
+
+/// Output of the background event loop for one exec turn
+enum TurnOutput {
+    Thread(ThreadEvent),
+    ExecApproval {
+        id: String,
+        request: ExecApprovalRequestEvent,
+    },
+    PatchApproval {
+        id: String,
+        request: ApplyPatchApprovalRequestEvent,
+    },
+    McpToolCallApproval {
+        id: String,
+        request: McpToolCallApprovalRequestEvent,
+    },
+}
+
 /// Handle WebSocket upgrade request
 ///
 /// This is the entry point for WebSocket connections. It upgrades the HTTP
-/// connection to a WebSocket connection and starts the message loop.
+/// connection to a WebSocket connection and starts the message loop. The
+/// caller's session slot, if any, is held until the connection closes.
 pub async fn handle_websocket_upgrade(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
+    caller: Option<Extension<ApiKeyInfo>>,
+    session_permit: Option<Extension<SessionPermit>>,
+    peer: Option<Extension<ConnectInfo<PeerAddr>>>,
 ) -> GatewayResult<Response> {
     info!("WebSocket upgrade requested");
-    Ok(ws.on_upgrade(|socket| handle_websocket_connection(socket, state)))
+    let local = peer.is_some_and(|Extension(ConnectInfo(peer))| peer.is_loopback());
+    let policy = WorkspacePolicy::for_caller(
+        state.config(),
+        state.codex_service.codex_config(),
+        caller.as_deref(),
+    )?;
+    let (ws, connection) = limit_websocket(&state, ws)?;
+    Ok(ws.on_upgrade(move |socket| async move {
+        let _session_permit = session_permit;
+        let _connection = connection;
+        let owner = caller.map(|Extension(key)| key.key_id);
+        handle_websocket_connection(socket, state, policy, owner, local).await;
+    }))
+}
+
+/// Apply the configured message limits and reserve a connection slot
+///
+/// Upgrades beyond `websocket.max_connections` are refused with 503; the
+/// slot is released when the returned guard is dropped.
+pub(crate) fn limit_websocket(
+    state: &AppState,
+    ws: WebSocketUpgrade,
+) -> GatewayResult<(WebSocketUpgrade, WebSocketGuard)> {
+    let config = &state.config().websocket;
+    let connection = state
+        .metrics
+        .try_track_websocket(config.max_connections)
+        .ok_or_else(|| {
+            warn!(
+                "Refusing WebSocket upgrade: {} connections open",
+                config.max_connections
+            );
+            GatewayError::ServiceUnavailable("too many WebSocket connections".to_string())
+        })?;
+    let ws = ws
+        .max_message_size(config.max_message_size)
+        .max_frame_size(config.max_frame_size);
+    Ok((ws, connection))
 }
 
 /// Handle WebSocket connection lifecycle
 ///
 /// Splits the WebSocket into sender and receiver, then enters the main
 /// message loop where it processes client requests and streams responses.
-async fn handle_websocket_connection(socket: WebSocket, state: AppState) {
+async fn handle_websocket_connection(
+    socket: WebSocket,
+    state: AppState,
+    policy: WorkspacePolicy,
+    owner: Option<String>,
+    local: bool,
+) {
     info!("WebSocket connection established");
 
     let (sender, mut receiver) = socket.split();
     let sender = Arc::new(Mutex::new(sender));
+    let pending_approvals: PendingApprovals = Arc::new(Mutex::new(ApprovalTable::new()));
 
     // Main message loop - process client messages
     while let Some(msg_result) = receiver.next().await {
@@ -116,7 +333,17 @@ async fn handle_websocket_connection(socket: WebSocket, state: AppState) {
                 let text_str = text.to_string();
                 debug!("Received WebSocket text message: {}", text_str);
                 let sender_clone = Arc::clone(&sender);
-                if let Err(e) = handle_text_message(text_str, &state, sender_clone).await {
+                if let Err(e) = handle_text_message(
+                    text_str,
+                    &state,
+                    &policy,
+                    owner.as_deref(),
+                    local,
+                    sender_clone,
+                    Arc::clone(&pending_approvals),
+                )
+                .await
+                {
                     error!("Error handling WebSocket message: {e}");
                     let sender_clone = Arc::clone(&sender);
                     let _ = send_error(sender_clone, format!("Error: {e}")).await;
@@ -147,17 +374,58 @@ async fn handle_websocket_connection(socket: WebSocket, state: AppState) {
         }
     }
 
+    // Nobody is left to answer outstanding approvals; deny them so the turns can finish
+    let pending = pending_approvals.lock().await.drain();
+    for (id, pending) in pending {
+        state
+            .codex_service
+            .audit()
+            .record(
+                &pending.audit,
+                AuditEvent::ApprovalDecision {
+                    approval_id: id.clone(),
+                    kind: pending.kind,
+                    decision: ReviewDecision::Denied,
+                    reason: Some("client disconnected".to_string()),
+                },
+            )
+            .await;
+        let op = match pending.kind {
+            ApprovalKind::Exec => Op::ExecApproval {
+                id,
+                decision: ReviewDecision::Denied,
+            },
+            ApprovalKind::Patch => Op::PatchApproval {
+                id,
+                decision: ReviewDecision::Denied,
+            },
+            ApprovalKind::McpToolCall => Op::McpToolCallApproval {
+                id,
+                decision: ReviewDecision::Denied,
+            },
+        };
+        if let Err(e) = pending.conversation.submit(op).await {
+            warn!("Failed to deny pending approval on disconnect: {e}");
+        }
+    }
+
     info!("WebSocket connection closed");
 }
 
 /// Handle incoming text messages from WebSocket client
 ///
 /// Parses the JSON message and dispatches to the appropriate handler
-/// based on the message type (exec, interrupt, ping).
+/// based on the message type (exec, interrupt, approvals, ping). Exec
+/// requests are streamed from a background task so the connection keeps
+/// reading approval and interrupt messages while the turn runs.
 async fn handle_text_message(
     text: String,
     state: &AppState,
-    sender: Arc<Mutex<SplitSink<WebSocket, Message>>>,
+    policy: &WorkspacePolicy,
+    owner: Option<&str>,
+    local: bool,
+    sender: WsSender,
+    pending_approvals: PendingApprovals,
 ) -> anyhow::Result<()> {
     let request: WebSocketRequest = serde_json::from_str(&text)?;
 
@@ -169,21 +437,90 @@ async fn handle_text_message(
             output_schema,
             cwd,
             model,
+            sandbox_mode,
+            approval_policy,
+        } => {
+            // Reject policy violations before spawning so the error is reported synchronously
+            check_host_images(&images, local)?;
+            for image in images.iter().filter(|img| !img.starts_with("data:")) {
+                policy.check_path(Path::new(image), false)?;
+            }
+            let settings =
+                policy.resolve(cwd.as_deref(), sandbox_mode.as_deref(), approval_policy)?;
+            let state = state.clone();
+            let owner = owner.map(str::to_string);
+            tokio::spawn(async move {
+                let error_sender = Arc::clone(&sender);
+                if let Err(e) = handle_exec_request(
+                    prompt,
+                    session_id,
+                    images,
+                    output_schema,
+                    settings,
+                    model,
+                    owner.as_deref(),
+                    &state,
+                    sender,
+                    pending_approvals,
+                )
+                .await
+                {
+                    error!("Error handling WebSocket exec request: {e}");
+                    let _ = send_error(error_sender, format!("Error: {e}")).await;
+                }
+            });
+            Ok(())
+        }
+        WebSocketRequest::Interrupt { session_id } => {
+            handle_interrupt_request(session_id, owner, state, sender).await
+        }
+        WebSocketRequest::ExecApproval {
+            conversation_id,
+            id,
+            decision,
         } => {
-            handle_exec_request(
-                prompt,
-                session_id,
-                images,
-                output_schema,
-                cwd,
-                model,
+            handle_approval_response(
+                ApprovalKind::Exec,
+                conversation_id,
+                id,
+                decision,
                 state,
                 sender,
+                pending_approvals,
             )
             .await
         }
-        WebSocketRequest::Interrupt { session_id } => {
-            handle_interrupt_request(session_id, state, sender).await
+        WebSocketRequest::PatchApproval {
+            conversation_id,
+            id,
+            decision,
+        } => {
+            handle_approval_response(
+                ApprovalKind::Patch,
+                conversation_id,
+                id,
+                decision,
+                state,
+                sender,
+                pending_approvals,
+            )
+            .await
+        }
+        WebSocketRequest::McpToolCallApproval {
+            conversation_id,
+            id,
+            decision,
+        } => {
+            handle_approval_response(
+                ApprovalKind::McpToolCall,
+                conversation_id,
+                id,
+                decision,
+                state,
+                sender,
+                pending_approvals,
+            )
+            .await
         }
         WebSocketRequest::Ping => {
             let response = WebSocketResponse::Pong;
@@ -201,17 +538,19 @@ async fn handle_text_message(
 /// 1. Creates/gets conversation
 /// 2. Spawns background task with EventProcessorWithJsonOutput
 /// 3. Submits Op::UserTurn
-/// 4. Streams ThreadEvents to client in real-time
+/// 4. Streams ThreadEvents and approval requests to client in real-time
 #[allow(clippy::too_many_arguments)]
 async fn handle_exec_request(
     prompt: String,
     session_id: Option<String>,
     images: Vec<String>,
     output_schema: Option<Value>,
-    cwd: Option<PathBuf>,
+    settings: TurnSettings,
     model: Option<String>,
+    owner: Option<&str>,
     state: &AppState,
-    sender: Arc<Mutex<SplitSink<WebSocket, Message>>>,
+    sender: WsSender,
+    pending_approvals: PendingApprovals,
 ) -> anyhow::Result<()> {
     info!(
         "Handling WebSocket exec request: prompt_len={}, session_id={:?}",
@@ -222,7 +561,7 @@ async fn handle_exec_request(
     // 1. Get or create conversation
     let conversation_id = state
         .codex_service
-        .get_or_create_conversation(session_id.as_deref())
+        .get_or_create_conversation(session_id.as_deref(), owner)
         .await?;
 
     debug!("Using conversation_id: {}", conversation_id);
@@ -232,6 +571,16 @@ async fn handle_exec_request(
         let manager = state.codex_service.conversation_manager().lock().await;
         manager.get_conversation(conversation_id).await?
     };
+    let turn = state
+        .codex_service
+        .drain()
+        .track(Arc::clone(&conversation))?;
+    let audit_context = AuditContext::new(owner, session_id.as_deref(), conversation_id);
+    let mut auditor = state
+        .codex_service
+        .audit()
+        .start_turn(audit_context.clone(), &prompt)
+        .await;
 
     // 3. Prepare UserInputs
     let mut user_inputs: Vec<UserInput> = vec![];
@@ -252,26 +601,68 @@ async fn handle_exec_request(
 
     // 4. Get config for Op::UserTurn params
     let config = state.codex_service.codex_config();
-    let cwd = cwd.unwrap_or_else(|| config.cwd.clone());
+    let cwd = settings.cwd;
     let model = model.unwrap_or_else(|| config.model.clone());
 
     // 5. Create channel for event streaming
-    let (tx, mut rx) = mpsc::unbounded_channel::<ThreadEvent>();
+    let (tx, mut rx) = mpsc::unbounded_channel::<TurnOutput>();
 
     // 6. Spawn background task to process events using REAL EventProcessorWithJsonOutput
     let conversation_clone = conversation.clone();
+    let metrics = state.metrics.clone();
+    let drain = state.codex_service.drain().clone();
     tokio::spawn(async move {
+        let _turn = turn;
         let mut processor = EventProcessorWithJsonOutput::new(None);
 
         loop {
             match conversation_clone.next_event().await {
                 Ok(event) => {
                     debug!("WebSocket: Processing event: {:?}", event.msg);
+                    let usage = metrics.observe_event(conversation_id, &event.msg);
+                    auditor.observe(&event, usage.as_ref()).await;
+
+                    // Approval requests are not ThreadEvents; forward them as-is
+                    let approval = match &event.msg {
+                        EventMsg::ExecApprovalRequest(request) => Some(TurnOutput::ExecApproval {
+                            id: event.id.clone(),
+                            request: request.clone(),
+                        }),
+                        EventMsg::ApplyPatchApprovalRequest(request) => {
+                            Some(TurnOutput::PatchApproval {
+                                id: event.id.clone(),
+                                request: request.clone(),
+                            })
+                        }
+                        EventMsg::McpToolCallApprovalRequest(request) => {
+                            Some(TurnOutput::McpToolCallApproval {
+                                id: event.id.clone(),
+                                request: request.clone(),
+                            })
+                        }
+                        _ => None,
+                    };
+                    if let Some(approval) = approval
+                        && tx.send(approval).is_err()
+                    {
+                        error!("WebSocket: Failed to send approval to channel (receiver dropped)");
+                        break;
+                    }
 
                     // Use REAL EventProcessorWithJsonOutput
-                    let thread_events = processor.collect_thread_events(&event);
+                    let mut thread_events = processor.collect_thread_events(&event);
+                    if matches!(event.msg, EventMsg::TurnAborted(_)) && drain.is_draining() {
+                        thread_events.push(ThreadEvent::TurnFailed(TurnFailedEvent {
+                            error: ThreadErrorEvent {
+                                message: interrupted_message(
+                                    conversation_id,
+                                    session_id.as_deref(),
+                                ),
+                            },
+                        }));
+                    }
                     for te in thread_events {
-                        if tx.send(te).is_err() {
+                        if tx.send(TurnOutput::Thread(te)).is_err() {
                             error!("WebSocket: Failed to send event to channel (receiver dropped)");
                             break;
                         }
@@ -303,8 +694,8 @@ async fn handle_exec_request(
         .submit(Op::UserTurn {
             items: user_inputs,
             cwd,
-            approval_policy: config.approval_policy,
-            sandbox_policy: config.sandbox_policy.clone(),
+            approval_policy: settings.approval_policy,
+            sandbox_policy: settings.sandbox_policy,
             model,
             effort: config.model_reasoning_effort,
             summary: config.model_reasoning_summary,
@@ -313,9 +704,59 @@ async fn handle_exec_request(
         .await?;
 
     // 8. Stream events to client in real-time
-    while let Some(thread_event) = rx.recv().await {
-        let response = WebSocketResponse::Event {
-            event: Box::new(thread_event),
+    while let Some(output) = rx.recv().await {
+        let response = match output {
+            TurnOutput::Thread(thread_event) => WebSocketResponse::Event {
+                event: Box::new(thread_event),
+            },
+            TurnOutput::ExecApproval { id, request } => {
+                pending_approvals.lock().await.insert(
+                    conversation_id.to_string(),
+                    id.clone(),
+                    PendingApproval {
+                        kind: ApprovalKind::Exec,
+                        conversation: Arc::clone(&conversation),
+                        audit: audit_context.clone(),
+                    },
+                );
+                WebSocketResponse::ExecApprovalRequest {
+                    id,
+                    conversation_id: conversation_id.to_string(),
+                    request: Box::new(request),
+                }
+            }
+            TurnOutput::PatchApproval { id, request } => {
+                pending_approvals.lock().await.insert(
+                    conversation_id.to_string(),
+                    id.clone(),
+                    PendingApproval {
+                        kind: ApprovalKind::Patch,
+                        conversation: Arc::clone(&conversation),
+                        audit: audit_context.clone(),
+                    },
+                );
+                WebSocketResponse::PatchApprovalRequest {
+                    id,
+                    conversation_id: conversation_id.to_string(),
+                    request: Box::new(request),
+                }
+            }
+            TurnOutput::McpToolCallApproval { id, request } => {
+                pending_approvals.lock().await.insert(
+                    conversation_id.to_string(),
+                    id.clone(),
+                    PendingApproval {
+                        kind: ApprovalKind::McpToolCall,
+                        conversation: Arc::clone(&conversation),
+                        audit: audit_context.clone(),
+                    },
+                );
+                WebSocketResponse::McpToolCallApprovalRequest {
+                    id,
+                    conversation_id: conversation_id.to_string(),
+                    request: Box::new(request),
+                }
+            }
         };
         let json = serde_json::to_string(&response)?;
 
@@ -326,6 +767,12 @@ async fn handle_exec_request(
         }
     }
 
+    // Drop approvals that can no longer be answered for this conversation
+    pending_approvals
+        .lock()
+        .await
+        .remove_conversation(&conversation_id.to_string());
+
     info!(
         "WebSocket: Exec completed for conversation_id={}",
         conversation_id
@@ -339,19 +786,18 @@ async fn handle_exec_request(
 /// Submits Op::Interrupt to the conversation to stop execution.
 async fn handle_interrupt_request(
     session_id: String,
+    owner: Option<&str>,
     state: &AppState,
-    sender: Arc<Mutex<SplitSink<WebSocket, Message>>>,
+    sender: WsSender,
 ) -> anyhow::Result<()> {
     info!("WebSocket: Interrupt requested for session: {}", session_id);
 
     // Get conversation ID from session ID
-    let conversation_id = {
-        let conversations = state.codex_service.active_conversations().lock().await;
-        conversations
-            .get(&session_id)
-            .copied()
-            .ok_or_else(|| anyhow::anyhow!("Session not found: {session_id}"))?
-    };
+    let conversation_id = state
+        .codex_service
+        .loaded_conversation(&session_id, owner)
+        .await
+        .ok_or_else(|| anyhow::anyhow!("Session not found: {session_id}"))?;
 
     // Get conversation
     let conversation = {
@@ -379,11 +825,72 @@ async fn handle_interrupt_request(
     Ok(())
 }
 
+/// Handle an approval decision sent by the client
+///
+/// Looks up the pending request by conversation and id and forwards the decision to the
+/// conversation as `Op::ExecApproval` / `Op::PatchApproval` /
+/// `Op::McpToolCallApproval`.
+async fn handle_approval_response(
+    kind: ApprovalKind,
+    conversation_id: String,
+    id: String,
+    decision: ReviewDecision,
+    state: &AppState,
+    sender: WsSender,
+    pending_approvals: PendingApprovals,
+) -> anyhow::Result<()> {
+    info!(
+        "WebSocket: {:?} approval decision {} for request {} of conversation {}",
+        kind, decision, id, conversation_id
+    );
+
+    let pending = pending_approvals
+        .lock()
+        .await
+        .take(kind, &conversation_id, &id)?;
+
+    let op = match kind {
+        ApprovalKind::Exec => Op::ExecApproval {
+            id: id.clone(),
+            decision,
+        },
+        ApprovalKind::Patch => Op::PatchApproval {
+            id: id.clone(),
+            decision,
+        },
+        ApprovalKind::McpToolCall => Op::McpToolCallApproval {
+            id: id.clone(),
+            decision,
+        },
+    };
+    pending.conversation.submit(op).await?;
+    state
+        .codex_service
+        .audit()
+        .record(
+            &pending.audit,
+            AuditEvent::ApprovalDecision {
+                approval_id: id.clone(),
+                kind,
+                decision,
+                reason: None,
+            },
+        )
+        .await;
+
+    let response = WebSocketResponse::Ack {
+        message: format!("Approval decision {decision} submitted for request {id}"),
+    };
+    let json = serde_json::to_string(&response)?;
+
+    let mut sender_lock = sender.lock().await;
+    sender_lock.send(Message::Text(json.into())).await?;
+
+    Ok(())
+}
+
 /// Send error message to WebSocket client
-async fn send_error(
-    sender: Arc<Mutex<SplitSink<WebSocket, Message>>>,
-    message: String,
-) -> Result<(), axum::Error> {
+async fn send_error(sender: WsSender, message: String) -> Result<(), axum::Error> {
     let response = WebSocketResponse::Error { message };
     let json = serde_json::to_string(&response).unwrap_or_else(|_| "{}".to_string());
 
@@ -422,6 +929,137 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_websocket_request_exec_approval_deserialization() {
+        let json = r#"{"type":"exec_approval","conversation_id":"conv","id":"1","decision":"approved_for_session"}"#;
+        let request: WebSocketRequest = serde_json::from_str(json).unwrap();
+
+        match request {
+            WebSocketRequest::ExecApproval {
+                conversation_id,
+                id,
+                decision,
+            } => {
+                assert_eq!(conversation_id, "conv");
+                assert_eq!(id, "1");
+                assert_eq!(decision, ReviewDecision::ApprovedForSession);
+            }
+            _ => panic!("Expected ExecApproval variant"),
+        }
+    }
+
+    #[test]
+    fn test_websocket_request_patch_approval_deserialization() {
+        let json =
+            r#"{"type":"patch_approval","conversation_id":"conv","id":"2","decision":"denied"}"#;
+        let request: WebSocketRequest = serde_json::from_str(json).unwrap();
+
+        match request {
+            WebSocketRequest::PatchApproval {
+                conversation_id,
+                id,
+                decision,
+            } => {
+                assert_eq!(conversation_id, "conv");
+                assert_eq!(id, "2");
+                assert_eq!(decision, ReviewDecision::Denied);
+            }
+            _ => panic!("Expected PatchApproval variant"),
+        }
+    }
+
+    #[test]
+    fn test_websocket_request_mcp_tool_call_approval_deserialization() {
+        let json = r#"{"type":"mcp_tool_call_approval","conversation_id":"conv","id":"3","decision":"approved"}"#;
+        let request: WebSocketRequest = serde_json::from_str(json).unwrap();
+
+        match request {
+            WebSocketRequest::McpToolCallApproval {
+                conversation_id,
+                id,
+                decision,
+            } => {
+                assert_eq!(conversation_id, "conv");
+                assert_eq!(id, "3");
+                assert_eq!(decision, ReviewDecision::Approved);
+            }
+            _ => panic!("Expected McpToolCallApproval variant"),
+        }
+    }
+
+    #[test]
+    fn test_approval_table_keys_by_conversation() {
+        let pending = |kind, conversation: &'static str| PendingApproval {
+            kind,
+            conversation,
+            audit: AuditContext::new(None, None, conversation),
+        };
+        let mut table = ApprovalTable::new();
+        // Both conversations number their first approval request "1"
+        table.insert(
+            "conv-a".to_string(),
+            "1".to_string(),
+            pending(ApprovalKind::Exec, "a"),
+        );
+        table.insert(
+            "conv-b".to_string(),
+            "1".to_string(),
+            pending(ApprovalKind::Exec, "b"),
+        );
+
+        assert!(table.take(ApprovalKind::Patch, "conv-b", "1").is_err());
+        let answered = table.take(ApprovalKind::Exec, "conv-b", "1").unwrap();
+        assert_eq!(answered.conversation, "b");
+        assert!(table.take(ApprovalKind::Exec, "conv-b", "1").is_err());
+
+        let answered = table.take(ApprovalKind::Exec, "conv-a", "1").unwrap();
+        assert_eq!(answered.conversation, "a");
+        assert!(table.drain().is_empty());
+    }
+
+    #[test]
+    fn test_approval_table_remove_conversation() {
+        let mut table = ApprovalTable::new();
+        for conversation in ["conv-a", "conv-b"] {
+            table.insert(
+                conversation.to_string(),
+                "1".to_string(),
+                PendingApproval {
+                    kind: ApprovalKind::Patch,
+                    conversation,
+                    audit: AuditContext::new(None, None, conversation),
+                },
+            );
+        }
+
+        table.remove_conversation("conv-a");
+        let remaining = table.drain();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].1.conversation, "conv-b");
+    }
+
+    #[test]
+    fn test_websocket_response_exec_approval_request_serialization() {
+        let response = WebSocketResponse::ExecApprovalRequest {
+            id: "1".to_string(),
+            conversation_id: "conv".to_string(),
+            request: Box::new(ExecApprovalRequestEvent {
+                call_id: "call_1".to_string(),
+                command: vec!["ls".to_string()],
+                cwd: PathBuf::from("/tmp"),
+                reason: None,
+                risk: None,
+                parsed_cmd: vec![],
+                proposed_prefix: None,
+            }),
+        };
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains("\"type\":\"exec_approval_request\""));
+        assert!(json.contains("\"id\":\"1\""));
+        assert!(json.contains("\"call_id\":\"call_1\""));
+    }
+
     #[test]
     fn test_websocket_response_event_serialization() {
         use codex_exec::exec_events::*;
@@ -448,4 +1086,19 @@ mod tests {
         assert!(json.contains("\"type\":\"ack\""));
         assert!(json.contains("\"message\":\"OK\""));
     }
+
+    #[test]
+    fn test_exec_host_images_need_loopback() {
+        let json = r#"{"type":"exec","prompt":"test","images":["data:image/png;base64,AAAA","/etc/passwd"]}"#;
+        let WebSocketRequest::Exec { images, .. } = serde_json::from_str(json).unwrap() else {
+            panic!("Expected Exec variant");
+        };
+
+        assert!(matches!(
+            check_host_images(&images, false),
+            Err(GatewayError::Forbidden(_))
+        ));
+        assert!(check_host_images(&images, true).is_ok());
+        assert!(check_host_images(&images[..1], false).is_ok());
+    }
 }
@@ -6,6 +6,7 @@
 pub mod config;
 pub mod error;
 pub mod handlers;
+pub mod listener;
 pub mod middleware;
 pub mod router;
 pub mod services;
@@ -0,0 +1,274 @@
+//! Listeners for `axum::serve`
+//!
+//! [`LimitedListener`] caps how many client connections are open at once:
+//! when `max_connections` are open, it stops accepting until one closes, so
+//! further clients wait in the kernel backlog. [`TlsListener`] terminates TLS
+//! on top of another listener. Handshakes run in their own tasks so a slow
+//! client cannot hold up the accept loop. Handlers see the client address
+//! as `ConnectInfo<`[`PeerAddr`]`>`.
+
+use crate::config::TlsConfig;
+use crate::error::GatewayError;
+use crate::error::GatewayResult;
+use axum::extract::connect_info::Connected;
+use axum::serve::IncomingStream;
+use axum::serve::Listener;
+use std::fmt::Debug;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::Context;
+use std::task::Poll;
+use std::time::Duration;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+use tokio::io::ReadBuf;
+use tokio::sync::OwnedSemaphorePermit;
+use tokio::sync::Semaphore;
+use tokio::sync::mpsc;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::CertificateDer;
+use tokio_rustls::rustls::pki_types::PrivateKeyDer;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::server::TlsStream;
+use tracing::debug;
+
+/// Time a client gets to complete the TLS handshake
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Completed handshakes waiting for the server to pick them up
+const TLS_ACCEPT_BACKLOG: usize = 64;
+
+/// Address of the client on the other end of a connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerAddr(pub SocketAddr);
+
+impl PeerAddr {
+    /// Whether the client connected from this host
+    pub fn is_loopback(&self) -> bool {
+        let ip = self.0.ip().to_canonical();
+        ip.is_loopback()
+    }
+}
+
+impl<L> Connected<IncomingStream<'_, L>> for PeerAddr
+where
+    L: Listener<Addr = SocketAddr>,
+{
+    fn connect_info(stream: IncomingStream<'_, L>) -> Self {
+        Self(*stream.remote_addr())
+    }
+}
+
+/// Listener that keeps at most `max_connections` connections open
+#[derive(Debug)]
+pub struct LimitedListener<L> {
+    inner: L,
+    permits: Arc<Semaphore>,
+}
+
+impl<L: Listener> LimitedListener<L> {
+    pub fn new(inner: L, max_connections: usize) -> Self {
+        Self {
+            inner,
+            permits: Arc::new(Semaphore::new(max_connections.max(1))),
+        }
+    }
+}
+
+impl<L: Listener> Listener for LimitedListener<L> {
+    type Io = LimitedIo<L::Io>;
+    type Addr = L::Addr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        // The semaphore is never closed, so this only waits for a free slot
+        let permit = Arc::clone(&self.permits).acquire_owned().await.ok();
+        let (io, addr) = self.inner.accept().await;
+        (
+            LimitedIo {
+                io,
+                _permit: permit,
+            },
+            addr,
+        )
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        self.inner.local_addr()
+    }
+}
+
+/// Connection holding one of a [`LimitedListener`]'s slots until dropped
+#[derive(Debug)]
+pub struct LimitedIo<T> {
+    io: T,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for LimitedIo<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for LimitedIo<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+}
+
+/// Listener terminating TLS on the connections of another listener
+pub struct TlsListener<L: Listener> {
+    incoming: mpsc::Receiver<(TlsStream<L::Io>, L::Addr)>,
+    local_addr: L::Addr,
+}
+
+impl<L> TlsListener<L>
+where
+    L: Listener,
+    L::Addr: Clone + Debug + 'static,
+{
+    /// Start accepting on `inner` and handshaking with `acceptor`
+    pub fn new(mut inner: L, acceptor: TlsAcceptor) -> io::Result<Self> {
+        let local_addr = inner.local_addr()?;
+        let (tx, incoming) = mpsc::channel(TLS_ACCEPT_BACKLOG);
+        tokio::spawn(async move {
+            while !tx.is_closed() {
+                let (io, addr) = inner.accept().await;
+                let acceptor = acceptor.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(io)).await {
+                        Ok(Ok(stream)) => {
+                            let _ = tx.send((stream, addr)).await;
+                        }
+                        Ok(Err(err)) => debug!("TLS handshake with {addr:?} failed: {err}"),
+                        Err(_) => debug!("TLS handshake with {addr:?} timed out"),
+                    }
+                });
+            }
+        });
+        Ok(Self {
+            incoming,
+            local_addr,
+        })
+    }
+}
+
+impl<L> Listener for TlsListener<L>
+where
+    L: Listener,
+    L::Addr: Clone,
+{
+    type Io = TlsStream<L::Io>;
+    type Addr = L::Addr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.incoming.recv().await {
+            Some(connection) => connection,
+            // The accept task only stops once this listener is dropped
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(self.local_addr.clone())
+    }
+}
+
+/// Load the certificate chain and key named in `config`
+pub fn tls_acceptor(config: &TlsConfig) -> GatewayResult<TlsAcceptor> {
+    let certs = CertificateDer::pem_file_iter(&config.cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| {
+            GatewayError::Config(format!(
+                "failed to read TLS certificate {}: {e}",
+                config.cert.display()
+            ))
+        })?;
+    if certs.is_empty() {
+        return Err(GatewayError::Config(format!(
+            "no certificate found in {}",
+            config.cert.display()
+        )));
+    }
+    let key = PrivateKeyDer::from_pem_file(&config.key).map_err(|e| {
+        GatewayError::Config(format!(
+            "failed to read TLS key {}: {e}",
+            config.key.display()
+        ))
+    })?;
+
+    let mut server_config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| GatewayError::Config(format!("unsupported TLS configuration: {e}")))?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| GatewayError::Config(format!("invalid TLS certificate or key: {e}")))?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+    use tokio::net::TcpStream;
+
+    #[tokio::test]
+    async fn test_connections_beyond_the_limit_wait() {
+        let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = tcp.local_addr().unwrap();
+        let mut listener = LimitedListener::new(tcp, 1);
+
+        let mut first = TcpStream::connect(addr).await.unwrap();
+        let (mut accepted, _) = listener.accept().await;
+        let _second = TcpStream::connect(addr).await.unwrap();
+
+        // The second connection is only accepted once the first closes
+        let pending = tokio::time::timeout(Duration::from_millis(100), listener.accept()).await;
+        assert!(pending.is_err());
+
+        first.write_all(b"ping").await.unwrap();
+        let mut buf = [0; 4];
+        accepted.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        drop(accepted);
+
+        let second = tokio::time::timeout(Duration::from_secs(5), listener.accept()).await;
+        assert!(second.is_ok());
+    }
+}
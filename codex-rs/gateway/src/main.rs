@@ -1,69 +1,146 @@
 //! Main entry point for the Codex Gateway server
 
-use codex_gateway::config::BodyLimitsConfig;
+use clap::Parser;
 use codex_gateway::config::GatewayConfig;
 use codex_gateway::error::GatewayError;
 use codex_gateway::error::GatewayResult;
+use codex_gateway::listener::LimitedListener;
+use codex_gateway::listener::PeerAddr;
+use codex_gateway::listener::TlsListener;
+use codex_gateway::listener::tls_acceptor;
 use codex_gateway::router::create_router;
 use codex_gateway::state::AppState;
-use std::env;
-use std::net::SocketAddr;
+use std::path::Path;
+use std::path::PathBuf;
 use std::process;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
 use tokio::signal;
+use tokio::sync::Notify;
 use tracing::error;
 use tracing::info;
 use tracing::warn;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 
+/// How long connections still open after the drain (WebSockets, SSE
+/// streams) get to deliver their last events before they are cut
+const CONNECTION_CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// HTTP/WebSocket gateway for Codex
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Cli {
+    /// Path to `gateway.toml`; defaults to `$GATEWAY_CONFIG` when set.
+    /// Environment variables override values from the file.
+    #[arg(long, short = 'c', value_name = "FILE")]
+    config: Option<PathBuf>,
+}
+
 /// Main entry point
 #[tokio::main]
 async fn main() {
-    if let Err(e) = run().await {
+    let cli = Cli::parse();
+    if let Err(e) = run(cli).await {
         error!("Server failed to start: {}", e);
         process::exit(1);
     }
 }
 
 /// Main server execution function
-async fn run() -> GatewayResult<()> {
+async fn run(cli: Cli) -> GatewayResult<()> {
     // Initialize tracing subscriber with structured logging
     init_tracing()?;
 
     info!("Starting Codex Gateway server");
 
     // Load configuration
-    let config = load_config()?;
+    let config_path = cli
+        .config
+        .or_else(|| std::env::var_os("GATEWAY_CONFIG").map(PathBuf::from));
+    let config = load_config(config_path.as_deref())?;
     info!("Configuration loaded: {:?}", config);
 
+    // Load the certificate before anything binds, so a bad path fails fast
+    let tls = config.tls.as_ref().map(tls_acceptor).transpose()?;
+
     // Create application state
     let state = AppState::new(config.clone()).await?;
 
+    // On SIGTERM/Ctrl+C: refuse new work, let in-flight turns finish within
+    // the grace period, interrupt the rest and flush their rollouts
+    let codex_service = Arc::clone(&state.codex_service);
+    let grace = config.timeouts.shutdown_grace_period;
+    let drained = Arc::new(Notify::new());
+    let shutdown = {
+        let drained = Arc::clone(&drained);
+        async move {
+            shutdown_signal().await;
+            codex_service.shutdown(grace).await;
+            drained.notify_one();
+        }
+    };
+
     // Create router with all routes and middleware (now async)
     let app = create_router(state).await?;
+    let make_service = app.into_make_service_with_connect_info::<PeerAddr>();
 
-    // Parse server address from config
-    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
-    info!("Server will bind to: {}", addr);
-
-    // Create TCP listener
-    let listener = TcpListener::bind(addr)
+    // Create TCP listener on the configured address
+    let addr = config.bind_address();
+    let listener = TcpListener::bind(&addr)
         .await
         .map_err(|e| GatewayError::ServerStart(format!("Failed to bind to {addr}: {e}")))?;
-
-    info!("Server listening on {}", addr);
+    let listener = LimitedListener::new(listener, config.max_connections);
 
     // Start server with graceful shutdown
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .map_err(|e| GatewayError::ServerStart(format!("Server error: {e}")))?;
+    let served = match tls {
+        Some(acceptor) => {
+            let listener = TlsListener::new(listener, acceptor).map_err(|e| {
+                GatewayError::ServerStart(format!("Failed to start TLS on {addr}: {e}"))
+            })?;
+            info!(
+                "Server listening on https://{} (max {} connections)",
+                addr, config.max_connections
+            );
+            let serve = axum::serve(listener, make_service).with_graceful_shutdown(shutdown);
+            serve_until_drained(serve, &drained).await
+        }
+        None => {
+            info!(
+                "Server listening on http://{} (max {} connections)",
+                addr, config.max_connections
+            );
+            let serve = axum::serve(listener, make_service).with_graceful_shutdown(shutdown);
+            serve_until_drained(serve, &drained).await
+        }
+    };
+    served.map_err(|e| GatewayError::ServerStart(format!("Server error: {e}")))?;
 
     info!("Server shutdown complete");
     Ok(())
 }
 
+/// Serve until graceful shutdown completes
+///
+/// Connections that outlive the drain by more than
+/// [`CONNECTION_CLOSE_TIMEOUT`] are dropped instead of holding the exit.
+async fn serve_until_drained(
+    serve: impl IntoFuture<Output = std::io::Result<()>>,
+    drained: &Notify,
+) -> std::io::Result<()> {
+    tokio::select! {
+        served = serve.into_future() => served,
+        _ = async {
+            drained.notified().await;
+            tokio::time::sleep(CONNECTION_CLOSE_TIMEOUT).await;
+        } => {
+            warn!("Closing connections still open after the drain");
+            Ok(())
+        }
+    }
+}
+
 /// Initialize structured logging with tracing subscriber
 fn init_tracing() -> GatewayResult<()> {
     tracing_subscriber::registry()
@@ -78,48 +155,39 @@ fn init_tracing() -> GatewayResult<()> {
     Ok(())
 }
 
-/// Load configuration from environment variables
-fn load_config() -> GatewayResult<GatewayConfig> {
-    // Get port from environment variable or use default
-    let port = env::var("PORT")
-        .ok()
-        .and_then(|p| p.parse().ok())
-        .unwrap_or(8080);
-
-    let mut config = GatewayConfig {
-        port,
-        ..Default::default()
-    };
-
-    // Override other config values from environment if available
-    if let Ok(timeout_str) = env::var("REQUEST_TIMEOUT_SECS") {
-        if let Ok(timeout_secs) = timeout_str.parse::<u64>() {
-            config.timeouts.request_timeout = std::time::Duration::from_secs(timeout_secs);
-        } else {
-            warn!(
-                "Invalid REQUEST_TIMEOUT_SECS value: {}, using default",
-                timeout_str
-            );
-        }
+/// Load configuration from `path` (if any) and environment variables
+///
+/// See [`GatewayConfig::load`] for the file format and
+/// [`GatewayConfig::apply_env`] for the variables.
+fn load_config(path: Option<&Path>) -> GatewayResult<GatewayConfig> {
+    if let Some(path) = path {
+        info!("Reading configuration from {}", path.display());
     }
+    let config = GatewayConfig::load(path).map_err(GatewayError::Config)?;
 
-    // Body size limits are fully implemented in router middleware with endpoint-specific limits
-    // Configuration is handled via BodyLimitsConfig and environment variables:
-    // - GATEWAY_BODY_LIMIT_DEFAULT (default: 2MB)
-    // - GATEWAY_BODY_LIMIT_JSONRPC (default: 1MB)
-    // - GATEWAY_BODY_LIMIT_WEBHOOK (default: 10MB)
-    // - GATEWAY_BODY_LIMIT_HEALTH (default: 1KB)
-    // - GATEWAY_BODY_LIMITS_ENABLED (default: true)
-
-    // Override body limits configuration from environment if available
-    config.body_limits = BodyLimitsConfig::from_env();
+    if config.webhooks.secret.is_none() {
+        warn!("No webhook secret configured; /webhook only accepts API key callers");
+    }
+    if !config.tenants.tenants.is_empty() {
+        info!(
+            "Workspace isolation enabled for {} tenant(s)",
+            config.tenants.tenants.len()
+        );
+    }
+    if config.tls.is_none() {
+        warn!("TLS is not configured; serving plain HTTP");
+    }
+    if config.cors.allowed_origins.is_empty() {
+        info!("No CORS origins configured; cross-origin browser requests are refused");
+    }
 
     info!(
-        "Body size limits configured: default={}KB, jsonrpc={}KB, webhook={}KB, health={}KB, enabled={}",
+        "Body size limits configured: default={}KB, jsonrpc={}KB, webhook={}KB, health={}KB, routes={}, enabled={}",
         config.body_limits.default_limit / 1024,
         config.body_limits.jsonrpc_limit / 1024,
         config.body_limits.webhook_limit / 1024,
         config.body_limits.health_limit / 1024,
+        config.body_limits.routes.len(),
         config.body_limits.enabled
     );
 
@@ -164,22 +232,30 @@ async fn shutdown_signal() {
 mod tests {
     use super::*;
 
+    use std::env;
+
     #[test]
     fn test_load_config_defaults() {
-        let config = load_config().unwrap();
+        let config = load_config(None).unwrap();
         assert_eq!(config.port, 8080);
         assert!(config.timeouts.request_timeout.as_secs() > 0);
     }
 
     #[test]
     fn test_load_config_with_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gateway.toml");
+        std::fs::write(&path, "port = 9000\nmax_connections = 50\n").unwrap();
+
         unsafe {
             env::set_var("PORT", "3000");
             env::set_var("REQUEST_TIMEOUT_SECS", "60");
         }
 
-        let config = load_config().unwrap();
+        // Environment variables win over the file
+        let config = load_config(Some(&path)).unwrap();
         assert_eq!(config.port, 3000);
+        assert_eq!(config.max_connections, 50);
         assert_eq!(config.timeouts.request_timeout.as_secs(), 60);
 
         // Clean up
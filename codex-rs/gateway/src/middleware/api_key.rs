@@ -1,20 +1,57 @@
 //! API Key Authentication Middleware
 //!
-//! This middleware validates API keys from the X-API-Key header
-//! and implements rate limiting per key.
+//! This middleware validates API keys from the X-API-Key header against a
+//! file-backed store, or OAuth access tokens from `Authorization: Bearer`
+//! (see [`crate::middleware::oauth`]); a bearer token acts as the key it was
+//! authorized with, restricted to the token's scopes. A bearer value that is
+//! not an access token is tried as an API key, which is how OpenAI SDKs
+//! authenticate against `/v1`. Only salted SHA-256 hashes of the keys are kept on disk;
+//! the plaintext key is returned exactly once, when it is created through the
+//! `/admin/keys` endpoints.
+//!
+//! Authenticated requests are also subject to the per-key token bucket and
+//! session concurrency cap from [`crate::middleware::rate_limit`].
 
+use crate::middleware::oauth::OAuthStore;
+use crate::middleware::rate_limit::RateLimiter;
+use crate::middleware::rate_limit::SessionLimiter;
+use crate::services::Metrics;
 use axum::extract::Request;
 use axum::http::StatusCode;
+use axum::http::header::AUTHORIZATION;
 use axum::middleware::Next;
 use axum::response::IntoResponse;
 use axum::response::Response;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::DateTime;
+use chrono::Utc;
+use rand::RngCore;
 use serde::Deserialize;
 use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
 use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::debug;
+use tracing::info;
 use tracing::warn;
+use uuid::Uuid;
+
+/// Scope required by the `/admin` routes
+pub const SCOPE_ADMIN: &str = "admin";
+
+/// Scope required by every other authenticated route (exec, ws, jsonrpc, ...)
+pub const SCOPE_AGENT: &str = "agent";
+
+/// Scopes a key can be granted
+pub const KNOWN_SCOPES: &[&str] = &[SCOPE_ADMIN, SCOPE_AGENT];
+
+/// Prefix of keys generated by the gateway
+const GENERATED_KEY_PREFIX: &str = "cgw_";
 
 /// API Key validation result
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,69 +64,310 @@ pub struct ApiKeyInfo {
     pub rate_limit: u32,
     /// Whether the key is active
     pub active: bool,
+    /// Scopes granted to this key
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// When the key was created
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    /// When the key stops being accepted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Maximum simultaneous `/exec` and `/ws` sessions (gateway default when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_sessions: Option<usize>,
+    /// Tenant whose workspace policy applies (see [`crate::config::TenantConfig`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+}
+
+impl ApiKeyInfo {
+    /// Whether the key grants the given scope
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+
+    /// Whether the key has expired at `now`
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// On-disk representation of a key: metadata plus the salted hash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredApiKey {
+    #[serde(flatten)]
+    info: ApiKeyInfo,
+    /// Base64url-encoded random salt
+    salt: String,
+    /// Base64url-encoded SHA-256 of salt || key
+    hash: String,
+}
+
+impl StoredApiKey {
+    fn new(api_key: &str, info: ApiKeyInfo) -> Self {
+        let mut salt = [0u8; 16];
+        rand::rng().fill_bytes(&mut salt);
+        let hash = hash_key(&salt, api_key);
+        Self {
+            info,
+            salt: URL_SAFE_NO_PAD.encode(salt),
+            hash,
+        }
+    }
+
+    fn matches(&self, api_key: &str) -> bool {
+        let Ok(salt) = URL_SAFE_NO_PAD.decode(&self.salt) else {
+            return false;
+        };
+        constant_time_eq(hash_key(&salt, api_key).as_bytes(), self.hash.as_bytes())
+    }
 }
 
-/// Simple in-memory API key store
-/// In production, this would be backed by Firestore or another database
+/// File format of the key store
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ApiKeyFile {
+    #[serde(default)]
+    keys: Vec<StoredApiKey>,
+}
+
+#[derive(Debug, Clone)]
+struct KeyEntry {
+    stored: StoredApiKey,
+    /// Keys seeded from the environment are never written to disk
+    persist: bool,
+}
+
+/// API key store keyed by `key_id`
+///
+/// When opened with a path, keys created through [`ApiKeyStore::create_key`]
+/// and revocations are written back to that file.
 #[derive(Debug, Clone)]
 pub struct ApiKeyStore {
-    keys: Arc<RwLock<HashMap<String, ApiKeyInfo>>>,
+    keys: Arc<RwLock<HashMap<String, KeyEntry>>>,
+    path: Option<PathBuf>,
 }
 
 impl ApiKeyStore {
-    /// Create a new API key store
+    /// Create a new in-memory API key store
     pub fn new() -> Self {
         Self {
             keys: Arc::new(RwLock::new(HashMap::new())),
+            path: None,
         }
     }
 
-    /// Add an API key to the store
+    /// Open a file-backed store, loading existing keys if the file exists
+    pub async fn open(path: PathBuf) -> std::io::Result<Self> {
+        let mut keys = HashMap::new();
+        match tokio::fs::read(&path).await {
+            Ok(contents) => {
+                let file: ApiKeyFile = serde_json::from_slice(&contents)?;
+                for stored in file.keys {
+                    keys.insert(
+                        stored.info.key_id.clone(),
+                        KeyEntry {
+                            stored,
+                            persist: true,
+                        },
+                    );
+                }
+                info!("Loaded {} API keys from {}", keys.len(), path.display());
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                debug!("API key file {} not found, starting empty", path.display());
+            }
+            Err(err) => return Err(err),
+        }
+
+        Ok(Self {
+            keys: Arc::new(RwLock::new(keys)),
+            path: Some(path),
+        })
+    }
+
+    /// Add an API key to the store (in memory only)
     pub async fn add_key(&self, api_key: String, info: ApiKeyInfo) {
         let mut keys = self.keys.write().await;
-        keys.insert(api_key, info);
+        keys.insert(
+            info.key_id.clone(),
+            KeyEntry {
+                stored: StoredApiKey::new(&api_key, info),
+                persist: false,
+            },
+        );
     }
 
     /// Validate an API key
     pub async fn validate_key(&self, api_key: &str) -> Option<ApiKeyInfo> {
         let keys = self.keys.read().await;
-        keys.get(api_key).cloned()
+        keys.values()
+            .find(|entry| entry.stored.matches(api_key))
+            .map(|entry| entry.stored.info.clone())
+    }
+
+    /// Metadata of a key by id
+    pub async fn get_key(&self, key_id: &str) -> Option<ApiKeyInfo> {
+        let keys = self.keys.read().await;
+        keys.get(key_id).map(|entry| entry.stored.info.clone())
+    }
+
+    /// Generate a new key, persist its hash and return the plaintext once
+    pub async fn create_key(
+        &self,
+        user_id: String,
+        scopes: Vec<String>,
+        rate_limit: u32,
+        expires_at: Option<DateTime<Utc>>,
+        tenant: Option<String>,
+    ) -> std::io::Result<(String, ApiKeyInfo)> {
+        let mut secret = [0u8; 32];
+        rand::rng().fill_bytes(&mut secret);
+        let api_key = format!("{GENERATED_KEY_PREFIX}{}", URL_SAFE_NO_PAD.encode(secret));
+
+        let info = ApiKeyInfo {
+            key_id: format!("key_{}", Uuid::new_v4().simple()),
+            user_id,
+            rate_limit,
+            active: true,
+            scopes,
+            created_at: Some(Utc::now()),
+            expires_at,
+            max_concurrent_sessions: None,
+            tenant,
+        };
+
+        let mut keys = self.keys.write().await;
+        keys.insert(
+            info.key_id.clone(),
+            KeyEntry {
+                stored: StoredApiKey::new(&api_key, info.clone()),
+                persist: true,
+            },
+        );
+        self.save(&keys).await?;
+
+        Ok((api_key, info))
+    }
+
+    /// List metadata for every known key
+    pub async fn list_keys(&self) -> Vec<ApiKeyInfo> {
+        let keys = self.keys.read().await;
+        let mut infos: Vec<ApiKeyInfo> = keys
+            .values()
+            .map(|entry| entry.stored.info.clone())
+            .collect();
+        infos.sort_by(|a, b| {
+            a.created_at
+                .cmp(&b.created_at)
+                .then_with(|| a.key_id.cmp(&b.key_id))
+        });
+        infos
+    }
+
+    /// Revoke a key by id. Returns `false` if the key does not exist.
+    pub async fn revoke_key(&self, key_id: &str) -> std::io::Result<bool> {
+        let mut keys = self.keys.write().await;
+        let Some(entry) = keys.get_mut(key_id) else {
+            return Ok(false);
+        };
+        entry.stored.info.active = false;
+        self.save(&keys).await?;
+        Ok(true)
+    }
+
+    /// Write persisted keys back to disk (no-op for in-memory stores)
+    async fn save(&self, keys: &HashMap<String, KeyEntry>) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let mut stored: Vec<StoredApiKey> = keys
+            .values()
+            .filter(|entry| entry.persist)
+            .map(|entry| entry.stored.clone())
+            .collect();
+        stored.sort_by(|a, b| a.info.key_id.cmp(&b.info.key_id));
+        let contents = serde_json::to_vec_pretty(&ApiKeyFile { keys: stored })?;
+
+        write_private_file(path, &contents).await
     }
 
     /// Initialize with default keys for testing
-    /// In production, keys would be loaded from Firestore
+    /// In production, keys would be loaded from the key file
     pub async fn with_default_keys() -> Self {
         let store = Self::new();
+        store.add_default_keys().await;
+        store
+    }
 
+    /// Open a file-backed store and add the keys seeded from the environment
+    pub async fn open_with_default_keys(path: PathBuf) -> std::io::Result<Self> {
+        let store = Self::open(path).await?;
+        store.add_default_keys().await;
+        Ok(store)
+    }
+
+    /// Seed keys that are configured outside the key file
+    ///
+    /// - `test-key-12345` (debug builds only)
+    /// - `GATEWAY_API_KEY`: internal agent key
+    /// - `GATEWAY_ADMIN_KEY`: bootstrap key for the `/admin` routes
+    async fn add_default_keys(&self) {
         // Add a default test key
-        store
-            .add_key(
-                "test-key-12345".to_string(),
+        #[cfg(debug_assertions)]
+        self.add_key(
+            "test-key-12345".to_string(),
+            ApiKeyInfo {
+                key_id: "key_001".to_string(),
+                user_id: "user_test".to_string(),
+                rate_limit: 100,
+                active: true,
+                scopes: vec![SCOPE_AGENT.to_string()],
+                created_at: None,
+                expires_at: None,
+                max_concurrent_sessions: None,
+                tenant: None,
+            },
+        )
+        .await;
+
+        // Add production keys from environment if available
+        if let Ok(gateway_key) = std::env::var("GATEWAY_API_KEY") {
+            self.add_key(
+                gateway_key,
                 ApiKeyInfo {
-                    key_id: "key_001".to_string(),
-                    user_id: "user_test".to_string(),
-                    rate_limit: 100,
+                    key_id: "key_gateway".to_string(),
+                    user_id: "gateway_internal".to_string(),
+                    rate_limit: 10000, // Higher limit for internal use
                     active: true,
+                    scopes: vec![SCOPE_AGENT.to_string()],
+                    created_at: None,
+                    expires_at: None,
+                    max_concurrent_sessions: None,
+                    tenant: None,
                 },
             )
             .await;
-
-        // Add production keys from environment if available
-        if let Ok(gateway_key) = std::env::var("GATEWAY_API_KEY") {
-            store
-                .add_key(
-                    gateway_key,
-                    ApiKeyInfo {
-                        key_id: "key_gateway".to_string(),
-                        user_id: "gateway_internal".to_string(),
-                        rate_limit: 10000, // Higher limit for internal use
-                        active: true,
-                    },
-                )
-                .await;
         }
 
-        store
+        if let Ok(admin_key) = std::env::var("GATEWAY_ADMIN_KEY") {
+            self.add_key(
+                admin_key,
+                ApiKeyInfo {
+                    key_id: "key_bootstrap_admin".to_string(),
+                    user_id: "gateway_admin".to_string(),
+                    rate_limit: 1000,
+                    active: true,
+                    scopes: vec![SCOPE_ADMIN.to_string()],
+                    created_at: None,
+                    expires_at: None,
+                    max_concurrent_sessions: None,
+                    tenant: None,
+                },
+            )
+            .await;
+        }
     }
 }
 
@@ -99,12 +377,60 @@ impl Default for ApiKeyStore {
     }
 }
 
+fn hash_key(salt: &[u8], api_key: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(api_key.as_bytes());
+    URL_SAFE_NO_PAD.encode(hasher.finalize())
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Atomically replace `path` with `contents`, readable only by the owner
+pub(crate) async fn write_private_file(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let tmp_path = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp_path, contents).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o600)).await?;
+    }
+    tokio::fs::rename(&tmp_path, path).await
+}
+
+/// Default cap on simultaneous agent sessions per key
+pub const DEFAULT_MAX_SESSIONS_PER_KEY: usize = 4;
+
 /// API Key Authentication middleware
 #[derive(Debug, Clone)]
 pub struct ApiKeyAuth {
     store: ApiKeyStore,
+    /// Issuer of bearer tokens accepted in place of an API key
+    oauth: Option<OAuthStore>,
+    /// Registry counting rejected credentials
+    metrics: Option<Metrics>,
+    rate_limiter: RateLimiter,
+    session_limiter: SessionLimiter,
+    /// Session cap for keys without `max_concurrent_sessions`
+    pub max_sessions_per_key: usize,
     /// Paths that don't require authentication
+    ///
+    /// Entries match exactly; an entry ending in `/` also matches every path
+    /// below it.
     pub exempt_paths: Vec<String>,
+    /// Paths whose handler verifies a request signature instead of a key
+    ///
+    /// Requests without `X-API-Key` are passed through untouched; requests
+    /// that do carry a key are still validated.
+    pub signed_paths: Vec<String>,
 }
 
 impl ApiKeyAuth {
@@ -112,13 +438,20 @@ impl ApiKeyAuth {
     pub fn new(store: ApiKeyStore) -> Self {
         Self {
             store,
+            oauth: None,
+            metrics: None,
+            rate_limiter: RateLimiter::new(),
+            session_limiter: SessionLimiter::new(),
+            max_sessions_per_key: DEFAULT_MAX_SESSIONS_PER_KEY,
             exempt_paths: vec![
                 "/health".to_string(),
+                "/health/".to_string(),
                 "/metrics".to_string(),
-                "/ready".to_string(),
+                "/openapi.json".to_string(),
                 "/oauth/authorize".to_string(),
                 "/oauth/token".to_string(),
             ],
+            signed_paths: vec!["/webhook".to_string()],
         }
     }
 
@@ -127,90 +460,246 @@ impl ApiKeyAuth {
         Self::new(ApiKeyStore::with_default_keys().await)
     }
 
+    /// Also accept OAuth access tokens issued by `oauth`
+    pub fn with_oauth(mut self, oauth: OAuthStore) -> Self {
+        self.oauth = Some(oauth);
+        self
+    }
+
+    /// Count rejected credentials in `metrics`
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    fn record_rejection(&self, reason: &'static str) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_auth_rejection(reason);
+        }
+    }
+
+    /// Resolve a bearer token to the key it was issued for
+    ///
+    /// The returned info only carries the scopes granted to the token.
+    async fn resolve_bearer(&self, token: &str) -> Option<ApiKeyInfo> {
+        let grant = self.oauth.as_ref()?.validate_access_token(token).await?;
+        let mut key_info = self.store.get_key(&grant.key_id).await?;
+        key_info.scopes.retain(|scope| grant.scopes.contains(scope));
+        Some(key_info)
+    }
+
+    /// Override the default per-key session cap
+    pub fn with_max_sessions_per_key(mut self, max_sessions_per_key: usize) -> Self {
+        self.max_sessions_per_key = max_sessions_per_key;
+        self
+    }
+
     /// Check if a path is exempt from authentication
     fn is_exempt_path(&self, path: &str) -> bool {
-        self.exempt_paths.iter().any(|p| path.starts_with(p))
+        self.exempt_paths.iter().any(|exempt| {
+            path == exempt || (exempt.ends_with('/') && path.starts_with(exempt.as_str()))
+        })
+    }
+
+    /// Check if a path may authenticate with a signature instead of a key
+    fn is_signed_path(&self, path: &str) -> bool {
+        self.signed_paths.iter().any(|p| p == path)
+    }
+}
+
+/// Whether `path` starts a long-running agent session
+fn is_session_path(path: &str) -> bool {
+    matches!(
+        path,
+        "/exec" | "/exec/stream" | "/ws" | "/app-server" | "/mcp" | "/v1/chat/completions"
+    )
+}
+
+/// Scope a key needs to access `path`
+fn required_scope(path: &str) -> &'static str {
+    if path.starts_with("/admin") {
+        SCOPE_ADMIN
+    } else {
+        SCOPE_AGENT
     }
 }
 
 /// Middleware function for API key authentication
+///
+/// On success the caller's [`ApiKeyInfo`] is inserted into the request
+/// extensions so handlers can see which key made the request. Session routes
+/// additionally get a [`crate::middleware::rate_limit::SessionPermit`] that
+/// handlers outliving the response must hold on to.
 pub async fn api_key_middleware(
     auth: Arc<ApiKeyAuth>,
-    request: Request,
+    mut request: Request,
     next: Next,
-) -> Result<Response, impl IntoResponse> {
-    let path = request.uri().path();
+) -> Result<Response, Response> {
+    let path = request.uri().path().to_string();
 
     // Skip authentication for exempt paths
-    if auth.is_exempt_path(path) {
+    if auth.is_exempt_path(&path) {
         debug!("Path {} is exempt from authentication", path);
         return Ok(next.run(request).await);
     }
 
-    // Extract API key from X-API-Key header
+    // Extract API key from X-API-Key header, or an OAuth bearer token
     let api_key = request
         .headers()
         .get("X-API-Key")
         .and_then(|h| h.to_str().ok());
+    let bearer_token = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim);
 
-    let api_key = match api_key {
-        Some(key) => key,
-        None => {
+    let key_info = match (api_key, bearer_token) {
+        (Some(key), _) => auth.store.validate_key(key).await,
+        // OpenAI SDKs can only send the key as a bearer token
+        (None, Some(token)) => match auth.resolve_bearer(token).await {
+            Some(key_info) => Some(key_info),
+            None => auth.store.validate_key(token).await,
+        },
+        (None, None) if auth.is_signed_path(&path) => {
+            debug!("No API key on signed path {}, deferring to handler", path);
+            return Ok(next.run(request).await);
+        }
+        (None, None) => {
             warn!("Missing X-API-Key header for path: {}", path);
+            auth.record_rejection("missing");
             return Err((
                 StatusCode::UNAUTHORIZED,
-                "Missing X-API-Key header. Please provide a valid API key.",
-            ));
+                "Missing X-API-Key header or bearer token. Please provide a valid API key.",
+            )
+                .into_response());
         }
     };
 
     // Validate the API key
-    match auth.store.validate_key(api_key).await {
-        Some(key_info) if key_info.active => {
-            debug!(
-                "API key validated: key_id={}, user_id={}, path={}",
-                key_info.key_id, key_info.user_id, path
+    let key_info = match key_info {
+        Some(key_info) if !key_info.active => {
+            warn!(
+                "Inactive API key attempted: key_id={}, user_id={}",
+                key_info.key_id, key_info.user_id
             );
-
-            // TODO: Implement rate limiting here
-            // For now, we just validate the key exists and is active
-
-            // Continue with the request
-            Ok(next.run(request).await)
+            auth.record_rejection("inactive");
+            return Err((StatusCode::FORBIDDEN, "API key is inactive").into_response());
         }
-        Some(key_info) => {
+        Some(key_info) if key_info.is_expired(Utc::now()) => {
             warn!(
-                "Inactive API key attempted: key_id={}, user_id={}",
+                "Expired API key attempted: key_id={}, user_id={}",
                 key_info.key_id, key_info.user_id
             );
-            Err((StatusCode::FORBIDDEN, "API key is inactive"))
+            auth.record_rejection("expired");
+            return Err((StatusCode::UNAUTHORIZED, "API key has expired").into_response());
+        }
+        Some(key_info) if !key_info.has_scope(required_scope(&path)) => {
+            warn!(
+                "API key missing scope '{}': key_id={}, path={}",
+                required_scope(&path),
+                key_info.key_id,
+                path
+            );
+            auth.record_rejection("scope");
+            return Err((
+                StatusCode::FORBIDDEN,
+                "API key is not allowed to access this endpoint",
+            )
+                .into_response());
         }
+        Some(key_info) => key_info,
         None => {
-            warn!("Invalid API key attempted for path: {}", path);
-            Err((StatusCode::UNAUTHORIZED, "Invalid API key"))
+            warn!("Invalid API key or access token for path: {}", path);
+            auth.record_rejection("invalid");
+            return Err(
+                (StatusCode::UNAUTHORIZED, "Invalid API key or access token").into_response(),
+            );
+        }
+    };
+
+    debug!(
+        "API key validated: key_id={}, user_id={}, path={}",
+        key_info.key_id, key_info.user_id, path
+    );
+
+    // Enforce the per-key request rate
+    let rate_decision = auth
+        .rate_limiter
+        .check(&key_info.key_id, key_info.rate_limit);
+    if let Some(decision) = rate_decision
+        && !decision.allowed
+    {
+        warn!(
+            "Rate limit exceeded: key_id={}, limit={}/min, path={}",
+            key_info.key_id, decision.limit, path
+        );
+        let mut response = (StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded").into_response();
+        decision.apply_headers(response.headers_mut());
+        return Err(response);
+    }
+
+    // Cap concurrent agent sessions per key
+    if is_session_path(&path) {
+        let max_sessions = key_info
+            .max_concurrent_sessions
+            .unwrap_or(auth.max_sessions_per_key);
+        match auth
+            .session_limiter
+            .try_acquire(&key_info.key_id, max_sessions)
+        {
+            Some(permit) => {
+                request.extensions_mut().insert(permit);
+            }
+            None => {
+                warn!(
+                    "Concurrent session cap reached: key_id={}, max={}, path={}",
+                    key_info.key_id, max_sessions, path
+                );
+                return Err((
+                    StatusCode::TOO_MANY_REQUESTS,
+                    "Too many concurrent sessions for this API key",
+                )
+                    .into_response());
+            }
         }
     }
+
+    // Continue with the request
+    request.extensions_mut().insert(key_info);
+    let mut response = next.run(request).await;
+    if let Some(decision) = rate_decision {
+        decision.apply_headers(response.headers_mut());
+    }
+    Ok(response)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn test_info(key_id: &str) -> ApiKeyInfo {
+        ApiKeyInfo {
+            key_id: key_id.to_string(),
+            user_id: "user_test".to_string(),
+            rate_limit: 100,
+            active: true,
+            scopes: vec![SCOPE_AGENT.to_string()],
+            created_at: None,
+            expires_at: None,
+            max_concurrent_sessions: None,
+            tenant: None,
+        }
+    }
+
     #[tokio::test]
     async fn test_api_key_store() {
         let store = ApiKeyStore::new();
 
         // Add a test key
         store
-            .add_key(
-                "test-key".to_string(),
-                ApiKeyInfo {
-                    key_id: "key_001".to_string(),
-                    user_id: "user_test".to_string(),
-                    rate_limit: 100,
-                    active: true,
-                },
-            )
+            .add_key("test-key".to_string(), test_info("key_001"))
             .await;
 
         // Validate the key
@@ -223,6 +712,79 @@ mod tests {
         assert!(info.is_none());
     }
 
+    #[tokio::test]
+    async fn test_file_store_persists_only_hashes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("api_keys.json");
+
+        let store = ApiKeyStore::open(path.clone()).await.unwrap();
+        let (api_key, info) = store
+            .create_key(
+                "user_a".to_string(),
+                vec![SCOPE_AGENT.to_string()],
+                60,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(api_key.starts_with(GENERATED_KEY_PREFIX));
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains(&api_key));
+        assert!(contents.contains(&info.key_id));
+
+        // Keys survive a reopen and can be revoked
+        let reopened = ApiKeyStore::open(path.clone()).await.unwrap();
+        let validated = reopened.validate_key(&api_key).await.unwrap();
+        assert_eq!(validated.key_id, info.key_id);
+        assert!(validated.active);
+
+        assert!(reopened.revoke_key(&info.key_id).await.unwrap());
+        assert!(!reopened.revoke_key("key_missing").await.unwrap());
+
+        let reopened = ApiKeyStore::open(path).await.unwrap();
+        assert!(!reopened.validate_key(&api_key).await.unwrap().active);
+    }
+
+    #[tokio::test]
+    async fn test_env_keys_are_not_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("api_keys.json");
+
+        let store = ApiKeyStore::open(path.clone()).await.unwrap();
+        store
+            .add_key("ephemeral".to_string(), test_info("key_env"))
+            .await;
+        store
+            .create_key("user_b".to_string(), vec![], 60, None, None)
+            .await
+            .unwrap();
+
+        let reopened = ApiKeyStore::open(path).await.unwrap();
+        assert!(reopened.validate_key("ephemeral").await.is_none());
+        assert_eq!(reopened.list_keys().await.len(), 1);
+    }
+
+    #[test]
+    fn test_key_expiry_and_scopes() {
+        let now = Utc::now();
+        let mut info = test_info("key_001");
+        assert!(!info.is_expired(now));
+        assert!(info.has_scope(SCOPE_AGENT));
+        assert!(!info.has_scope(SCOPE_ADMIN));
+
+        info.expires_at = Some(now - chrono::Duration::seconds(1));
+        assert!(info.is_expired(now));
+
+        assert_eq!(required_scope("/admin/keys"), SCOPE_ADMIN);
+        assert_eq!(required_scope("/exec"), SCOPE_AGENT);
+
+        assert!(is_session_path("/exec"));
+        assert!(is_session_path("/ws"));
+        assert!(!is_session_path("/exec/resume"));
+    }
+
     #[tokio::test]
     async fn test_exempt_paths() {
         let auth = ApiKeyAuth::default_config().await;
@@ -230,7 +792,67 @@ mod tests {
         assert!(auth.is_exempt_path("/health"));
         assert!(auth.is_exempt_path("/health/ready"));
         assert!(auth.is_exempt_path("/metrics"));
+        assert!(auth.is_exempt_path("/openapi.json"));
+        assert!(auth.is_exempt_path("/oauth/token"));
         assert!(!auth.is_exempt_path("/jsonrpc"));
+        assert!(!auth.is_exempt_path("/ready"));
+        assert!(!auth.is_exempt_path("/healthz"));
+        assert!(!auth.is_exempt_path("/metrics/admin"));
+        assert!(!auth.is_exempt_path("/oauth/token-introspect"));
         assert!(!auth.is_exempt_path("/ws"));
+
+        assert!(auth.is_signed_path("/webhook"));
+        assert!(!auth.is_signed_path("/webhook/jobs/123"));
+        assert!(!auth.is_exempt_path("/webhook"));
+    }
+
+    #[tokio::test]
+    async fn test_bearer_token_acts_as_its_key() {
+        let store = ApiKeyStore::new();
+        let mut info = test_info("key_001");
+        info.scopes.push(SCOPE_ADMIN.to_string());
+        store.add_key("test-key".to_string(), info).await;
+
+        let oauth = OAuthStore::new();
+        let redirect_uri = "https://example.com/callback";
+        let (client, secret) = oauth
+            .register_client(None, vec![redirect_uri.to_string()], true)
+            .await
+            .unwrap();
+        let code = oauth
+            .create_authorization_code(
+                &client.client_id,
+                redirect_uri,
+                "key_001",
+                vec![SCOPE_AGENT.to_string()],
+                None,
+            )
+            .await;
+        let tokens = oauth
+            .exchange_code(
+                &client.client_id,
+                secret.as_deref(),
+                &code,
+                redirect_uri,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let auth = ApiKeyAuth::new(store.clone()).with_oauth(oauth);
+        let resolved = auth.resolve_bearer(&tokens.access_token).await.unwrap();
+        assert_eq!(resolved.key_id, "key_001");
+        assert_eq!(resolved.scopes, vec![SCOPE_AGENT.to_string()]);
+        assert!(auth.resolve_bearer("cgw_at_unknown").await.is_none());
+
+        // Revoking the key revokes what its tokens can do
+        store.revoke_key("key_001").await.unwrap();
+        assert!(
+            !auth
+                .resolve_bearer(&tokens.access_token)
+                .await
+                .unwrap()
+                .active
+        );
     }
 }
@@ -0,0 +1,98 @@
+//! Per-route request body limits
+//!
+//! Applies [`BodyLimitsConfig::get_limit_for_path`] to every request.
+//! Requests whose `Content-Length` is over the limit are refused up front;
+//! other bodies are cut off once they exceed it, which the body extractors
+//! report as `413 Payload Too Large` as well.
+
+use crate::config::BodyLimitsConfig;
+use crate::error::GatewayError;
+use axum::body::Body;
+use axum::extract::Request;
+use axum::http::header::CONTENT_LENGTH;
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use http_body_util::Limited;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Middleware function enforcing the body limit configured for the path
+pub async fn body_limit_middleware(
+    limits: Arc<BodyLimitsConfig>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let limit = limits.get_limit_for_path(request.uri().path());
+    if limit == usize::MAX {
+        return next.run(request).await;
+    }
+
+    let declared = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if let Some(size) = declared
+        && size > limit
+    {
+        let path = request.uri().path().to_string();
+        warn!("Refusing {size} byte body for {path} (limit {limit})");
+        return GatewayError::PayloadTooLarge {
+            max_size: limit,
+            actual_size: Some(size),
+            path,
+        }
+        .into_response();
+    }
+
+    next.run(request.map(|body| Body::new(Limited::new(body, limit))))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::http::StatusCode;
+    use axum::middleware;
+    use axum::routing::post;
+    use std::collections::BTreeMap;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_limit_follows_route() {
+        let limits = Arc::new(BodyLimitsConfig {
+            routes: BTreeMap::from([("/small".to_string(), 4)]),
+            ..Default::default()
+        });
+        let app = Router::new()
+            .route("/small", post(|body: String| async move { body }))
+            .route("/large", post(|body: String| async move { body }))
+            .layer(middleware::from_fn(move |req, next| {
+                body_limit_middleware(Arc::clone(&limits), req, next)
+            }));
+
+        let send = |path: &str, body: &'static str, chunked: bool| {
+            let mut request = Request::post(path);
+            if !chunked {
+                request = request.header(CONTENT_LENGTH, body.len());
+            }
+            let body = if chunked {
+                Body::from_stream(futures::stream::iter([Ok::<_, std::io::Error>(body)]))
+            } else {
+                Body::from(body)
+            };
+            app.clone().oneshot(request.body(body).unwrap())
+        };
+
+        let response = send("/small", "too long", false).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let response = send("/small", "too long", true).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let response = send("/small", "ok", false).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = send("/large", "too long", false).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
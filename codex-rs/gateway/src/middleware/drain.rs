@@ -0,0 +1,66 @@
+//! Refuse new work while the gateway drains on shutdown
+//!
+//! Once [`Drain::run`](crate::services::Drain::run) has started, every
+//! request that could start a turn (anything but `GET`, `HEAD` and
+//! `OPTIONS`, plus WebSocket upgrades) gets 503 so clients retry on another
+//! instance. Reads such as `/health`, job status and session downloads keep
+//! working until the listener closes.
+
+use crate::services::Drain;
+use axum::extract::Request;
+use axum::http::Method;
+use axum::http::header::UPGRADE;
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+use axum::response::Response;
+
+/// Middleware function answering 503 to new work during a drain
+pub async fn drain_middleware(drain: Drain, request: Request, next: Next) -> Response {
+    let read_only = matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    ) && !request.headers().contains_key(UPGRADE);
+    if !read_only && let Err(err) = drain.check() {
+        return err.into_response();
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::body::Body;
+    use axum::http::StatusCode;
+    use axum::middleware;
+    use axum::routing::get;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_refuses_new_work_while_draining() {
+        let drain = Drain::new();
+        let layer_drain = drain.clone();
+        let app = Router::new()
+            .route("/work", get(|| async { "ok" }).post(|| async { "ok" }))
+            .layer(middleware::from_fn(move |req, next| {
+                drain_middleware(layer_drain.clone(), req, next)
+            }));
+        let send = |request: axum::http::request::Builder| {
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        let response = send(Request::post("/work")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        drain.run(Duration::ZERO).await;
+        let response = send(Request::post("/work")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let response = send(Request::get("/work").header(UPGRADE, "websocket"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let response = send(Request::get("/work")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
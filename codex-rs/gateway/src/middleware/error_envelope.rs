@@ -0,0 +1,131 @@
+//! Uniform JSON bodies for error responses
+//!
+//! Errors raised as [`GatewayError`](crate::error::GatewayError) already carry
+//! an [`ErrorResponse`]. Those produced elsewhere (the authentication
+//! middleware, axum's extractor rejections, unknown routes, request
+//! timeouts) come back as plain text or with an empty body; this middleware
+//! wraps them in the same envelope so clients only ever parse one shape.
+//! Error responses that are already JSON or HTML are left untouched.
+
+use crate::error::ErrorResponse;
+use axum::body::Body;
+use axum::body::to_bytes;
+use axum::extract::Request;
+use axum::http::HeaderValue;
+use axum::http::header::CONTENT_LENGTH;
+use axum::http::header::CONTENT_TYPE;
+use axum::middleware::Next;
+use axum::response::Response;
+
+/// Largest plain-text error body carried over into the envelope
+const MAX_MESSAGE_BYTES: usize = 64 * 1024;
+
+/// Middleware function rewriting plain-text error responses as [`ErrorResponse`]
+pub async fn error_envelope_middleware(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    let status = response.status();
+    if !(status.is_client_error() || status.is_server_error()) {
+        return response;
+    }
+    let is_plain = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_none_or(|content_type| content_type.starts_with("text/plain"));
+    if !is_plain {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let text = to_bytes(body, MAX_MESSAGE_BYTES)
+        .await
+        .map(|bytes| String::from_utf8_lossy(&bytes).trim().to_string())
+        .unwrap_or_default();
+    let message = if text.is_empty() {
+        status.canonical_reason().unwrap_or("Error").to_string()
+    } else {
+        text
+    };
+
+    let body = match serde_json::to_vec(&ErrorResponse::new(status, message)) {
+        Ok(body) => body,
+        Err(_) => return Response::from_parts(parts, Body::empty()),
+    };
+    parts
+        .headers
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    parts.headers.remove(CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use axum::Router;
+    use axum::http::StatusCode;
+    use axum::http::header::RETRY_AFTER;
+    use axum::middleware;
+    use axum::response::IntoResponse;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_plain_errors_are_wrapped() -> Result<(), Box<dyn std::error::Error>> {
+        let app = Router::new()
+            .route(
+                "/limited",
+                get(|| async {
+                    (
+                        StatusCode::TOO_MANY_REQUESTS,
+                        [(RETRY_AFTER, "30")],
+                        "Rate limit exceeded",
+                    )
+                        .into_response()
+                }),
+            )
+            .route(
+                "/json",
+                get(|| async {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        axum::Json(serde_json::json!({"error": {"message": "openai"}})),
+                    )
+                }),
+            )
+            .layer(middleware::from_fn(error_envelope_middleware));
+
+        let response = app
+            .clone()
+            .oneshot(Request::get("/limited").body(Body::empty())?)
+            .await?;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            response.headers().get(RETRY_AFTER),
+            Some(&HeaderValue::from_static("30"))
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        let body: ErrorResponse = serde_json::from_slice(&body)?;
+        assert_eq!(body.code, ErrorCode::RateLimited);
+        assert_eq!(body.error, "Rate limit exceeded");
+
+        // Unknown routes get an empty 404 from axum
+        let response = app
+            .clone()
+            .oneshot(Request::get("/missing").body(Body::empty())?)
+            .await?;
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        let body: ErrorResponse = serde_json::from_slice(&body)?;
+        assert_eq!(body.code, ErrorCode::NotFound);
+        assert_eq!(body.error, "Not Found");
+
+        // JSON error bodies are someone else's envelope
+        let response = app
+            .oneshot(Request::get("/json").body(Body::empty())?)
+            .await?;
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+        let body: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(body["error"]["message"], "openai");
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+//! HTTP request metrics middleware
+//!
+//! Records a count and latency sample for every request, labelled with the
+//! matched route template (e.g. `/jobs/{id}`) rather than the raw path so the
+//! number of series stays bounded. Requests that match no route are grouped
+//! under `unmatched`.
+
+use crate::services::Metrics;
+use axum::extract::MatchedPath;
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use std::time::Instant;
+
+/// Middleware function recording request counts and latencies into `metrics`
+pub async fn metrics_middleware(metrics: Metrics, request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    metrics.record_request(
+        &method,
+        &route,
+        response.status().as_u16(),
+        started.elapsed(),
+    );
+    response
+}
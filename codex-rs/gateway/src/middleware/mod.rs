@@ -1,5 +1,11 @@
 //! Middleware modules for the Codex Gateway
 
 pub mod api_key;
+pub mod body_limit;
+pub mod drain;
+pub mod error_envelope;
+pub mod metrics;
+pub mod oauth;
+pub mod rate_limit;
 
 pub use api_key::ApiKeyAuth;
@@ -0,0 +1,793 @@
+//! OAuth 2.0 authorization server state
+//!
+//! Backs the `/oauth/*` endpoints used by ChatGPT GPT Actions:
+//!
+//! - Registered clients with exact-match redirect URIs. Confidential clients
+//!   authenticate with a secret; public clients must use PKCE.
+//! - Single-use authorization codes (10 minutes) with PKCE `S256` validation.
+//! - Expiring access tokens (1 hour) and rotating refresh tokens (30 days).
+//!
+//! Every grant is bound to the API key the user authorized with, so a bearer
+//! token carries that key's identity, rate limit and (a subset of) its scopes,
+//! and revoking the key revokes its tokens. Only SHA-256 hashes of secrets,
+//! codes and tokens are kept; clients and tokens are persisted to disk.
+
+use crate::middleware::api_key::constant_time_eq;
+use crate::middleware::api_key::write_private_file;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::Utc;
+use rand::RngCore;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::debug;
+use tracing::info;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Lifetime of access tokens in seconds
+pub const ACCESS_TOKEN_TTL_SECS: i64 = 60 * 60;
+
+/// Lifetime of refresh tokens in seconds
+pub const REFRESH_TOKEN_TTL_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Lifetime of authorization codes in seconds
+pub const AUTHORIZATION_CODE_TTL_SECS: i64 = 10 * 60;
+
+const ACCESS_TOKEN_PREFIX: &str = "cgw_at_";
+const REFRESH_TOKEN_PREFIX: &str = "cgw_rt_";
+const CLIENT_SECRET_PREFIX: &str = "cgw_cs_";
+
+/// A registered OAuth client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthClient {
+    pub client_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Redirect URIs accepted for this client (exact match)
+    pub redirect_uris: Vec<String>,
+    /// Hash of the client secret; `None` for public (PKCE-only) clients
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secret_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl OAuthClient {
+    /// Public clients have no secret and must use PKCE
+    pub fn is_public(&self) -> bool {
+        self.secret_hash.is_none()
+    }
+
+    pub fn allows_redirect_uri(&self, redirect_uri: &str) -> bool {
+        self.redirect_uris.iter().any(|uri| uri == redirect_uri)
+    }
+
+    fn verify_secret(&self, secret: Option<&str>) -> bool {
+        match (&self.secret_hash, secret) {
+            (None, _) => true,
+            (Some(expected), Some(secret)) => {
+                constant_time_eq(hash_secret(secret).as_bytes(), expected.as_bytes())
+            }
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// What a code or token grants: a client acting for an API key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthGrant {
+    pub client_id: String,
+    /// API key the user authorized with
+    pub key_id: String,
+    pub scopes: Vec<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl OAuthGrant {
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// Tokens returned by the token endpoint
+#[derive(Debug, Clone)]
+pub struct TokenSet {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: i64,
+    pub scopes: Vec<String>,
+}
+
+/// Token endpoint failures, mapped to RFC 6749 error codes by the handler
+#[derive(Debug, thiserror::Error)]
+pub enum OAuthError {
+    #[error("invalid_request: {0}")]
+    InvalidRequest(String),
+    #[error("invalid_client")]
+    InvalidClient,
+    #[error("invalid_grant: {0}")]
+    InvalidGrant(String),
+    #[error("invalid_scope")]
+    InvalidScope,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug, Clone)]
+struct AuthorizationCode {
+    grant: OAuthGrant,
+    redirect_uri: String,
+    code_challenge: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct ClientEntry {
+    client: OAuthClient,
+    /// Clients seeded from the environment are never written to disk
+    persist: bool,
+}
+
+/// File format of the OAuth store; token maps are keyed by token hash
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct OAuthFile {
+    #[serde(default)]
+    clients: Vec<OAuthClient>,
+    #[serde(default)]
+    access_tokens: HashMap<String, OAuthGrant>,
+    #[serde(default)]
+    refresh_tokens: HashMap<String, OAuthGrant>,
+}
+
+#[derive(Debug, Default)]
+struct OAuthState {
+    clients: HashMap<String, ClientEntry>,
+    codes: HashMap<String, AuthorizationCode>,
+    access_tokens: HashMap<String, OAuthGrant>,
+    refresh_tokens: HashMap<String, OAuthGrant>,
+}
+
+/// OAuth clients, codes and tokens
+///
+/// When opened with a path, clients and tokens are written back to that
+/// file. Authorization codes are short-lived and only kept in memory.
+#[derive(Debug, Clone, Default)]
+pub struct OAuthStore {
+    state: Arc<RwLock<OAuthState>>,
+    path: Option<PathBuf>,
+}
+
+impl OAuthStore {
+    /// Create a new in-memory store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open a file-backed store, loading existing clients and tokens
+    pub async fn open(path: PathBuf) -> io::Result<Self> {
+        let mut state = OAuthState::default();
+        match tokio::fs::read(&path).await {
+            Ok(contents) => {
+                let file: OAuthFile = serde_json::from_slice(&contents)?;
+                for client in file.clients {
+                    state.clients.insert(
+                        client.client_id.clone(),
+                        ClientEntry {
+                            client,
+                            persist: true,
+                        },
+                    );
+                }
+                state.access_tokens = file.access_tokens;
+                state.refresh_tokens = file.refresh_tokens;
+                info!(
+                    "Loaded {} OAuth clients from {}",
+                    state.clients.len(),
+                    path.display()
+                );
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                debug!("OAuth file {} not found, starting empty", path.display());
+            }
+            Err(err) => return Err(err),
+        }
+
+        Ok(Self {
+            state: Arc::new(RwLock::new(state)),
+            path: Some(path),
+        })
+    }
+
+    /// Open a file-backed store and add the client configured in the environment
+    ///
+    /// `OAUTH_CLIENT_ID`, `OAUTH_CLIENT_SECRET` and `OAUTH_REDIRECT_URIS`
+    /// (comma-separated) register a confidential client, typically the GPT
+    /// Action.
+    pub async fn open_with_default_client(path: PathBuf) -> io::Result<Self> {
+        let store = Self::open(path).await?;
+
+        let client_id = std::env::var("OAUTH_CLIENT_ID").ok();
+        let client_secret = std::env::var("OAUTH_CLIENT_SECRET").ok();
+        if let (Some(client_id), Some(client_secret)) = (client_id, client_secret) {
+            let redirect_uris: Vec<String> = std::env::var("OAUTH_REDIRECT_URIS")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|uri| !uri.is_empty())
+                .map(str::to_string)
+                .collect();
+            if redirect_uris.is_empty() {
+                warn!("OAUTH_REDIRECT_URIS is empty; client {client_id} cannot authorize");
+            }
+            store
+                .add_client(
+                    OAuthClient {
+                        client_id,
+                        name: Some("environment".to_string()),
+                        redirect_uris,
+                        secret_hash: Some(hash_secret(&client_secret)),
+                        created_at: None,
+                    },
+                    false,
+                )
+                .await;
+        }
+
+        Ok(store)
+    }
+
+    async fn add_client(&self, client: OAuthClient, persist: bool) {
+        let mut state = self.state.write().await;
+        state
+            .clients
+            .insert(client.client_id.clone(), ClientEntry { client, persist });
+    }
+
+    /// Register a client; the plaintext secret is returned exactly once
+    pub async fn register_client(
+        &self,
+        name: Option<String>,
+        redirect_uris: Vec<String>,
+        confidential: bool,
+    ) -> io::Result<(OAuthClient, Option<String>)> {
+        let secret = confidential.then(|| format!("{CLIENT_SECRET_PREFIX}{}", random_token()));
+        let client = OAuthClient {
+            client_id: format!("client_{}", Uuid::new_v4().simple()),
+            name,
+            redirect_uris,
+            secret_hash: secret.as_deref().map(hash_secret),
+            created_at: Some(Utc::now()),
+        };
+
+        let mut state = self.state.write().await;
+        state.clients.insert(
+            client.client_id.clone(),
+            ClientEntry {
+                client: client.clone(),
+                persist: true,
+            },
+        );
+        self.save(&mut state).await?;
+
+        Ok((client, secret))
+    }
+
+    /// List every registered client
+    pub async fn list_clients(&self) -> Vec<OAuthClient> {
+        let state = self.state.read().await;
+        let mut clients: Vec<OAuthClient> = state
+            .clients
+            .values()
+            .map(|entry| entry.client.clone())
+            .collect();
+        clients.sort_by(|a, b| a.client_id.cmp(&b.client_id));
+        clients
+    }
+
+    /// Remove a client and every token issued to it
+    pub async fn remove_client(&self, client_id: &str) -> io::Result<bool> {
+        let mut state = self.state.write().await;
+        if state.clients.remove(client_id).is_none() {
+            return Ok(false);
+        }
+        state
+            .codes
+            .retain(|_, code| code.grant.client_id != client_id);
+        state
+            .access_tokens
+            .retain(|_, grant| grant.client_id != client_id);
+        state
+            .refresh_tokens
+            .retain(|_, grant| grant.client_id != client_id);
+        self.save(&mut state).await?;
+        Ok(true)
+    }
+
+    pub async fn get_client(&self, client_id: &str) -> Option<OAuthClient> {
+        let state = self.state.read().await;
+        state
+            .clients
+            .get(client_id)
+            .map(|entry| entry.client.clone())
+    }
+
+    /// Issue a single-use authorization code for `key_id`
+    pub async fn create_authorization_code(
+        &self,
+        client_id: &str,
+        redirect_uri: &str,
+        key_id: &str,
+        scopes: Vec<String>,
+        code_challenge: Option<String>,
+    ) -> String {
+        let code = random_token();
+        let mut state = self.state.write().await;
+        state.codes.insert(
+            hash_secret(&code),
+            AuthorizationCode {
+                grant: OAuthGrant {
+                    client_id: client_id.to_string(),
+                    key_id: key_id.to_string(),
+                    scopes,
+                    expires_at: Utc::now() + Duration::seconds(AUTHORIZATION_CODE_TTL_SECS),
+                },
+                redirect_uri: redirect_uri.to_string(),
+                code_challenge,
+            },
+        );
+        code
+    }
+
+    /// `authorization_code` grant
+    pub async fn exchange_code(
+        &self,
+        client_id: &str,
+        client_secret: Option<&str>,
+        code: &str,
+        redirect_uri: &str,
+        code_verifier: Option<&str>,
+    ) -> Result<TokenSet, OAuthError> {
+        let mut state = self.state.write().await;
+        let client = authenticate_client(&state, client_id, client_secret)?;
+
+        // Codes are single-use: remove before any further validation
+        let Some(code) = state.codes.remove(&hash_secret(code)) else {
+            return Err(OAuthError::InvalidGrant(
+                "unknown or already used authorization code".to_string(),
+            ));
+        };
+        if code.grant.is_expired(Utc::now()) {
+            return Err(OAuthError::InvalidGrant(
+                "authorization code expired".to_string(),
+            ));
+        }
+        if code.grant.client_id != client.client_id || code.redirect_uri != redirect_uri {
+            return Err(OAuthError::InvalidGrant(
+                "authorization code was issued to another client or redirect_uri".to_string(),
+            ));
+        }
+        match (&code.code_challenge, code_verifier) {
+            (Some(challenge), Some(verifier)) => {
+                if !verify_pkce(verifier, challenge) {
+                    return Err(OAuthError::InvalidGrant(
+                        "code_verifier does not match code_challenge".to_string(),
+                    ));
+                }
+            }
+            (Some(_), None) => {
+                return Err(OAuthError::InvalidRequest(
+                    "code_verifier is required".to_string(),
+                ));
+            }
+            (None, _) if client.is_public() => {
+                return Err(OAuthError::InvalidGrant(
+                    "public clients must use PKCE".to_string(),
+                ));
+            }
+            (None, _) => {}
+        }
+
+        let tokens = issue_tokens(
+            &mut state,
+            &client.client_id,
+            &code.grant.key_id,
+            code.grant.scopes,
+        );
+        self.save(&mut state).await?;
+        Ok(tokens)
+    }
+
+    /// `refresh_token` grant; the refresh token is rotated
+    pub async fn refresh(
+        &self,
+        client_id: &str,
+        client_secret: Option<&str>,
+        refresh_token: &str,
+        scope: Option<&str>,
+    ) -> Result<TokenSet, OAuthError> {
+        let mut state = self.state.write().await;
+        let client = authenticate_client(&state, client_id, client_secret)?;
+
+        let token_hash = hash_secret(refresh_token);
+        let grant = match state.refresh_tokens.get(&token_hash) {
+            Some(grant) if grant.client_id != client.client_id => {
+                return Err(OAuthError::InvalidGrant(
+                    "refresh token was issued to another client".to_string(),
+                ));
+            }
+            Some(grant) if grant.is_expired(Utc::now()) => {
+                return Err(OAuthError::InvalidGrant(
+                    "refresh token expired".to_string(),
+                ));
+            }
+            Some(grant) => grant.clone(),
+            None => {
+                return Err(OAuthError::InvalidGrant(
+                    "unknown refresh token".to_string(),
+                ));
+            }
+        };
+
+        // A refresh may narrow the scopes, never widen them
+        let scopes = match scope {
+            Some(scope) => {
+                let requested = parse_scopes(scope);
+                if requested.iter().any(|s| !grant.scopes.contains(s)) {
+                    return Err(OAuthError::InvalidScope);
+                }
+                requested
+            }
+            None => grant.scopes.clone(),
+        };
+
+        state.refresh_tokens.remove(&token_hash);
+        let tokens = issue_tokens(&mut state, &client.client_id, &grant.key_id, scopes);
+        self.save(&mut state).await?;
+        Ok(tokens)
+    }
+
+    /// Resolve a bearer token to its grant, if valid and unexpired
+    pub async fn validate_access_token(&self, token: &str) -> Option<OAuthGrant> {
+        let state = self.state.read().await;
+        state
+            .access_tokens
+            .get(&hash_secret(token))
+            .filter(|grant| !grant.is_expired(Utc::now()))
+            .filter(|grant| state.clients.contains_key(&grant.client_id))
+            .cloned()
+    }
+
+    /// Drop expired entries and write clients and tokens back to disk
+    async fn save(&self, state: &mut OAuthState) -> io::Result<()> {
+        let now = Utc::now();
+        state.codes.retain(|_, code| !code.grant.is_expired(now));
+        state
+            .access_tokens
+            .retain(|_, grant| !grant.is_expired(now));
+        state
+            .refresh_tokens
+            .retain(|_, grant| !grant.is_expired(now));
+
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let mut clients: Vec<OAuthClient> = state
+            .clients
+            .values()
+            .filter(|entry| entry.persist)
+            .map(|entry| entry.client.clone())
+            .collect();
+        clients.sort_by(|a, b| a.client_id.cmp(&b.client_id));
+        let file = OAuthFile {
+            clients,
+            access_tokens: state.access_tokens.clone(),
+            refresh_tokens: state.refresh_tokens.clone(),
+        };
+
+        write_private_file(path, &serde_json::to_vec_pretty(&file)?).await
+    }
+}
+
+fn authenticate_client(
+    state: &OAuthState,
+    client_id: &str,
+    client_secret: Option<&str>,
+) -> Result<OAuthClient, OAuthError> {
+    match state.clients.get(client_id) {
+        Some(entry) if entry.client.verify_secret(client_secret) => Ok(entry.client.clone()),
+        _ => {
+            warn!(
+                "OAuth client authentication failed: client_id={}",
+                client_id
+            );
+            Err(OAuthError::InvalidClient)
+        }
+    }
+}
+
+fn issue_tokens(
+    state: &mut OAuthState,
+    client_id: &str,
+    key_id: &str,
+    scopes: Vec<String>,
+) -> TokenSet {
+    let now = Utc::now();
+    let access_token = format!("{ACCESS_TOKEN_PREFIX}{}", random_token());
+    let refresh_token = format!("{REFRESH_TOKEN_PREFIX}{}", random_token());
+
+    let grant = |ttl: i64| OAuthGrant {
+        client_id: client_id.to_string(),
+        key_id: key_id.to_string(),
+        scopes: scopes.clone(),
+        expires_at: now + Duration::seconds(ttl),
+    };
+    state
+        .access_tokens
+        .insert(hash_secret(&access_token), grant(ACCESS_TOKEN_TTL_SECS));
+    state
+        .refresh_tokens
+        .insert(hash_secret(&refresh_token), grant(REFRESH_TOKEN_TTL_SECS));
+
+    info!(
+        "OAuth tokens issued: client_id={}, key_id={}",
+        client_id, key_id
+    );
+
+    TokenSet {
+        access_token,
+        refresh_token,
+        expires_in: ACCESS_TOKEN_TTL_SECS,
+        scopes,
+    }
+}
+
+/// Split a space-separated `scope` parameter
+pub fn parse_scopes(scope: &str) -> Vec<String> {
+    scope.split_whitespace().map(str::to_string).collect()
+}
+
+/// PKCE `S256`: BASE64URL(SHA256(code_verifier)) == code_challenge
+pub fn verify_pkce(code_verifier: &str, code_challenge: &str) -> bool {
+    // RFC 7636 section 4.1: 43 to 128 unreserved characters
+    let valid_verifier = (43..=128).contains(&code_verifier.len())
+        && code_verifier
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~'));
+    if !valid_verifier {
+        return false;
+    }
+    let computed = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+    constant_time_eq(computed.as_bytes(), code_challenge.as_bytes())
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn hash_secret(secret: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(secret.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const REDIRECT_URI: &str = "https://chat.openai.com/aip/g-123/oauth/callback";
+
+    // RFC 7636 appendix B
+    const VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+    const CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+    #[test]
+    fn test_verify_pkce() {
+        assert!(verify_pkce(VERIFIER, CHALLENGE));
+        assert!(!verify_pkce(
+            VERIFIER,
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cX"
+        ));
+        assert!(!verify_pkce("short", CHALLENGE));
+    }
+
+    #[tokio::test]
+    async fn test_code_exchange_and_refresh() -> Result<(), Box<dyn std::error::Error>> {
+        let store = OAuthStore::new();
+        let (client, secret) = store
+            .register_client(None, vec![REDIRECT_URI.to_string()], true)
+            .await?;
+        let secret = secret.ok_or("confidential client without secret")?;
+
+        let code = store
+            .create_authorization_code(
+                &client.client_id,
+                REDIRECT_URI,
+                "key_001",
+                vec!["agent".to_string()],
+                Some(CHALLENGE.to_string()),
+            )
+            .await;
+
+        // Wrong secret is rejected without consuming the code
+        assert!(matches!(
+            store
+                .exchange_code(
+                    &client.client_id,
+                    Some("nope"),
+                    &code,
+                    REDIRECT_URI,
+                    Some(VERIFIER)
+                )
+                .await,
+            Err(OAuthError::InvalidClient)
+        ));
+
+        let tokens = store
+            .exchange_code(
+                &client.client_id,
+                Some(&secret),
+                &code,
+                REDIRECT_URI,
+                Some(VERIFIER),
+            )
+            .await?;
+        let grant = store
+            .validate_access_token(&tokens.access_token)
+            .await
+            .ok_or("access token not accepted")?;
+        assert_eq!(grant.key_id, "key_001");
+        assert_eq!(grant.scopes, vec!["agent".to_string()]);
+
+        // Codes are single-use
+        assert!(matches!(
+            store
+                .exchange_code(
+                    &client.client_id,
+                    Some(&secret),
+                    &code,
+                    REDIRECT_URI,
+                    Some(VERIFIER)
+                )
+                .await,
+            Err(OAuthError::InvalidGrant(_))
+        ));
+
+        // Refresh tokens rotate and cannot widen scopes
+        assert!(matches!(
+            store
+                .refresh(
+                    &client.client_id,
+                    Some(&secret),
+                    &tokens.refresh_token,
+                    Some("agent admin")
+                )
+                .await,
+            Err(OAuthError::InvalidScope)
+        ));
+        let refreshed = store
+            .refresh(
+                &client.client_id,
+                Some(&secret),
+                &tokens.refresh_token,
+                None,
+            )
+            .await?;
+        assert!(
+            store
+                .validate_access_token(&refreshed.access_token)
+                .await
+                .is_some()
+        );
+        assert!(
+            store
+                .refresh(
+                    &client.client_id,
+                    Some(&secret),
+                    &tokens.refresh_token,
+                    None
+                )
+                .await
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pkce_required_for_public_clients() -> Result<(), Box<dyn std::error::Error>> {
+        let store = OAuthStore::new();
+        let (client, secret) = store
+            .register_client(None, vec![REDIRECT_URI.to_string()], false)
+            .await?;
+        assert!(secret.is_none());
+        assert!(client.is_public());
+
+        let code = store
+            .create_authorization_code(&client.client_id, REDIRECT_URI, "key_001", Vec::new(), None)
+            .await;
+        assert!(matches!(
+            store
+                .exchange_code(&client.client_id, None, &code, REDIRECT_URI, None)
+                .await,
+            Err(OAuthError::InvalidGrant(_))
+        ));
+
+        let code = store
+            .create_authorization_code(
+                &client.client_id,
+                REDIRECT_URI,
+                "key_001",
+                Vec::new(),
+                Some(CHALLENGE.to_string()),
+            )
+            .await;
+        assert!(matches!(
+            store
+                .exchange_code(
+                    &client.client_id,
+                    None,
+                    &code,
+                    REDIRECT_URI,
+                    Some("x".repeat(43).as_str())
+                )
+                .await,
+            Err(OAuthError::InvalidGrant(_))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tokens_persist_and_die_with_client() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("oauth.json");
+
+        let store = OAuthStore::open(path.clone()).await?;
+        let (client, secret) = store
+            .register_client(
+                Some("gpt".to_string()),
+                vec![REDIRECT_URI.to_string()],
+                true,
+            )
+            .await?;
+        let code = store
+            .create_authorization_code(&client.client_id, REDIRECT_URI, "key_001", Vec::new(), None)
+            .await;
+        let tokens = store
+            .exchange_code(
+                &client.client_id,
+                secret.as_deref(),
+                &code,
+                REDIRECT_URI,
+                None,
+            )
+            .await?;
+
+        let reopened = OAuthStore::open(path).await?;
+        assert!(reopened.get_client(&client.client_id).await.is_some());
+        assert!(
+            reopened
+                .validate_access_token(&tokens.access_token)
+                .await
+                .is_some()
+        );
+
+        assert!(reopened.remove_client(&client.client_id).await?);
+        assert!(
+            reopened
+                .validate_access_token(&tokens.access_token)
+                .await
+                .is_none()
+        );
+        Ok(())
+    }
+}
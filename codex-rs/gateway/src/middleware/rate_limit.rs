@@ -0,0 +1,241 @@
+//! Per-key rate limiting and session concurrency caps
+//!
+//! [`RateLimiter`] is a token bucket keyed by `key_id`: each key holds up to
+//! `rate_limit` tokens and regains `rate_limit` tokens per minute.
+//! [`SessionLimiter`] bounds how many agent sessions (`/exec`, `/ws`) a single
+//! key can run at the same time.
+
+use axum::http::HeaderMap;
+use axum::http::HeaderValue;
+use axum::http::header::RETRY_AFTER;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::time::Duration;
+use std::time::Instant;
+use tokio::sync::OwnedSemaphorePermit;
+use tokio::sync::Semaphore;
+
+pub const RATE_LIMIT_LIMIT_HEADER: &str = "x-ratelimit-limit";
+pub const RATE_LIMIT_REMAINING_HEADER: &str = "x-ratelimit-remaining";
+pub const RATE_LIMIT_RESET_HEADER: &str = "x-ratelimit-reset";
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Outcome of a rate limit check
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitDecision {
+    /// Whether the request may proceed
+    pub allowed: bool,
+    /// Requests per minute allowed for the key
+    pub limit: u32,
+    /// Whole requests left in the bucket after this one
+    pub remaining: u32,
+    /// Time until the bucket is full again
+    pub reset_after: Duration,
+    /// Time until the next request would be allowed (zero when allowed)
+    pub retry_after: Duration,
+}
+
+impl RateLimitDecision {
+    /// Set `X-RateLimit-*` (and `Retry-After` when denied) on a response
+    pub fn apply_headers(&self, headers: &mut HeaderMap) {
+        headers.insert(RATE_LIMIT_LIMIT_HEADER, HeaderValue::from(self.limit));
+        headers.insert(
+            RATE_LIMIT_REMAINING_HEADER,
+            HeaderValue::from(self.remaining),
+        );
+        headers.insert(
+            RATE_LIMIT_RESET_HEADER,
+            HeaderValue::from(ceil_secs(self.reset_after)),
+        );
+        if !self.allowed {
+            headers.insert(
+                RETRY_AFTER,
+                HeaderValue::from(ceil_secs(self.retry_after).max(1)),
+            );
+        }
+    }
+}
+
+/// Token bucket rate limiter keyed by API key id
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    buckets: Arc<Mutex<HashMap<String, Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Consume one token for `key_id`. A `limit_per_minute` of 0 disables limiting.
+    pub fn check(&self, key_id: &str, limit_per_minute: u32) -> Option<RateLimitDecision> {
+        self.check_at(key_id, limit_per_minute, Instant::now())
+    }
+
+    fn check_at(
+        &self,
+        key_id: &str,
+        limit_per_minute: u32,
+        now: Instant,
+    ) -> Option<RateLimitDecision> {
+        if limit_per_minute == 0 {
+            return None;
+        }
+
+        let capacity = f64::from(limit_per_minute);
+        let refill_per_sec = capacity / 60.0;
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        let bucket = buckets.entry(key_id.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            last_refill: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.last_refill);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * refill_per_sec).min(capacity);
+        bucket.last_refill = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+
+        let retry_after = if allowed {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - bucket.tokens) / refill_per_sec)
+        };
+
+        Some(RateLimitDecision {
+            allowed,
+            limit: limit_per_minute,
+            remaining: bucket.tokens.floor() as u32,
+            reset_after: Duration::from_secs_f64((capacity - bucket.tokens) / refill_per_sec),
+            retry_after,
+        })
+    }
+}
+
+/// Slot held for the lifetime of an agent session
+///
+/// Cloneable so it can be moved into the task that outlives the HTTP response
+/// (SSE streams, WebSocket connections). The slot is released when the last
+/// clone is dropped.
+#[derive(Debug, Clone)]
+pub struct SessionPermit {
+    _permit: Arc<OwnedSemaphorePermit>,
+}
+
+/// Caps concurrent agent sessions per API key
+#[derive(Debug, Clone, Default)]
+pub struct SessionLimiter {
+    semaphores: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+}
+
+impl SessionLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Try to take a session slot for `key_id`; `None` when the cap is reached
+    pub fn try_acquire(&self, key_id: &str, max_sessions: usize) -> Option<SessionPermit> {
+        let semaphore = {
+            let mut semaphores = self
+                .semaphores
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            Arc::clone(
+                semaphores
+                    .entry(key_id.to_string())
+                    .or_insert_with(|| Arc::new(Semaphore::new(max_sessions))),
+            )
+        };
+        semaphore
+            .try_acquire_owned()
+            .ok()
+            .map(|permit| SessionPermit {
+                _permit: Arc::new(permit),
+            })
+    }
+}
+
+fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_secs_f64().ceil() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket_exhausts_and_refills() {
+        let limiter = RateLimiter::new();
+        let start = Instant::now();
+
+        for expected_remaining in (0..3).rev() {
+            let decision = limiter.check_at("key", 3, start).unwrap();
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, expected_remaining);
+        }
+
+        let denied = limiter.check_at("key", 3, start).unwrap();
+        assert!(!denied.allowed);
+        assert_eq!(ceil_secs(denied.retry_after), 20);
+
+        // 3 per minute refills one token every 20 seconds
+        let later = start + Duration::from_secs(21);
+        assert!(limiter.check_at("key", 3, later).unwrap().allowed);
+
+        // Other keys have their own bucket
+        assert!(limiter.check_at("other", 3, start).unwrap().allowed);
+    }
+
+    #[test]
+    fn test_zero_limit_disables_rate_limiting() {
+        let limiter = RateLimiter::new();
+        assert!(limiter.check("key", 0).is_none());
+    }
+
+    #[test]
+    fn test_denied_decision_sets_retry_after() {
+        let decision = RateLimitDecision {
+            allowed: false,
+            limit: 60,
+            remaining: 0,
+            reset_after: Duration::from_secs(60),
+            retry_after: Duration::from_millis(500),
+        };
+        let mut headers = HeaderMap::new();
+        decision.apply_headers(&mut headers);
+
+        assert_eq!(headers[RATE_LIMIT_LIMIT_HEADER], "60");
+        assert_eq!(headers[RATE_LIMIT_REMAINING_HEADER], "0");
+        assert_eq!(headers[RATE_LIMIT_RESET_HEADER], "60");
+        assert_eq!(headers[RETRY_AFTER], "1");
+    }
+
+    #[test]
+    fn test_session_limiter_caps_concurrency() {
+        let limiter = SessionLimiter::new();
+
+        let first = limiter.try_acquire("key", 2).unwrap();
+        let second = limiter.try_acquire("key", 2).unwrap();
+        assert!(limiter.try_acquire("key", 2).is_none());
+
+        // Clones share the slot; it is freed once every clone is gone
+        let first_clone = first.clone();
+        drop(first);
+        assert!(limiter.try_acquire("key", 2).is_none());
+        drop(first_clone);
+        assert!(limiter.try_acquire("key", 2).is_some());
+
+        drop(second);
+    }
+}
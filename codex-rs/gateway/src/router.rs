@@ -1,22 +1,71 @@
 //! Router configuration for the Codex Gateway
 
+use crate::config::CorsConfig;
+use crate::error::GatewayError;
 use crate::error::GatewayResult;
-use crate::handlers::exec::{handle_exec, handle_exec_resume};
+use crate::handlers::admin::handle_create_key;
+use crate::handlers::admin::handle_create_oauth_client;
+use crate::handlers::admin::handle_list_keys;
+use crate::handlers::admin::handle_list_oauth_clients;
+use crate::handlers::admin::handle_query_audit;
+use crate::handlers::admin::handle_remove_oauth_client;
+use crate::handlers::admin::handle_revoke_key;
+use crate::handlers::app_server::handle_app_server_upgrade;
+use crate::handlers::exec::handle_exec_resume;
+use crate::handlers::exec_stream::handle_exec_negotiated;
+use crate::handlers::exec_stream::handle_exec_stream;
 use crate::handlers::health::health_check;
+use crate::handlers::health::health_live;
+use crate::handlers::health::health_ready;
+use crate::handlers::jobs::handle_cancel_job;
+use crate::handlers::jobs::handle_create_job;
+use crate::handlers::jobs::handle_get_job;
+use crate::handlers::jobs::handle_job_events;
 use crate::handlers::jsonrpc::handle_jsonrpc;
-use crate::handlers::oauth::{handle_oauth_authorize, handle_oauth_token};
+use crate::handlers::mcp::MCP_SESSION_ID_HEADER;
+use crate::handlers::mcp::handle_mcp_delete;
+use crate::handlers::mcp::handle_mcp_get;
+use crate::handlers::mcp::handle_mcp_post;
+use crate::handlers::metrics::handle_metrics;
+use crate::handlers::oauth::handle_oauth_authorize;
+use crate::handlers::oauth::handle_oauth_authorize_submit;
+use crate::handlers::oauth::handle_oauth_token;
+use crate::handlers::openai::handle_chat_completions;
+use crate::handlers::openai::handle_list_models;
+use crate::handlers::openapi::handle_openapi;
+use crate::handlers::sessions::handle_delete_session;
+use crate::handlers::sessions::handle_list_sessions;
+use crate::handlers::sessions::handle_session_archive;
+use crate::handlers::sessions::handle_session_diff;
+use crate::handlers::sessions::handle_session_files;
 use crate::handlers::webhook::handle_webhook;
 use crate::handlers::websocket::handle_websocket_upgrade;
-use crate::middleware::api_key::api_key_middleware;
 use crate::middleware::api_key::ApiKeyAuth;
+use crate::middleware::api_key::api_key_middleware;
+use crate::middleware::body_limit::body_limit_middleware;
+use crate::middleware::drain::drain_middleware;
+use crate::middleware::error_envelope::error_envelope_middleware;
+use crate::middleware::metrics::metrics_middleware;
+use crate::middleware::rate_limit::RATE_LIMIT_LIMIT_HEADER;
+use crate::middleware::rate_limit::RATE_LIMIT_REMAINING_HEADER;
+use crate::middleware::rate_limit::RATE_LIMIT_RESET_HEADER;
 use crate::state::AppState;
-use axum::middleware;
 use axum::Router;
+use axum::extract::DefaultBodyLimit;
+use axum::http::HeaderName;
+use axum::http::HeaderValue;
+use axum::http::Method;
+use axum::http::header::ACCEPT;
+use axum::http::header::AUTHORIZATION;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::header::RETRY_AFTER;
+use axum::middleware;
+use axum::routing::delete;
 use axum::routing::get;
 use axum::routing::post;
 use std::sync::Arc;
+use tower_http::cors::AllowOrigin;
 use tower_http::cors::CorsLayer;
-use tower_http::limit::RequestBodyLimitLayer;
 use tower_http::timeout::TimeoutLayer;
 use tower_http::trace::TraceLayer;
 use tracing::info;
@@ -27,24 +76,25 @@ pub async fn create_router(state: AppState) -> GatewayResult<Router> {
 
     // Store config values before moving state
     let request_timeout = state.config().timeouts.request_timeout;
-    let default_limit = state.config().body_limits.default_limit;
-    let health_limit = state.config().body_limits.health_limit;
-    let jsonrpc_limit = state.config().body_limits.jsonrpc_limit;
-    let webhook_limit = state.config().body_limits.webhook_limit;
+    let body_limits = Arc::new(state.config().body_limits.clone());
 
     // Initialize API Key authentication
-    let api_key_auth = Arc::new(ApiKeyAuth::default_config().await);
+    let api_key_auth = Arc::new(
+        ApiKeyAuth::new(state.api_keys.clone())
+            .with_oauth(state.oauth.clone())
+            .with_max_sessions_per_key(state.config().max_sessions_per_key)
+            .with_metrics(state.metrics.clone()),
+    );
+    let metrics = state.metrics.clone();
+    let drain = state.codex_service.drain().clone();
     info!("API Key authentication initialized");
 
-    // Configure CORS - allow all origins for now, can be restricted later
-    let cors = CorsLayer::permissive();
+    // Configure CORS from the origin allow-list
+    let cors = cors_layer(&state.config().cors)?;
 
     // Configure timeout from state config
     let timeout = TimeoutLayer::new(request_timeout);
 
-    // Configure request body size limits based on endpoint
-    let global_body_limit = RequestBodyLimitLayer::new(default_limit);
-
     // Configure tracing middleware
     let trace = TraceLayer::new_for_http();
 
@@ -52,43 +102,144 @@ pub async fn create_router(state: AppState) -> GatewayResult<Router> {
     let app = Router::new()
         // Health check endpoint (no auth required)
         .route("/health", get(health_check))
+        // Liveness and dependency readiness probes (no auth required)
+        .route("/health/live", get(health_live))
+        .route("/health/ready", get(health_ready))
+        // Prometheus metrics (no auth required)
+        .route("/metrics", get(handle_metrics))
+        // OpenAPI document of the REST surface (no auth required)
+        .route("/openapi.json", get(handle_openapi))
         // OAuth endpoints (no auth required for OAuth flow)
-        .route("/oauth/authorize", get(handle_oauth_authorize))
+        .route(
+            "/oauth/authorize",
+            get(handle_oauth_authorize).post(handle_oauth_authorize_submit),
+        )
         .route("/oauth/token", post(handle_oauth_token))
         // JSON-RPC endpoint for protocol communication
         .route("/jsonrpc", post(handle_jsonrpc))
-        // Exec endpoint for real codex-exec mode (JSONL events)
-        .route("/exec", post(handle_exec))
+        // Exec endpoint for real codex-exec mode (JSONL events, or SSE via Accept)
+        .route("/exec", post(handle_exec_negotiated))
+        // Exec endpoint streaming ThreadEvents as Server-Sent Events
+        .route("/exec/stream", post(handle_exec_stream))
+        // Fire-and-forget exec jobs backed by the durable job queue
+        .route("/jobs", post(handle_create_job))
+        .route("/jobs/{id}", get(handle_get_job).delete(handle_cancel_job))
+        .route("/jobs/{id}/events", get(handle_job_events))
         // Exec resume endpoint for resuming conversations
         .route("/exec/resume", post(handle_exec_resume))
+        // Sessions of the calling key, rehydrated lazily after restarts
+        .route("/sessions", get(handle_list_sessions))
+        .route("/sessions/{id}", delete(handle_delete_session))
+        // Diffs and changed files of a session's turns
+        .route("/sessions/{id}/diff", get(handle_session_diff))
+        .route("/sessions/{id}/files", get(handle_session_files))
+        .route("/sessions/{id}/archive", get(handle_session_archive))
         // WebSocket endpoint for real-time communication
         .route("/ws", get(handle_websocket_upgrade))
+        // App-server JSON-RPC protocol (thread/turn/item) over WebSocket
+        .route("/app-server", get(handle_app_server_upgrade))
+        // MCP server (codex / codex-reply tools) over Streamable HTTP
+        .route(
+            "/mcp",
+            post(handle_mcp_post)
+                .get(handle_mcp_get)
+                .delete(handle_mcp_delete),
+        )
         // Webhook endpoint for external integrations
         .route("/webhook", post(handle_webhook))
+        // Status and result of webhook-triggered agent runs
+        .route("/webhook/jobs/{id}", get(handle_get_job))
+        // OpenAI-compatible Chat Completions facade
+        .route("/v1/models", get(handle_list_models))
+        .route("/v1/chat/completions", post(handle_chat_completions))
+        // Admin endpoints for API key management (admin scope required)
+        .route("/admin/keys", get(handle_list_keys).post(handle_create_key))
+        .route("/admin/keys/{key_id}", delete(handle_revoke_key))
+        .route(
+            "/admin/oauth/clients",
+            get(handle_list_oauth_clients).post(handle_create_oauth_client),
+        )
+        .route(
+            "/admin/oauth/clients/{client_id}",
+            delete(handle_remove_oauth_client),
+        )
+        // Audit log of agent actions (admin scope required)
+        .route("/admin/audit", get(handle_query_audit))
         // Apply global middleware stack in correct order
         .layer(middleware::from_fn(move |req, next| {
             let auth = Arc::clone(&api_key_auth);
             api_key_middleware(auth, req, next)
         })) // API Key authentication
-        .layer(global_body_limit) // Global body size limit fallback
+        .layer(middleware::from_fn(move |req, next| {
+            drain_middleware(drain.clone(), req, next)
+        })) // 503 for new work once shutdown has started
+        .layer(middleware::from_fn(move |req, next| {
+            metrics_middleware(metrics.clone(), req, next)
+        })) // Request counts and latencies, including auth rejections
+        // The extractors' built-in 2 MB cap would override larger route limits
+        .layer(DefaultBodyLimit::disable())
+        .layer(middleware::from_fn(move |req, next| {
+            body_limit_middleware(Arc::clone(&body_limits), req, next)
+        })) // Per-route body size limits
         .layer(trace) // Request tracing
         .layer(timeout) // Request timeout
+        .layer(middleware::from_fn(error_envelope_middleware)) // JSON bodies for all errors
         .layer(cors) // CORS handling
         // Add shared state
         .with_state(state);
 
-    info!(
-        "Router created successfully with body size limits: health={}KB, jsonrpc={}KB, websocket={}KB, webhook={}KB, default={}KB",
-        health_limit / 1024,
-        jsonrpc_limit / 1024,
-        default_limit / 1024,
-        webhook_limit / 1024,
-        default_limit / 1024
-    );
+    info!("Router created successfully");
     info!("API Key authentication middleware enabled");
     Ok(app)
 }
 
+/// CORS layer allowing the configured origins
+///
+/// Without origins no CORS headers are sent, so browsers block cross-origin
+/// calls; the gateway's own clients (CLIs, GPT Actions, webhooks) are not
+/// browsers and are unaffected.
+fn cors_layer(config: &CorsConfig) -> GatewayResult<CorsLayer> {
+    if config.allowed_origins.is_empty() {
+        return Ok(CorsLayer::new());
+    }
+
+    let allow_origin = if config.allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        let origins = config
+            .allowed_origins
+            .iter()
+            .map(|origin| {
+                HeaderValue::from_str(origin)
+                    .map_err(|e| GatewayError::Config(format!("invalid CORS origin {origin}: {e}")))
+            })
+            .collect::<GatewayResult<Vec<_>>>()?;
+        AllowOrigin::list(origins)
+    };
+
+    let mut cors = CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST, Method::DELETE, Method::OPTIONS])
+        .allow_headers([
+            AUTHORIZATION,
+            CONTENT_TYPE,
+            ACCEPT,
+            HeaderName::from_static("x-api-key"),
+            HeaderName::from_static(MCP_SESSION_ID_HEADER),
+        ])
+        .expose_headers([
+            RETRY_AFTER,
+            HeaderName::from_static(MCP_SESSION_ID_HEADER),
+            HeaderName::from_static(RATE_LIMIT_LIMIT_HEADER),
+            HeaderName::from_static(RATE_LIMIT_REMAINING_HEADER),
+            HeaderName::from_static(RATE_LIMIT_RESET_HEADER),
+        ]);
+    if let Some(max_age) = config.max_age {
+        cors = cors.max_age(max_age);
+    }
+    Ok(cors)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -102,4 +253,55 @@ mod tests {
         // Se chegou até aqui, o router foi criado com sucesso
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_cors_allow_list() -> Result<(), Box<dyn std::error::Error>> {
+        use axum::body::Body;
+        use axum::http::Request;
+        use axum::http::header::ACCESS_CONTROL_ALLOW_ORIGIN;
+        use axum::http::header::ORIGIN;
+        use tower::ServiceExt;
+
+        let config = CorsConfig {
+            allowed_origins: vec!["https://chat.openai.com".to_string()],
+            max_age: None,
+        };
+        let app = Router::new()
+            .route("/health", get(|| async { "ok" }))
+            .layer(cors_layer(&config)?);
+
+        for (origin, allowed) in [
+            ("https://chat.openai.com", true),
+            ("https://evil.example", false),
+        ] {
+            let request = Request::get("/health")
+                .header(ORIGIN, origin)
+                .body(Body::empty())?;
+            let response = app.clone().oneshot(request).await?;
+            assert_eq!(
+                response
+                    .headers()
+                    .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+                    .is_some(),
+                allowed,
+                "{origin}"
+            );
+        }
+
+        let no_origins = cors_layer(&CorsConfig::default())?;
+        let app = Router::new()
+            .route("/health", get(|| async { "ok" }))
+            .layer(no_origins);
+        let request = Request::get("/health")
+            .header(ORIGIN, "https://chat.openai.com")
+            .body(Body::empty())?;
+        let response = app.oneshot(request).await?;
+        assert!(
+            response
+                .headers()
+                .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+                .is_none()
+        );
+        Ok(())
+    }
 }
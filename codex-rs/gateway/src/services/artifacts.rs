@@ -0,0 +1,438 @@
+//! Per-session turn artifacts
+//!
+//! While a turn of a named session runs, a [`TurnRecorder`] watches its events:
+//! the last `TurnDiff` of the turn is the aggregated unified diff produced by
+//! core's `TurnDiffTracker`, and every successful `PatchApplyBegin`/`End` pair
+//! names the files the agent changed. When the turn ends the recorder appends
+//! a [`TurnArtifacts`] entry to the session's history in the [`ArtifactStore`].
+//!
+//! Histories are written to `$CODEX_HOME/gateway/artifacts/` (or
+//! `GATEWAY_ARTIFACTS_DIR`), one JSON file per session named after a hash of
+//! the `session_id`, so client-chosen ids never become paths.
+//!
+//! Only edits made through `apply_patch` are tracked; files changed by shell
+//! commands do not show up in the diff or the file list.
+
+use crate::middleware::api_key::write_private_file;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::DateTime;
+use chrono::Utc;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::FileChange;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::debug;
+use tracing::warn;
+
+/// What one turn changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TurnArtifacts {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Working directory of the turn
+    pub cwd: PathBuf,
+    /// Aggregated unified diff of the turn, if it changed anything
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unified_diff: Option<String>,
+    /// Absolute paths of files added or updated by the turn
+    #[serde(default)]
+    pub changed_files: BTreeSet<PathBuf>,
+    /// Absolute paths of files deleted (or moved away) by the turn
+    #[serde(default)]
+    pub deleted_files: BTreeSet<PathBuf>,
+}
+
+/// Turn history of one session, oldest turn first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionArtifacts {
+    pub session_id: String,
+    pub turns: Vec<TurnArtifacts>,
+}
+
+impl SessionArtifacts {
+    /// Workspace of the session: the `cwd` of its latest turn
+    pub fn cwd(&self) -> Option<&Path> {
+        self.turns.last().map(|turn| turn.cwd.as_path())
+    }
+
+    /// Unified diff of the latest turn, or of every turn in order
+    ///
+    /// The per-turn diffs are concatenated, so the session diff applies with
+    /// `git apply` in one go on top of the state before the first turn.
+    pub fn diff(&self, whole_session: bool) -> String {
+        let turns = if whole_session {
+            &self.turns[..]
+        } else {
+            &self.turns[self.turns.len().saturating_sub(1)..]
+        };
+        let mut diff = String::new();
+        for unified_diff in turns.iter().filter_map(|turn| turn.unified_diff.as_deref()) {
+            diff.push_str(unified_diff);
+            if !diff.ends_with('\n') {
+                diff.push('\n');
+            }
+        }
+        diff
+    }
+
+    /// Files changed by the latest turn or by the whole session that still
+    /// exist after it
+    pub fn changed_files(&self, whole_session: bool) -> BTreeSet<PathBuf> {
+        let turns = if whole_session {
+            &self.turns[..]
+        } else {
+            &self.turns[self.turns.len().saturating_sub(1)..]
+        };
+        let mut files = BTreeSet::new();
+        for turn in turns {
+            for deleted in &turn.deleted_files {
+                files.remove(deleted);
+            }
+            files.extend(turn.changed_files.iter().cloned());
+        }
+        files
+    }
+}
+
+/// Turn histories keyed by `session_id`, optionally backed by a directory
+#[derive(Debug, Clone, Default)]
+pub struct ArtifactStore {
+    sessions: Arc<Mutex<HashMap<String, SessionArtifacts>>>,
+    dir: Option<PathBuf>,
+}
+
+impl ArtifactStore {
+    /// Create an in-memory store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open a store persisted under `dir`; histories are loaded on first use
+    pub fn open(dir: PathBuf) -> Self {
+        Self {
+            sessions: Arc::default(),
+            dir: Some(dir),
+        }
+    }
+
+    /// Start recording a turn of `session_id` running in `cwd`
+    ///
+    /// Session-less turns cannot be looked up afterwards, so nothing is
+    /// recorded for them.
+    pub fn start_turn(&self, session_id: Option<&str>, cwd: PathBuf) -> Option<TurnRecorder> {
+        Some(TurnRecorder {
+            store: self.clone(),
+            session_id: session_id?.to_string(),
+            started_at: Utc::now(),
+            cwd,
+            unified_diff: None,
+            pending_patches: HashMap::new(),
+            changed_files: BTreeSet::new(),
+            deleted_files: BTreeSet::new(),
+        })
+    }
+
+    /// History of `session_id`, if any of its turns was recorded
+    pub async fn get(&self, session_id: &str) -> Option<SessionArtifacts> {
+        let mut sessions = self.sessions.lock().await;
+        self.load(&mut sessions, session_id).await;
+        sessions.get(session_id).cloned()
+    }
+
+    /// Forget the history of `session_id`
+    pub async fn remove(&self, session_id: &str) {
+        self.sessions.lock().await.remove(session_id);
+        if let Some(path) = self.path_for(session_id)
+            && let Err(err) = tokio::fs::remove_file(&path).await
+            && err.kind() != io::ErrorKind::NotFound
+        {
+            warn!("Failed to remove artifacts {}: {err}", path.display());
+        }
+    }
+
+    async fn record(&self, session_id: String, turn: TurnArtifacts) {
+        let mut sessions = self.sessions.lock().await;
+        self.load(&mut sessions, &session_id).await;
+        let artifacts = sessions
+            .entry(session_id.clone())
+            .or_insert_with(|| SessionArtifacts {
+                session_id: session_id.clone(),
+                turns: Vec::new(),
+            });
+        artifacts.turns.push(turn);
+
+        // A failed write only loses the history across restarts
+        let Some(path) = self.path_for(&session_id) else {
+            return;
+        };
+        let result = match serde_json::to_vec(artifacts) {
+            Ok(contents) => write_private_file(&path, &contents).await,
+            Err(err) => Err(err.into()),
+        };
+        if let Err(err) = result {
+            warn!("Failed to write artifacts to {}: {err}", path.display());
+        }
+    }
+
+    /// Read the history of `session_id` from disk unless it is cached
+    async fn load(&self, sessions: &mut HashMap<String, SessionArtifacts>, session_id: &str) {
+        if sessions.contains_key(session_id) {
+            return;
+        }
+        let Some(path) = self.path_for(session_id) else {
+            return;
+        };
+        let artifacts = match tokio::fs::read(&path).await {
+            Ok(contents) => serde_json::from_slice::<SessionArtifacts>(&contents),
+            Err(err) => {
+                if err.kind() != io::ErrorKind::NotFound {
+                    warn!("Failed to read artifacts {}: {err}", path.display());
+                }
+                return;
+            }
+        };
+        match artifacts {
+            Ok(artifacts) => {
+                debug!(
+                    "Loaded {} turns of session {session_id} from {}",
+                    artifacts.turns.len(),
+                    path.display()
+                );
+                sessions.insert(session_id.to_string(), artifacts);
+            }
+            Err(err) => warn!("Ignoring corrupt artifacts {}: {err}", path.display()),
+        }
+    }
+
+    fn path_for(&self, session_id: &str) -> Option<PathBuf> {
+        let name = URL_SAFE_NO_PAD.encode(Sha256::digest(session_id.as_bytes()));
+        Some(self.dir.as_ref()?.join(format!("{name}.json")))
+    }
+}
+
+/// Pack `files`, given as `(path on disk, name in the archive)`, into a
+/// gzip-compressed tarball
+///
+/// Symlinks are stored as links rather than followed. Blocking; run it off
+/// the async runtime.
+pub fn build_archive(files: &[(PathBuf, PathBuf)]) -> io::Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    builder.follow_symlinks(false);
+    for (path, name) in files {
+        builder.append_path_with_name(path, name)?;
+    }
+    builder.into_inner()?.finish()
+}
+
+/// Collects the artifacts of one running turn
+#[derive(Debug)]
+pub struct TurnRecorder {
+    store: ArtifactStore,
+    session_id: String,
+    started_at: DateTime<Utc>,
+    cwd: PathBuf,
+    unified_diff: Option<String>,
+    /// Changes of patches that have begun but not finished, by `call_id`
+    pending_patches: HashMap<String, HashMap<PathBuf, FileChange>>,
+    changed_files: BTreeSet<PathBuf>,
+    deleted_files: BTreeSet<PathBuf>,
+}
+
+impl TurnRecorder {
+    /// Feed one event of the turn
+    pub fn observe(&mut self, msg: &EventMsg) {
+        match msg {
+            // Each TurnDiff covers everything the turn changed so far
+            EventMsg::TurnDiff(event) => {
+                self.unified_diff = Some(event.unified_diff.clone());
+            }
+            EventMsg::PatchApplyBegin(event) => {
+                self.pending_patches
+                    .insert(event.call_id.clone(), event.changes.clone());
+            }
+            EventMsg::PatchApplyEnd(event) => {
+                let Some(changes) = self.pending_patches.remove(&event.call_id) else {
+                    return;
+                };
+                if event.success {
+                    for (path, change) in changes {
+                        self.apply(path, change);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn apply(&mut self, path: PathBuf, change: FileChange) {
+        let path = self.cwd.join(path);
+        match change {
+            FileChange::Add { .. }
+            | FileChange::Update {
+                move_path: None, ..
+            } => {
+                self.deleted_files.remove(&path);
+                self.changed_files.insert(path);
+            }
+            FileChange::Update {
+                move_path: Some(dest),
+                ..
+            } => {
+                let dest = self.cwd.join(dest);
+                self.changed_files.remove(&path);
+                self.deleted_files.insert(path);
+                self.deleted_files.remove(&dest);
+                self.changed_files.insert(dest);
+            }
+            FileChange::Delete { .. } => {
+                self.changed_files.remove(&path);
+                self.deleted_files.insert(path);
+            }
+        }
+    }
+
+    /// Store what the turn changed; called once the turn has ended
+    pub async fn finish(self) {
+        let turn = TurnArtifacts {
+            started_at: self.started_at,
+            finished_at: Utc::now(),
+            cwd: self.cwd,
+            unified_diff: self.unified_diff.filter(|diff| !diff.is_empty()),
+            changed_files: self.changed_files,
+            deleted_files: self.deleted_files,
+        };
+        self.store.record(self.session_id, turn).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::protocol::PatchApplyBeginEvent;
+    use codex_protocol::protocol::PatchApplyEndEvent;
+    use codex_protocol::protocol::TurnDiffEvent;
+
+    fn patch(recorder: &mut TurnRecorder, call_id: &str, path: &str, change: FileChange) {
+        recorder.observe(&EventMsg::PatchApplyBegin(PatchApplyBeginEvent {
+            call_id: call_id.to_string(),
+            auto_approved: true,
+            changes: HashMap::from([(PathBuf::from(path), change)]),
+        }));
+        recorder.observe(&EventMsg::PatchApplyEnd(PatchApplyEndEvent {
+            call_id: call_id.to_string(),
+            stdout: String::new(),
+            stderr: String::new(),
+            success: true,
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_turns_are_recorded_and_reloaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let cwd = dir.path().join("repo");
+        let store = ArtifactStore::open(dir.path().join("artifacts"));
+        assert!(store.start_turn(None, cwd.clone()).is_none());
+
+        let mut first = store.start_turn(Some("alpha"), cwd.clone()).unwrap();
+        let add = || FileChange::Add {
+            content: "x\n".to_string(),
+        };
+        patch(&mut first, "c1", "a.txt", add());
+        patch(&mut first, "c2", "b.txt", add());
+        first.observe(&EventMsg::TurnDiff(TurnDiffEvent {
+            unified_diff: "diff --git a/a.txt b/a.txt\n".to_string(),
+        }));
+        first.finish().await;
+
+        let mut second = store.start_turn(Some("alpha"), cwd.clone()).unwrap();
+        patch(
+            &mut second,
+            "c3",
+            "b.txt",
+            FileChange::Delete {
+                content: "x\n".to_string(),
+            },
+        );
+        second.observe(&EventMsg::TurnDiff(TurnDiffEvent {
+            unified_diff: "diff --git a/b.txt b/b.txt\n".to_string(),
+        }));
+        second.finish().await;
+
+        let reopened = ArtifactStore::open(dir.path().join("artifacts"));
+        let artifacts = reopened.get("alpha").await.unwrap();
+        assert_eq!(artifacts.turns.len(), 2);
+        assert_eq!(artifacts.cwd(), Some(cwd.as_path()));
+        assert_eq!(artifacts.diff(false), "diff --git a/b.txt b/b.txt\n");
+        assert_eq!(
+            artifacts.diff(true),
+            "diff --git a/a.txt b/a.txt\ndiff --git a/b.txt b/b.txt\n"
+        );
+        assert_eq!(
+            artifacts.changed_files(true),
+            BTreeSet::from([cwd.join("a.txt")])
+        );
+        assert!(artifacts.changed_files(false).is_empty());
+
+        reopened.remove("alpha").await;
+        assert!(
+            ArtifactStore::open(dir.path().join("artifacts"))
+                .get("alpha")
+                .await
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_build_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "fn main() {}\n").unwrap();
+
+        let archive =
+            build_archive(&[(dir.path().join("src/lib.rs"), PathBuf::from("src/lib.rs"))]).unwrap();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(&archive[..]));
+        let names: Vec<PathBuf> = archive
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().into_owned())
+            .collect();
+        assert_eq!(names, vec![PathBuf::from("src/lib.rs")]);
+    }
+
+    #[test]
+    fn test_failed_patches_are_ignored() {
+        let store = ArtifactStore::new();
+        let mut recorder = store
+            .start_turn(Some("alpha"), PathBuf::from("/repo"))
+            .unwrap();
+        recorder.observe(&EventMsg::PatchApplyBegin(PatchApplyBeginEvent {
+            call_id: "c1".to_string(),
+            auto_approved: true,
+            changes: HashMap::from([(
+                PathBuf::from("/repo/a.txt"),
+                FileChange::Add {
+                    content: String::new(),
+                },
+            )]),
+        }));
+        recorder.observe(&EventMsg::PatchApplyEnd(PatchApplyEndEvent {
+            call_id: "c1".to_string(),
+            stdout: String::new(),
+            stderr: "conflict".to_string(),
+            success: false,
+        }));
+        assert!(recorder.changed_files.is_empty());
+    }
+}
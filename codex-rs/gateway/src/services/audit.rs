@@ -0,0 +1,683 @@
+//! Append-only audit log of agent actions
+//!
+//! Every turn the gateway runs gets an [`AuditContext`] naming the calling
+//! key, the session and a fresh `request_id`. A [`TurnAuditor`] watches the
+//! turn's events and appends one [`AuditRecord`] per action: the prompt hash
+//! when the turn starts, every command begin/end, every applied patch, every
+//! approval request and the turn's token usage when it ends. Approval
+//! decisions are recorded by whoever answers them, through
+//! [`AuditLog::record`].
+//!
+//! Records are JSON lines in a single file. When it reaches the configured
+//! size it is renamed to `<file>.1`, older files shift to `<file>.2` and so
+//! on, and the oldest beyond `max_files` is deleted. Write failures are
+//! logged and never fail the turn.
+
+use crate::config::AuditConfig;
+use chrono::DateTime;
+use chrono::Utc;
+use codex_protocol::protocol::Event;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::ReviewDecision;
+use codex_protocol::protocol::TokenUsage;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Most records returned by one [`AuditLog::query`] unless a limit is given
+pub const DEFAULT_QUERY_LIMIT: usize = 1000;
+
+/// Kind of approval the agent asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalKind {
+    Exec,
+    Patch,
+    McpToolCall,
+}
+
+/// Who and what a turn's records belong to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditContext {
+    /// Shared by every record of one turn
+    pub request_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    pub conversation_id: String,
+}
+
+impl AuditContext {
+    /// Context for a new turn of `conversation_id`
+    pub fn new(
+        key_id: Option<&str>,
+        session_id: Option<&str>,
+        conversation_id: impl ToString,
+    ) -> Self {
+        Self {
+            request_id: Uuid::new_v4().to_string(),
+            key_id: key_id.map(str::to_string),
+            session_id: session_id.map(str::to_string),
+            conversation_id: conversation_id.to_string(),
+        }
+    }
+}
+
+/// One audited action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// A prompt was submitted; only its SHA-256 is kept
+    TurnStarted { prompt_sha256: String },
+    ExecCommandBegin {
+        call_id: String,
+        command: Vec<String>,
+        cwd: PathBuf,
+    },
+    ExecCommandEnd {
+        call_id: String,
+        command: Vec<String>,
+        cwd: PathBuf,
+        exit_code: i32,
+        duration_ms: u64,
+    },
+    /// An `apply_patch` call finished
+    PatchApplied {
+        call_id: String,
+        success: bool,
+        paths: Vec<PathBuf>,
+    },
+    ApprovalRequested {
+        approval_id: String,
+        kind: ApprovalKind,
+        call_id: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        command: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        paths: Vec<PathBuf>,
+        /// `server.tool` of an MCP tool call
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tool: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+    ApprovalDecision {
+        approval_id: String,
+        kind: ApprovalKind,
+        decision: ReviewDecision,
+        /// Set when the gateway decided because no client could
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+    /// Tokens used by the whole turn
+    TokenUsage {
+        #[serde(flatten)]
+        usage: TokenUsage,
+    },
+    /// The turn reached `completed`, `failed` or `aborted`
+    TurnEnded { status: String },
+}
+
+impl AuditEvent {
+    /// The `type` tag of the event
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::TurnStarted { .. } => "turn_started",
+            Self::ExecCommandBegin { .. } => "exec_command_begin",
+            Self::ExecCommandEnd { .. } => "exec_command_end",
+            Self::PatchApplied { .. } => "patch_applied",
+            Self::ApprovalRequested { .. } => "approval_requested",
+            Self::ApprovalDecision { .. } => "approval_decision",
+            Self::TokenUsage { .. } => "token_usage",
+            Self::TurnEnded { .. } => "turn_ended",
+        }
+    }
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub context: AuditContext,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
+
+/// Criteria for [`AuditLog::query`]; unset fields match everything
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AuditFilter {
+    pub key_id: Option<String>,
+    pub session_id: Option<String>,
+    pub conversation_id: Option<String>,
+    pub request_id: Option<String>,
+    /// Event `type`, e.g. `exec_command_end`
+    #[serde(rename = "type")]
+    pub event_type: Option<String>,
+    /// Inclusive lower bound on the timestamp
+    pub since: Option<DateTime<Utc>>,
+    /// Exclusive upper bound on the timestamp
+    pub until: Option<DateTime<Utc>>,
+    /// Return at most this many of the newest matches
+    pub limit: Option<usize>,
+}
+
+impl AuditFilter {
+    fn matches(&self, record: &AuditRecord) -> bool {
+        let context = &record.context;
+        self.key_id
+            .as_ref()
+            .is_none_or(|id| Some(id) == context.key_id.as_ref())
+            && self
+                .session_id
+                .as_ref()
+                .is_none_or(|id| Some(id) == context.session_id.as_ref())
+            && self
+                .conversation_id
+                .as_ref()
+                .is_none_or(|id| *id == context.conversation_id)
+            && self
+                .request_id
+                .as_ref()
+                .is_none_or(|id| *id == context.request_id)
+            && self
+                .event_type
+                .as_deref()
+                .is_none_or(|kind| kind == record.event.kind())
+            && self.since.is_none_or(|since| record.timestamp >= since)
+            && self.until.is_none_or(|until| record.timestamp < until)
+    }
+}
+
+/// The live file and its rotation settings
+#[derive(Debug)]
+struct AuditFile {
+    path: PathBuf,
+    max_file_size: u64,
+    max_files: usize,
+    file: Option<tokio::fs::File>,
+    size: u64,
+}
+
+/// Handle to the audit log; cheap to clone, a no-op when disabled
+#[derive(Debug, Clone, Default)]
+pub struct AuditLog {
+    inner: Option<Arc<Mutex<AuditFile>>>,
+}
+
+impl AuditLog {
+    /// A log that records nothing
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Log to `config.file`, or to `default_path` when it is unset
+    pub fn open(config: &AuditConfig, default_path: PathBuf) -> Self {
+        if !config.enabled {
+            return Self::disabled();
+        }
+        Self {
+            inner: Some(Arc::new(Mutex::new(AuditFile {
+                path: config.file.clone().unwrap_or(default_path),
+                max_file_size: config.max_file_size,
+                max_files: config.max_files,
+                file: None,
+                size: 0,
+            }))),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Record the start of a turn and return the auditor for its events
+    pub async fn start_turn(&self, context: AuditContext, prompt: &str) -> TurnAuditor {
+        let prompt_sha256 = format!("{:x}", Sha256::digest(prompt.as_bytes()));
+        self.record(&context, AuditEvent::TurnStarted { prompt_sha256 })
+            .await;
+        TurnAuditor {
+            log: self.clone(),
+            context,
+            commands: HashMap::new(),
+            patches: HashMap::new(),
+            usage: TokenUsage::default(),
+        }
+    }
+
+    /// Append one record
+    pub async fn record(&self, context: &AuditContext, event: AuditEvent) {
+        let Some(inner) = &self.inner else {
+            return;
+        };
+        let record = AuditRecord {
+            timestamp: Utc::now(),
+            context: context.clone(),
+            event,
+        };
+        let mut line = match serde_json::to_vec(&record) {
+            Ok(line) => line,
+            Err(err) => {
+                warn!("Failed to serialize audit record: {err}");
+                return;
+            }
+        };
+        line.push(b'\n');
+
+        let mut file = inner.lock().await;
+        if let Err(err) = file.append(&line).await {
+            warn!("Failed to write audit log {}: {err}", file.path.display());
+        }
+    }
+
+    /// The newest records matching `filter`, oldest first
+    pub async fn query(&self, filter: &AuditFilter) -> io::Result<Vec<AuditRecord>> {
+        let Some(inner) = &self.inner else {
+            return Ok(Vec::new());
+        };
+        let limit = filter.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Hold the lock so no rotation happens between the reads
+        let file = inner.lock().await;
+        let mut records = VecDeque::new();
+        for index in (0..=file.max_files).rev() {
+            let path = rotated_path(&file.path, index);
+            let contents = match tokio::fs::read_to_string(&path).await {
+                Ok(contents) => contents,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err),
+            };
+            for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+                let record = match serde_json::from_str::<AuditRecord>(line) {
+                    Ok(record) => record,
+                    Err(err) => {
+                        warn!(
+                            "Skipping malformed audit record in {}: {err}",
+                            path.display()
+                        );
+                        continue;
+                    }
+                };
+                if filter.matches(&record) {
+                    if records.len() == limit {
+                        records.pop_front();
+                    }
+                    records.push_back(record);
+                }
+            }
+        }
+        Ok(records.into())
+    }
+}
+
+impl AuditFile {
+    async fn append(&mut self, line: &[u8]) -> io::Result<()> {
+        if self.file.is_none() {
+            let file = open_append(&self.path).await?;
+            self.size = file.metadata().await?.len();
+            self.file = Some(file);
+        }
+        if self.size > 0 && self.size + line.len() as u64 > self.max_file_size {
+            self.rotate().await?;
+            self.file = Some(open_append(&self.path).await?);
+        }
+        if let Some(file) = &mut self.file {
+            file.write_all(line).await?;
+            file.flush().await?;
+            self.size += line.len() as u64;
+        }
+        Ok(())
+    }
+
+    /// Shift `<file>.N` to `<file>.N+1`, dropping the oldest
+    async fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        self.size = 0;
+        if self.max_files == 0 {
+            return tokio::fs::remove_file(&self.path).await;
+        }
+        remove_if_exists(&rotated_path(&self.path, self.max_files)).await?;
+        for index in (0..self.max_files).rev() {
+            let from = rotated_path(&self.path, index);
+            if let Err(err) = tokio::fs::rename(&from, rotated_path(&self.path, index + 1)).await
+                && err.kind() != io::ErrorKind::NotFound
+            {
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `<path>` for index 0, `<path>.<index>` otherwise
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        return path.to_path_buf();
+    }
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{index}"));
+    PathBuf::from(name)
+}
+
+async fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// Open `path` for appending, creating it (and its directory) readable by
+/// the owner only
+async fn open_append(path: &Path) -> io::Result<tokio::fs::File> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut options = tokio::fs::OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    options.open(path).await
+}
+
+/// Records the actions of one turn
+#[derive(Debug)]
+pub struct TurnAuditor {
+    log: AuditLog,
+    context: AuditContext,
+    /// Command and cwd of running commands, keyed by call id
+    commands: HashMap<String, (Vec<String>, PathBuf)>,
+    /// Paths of patches being applied, keyed by call id
+    patches: HashMap<String, Vec<PathBuf>>,
+    usage: TokenUsage,
+}
+
+impl TurnAuditor {
+    /// Who and what the turn's records belong to
+    pub fn context(&self) -> &AuditContext {
+        &self.context
+    }
+
+    /// Record an action of the turn that is not an event, such as a decision
+    pub async fn record(&self, event: AuditEvent) {
+        self.log.record(&self.context, event).await;
+    }
+
+    /// Record the auditable part of one event of the turn
+    ///
+    /// `usage` is what [`crate::services::metrics::Metrics::observe_event`]
+    /// counted for the event, so the turn's `token_usage` record matches the
+    /// token counters.
+    pub async fn observe(&mut self, event: &Event, usage: Option<&TokenUsage>) {
+        if let Some(usage) = usage {
+            self.usage.add_assign(usage);
+        }
+        let audit_event = match &event.msg {
+            EventMsg::ExecCommandBegin(begin) => {
+                self.commands.insert(
+                    begin.call_id.clone(),
+                    (begin.command.clone(), begin.cwd.clone()),
+                );
+                AuditEvent::ExecCommandBegin {
+                    call_id: begin.call_id.clone(),
+                    command: begin.command.clone(),
+                    cwd: begin.cwd.clone(),
+                }
+            }
+            EventMsg::ExecCommandEnd(end) => {
+                let (command, cwd) = self.commands.remove(&end.call_id).unwrap_or_default();
+                AuditEvent::ExecCommandEnd {
+                    call_id: end.call_id.clone(),
+                    command,
+                    cwd,
+                    exit_code: end.exit_code,
+                    duration_ms: u64::try_from(end.duration.as_millis()).unwrap_or(u64::MAX),
+                }
+            }
+            EventMsg::PatchApplyBegin(begin) => {
+                let mut paths: Vec<PathBuf> = begin.changes.keys().cloned().collect();
+                paths.sort();
+                self.patches.insert(begin.call_id.clone(), paths);
+                return;
+            }
+            EventMsg::PatchApplyEnd(end) => AuditEvent::PatchApplied {
+                call_id: end.call_id.clone(),
+                success: end.success,
+                paths: self.patches.remove(&end.call_id).unwrap_or_default(),
+            },
+            EventMsg::ExecApprovalRequest(request) => AuditEvent::ApprovalRequested {
+                approval_id: event.id.clone(),
+                kind: ApprovalKind::Exec,
+                call_id: request.call_id.clone(),
+                command: request.command.clone(),
+                paths: Vec::new(),
+                tool: None,
+                reason: request.reason.clone(),
+            },
+            EventMsg::ApplyPatchApprovalRequest(request) => {
+                let mut paths: Vec<PathBuf> = request.changes.keys().cloned().collect();
+                paths.sort();
+                AuditEvent::ApprovalRequested {
+                    approval_id: event.id.clone(),
+                    kind: ApprovalKind::Patch,
+                    call_id: request.call_id.clone(),
+                    command: Vec::new(),
+                    paths,
+                    tool: None,
+                    reason: request.reason.clone(),
+                }
+            }
+            EventMsg::McpToolCallApprovalRequest(request) => AuditEvent::ApprovalRequested {
+                approval_id: event.id.clone(),
+                kind: ApprovalKind::McpToolCall,
+                call_id: request.call_id.clone(),
+                command: Vec::new(),
+                paths: Vec::new(),
+                tool: Some(format!(
+                    "{}.{}",
+                    request.invocation.server, request.invocation.tool
+                )),
+                reason: request.reason.clone(),
+            },
+            EventMsg::TaskComplete(_) => return self.end("completed").await,
+            EventMsg::Error(_) => return self.end("failed").await,
+            EventMsg::TurnAborted(_) => return self.end("aborted").await,
+            _ => return,
+        };
+        self.log.record(&self.context, audit_event).await;
+    }
+
+    async fn end(&mut self, status: &str) {
+        let usage = std::mem::take(&mut self.usage);
+        self.log
+            .record(&self.context, AuditEvent::TokenUsage { usage })
+            .await;
+        self.log
+            .record(
+                &self.context,
+                AuditEvent::TurnEnded {
+                    status: status.to_string(),
+                },
+            )
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::protocol::ExecCommandBeginEvent;
+    use codex_protocol::protocol::ExecCommandEndEvent;
+    use codex_protocol::protocol::TaskCompleteEvent;
+    use codex_protocol::protocol::TokenCountEvent;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn config(dir: &TempDir, max_file_size: u64, max_files: usize) -> (AuditConfig, PathBuf) {
+        let path = dir.path().join("audit.jsonl");
+        let config = AuditConfig {
+            enabled: true,
+            file: Some(path.clone()),
+            max_file_size,
+            max_files,
+        };
+        (config, path)
+    }
+
+    fn event(msg: EventMsg) -> Event {
+        Event {
+            id: "1".to_string(),
+            msg,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_turn_records_commands_and_usage() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = TempDir::new()?;
+        let (config, _) = config(&dir, 1024 * 1024, 2);
+        let log = AuditLog::open(&config, PathBuf::new());
+
+        let context = AuditContext::new(Some("key-1"), Some("s1"), "conv-1");
+        let mut auditor = log.start_turn(context.clone(), "hello").await;
+        auditor
+            .observe(
+                &event(EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
+                    call_id: "call-1".to_string(),
+                    command: vec!["ls".to_string()],
+                    cwd: PathBuf::from("/work"),
+                    parsed_cmd: Vec::new(),
+                    is_user_shell_command: false,
+                })),
+                None,
+            )
+            .await;
+        auditor
+            .observe(
+                &event(EventMsg::ExecCommandEnd(ExecCommandEndEvent {
+                    call_id: "call-1".to_string(),
+                    stdout: String::new(),
+                    stderr: String::new(),
+                    aggregated_output: String::new(),
+                    exit_code: 2,
+                    duration: Duration::from_millis(15),
+                    formatted_output: String::new(),
+                })),
+                None,
+            )
+            .await;
+        let usage = TokenUsage {
+            input_tokens: 10,
+            output_tokens: 5,
+            total_tokens: 15,
+            ..TokenUsage::default()
+        };
+        let token_count = event(EventMsg::TokenCount(TokenCountEvent {
+            info: None,
+            rate_limits: None,
+        }));
+        auditor.observe(&token_count, Some(&usage)).await;
+        // A repeated count adds nothing
+        auditor.observe(&token_count, None).await;
+        auditor
+            .observe(
+                &event(EventMsg::TaskComplete(TaskCompleteEvent {
+                    last_agent_message: None,
+                })),
+                None,
+            )
+            .await;
+
+        let records = log.query(&AuditFilter::default()).await?;
+        let kinds: Vec<&str> = records.iter().map(|record| record.event.kind()).collect();
+        assert_eq!(
+            kinds,
+            [
+                "turn_started",
+                "exec_command_begin",
+                "exec_command_end",
+                "token_usage",
+                "turn_ended"
+            ]
+        );
+        assert!(records.iter().all(|record| record.context == context));
+        match &records[2].event {
+            AuditEvent::ExecCommandEnd {
+                command,
+                cwd,
+                exit_code,
+                duration_ms,
+                ..
+            } => {
+                assert_eq!(command, &vec!["ls".to_string()]);
+                assert_eq!(cwd, &PathBuf::from("/work"));
+                assert_eq!(*exit_code, 2);
+                assert_eq!(*duration_ms, 15);
+            }
+            other => panic!("unexpected event {other:?}"),
+        }
+        match &records[3].event {
+            AuditEvent::TokenUsage { usage } => assert_eq!(usage.total_tokens, 15),
+            other => panic!("unexpected event {other:?}"),
+        }
+
+        let filter = AuditFilter {
+            event_type: Some("exec_command_end".to_string()),
+            key_id: Some("key-1".to_string()),
+            ..AuditFilter::default()
+        };
+        assert_eq!(log.query(&filter).await?.len(), 1);
+        let filter = AuditFilter {
+            key_id: Some("key-2".to_string()),
+            ..AuditFilter::default()
+        };
+        assert!(log.query(&filter).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rotation_keeps_max_files() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = TempDir::new()?;
+        let (config, path) = config(&dir, 600, 2);
+        let log = AuditLog::open(&config, PathBuf::new());
+
+        let context = AuditContext::new(None, None, "conv-1");
+        for _ in 0..20 {
+            log.start_turn(context.clone(), "prompt").await;
+        }
+
+        assert!(path.exists());
+        assert!(rotated_path(&path, 1).exists());
+        assert!(rotated_path(&path, 2).exists());
+        assert!(!rotated_path(&path, 3).exists());
+        for index in 0..=2 {
+            let size = std::fs::metadata(rotated_path(&path, index))?.len();
+            assert!(size <= 600, "{size}");
+        }
+
+        // Queries span the rotated files, newest records last
+        let records = log
+            .query(&AuditFilter {
+                limit: Some(2),
+                ..AuditFilter::default()
+            })
+            .await?;
+        assert_eq!(records.len(), 2);
+        assert!(records[0].timestamp <= records[1].timestamp);
+        Ok(())
+    }
+}
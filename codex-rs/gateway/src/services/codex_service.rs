@@ -6,7 +6,18 @@
 
 use crate::error::GatewayError;
 use crate::error::GatewayResult;
+use crate::services::artifacts::ArtifactStore;
+use crate::services::audit::AuditContext;
+use crate::services::audit::AuditLog;
+use crate::services::drain::Drain;
+use crate::services::drain::TurnGuard;
+use crate::services::drain::interrupted_message;
+use crate::services::metrics::Metrics;
+use crate::services::session_registry::SessionRecord;
+use crate::services::session_registry::SessionRegistry;
+use crate::services::workspace::TurnSettings;
 use chrono::Utc;
+use codex_core::CodexConversation;
 use codex_core::ConversationManager;
 use codex_core::auth::AuthManager;
 use codex_core::config::Config as CodexConfig;
@@ -25,13 +36,19 @@ use serde_json::Value;
 use serde_json::json;
 use serde_json::to_value;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
+use tokio::task::JoinSet;
 use tracing::debug;
 use tracing::info;
 use tracing::trace;
 use tracing::warn;
 
+/// How long a conversation gets to flush its rollout on shutdown
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
 /// Codex Service para processar prompts de IA e gerenciar conversas
 ///
 /// Integração direta com o stack do Codex core:
@@ -42,6 +59,14 @@ use tracing::warn;
 pub struct CodexService {
     /// Active conversations mapped by session ID to conversation ID
     active_conversations: Arc<Mutex<HashMap<String, ConversationId>>>,
+    /// Durable session records, used to rehydrate sessions after a restart
+    sessions: SessionRegistry,
+    /// Diffs and changed files of each session's turns
+    artifacts: ArtifactStore,
+    /// Audit trail of the agent's actions
+    audit: AuditLog,
+    /// In-flight turns and the draining flag used on shutdown
+    drain: Drain,
 
     /// Base Codex configuration loaded from disk/CLI overrides
     codex_config: Arc<CodexConfig>,
@@ -52,6 +77,8 @@ pub struct CodexService {
     conversation_manager: Arc<Mutex<ConversationManager>>,
     /// Request counter for generating unique request IDs
     request_counter: Arc<Mutex<u64>>,
+    /// Turn and token counters exported on `/metrics`
+    metrics: Metrics,
 }
 
 /// Status básico de uma sessão ativa exposto via JSON-RPC
@@ -96,17 +123,51 @@ impl CodexService {
             SessionSource::Exec,
         )));
 
+        let sessions_path = std::env::var_os("GATEWAY_SESSIONS_FILE")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                codex_config
+                    .codex_home
+                    .join("gateway")
+                    .join("sessions.json")
+            });
+        let sessions = SessionRegistry::open(sessions_path.clone())
+            .await
+            .map_err(|err| {
+                GatewayError::Config(format!(
+                    "failed to load sessions from {}: {err}",
+                    sessions_path.display()
+                ))
+            })?;
+
+        let artifacts = ArtifactStore::open(
+            std::env::var_os("GATEWAY_ARTIFACTS_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| codex_config.codex_home.join("gateway").join("artifacts")),
+        );
+
         info!("CodexService initialized successfully with real Codex components");
 
         Ok(Self {
             active_conversations: Arc::new(Mutex::new(HashMap::new())),
+            sessions,
+            artifacts,
+            audit: AuditLog::disabled(),
+            drain: Drain::new(),
             codex_config: Arc::new(codex_config),
             conversation_metadata: Arc::new(Mutex::new(HashMap::new())),
             conversation_manager,
             request_counter: Arc::new(Mutex::new(0)),
+            metrics: Metrics::new(),
         })
     }
 
+    /// Record the agent's actions in `audit`
+    pub fn with_audit_log(mut self, audit: AuditLog) -> Self {
+        self.audit = audit;
+        self
+    }
+
     /// Execute a prompt using REAL Codex AI processing
     ///
     /// This method implements the complete integration flow:
@@ -116,10 +177,15 @@ impl CodexService {
     /// 4. Submits to MessageProcessor via ClientRequest::SendUserTurn
     /// 5. Processes ResponseStream events from actual AI
     /// 6. Returns structured JSON with real AI response
+    ///
+    /// `settings` carry the caller's resolved cwd, sandbox and approval policy;
+    /// `owner` is the calling key, which a named session must belong to.
     pub async fn execute_prompt(
         &self,
         prompt: &str,
         session_id: Option<&str>,
+        owner: Option<&str>,
+        settings: TurnSettings,
     ) -> GatewayResult<Value> {
         let start_time = Utc::now();
         info!(
@@ -129,7 +195,7 @@ impl CodexService {
         );
 
         // Get or create conversation ID for this session
-        let conversation_id = self.get_or_create_conversation(session_id).await?;
+        let conversation_id = self.get_or_create_conversation(session_id, owner).await?;
         debug!("Using conversation_id: {}", conversation_id);
 
         // Generate unique request ID (currently only used for logging)
@@ -153,13 +219,23 @@ impl CodexService {
                 .map_err(|e| GatewayError::Internal(format!("failed to get conversation: {e}")))?
         };
 
+        let _turn = self.drain.track(Arc::clone(&conversation))?;
+        let mut recorder = self.artifacts.start_turn(session_id, settings.cwd.clone());
+        let mut auditor = self
+            .audit
+            .start_turn(
+                AuditContext::new(owner, session_id, conversation_id),
+                prompt,
+            )
+            .await;
+
         // Submit the prompt via CodexConversation using real Op::UserTurn
         let submission_id = conversation
             .submit(Op::UserTurn {
                 items: user_inputs,
-                cwd: codex_config.cwd.clone(),
-                approval_policy: codex_config.approval_policy,
-                sandbox_policy: codex_config.sandbox_policy.clone(),
+                cwd: settings.cwd,
+                approval_policy: settings.approval_policy,
+                sandbox_policy: settings.sandbox_policy,
                 model: codex_config.model.clone(),
                 effort: codex_config.model_reasoning_effort,
                 summary: codex_config.model_reasoning_summary,
@@ -181,6 +257,18 @@ impl CodexService {
                 .await
                 .map_err(|e| GatewayError::Internal(format!("failed to get event: {e}")))?;
 
+            let usage = self.metrics.observe_event(conversation_id, &event.msg);
+            auditor.observe(&event, usage.as_ref()).await;
+            if let Some(recorder) = recorder.as_mut() {
+                recorder.observe(&event.msg);
+            }
+            if matches!(
+                event.msg,
+                EventMsg::TaskComplete(_) | EventMsg::Error(_) | EventMsg::TurnAborted(_)
+            ) && let Some(recorder) = recorder.take()
+            {
+                recorder.finish().await;
+            }
             let event_json = to_value(&event.msg)
                 .unwrap_or_else(|err| json!({ "serialization_error": err.to_string() }));
             streamed_events.push(event_json);
@@ -233,6 +321,12 @@ impl CodexService {
                         error_event.message
                     )));
                 }
+                EventMsg::TurnAborted(_) if self.drain.is_draining() => {
+                    return Err(GatewayError::ServiceUnavailable(interrupted_message(
+                        conversation_id,
+                        session_id,
+                    )));
+                }
                 EventMsg::TurnAborted(aborted) => {
                     return Err(GatewayError::Internal(format!(
                         "Codex turn aborted: {:?}",
@@ -287,43 +381,181 @@ impl CodexService {
     }
 
     /// Get or create a conversation for the given session
+    ///
+    /// Sessions not loaded in this process are looked up in the session
+    /// registry and rehydrated from their rollout, so a `session_id` keeps
+    /// its history across gateway restarts. A session created by another key
+    /// is refused, and so is any request once shutdown has started.
     pub async fn get_or_create_conversation(
         &self,
         session_id: Option<&str>,
+        owner: Option<&str>,
     ) -> GatewayResult<ConversationId> {
+        self.drain.check()?;
+        let Some(sid) = session_id else {
+            // Create ephemeral conversation for session-less requests
+            warn!("Creating ephemeral conversation for session-less request");
+            let conversation_id = self.create_new_conversation().await?;
+            debug!("Created ephemeral conversation: {}", conversation_id);
+            return Ok(conversation_id);
+        };
+
         let mut conversations = self.active_conversations.lock().await;
+        let record = self.sessions.get(sid).await;
+        if let Some(record) = &record
+            && record.owner.as_deref() != owner
+        {
+            return Err(GatewayError::Forbidden(format!(
+                "session {sid} belongs to another API key"
+            )));
+        }
 
-        match session_id {
-            Some(sid) => {
-                if let Some(conversation_id) = conversations.get(sid) {
-                    debug!(
-                        "Found existing conversation for session {}: {}",
-                        sid, conversation_id
-                    );
-                    Ok(*conversation_id)
-                } else {
-                    // Create new conversation via ConversationManager
-                    warn!(
-                        "No existing conversation found for session {}, creating new one",
-                        sid
-                    );
-                    let conversation_id = self.create_new_conversation().await?;
-                    conversations.insert(sid.to_string(), conversation_id);
-                    info!(
-                        "Created new conversation for session {}: {}",
-                        sid, conversation_id
-                    );
-                    Ok(conversation_id)
-                }
-            }
-            None => {
-                // Create ephemeral conversation for session-less requests
-                warn!("Creating ephemeral conversation for session-less request");
-                let conversation_id = self.create_new_conversation().await?;
-                debug!("Created ephemeral conversation: {}", conversation_id);
-                Ok(conversation_id)
-            }
+        if let Some(conversation_id) = conversations.get(sid).copied() {
+            debug!(
+                "Found existing conversation for session {}: {}",
+                sid, conversation_id
+            );
+            self.sessions.touch(sid, conversation_id, None).await;
+            return Ok(conversation_id);
+        }
+
+        if let Some(record) = record {
+            let conversation_id = self.rehydrate(&record).await?;
+            conversations.insert(sid.to_string(), conversation_id);
+            return Ok(conversation_id);
         }
+
+        // Create new conversation via ConversationManager
+        warn!(
+            "No existing conversation found for session {}, creating new one",
+            sid
+        );
+        let conversation_id = self.create_new_conversation().await?;
+        conversations.insert(sid.to_string(), conversation_id);
+        let now = Utc::now();
+        self.sessions
+            .insert(SessionRecord {
+                session_id: sid.to_string(),
+                conversation_id,
+                rollout_path: self.rollout_path(conversation_id).await,
+                owner: owner.map(str::to_string),
+                created_at: now,
+                last_used_at: now,
+            })
+            .await;
+        info!(
+            "Created new conversation for session {}: {}",
+            sid, conversation_id
+        );
+        Ok(conversation_id)
+    }
+
+    /// Load a registered session's conversation back from its rollout
+    async fn rehydrate(&self, record: &SessionRecord) -> GatewayResult<ConversationId> {
+        let rollout_path = match &record.rollout_path {
+            Some(path) if path.exists() => path.clone(),
+            _ => find_conversation_path_by_id_str(
+                &self.codex_config.codex_home,
+                &record.conversation_id.to_string(),
+            )
+            .await
+            .map_err(|e| GatewayError::Internal(format!("Failed to find conversation path: {e}")))?
+            .ok_or_else(|| {
+                GatewayError::NotFound(format!(
+                    "rollout of session {} is no longer available",
+                    record.session_id
+                ))
+            })?,
+        };
+
+        let conversation_id = self.load_rollout(rollout_path.clone()).await?;
+        self.sessions
+            .touch(&record.session_id, conversation_id, Some(rollout_path))
+            .await;
+        info!(
+            "Rehydrated session {} from rollout: conversation_id={}",
+            record.session_id, conversation_id
+        );
+        Ok(conversation_id)
+    }
+
+    /// Resume a conversation from `rollout_path` via ConversationManager
+    async fn load_rollout(&self, rollout_path: PathBuf) -> GatewayResult<ConversationId> {
+        let config = (*self.codex_config).clone();
+        let auth_manager = AuthManager::shared(
+            self.codex_config.codex_home.clone(),
+            false,
+            self.codex_config.cli_auth_credentials_store_mode,
+        );
+
+        let new_conversation = {
+            let manager = self.conversation_manager.lock().await;
+            manager
+                .resume_conversation_from_rollout(config, rollout_path, auth_manager)
+                .await
+                .map_err(|e| {
+                    GatewayError::Internal(format!("Failed to resume conversation: {e}"))
+                })?
+        };
+
+        let conversation_id = new_conversation.conversation_id;
+        self.conversation_metadata
+            .lock()
+            .await
+            .insert(conversation_id, new_conversation.session_configured);
+        Ok(conversation_id)
+    }
+
+    /// Rollout file recorded for a loaded conversation
+    async fn rollout_path(&self, conversation_id: ConversationId) -> Option<PathBuf> {
+        self.conversation_metadata
+            .lock()
+            .await
+            .get(&conversation_id)
+            .map(|metadata| metadata.rollout_path.clone())
+    }
+
+    /// Submit a user turn and return the conversation to read its events from
+    ///
+    /// For handlers that need the raw [`EventMsg`] feed (message deltas, token
+    /// counts) rather than the ThreadEvents produced for `codex exec`.
+    /// `model` overrides the configured model when set. Keep the returned
+    /// [`TurnGuard`] until the turn's terminal event.
+    pub async fn start_turn(
+        &self,
+        session_id: Option<&str>,
+        owner: Option<&str>,
+        items: Vec<UserInput>,
+        settings: TurnSettings,
+        model: Option<String>,
+    ) -> GatewayResult<(ConversationId, Arc<CodexConversation>, TurnGuard)> {
+        let conversation_id = self.get_or_create_conversation(session_id, owner).await?;
+        let conversation = {
+            let manager = self.conversation_manager.lock().await;
+            manager
+                .get_conversation(conversation_id)
+                .await
+                .map_err(|e| GatewayError::Internal(format!("failed to get conversation: {e}")))?
+        };
+
+        let turn = self.drain.track(Arc::clone(&conversation))?;
+
+        let model = model.unwrap_or_else(|| self.codex_config.model.clone());
+        conversation
+            .submit(Op::UserTurn {
+                items,
+                cwd: settings.cwd,
+                approval_policy: settings.approval_policy,
+                sandbox_policy: settings.sandbox_policy,
+                model,
+                effort: self.codex_config.model_reasoning_effort,
+                summary: self.codex_config.model_reasoning_summary,
+                final_output_json_schema: None,
+            })
+            .await
+            .map_err(|e| GatewayError::Internal(format!("submission failed: {e}")))?;
+
+        Ok((conversation_id, conversation, turn))
     }
 
     /// Create a new conversation using ConversationManager from codex-core
@@ -359,17 +591,23 @@ impl CodexService {
     }
 
     /// Recupera status básico da sessão para JSON-RPC
+    ///
+    /// Sessões registradas mas ainda não recarregadas não têm metadata.
     pub async fn get_session_status(
         &self,
         session_id: &str,
+        owner: Option<&str>,
     ) -> GatewayResult<Option<SessionStatus>> {
-        let conversation_id = {
-            let conversations = self.active_conversations.lock().await;
-            match conversations.get(session_id) {
-                Some(id) => *id,
-                None => return Ok(None),
-            }
+        let Some(record) = self.owned_session(session_id, owner).await else {
+            return Ok(None);
         };
+        let conversation_id = self
+            .active_conversations
+            .lock()
+            .await
+            .get(session_id)
+            .copied()
+            .unwrap_or(record.conversation_id);
 
         let metadata = {
             let metadata_map = self.conversation_metadata.lock().await;
@@ -382,22 +620,117 @@ impl CodexService {
         }))
     }
 
-    /// Cancela sessão ativa e remove rastros em memória
-    pub async fn cancel_session(&self, session_id: &str) -> GatewayResult<Option<ConversationId>> {
-        let conversation_id = {
-            let mut conversations = self.active_conversations.lock().await;
-            conversations.remove(session_id)
+    /// Cancela sessão e remove seus rastros em memória e no registro
+    ///
+    /// O rollout permanece em disco e pode ser retomado via `/exec/resume`.
+    pub async fn cancel_session(
+        &self,
+        session_id: &str,
+        owner: Option<&str>,
+    ) -> GatewayResult<Option<ConversationId>> {
+        let Some(record) = self.owned_session(session_id, owner).await else {
+            return Ok(None);
         };
 
-        if let Some(conversation_id) = conversation_id {
-            self.conversation_metadata
-                .lock()
-                .await
-                .remove(&conversation_id);
-            Ok(Some(conversation_id))
-        } else {
-            Ok(None)
+        let loaded = self.active_conversations.lock().await.remove(session_id);
+        self.sessions.remove(session_id).await;
+        self.artifacts.remove(session_id).await;
+        let conversation_id = loaded.unwrap_or(record.conversation_id);
+
+        self.conversation_metadata
+            .lock()
+            .await
+            .remove(&conversation_id);
+        let conversation = self
+            .conversation_manager
+            .lock()
+            .await
+            .remove_conversation(&conversation_id)
+            .await;
+        if let Some(conversation) = conversation
+            && let Err(err) = conversation.submit(Op::Shutdown).await
+        {
+            debug!("Conversation {conversation_id} already stopped: {err}");
+        }
+
+        info!("Session {session_id} closed: conversation_id={conversation_id}");
+        Ok(Some(conversation_id))
+    }
+
+    /// Drain in-flight turns, then shut down every loaded conversation
+    ///
+    /// Turns get `grace` to finish before they are interrupted (see
+    /// [`Drain::run`]), including those of the embedded `/app-server` and
+    /// `/mcp` processors. `Op::Shutdown` makes core flush each rollout through
+    /// `RolloutRecorder::shutdown` before it reports `ShutdownComplete`, so
+    /// interrupted sessions resume from their last event after a restart.
+    pub async fn shutdown(&self, grace: Duration) {
+        let mut conversations = self.drain.run(grace).await;
+        for conversation in self.drain.registered() {
+            if !conversations
+                .iter()
+                .any(|known| Arc::ptr_eq(known, &conversation))
+            {
+                conversations.push(conversation);
+            }
         }
+        let loaded: Vec<ConversationId> = self
+            .active_conversations
+            .lock()
+            .await
+            .values()
+            .copied()
+            .collect();
+        {
+            let manager = self.conversation_manager.lock().await;
+            for conversation_id in loaded {
+                if let Ok(conversation) = manager.get_conversation(conversation_id).await
+                    && !conversations
+                        .iter()
+                        .any(|known| Arc::ptr_eq(known, &conversation))
+                {
+                    conversations.push(conversation);
+                }
+            }
+        }
+
+        info!("Shutting down {} conversation(s)", conversations.len());
+        let mut shutdowns = JoinSet::new();
+        for conversation in conversations {
+            shutdowns.spawn(shutdown_conversation(conversation));
+        }
+        while shutdowns.join_next().await.is_some() {}
+    }
+
+    /// Sessions created by `owner`, most recently used first
+    pub async fn list_sessions(&self, owner: Option<&str>) -> Vec<SessionRecord> {
+        self.sessions.list(owner).await
+    }
+
+    /// Whether `session_id` exists for `owner`, loaded or not
+    pub async fn session_exists(&self, session_id: &str, owner: Option<&str>) -> bool {
+        self.owned_session(session_id, owner).await.is_some()
+    }
+
+    /// Conversation currently loaded for `session_id`, if `owner` may use it
+    pub async fn loaded_conversation(
+        &self,
+        session_id: &str,
+        owner: Option<&str>,
+    ) -> Option<ConversationId> {
+        self.owned_session(session_id, owner).await?;
+        self.active_conversations
+            .lock()
+            .await
+            .get(session_id)
+            .copied()
+    }
+
+    async fn owned_session(&self, session_id: &str, owner: Option<&str>) -> Option<SessionRecord> {
+        self.sessions
+            .get(session_id)
+            .await
+            .filter(|record| record.owner.as_deref() == owner)
     }
 
     /// Get public accessor to conversation manager
@@ -405,14 +738,34 @@ impl CodexService {
         &self.conversation_manager
     }
 
+    /// Get public accessor to the per-session turn artifacts
+    pub fn artifacts(&self) -> &ArtifactStore {
+        &self.artifacts
+    }
+
+    /// Get public accessor to the audit log
+    pub fn audit(&self) -> &AuditLog {
+        &self.audit
+    }
+
+    /// Get public accessor to the in-flight turn registry
+    pub fn drain(&self) -> &Drain {
+        &self.drain
+    }
+
     /// Get public accessor to codex config
     pub fn codex_config(&self) -> &Arc<CodexConfig> {
         &self.codex_config
     }
 
-    /// Get public accessor to active conversations (for WebSocket interrupt)
-    pub fn active_conversations(&self) -> &Arc<Mutex<HashMap<String, ConversationId>>> {
-        &self.active_conversations
+    /// Number of conversations loaded in this process
+    pub async fn loaded_conversation_count(&self) -> usize {
+        self.active_conversations.lock().await.len()
+    }
+
+    /// Get public accessor to the metrics registry
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
     }
 
     /// Resume a conversation from a previous session
@@ -420,12 +773,13 @@ impl CodexService {
     /// This method allows resuming a conversation by its ID. It:
     /// 1. Finds the rollout path for the conversation
     /// 2. Uses ConversationManager to resume from the rollout
-    /// 3. Registers the conversation with a new session_id
+    /// 3. Registers the conversation with a new session_id owned by `owner`
     ///
     /// # Arguments
     ///
     /// * `conversation_id_str` - The conversation ID to resume
     /// * `session_id` - New session ID to associate with the resumed conversation
+    /// * `owner` - Key the session is registered to
     ///
     /// # Returns
     ///
@@ -434,12 +788,21 @@ impl CodexService {
         &self,
         conversation_id_str: &str,
         session_id: &str,
+        owner: Option<&str>,
     ) -> GatewayResult<ConversationId> {
         info!(
             "Resuming conversation: conversation_id={}, session_id={}",
             conversation_id_str, session_id
         );
 
+        if let Some(record) = self.sessions.get(session_id).await
+            && record.owner.as_deref() != owner
+        {
+            return Err(GatewayError::Forbidden(format!(
+                "session {session_id} belongs to another API key"
+            )));
+        }
+
         // Find rollout path by conversation ID
         let rollout_path =
             find_conversation_path_by_id_str(&self.codex_config.codex_home, conversation_id_str)
@@ -456,36 +819,24 @@ impl CodexService {
         debug!("Found rollout path: {:?}", rollout_path);
 
         // Resume via ConversationManager
-        let config = (*self.codex_config).clone();
-        let auth_manager = AuthManager::shared(
-            self.codex_config.codex_home.clone(),
-            false,
-            self.codex_config.cli_auth_credentials_store_mode,
-        );
-
-        let new_conversation = {
-            let manager = self.conversation_manager.lock().await;
-            manager
-                .resume_conversation_from_rollout(config, rollout_path, auth_manager)
-                .await
-                .map_err(|e| {
-                    GatewayError::Internal(format!("Failed to resume conversation: {e}"))
-                })?
-        };
-
-        let conversation_id = new_conversation.conversation_id;
-        let session_configured = new_conversation.session_configured.clone();
+        let conversation_id = self.load_rollout(rollout_path.clone()).await?;
 
         // Register with session_id
         self.active_conversations
             .lock()
             .await
             .insert(session_id.to_string(), conversation_id);
-
-        self.conversation_metadata
-            .lock()
-            .await
-            .insert(conversation_id, session_configured);
+        let now = Utc::now();
+        self.sessions
+            .insert(SessionRecord {
+                session_id: session_id.to_string(),
+                conversation_id,
+                rollout_path: Some(rollout_path),
+                owner: owner.map(str::to_string),
+                created_at: now,
+                last_used_at: now,
+            })
+            .await;
 
         info!(
             "Successfully resumed conversation: conversation_id={}, session_id={}",
@@ -495,3 +846,26 @@ impl CodexService {
         Ok(conversation_id)
     }
 }
+
+/// Submit `Op::Shutdown` and wait for core to report `ShutdownComplete`
+async fn shutdown_conversation(conversation: Arc<CodexConversation>) {
+    if let Err(err) = conversation.submit(Op::Shutdown).await {
+        debug!("Conversation already stopped: {err}");
+        return;
+    }
+    let complete = async {
+        loop {
+            match conversation.next_event().await {
+                Ok(event) if matches!(event.msg, EventMsg::ShutdownComplete) => break,
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+    };
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, complete)
+        .await
+        .is_err()
+    {
+        warn!("Conversation did not confirm shutdown; its rollout may be incomplete");
+    }
+}
@@ -0,0 +1,259 @@
+//! Graceful drain of in-flight agent turns on shutdown
+//!
+//! Every turn the gateway starts is registered here for as long as its event
+//! loop runs, through a [`TurnGuard`]. When the process is asked to stop,
+//! [`Drain::run`] switches the gateway to draining: new work is refused with
+//! 503 and `/health` reports `draining`. Running turns get a grace period to
+//! finish; those still running afterwards receive `Op::Interrupt`, and their
+//! clients get a final `turn.failed` event built by [`interrupted_message`]
+//! telling them how to resume.
+//!
+//! Conversations of the embedded `/app-server` and `/mcp` processors are also
+//! [registered](Drain::register) so the shutdown flushes their rollouts,
+//! even when no turn is running on them.
+
+use crate::error::GatewayError;
+use crate::error::GatewayResult;
+use codex_core::CodexConversation;
+use codex_protocol::ConversationId;
+use codex_protocol::protocol::Op;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::sync::Weak;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::debug;
+use tracing::info;
+use tracing::warn;
+
+/// How long interrupted turns get to report `TurnAborted`
+const INTERRUPT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Prefix of the error sent to clients whose turn was cut by a shutdown
+pub const INTERRUPTED_BY_SHUTDOWN: &str = "interrupted by shutdown";
+
+/// Draining flag and registry of the turns currently running
+#[derive(Clone)]
+pub struct Drain {
+    inner: Arc<DrainInner>,
+}
+
+struct DrainInner {
+    draining: AtomicBool,
+    next_id: AtomicU64,
+    /// Running turns by registration id; a watch channel so the drain can
+    /// wait for the registry to empty
+    turns: watch::Sender<HashMap<u64, Arc<CodexConversation>>>,
+    /// Conversations of the embedded processors; weak so a closed
+    /// connection still frees its conversations
+    conversations: Mutex<Vec<Weak<CodexConversation>>>,
+}
+
+/// Registration of a running turn, removed from the registry on drop
+///
+/// Hold it in the task reading the turn's events until the terminal event.
+pub struct TurnGuard {
+    drain: Drain,
+    id: u64,
+}
+
+impl Drop for TurnGuard {
+    fn drop(&mut self) {
+        self.drain.inner.turns.send_modify(|turns| {
+            turns.remove(&self.id);
+        });
+    }
+}
+
+impl Default for Drain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for Drain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Drain")
+            .field("draining", &self.is_draining())
+            .field("in_flight", &self.in_flight())
+            .finish()
+    }
+}
+
+impl Drain {
+    pub fn new() -> Self {
+        let (turns, _) = watch::channel(HashMap::new());
+        Self {
+            inner: Arc::new(DrainInner {
+                draining: AtomicBool::new(false),
+                next_id: AtomicU64::new(0),
+                turns,
+                conversations: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Whether shutdown has started
+    pub fn is_draining(&self) -> bool {
+        self.inner.draining.load(Ordering::SeqCst)
+    }
+
+    /// Refuse new work once shutdown has started
+    pub fn check(&self) -> GatewayResult<()> {
+        if self.is_draining() {
+            return Err(GatewayError::ServiceUnavailable(
+                "gateway is shutting down; retry on another instance".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Register a turn running on `conversation`
+    pub fn track(&self, conversation: Arc<CodexConversation>) -> GatewayResult<TurnGuard> {
+        self.check()?;
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        self.inner.turns.send_modify(|turns| {
+            turns.insert(id, conversation);
+        });
+        Ok(TurnGuard {
+            drain: self.clone(),
+            id,
+        })
+    }
+
+    /// Remember a conversation of an embedded processor for the shutdown
+    pub fn register(&self, conversation: &Arc<CodexConversation>) {
+        let mut conversations = self
+            .inner
+            .conversations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        conversations.retain(|known| known.strong_count() > 0);
+        if !conversations
+            .iter()
+            .any(|known| std::ptr::eq(known.as_ptr(), Arc::as_ptr(conversation)))
+        {
+            conversations.push(Arc::downgrade(conversation));
+        }
+    }
+
+    /// Registered conversations that are still open
+    pub fn registered(&self) -> Vec<Arc<CodexConversation>> {
+        self.inner
+            .conversations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect()
+    }
+
+    /// Number of turns currently running
+    pub fn in_flight(&self) -> usize {
+        self.inner.turns.borrow().len()
+    }
+
+    /// Stop accepting work and wait up to `grace` for running turns
+    ///
+    /// Turns still running once `grace` expires are interrupted; their
+    /// conversations are returned so the caller can shut them down.
+    pub async fn run(&self, grace: Duration) -> Vec<Arc<CodexConversation>> {
+        self.inner.draining.store(true, Ordering::SeqCst);
+        info!(
+            "Draining {} in-flight turn(s) for up to {}s",
+            self.in_flight(),
+            grace.as_secs()
+        );
+        if self.wait_idle(grace).await {
+            return Vec::new();
+        }
+
+        let remaining: Vec<Arc<CodexConversation>> =
+            self.inner.turns.borrow().values().cloned().collect();
+        warn!(
+            "Grace period expired; interrupting {} turn(s)",
+            remaining.len()
+        );
+        for conversation in &remaining {
+            if let Err(err) = conversation.submit(Op::Interrupt).await {
+                debug!("Turn already stopped: {err}");
+            }
+        }
+        if !self.wait_idle(INTERRUPT_TIMEOUT).await {
+            warn!(
+                "{} turn(s) did not stop after Op::Interrupt",
+                self.in_flight()
+            );
+        }
+        remaining
+    }
+
+    /// Wait up to `limit` for the registry to empty
+    async fn wait_idle(&self, limit: Duration) -> bool {
+        let mut turns = self.inner.turns.subscribe();
+        matches!(
+            tokio::time::timeout(limit, turns.wait_for(HashMap::is_empty)).await,
+            Ok(Ok(_))
+        )
+    }
+}
+
+/// Final error for a turn interrupted by shutdown, with how to resume it
+///
+/// Named sessions are rehydrated from their rollout on their next prompt;
+/// session-less turns can be bound to a session with `POST /exec/resume`.
+pub fn interrupted_message(conversation_id: ConversationId, session_id: Option<&str>) -> String {
+    match session_id {
+        Some(session_id) => format!(
+            "{INTERRUPTED_BY_SHUTDOWN}; send the next prompt with session_id {session_id} to resume conversation {conversation_id}"
+        ),
+        None => format!(
+            "{INTERRUPTED_BY_SHUTDOWN}; resume conversation {conversation_id} with POST /exec/resume"
+        ),
+    }
+}
+
+/// Final error for an `/app-server` or `/mcp` turn interrupted by shutdown
+///
+/// The rollout is flushed, but the conversation lived in the connection's
+/// processor, so the client resumes it from a new connection.
+pub fn interrupted_connection_message(conversation_id: ConversationId) -> String {
+    format!(
+        "{INTERRUPTED_BY_SHUTDOWN}; conversation {conversation_id} was saved, reconnect to resume it"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_without_turns_refuses_new_work() {
+        let drain = Drain::new();
+        assert!(drain.check().is_ok());
+
+        let interrupted = drain.run(Duration::from_secs(30)).await;
+        assert!(interrupted.is_empty());
+        assert!(drain.is_draining());
+        assert!(matches!(
+            drain.check(),
+            Err(GatewayError::ServiceUnavailable(_))
+        ));
+    }
+
+    #[test]
+    fn test_interrupted_message() {
+        let conversation_id = ConversationId::new();
+        let named = interrupted_message(conversation_id, Some("alpha"));
+        assert!(named.starts_with(INTERRUPTED_BY_SHUTDOWN));
+        assert!(named.contains("session_id alpha"));
+        let anonymous = interrupted_message(conversation_id, None);
+        assert!(anonymous.contains(&conversation_id.to_string()));
+        assert!(anonymous.contains("/exec/resume"));
+    }
+}
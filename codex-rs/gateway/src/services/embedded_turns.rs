@@ -0,0 +1,310 @@
+//! Turns run by the embedded app-server and MCP processors
+//!
+//! `/app-server` and `/mcp` hand requests to their own Codex processors, so
+//! the gateway never drives those turns itself. Both processors forward every
+//! core event to their client (`codex/event/<type>` notifications carrying a
+//! `conversationId` on `/app-server`, `codex/event` notifications tagged with
+//! the `tools/call` request id on `/mcp`). [`EmbeddedTurns`] reads those
+//! events back on their way out, so these turns show up in the metrics and
+//! the audit log like the ones the gateway runs.
+//!
+//! The prompt of a turn is taken from the client request that submitted it;
+//! the turn itself starts with the conversation's `TaskStarted` event.
+//!
+//! The processors keep their conversations in a [`ConversationManager`]
+//! shared with the gateway, so running turns are also registered with the
+//! [`Drain`]: shutdown waits for them, interrupts them after the grace period
+//! and flushes their rollouts like any other turn.
+
+use crate::services::audit::AuditContext;
+use crate::services::audit::AuditLog;
+use crate::services::audit::TurnAuditor;
+use crate::services::drain::Drain;
+use crate::services::drain::TurnGuard;
+use crate::services::drain::interrupted_connection_message;
+use crate::services::metrics::Metrics;
+use codex_core::ConversationManager;
+use codex_protocol::ConversationId;
+use codex_protocol::protocol::ErrorEvent;
+use codex_protocol::protocol::Event;
+use codex_protocol::protocol::EventMsg;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Prefix of the app-server notifications that carry a core event
+const APP_SERVER_EVENT_PREFIX: &str = "codex/event/";
+
+/// Method of the MCP notifications that carry a core event
+pub const MCP_EVENT_METHOD: &str = "codex/event";
+
+/// Audit, metrics and drain state of the turns running on one connection
+pub struct EmbeddedTurns {
+    audit: AuditLog,
+    metrics: Metrics,
+    drain: Drain,
+    /// Conversations of the connection's processor
+    conversations: Arc<ConversationManager>,
+    /// Key that opened the connection
+    owner: Option<String>,
+    /// Conversations already registered with the drain
+    registered: HashSet<ConversationId>,
+    /// Prompts submitted to a conversation whose turn has not started yet
+    prompts: HashMap<ConversationId, String>,
+    /// The running turns
+    turns: HashMap<ConversationId, RunningTurn>,
+}
+
+struct RunningTurn {
+    auditor: TurnAuditor,
+    /// Registration with the drain; `None` if the turn started while draining
+    _guard: Option<TurnGuard>,
+}
+
+impl std::fmt::Debug for EmbeddedTurns {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmbeddedTurns")
+            .field("owner", &self.owner)
+            .field("running", &self.turns.len())
+            .finish()
+    }
+}
+
+impl EmbeddedTurns {
+    pub fn new(
+        audit: AuditLog,
+        metrics: Metrics,
+        drain: Drain,
+        conversations: Arc<ConversationManager>,
+        owner: Option<String>,
+    ) -> Self {
+        Self {
+            audit,
+            metrics,
+            drain,
+            conversations,
+            owner,
+            registered: HashSet::new(),
+            prompts: HashMap::new(),
+            turns: HashMap::new(),
+        }
+    }
+
+    /// Remember the prompt a client submitted to `conversation_id`
+    pub fn submitted(&mut self, conversation_id: ConversationId, prompt: String) {
+        self.prompts.insert(conversation_id, prompt);
+    }
+
+    /// Count, audit and track one event the processor sent to the client
+    ///
+    /// Returns the final error to send after the event when it ends a turn
+    /// that the shutdown interrupted.
+    pub async fn observe(
+        &mut self,
+        conversation_id: ConversationId,
+        event: &Event,
+    ) -> Option<Event> {
+        let conversation = self
+            .conversations
+            .get_conversation(conversation_id)
+            .await
+            .ok();
+        if let Some(conversation) = &conversation
+            && self.registered.insert(conversation_id)
+        {
+            self.drain.register(conversation);
+        }
+
+        let usage = self.metrics.observe_event(conversation_id, &event.msg);
+        if matches!(event.msg, EventMsg::TaskStarted(_)) {
+            let prompt = self.prompts.remove(&conversation_id).unwrap_or_default();
+            let context = AuditContext::new(self.owner.as_deref(), None, conversation_id);
+            let auditor = self.audit.start_turn(context, &prompt).await;
+            let guard = conversation.and_then(|conversation| self.drain.track(conversation).ok());
+            self.turns.insert(
+                conversation_id,
+                RunningTurn {
+                    auditor,
+                    _guard: guard,
+                },
+            );
+            return None;
+        }
+
+        let turn = self.turns.get_mut(&conversation_id)?;
+        turn.auditor.observe(event, usage.as_ref()).await;
+        if !matches!(
+            event.msg,
+            EventMsg::TaskComplete(_) | EventMsg::Error(_) | EventMsg::TurnAborted(_)
+        ) {
+            return None;
+        }
+        self.turns.remove(&conversation_id);
+        (matches!(event.msg, EventMsg::TurnAborted(_)) && self.drain.is_draining()).then(|| Event {
+            id: event.id.clone(),
+            msg: EventMsg::Error(ErrorEvent {
+                message: interrupted_connection_message(conversation_id),
+            }),
+        })
+    }
+}
+
+/// The core event carried by an app-server notification, if any
+pub fn app_server_event(message: &Value) -> Option<(ConversationId, Event)> {
+    let method = message.get("method")?.as_str()?;
+    if !method.starts_with(APP_SERVER_EVENT_PREFIX) {
+        return None;
+    }
+    let params = message.get("params")?;
+    let conversation_id =
+        ConversationId::from_string(params.get("conversationId")?.as_str()?).ok()?;
+    let event = serde_json::from_value(params.clone()).ok()?;
+    Some((conversation_id, event))
+}
+
+/// `event` as the app-server would send it for `conversation_id`
+pub fn app_server_notification(conversation_id: ConversationId, event: &Event) -> Option<Value> {
+    let mut params = serde_json::to_value(event).ok()?;
+    params.as_object_mut()?.insert(
+        "conversationId".to_string(),
+        conversation_id.to_string().into(),
+    );
+    Some(serde_json::json!({
+        "method": format!("{APP_SERVER_EVENT_PREFIX}{}", event.msg),
+        "params": params,
+    }))
+}
+
+/// The core event carried by the params of an MCP `codex/event` notification
+pub fn mcp_event(params: &Value) -> Option<Event> {
+    serde_json::from_value(params.clone()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AuditConfig;
+    use crate::services::audit::AuditFilter;
+    use crate::services::drain::INTERRUPTED_BY_SHUTDOWN;
+    use codex_core::CodexAuth;
+    use codex_protocol::protocol::TaskStartedEvent;
+    use codex_protocol::protocol::TurnAbortReason;
+    use codex_protocol::protocol::TurnAbortedEvent;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn test_turns(log: AuditLog, drain: Drain) -> EmbeddedTurns {
+        EmbeddedTurns::new(
+            log,
+            Metrics::default(),
+            drain,
+            Arc::new(ConversationManager::with_auth(CodexAuth::from_api_key(
+                "test",
+            ))),
+            Some("key-1".into()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_app_server_events_are_audited() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let config = AuditConfig {
+            enabled: true,
+            file: Some(dir.path().join("audit.jsonl")),
+            ..AuditConfig::default()
+        };
+        let log = AuditLog::open(&config, dir.path().join("unused.jsonl"));
+        let mut turns = test_turns(log.clone(), Drain::new());
+
+        let conversation_id = ConversationId::new();
+        turns.submitted(conversation_id, "list files".to_string());
+        let notifications = [
+            json!({"type": "task_started", "model_context_window": null}),
+            json!({
+                "type": "exec_command_begin",
+                "call_id": "call-1",
+                "command": ["ls"],
+                "cwd": "/work",
+                "parsed_cmd": [],
+            }),
+            json!({"type": "task_complete", "last_agent_message": null}),
+        ]
+        .map(|msg| {
+            json!({
+                "method": format!("codex/event/{}", msg["type"].as_str().unwrap_or_default()),
+                "params": {"id": "1", "msg": msg, "conversationId": conversation_id.to_string()},
+            })
+        });
+        for notification in &notifications {
+            let (id, event) = app_server_event(notification).ok_or("not an event")?;
+            assert_eq!(id, conversation_id);
+            assert!(turns.observe(id, &event).await.is_none());
+        }
+        assert!(app_server_event(&json!({"method": "turn/started", "params": {}})).is_none());
+
+        let records = log.query(&AuditFilter::default()).await?;
+        let kinds: Vec<&str> = records.iter().map(|record| record.event.kind()).collect();
+        assert_eq!(
+            kinds,
+            [
+                "turn_started",
+                "exec_command_begin",
+                "token_usage",
+                "turn_ended"
+            ]
+        );
+        assert!(records.iter().all(|record| {
+            record.context.key_id.as_deref() == Some("key-1")
+                && record.context.conversation_id == conversation_id.to_string()
+        }));
+        assert!(turns.turns.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_turn_aborted_by_shutdown_gets_final_error() {
+        let drain = Drain::new();
+        let mut turns = test_turns(AuditLog::disabled(), drain.clone());
+        let conversation_id = ConversationId::new();
+        let event = |msg| Event {
+            id: "1".to_string(),
+            msg,
+        };
+        let aborted = event(EventMsg::TurnAborted(TurnAbortedEvent {
+            reason: TurnAbortReason::Interrupted,
+        }));
+
+        turns
+            .observe(
+                conversation_id,
+                &event(EventMsg::TaskStarted(TaskStartedEvent {
+                    model_context_window: None,
+                })),
+            )
+            .await;
+        assert!(turns.observe(conversation_id, &aborted).await.is_none());
+
+        drain.run(Duration::ZERO).await;
+        turns
+            .observe(
+                conversation_id,
+                &event(EventMsg::TaskStarted(TaskStartedEvent {
+                    model_context_window: None,
+                })),
+            )
+            .await;
+        let final_error = turns
+            .observe(conversation_id, &aborted)
+            .await
+            .map(|event| event.msg);
+        let Some(EventMsg::Error(error)) = final_error else {
+            panic!("expected a final error, got {final_error:?}");
+        };
+        assert!(error.message.starts_with(INTERRUPTED_BY_SHUTDOWN));
+
+        let notification = app_server_notification(conversation_id, &aborted)
+            .and_then(|notification| app_server_event(&notification));
+        assert_eq!(notification.map(|(id, _)| id), Some(conversation_id));
+    }
+}
@@ -0,0 +1,612 @@
+//! Durable job queue for agent runs that are not tied to an HTTP request
+//!
+//! Jobs are submitted with an [`ExecRequest`] and run on a bounded pool of
+//! tokio tasks (a semaphore caps how many turns run at once). Every job is
+//! persisted under the queue directory as two files:
+//!
+//! ```text
+//! <dir>/<job_id>.json    job metadata and final status
+//! <dir>/<job_id>.jsonl   ThreadEvent log, one event per line
+//! ```
+//!
+//! so `GET /jobs/{id}` and `GET /jobs/{id}/events` keep working after a
+//! gateway restart. Jobs that were still queued or running when the gateway
+//! stopped are marked failed on the next start.
+//!
+//! A job belongs to the key that submitted it; other keys get a 404 for it.
+
+use crate::error::GatewayError;
+use crate::error::GatewayResult;
+use crate::handlers::exec::ExecRequest;
+use crate::handlers::exec::start_exec_turn;
+use crate::services::workspace::TurnSettings;
+use crate::state::AppState;
+use chrono::DateTime;
+use chrono::Utc;
+use codex_exec::exec_events::ItemCompletedEvent;
+use codex_exec::exec_events::ThreadEvent;
+use codex_exec::exec_events::ThreadItemDetails;
+use codex_protocol::ConversationId;
+use codex_protocol::protocol::Op;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::RwLock;
+use tokio::sync::Semaphore;
+use tracing::info;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Finished jobs kept before the oldest ones are evicted (with their files)
+const MAX_RETAINED_JOBS: usize = 1000;
+
+/// Lifecycle of a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
+
+/// Job metadata as stored on disk and returned by the status endpoints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: String,
+    /// What submitted the job (`api`, `webhook:push`, ...)
+    pub source: String,
+    pub status: JobStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Number of ThreadEvents recorded so far
+    #[serde(default)]
+    pub event_count: usize,
+    /// Text of the last agent message, i.e. the job's answer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_agent_message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// `key_id` of the submitting key; persisted through [`JobRecord`]
+    #[serde(skip)]
+    owner: Option<String>,
+    /// Set by `DELETE /jobs/{id}` while the job is still running
+    #[serde(skip)]
+    cancel_requested: bool,
+}
+
+impl Job {
+    fn new(source: String, owner: Option<String>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            source,
+            status: JobStatus::Queued,
+            conversation_id: None,
+            created_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+            event_count: 0,
+            last_agent_message: None,
+            error: None,
+            owner,
+            cancel_requested: false,
+        }
+    }
+
+    fn is_owned_by(&self, owner: Option<&str>) -> bool {
+        self.owner.as_deref() == owner
+    }
+}
+
+/// Job metadata file: the API view plus the owner, which is never returned
+#[derive(Debug, Serialize, Deserialize)]
+struct JobRecord {
+    #[serde(flatten)]
+    job: Job,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    owner: Option<String>,
+}
+
+/// Durable job queue with a bounded worker pool
+#[derive(Debug, Clone)]
+pub struct JobQueue {
+    jobs: Arc<RwLock<HashMap<String, Job>>>,
+    workers: Arc<Semaphore>,
+    dir: PathBuf,
+}
+
+impl JobQueue {
+    /// Open the queue stored in `dir`, recovering jobs from a previous run
+    pub async fn open(dir: PathBuf, max_concurrent_jobs: usize) -> io::Result<Self> {
+        tokio::fs::create_dir_all(&dir).await?;
+
+        let mut jobs = HashMap::new();
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let mut job = match tokio::fs::read(&path)
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| {
+                    serde_json::from_slice::<JobRecord>(&bytes).map_err(|e| e.to_string())
+                }) {
+                Ok(record) => Job {
+                    owner: record.owner,
+                    ..record.job
+                },
+                Err(err) => {
+                    warn!("Skipping unreadable job file {}: {}", path.display(), err);
+                    continue;
+                }
+            };
+
+            if !job.status.is_finished() {
+                job.event_count = read_events(&events_path(&dir, &job.id)).await?.len();
+                job.status = JobStatus::Failed;
+                job.error = Some("gateway restarted before the job finished".to_string());
+                job.finished_at = Some(Utc::now());
+                write_job(&dir, &job).await?;
+            }
+            jobs.insert(job.id.clone(), job);
+        }
+
+        info!(
+            "Job queue opened: dir={}, jobs={}",
+            dir.display(),
+            jobs.len()
+        );
+
+        Ok(Self {
+            jobs: Arc::new(RwLock::new(jobs)),
+            workers: Arc::new(Semaphore::new(max_concurrent_jobs.max(1))),
+            dir,
+        })
+    }
+
+    /// Queue `request` and return the new job id immediately
+    ///
+    /// `settings` must already be checked against the submitter's policy;
+    /// `owner` is the submitting key.
+    pub async fn submit(
+        &self,
+        state: AppState,
+        source: String,
+        owner: Option<String>,
+        request: ExecRequest,
+        settings: TurnSettings,
+    ) -> io::Result<String> {
+        let job = Job::new(source, owner.clone());
+        let job_id = job.id.clone();
+        write_job(&self.dir, &job).await?;
+        tokio::fs::File::create(events_path(&self.dir, &job_id)).await?;
+
+        let evicted = {
+            let mut jobs = self.jobs.write().await;
+            let evicted = evict_finished(&mut jobs);
+            jobs.insert(job_id.clone(), job);
+            evicted
+        };
+        for id in evicted {
+            let _ = tokio::fs::remove_file(job_path(&self.dir, &id)).await;
+            let _ = tokio::fs::remove_file(events_path(&self.dir, &id)).await;
+        }
+
+        let queue = self.clone();
+        let id = job_id.clone();
+        tokio::spawn(async move {
+            if let Err(err) = queue.run(&state, &id, owner, request, settings).await {
+                warn!("Job failed: job_id={}, error={}", id, err);
+                queue.finish(&id, Some(err.to_string())).await;
+            }
+        });
+
+        Ok(job_id)
+    }
+
+    /// Current metadata of a job, if `owner` submitted it
+    pub async fn get(&self, job_id: &str, owner: Option<&str>) -> Option<Job> {
+        self.jobs
+            .read()
+            .await
+            .get(job_id)
+            .filter(|job| job.is_owned_by(owner))
+            .cloned()
+    }
+
+    /// Events recorded for a job of `owner`, skipping the first `after`
+    pub async fn events(
+        &self,
+        job_id: &str,
+        owner: Option<&str>,
+        after: usize,
+    ) -> GatewayResult<Vec<ThreadEvent>> {
+        if self.get(job_id, owner).await.is_none() {
+            return Err(GatewayError::NotFound(format!("Job {job_id}")));
+        }
+        let events = read_events(&events_path(&self.dir, job_id))
+            .await
+            .map_err(|e| GatewayError::Internal(format!("Failed to read job events: {e}")))?;
+        Ok(events.into_iter().skip(after).collect())
+    }
+
+    /// Cancel a job of `owner`: queued jobs never start, running ones get
+    /// `Op::Interrupt`
+    pub async fn cancel(
+        &self,
+        state: &AppState,
+        job_id: &str,
+        owner: Option<&str>,
+    ) -> GatewayResult<Job> {
+        let (job, conversation_id) = {
+            let mut jobs = self.jobs.write().await;
+            let Some(job) = jobs.get_mut(job_id).filter(|job| job.is_owned_by(owner)) else {
+                return Err(GatewayError::NotFound(format!("Job {job_id}")));
+            };
+            if job.status.is_finished() {
+                return Err(GatewayError::InvalidRequest(format!(
+                    "Job {job_id} already {:?}",
+                    job.status
+                )));
+            }
+            job.cancel_requested = true;
+            if job.status == JobStatus::Queued {
+                job.status = JobStatus::Cancelled;
+                job.finished_at = Some(Utc::now());
+            }
+            (job.clone(), job.conversation_id.clone())
+        };
+
+        if job.status == JobStatus::Cancelled {
+            self.persist(&job).await;
+        } else if let Some(conversation_id) = conversation_id {
+            interrupt(state, &conversation_id).await?;
+        }
+
+        info!("Job cancellation requested: job_id={}", job_id);
+        Ok(job)
+    }
+
+    async fn run(
+        &self,
+        state: &AppState,
+        job_id: &str,
+        owner: Option<String>,
+        request: ExecRequest,
+        settings: TurnSettings,
+    ) -> io::Result<()> {
+        let Ok(_worker) = Arc::clone(&self.workers).acquire_owned().await else {
+            return Err(io::Error::other("job queue closed"));
+        };
+
+        let Some(job) = self
+            .update(job_id, |job| {
+                if job.status == JobStatus::Queued {
+                    job.status = JobStatus::Running;
+                    job.started_at = Some(Utc::now());
+                }
+            })
+            .await
+        else {
+            return Ok(());
+        };
+        if job.status != JobStatus::Running {
+            // Cancelled while waiting for a worker
+            return Ok(());
+        }
+        info!("Job started: job_id={}", job_id);
+
+        let (conversation_id, mut rx) = start_exec_turn(state, request, owner.as_deref(), settings)
+            .await
+            .map_err(|err| io::Error::other(err.to_string()))?;
+        let conversation_id = conversation_id.to_string();
+        let job = self
+            .update(job_id, |job| {
+                job.conversation_id = Some(conversation_id.clone());
+            })
+            .await;
+        // Cancelled between starting the turn and recording its conversation
+        if job.is_some_and(|job| job.cancel_requested)
+            && let Err(err) = interrupt(state, &conversation_id).await
+        {
+            warn!("Failed to interrupt job {}: {}", job_id, err);
+        }
+
+        let mut log = tokio::fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(events_path(&self.dir, job_id))
+            .await?;
+
+        let mut outcome = TurnOutcome::default();
+        while let Some(event) = rx.recv().await {
+            let mut line = serde_json::to_vec(&event)?;
+            line.push(b'\n');
+            log.write_all(&line).await?;
+            log.flush().await?;
+
+            outcome.observe(&event);
+            let last_agent_message = outcome.last_agent_message.clone();
+            self.update(job_id, |job| {
+                job.event_count += 1;
+                job.last_agent_message = last_agent_message;
+            })
+            .await;
+        }
+
+        self.finish(job_id, outcome.error()).await;
+        Ok(())
+    }
+
+    async fn finish(&self, job_id: &str, error: Option<String>) {
+        let job = self
+            .update(job_id, |job| {
+                job.status = if job.cancel_requested {
+                    JobStatus::Cancelled
+                } else if error.is_some() {
+                    JobStatus::Failed
+                } else {
+                    JobStatus::Completed
+                };
+                job.error = error;
+                job.finished_at = Some(Utc::now());
+            })
+            .await;
+        if let Some(job) = job {
+            info!("Job finished: job_id={}, status={:?}", job.id, job.status);
+            self.persist(&job).await;
+        }
+    }
+
+    /// Apply `f` to a job and return the updated snapshot
+    async fn update(&self, job_id: &str, f: impl FnOnce(&mut Job)) -> Option<Job> {
+        let mut jobs = self.jobs.write().await;
+        let job = jobs.get_mut(job_id)?;
+        f(job);
+        Some(job.clone())
+    }
+
+    async fn persist(&self, job: &Job) {
+        if let Err(err) = write_job(&self.dir, job).await {
+            warn!("Failed to persist job {}: {}", job.id, err);
+        }
+    }
+}
+
+/// Final status of a turn, derived from its ThreadEvents
+#[derive(Debug, Default)]
+struct TurnOutcome {
+    completed: bool,
+    error: Option<String>,
+    last_agent_message: Option<String>,
+}
+
+impl TurnOutcome {
+    fn observe(&mut self, event: &ThreadEvent) {
+        match event {
+            ThreadEvent::TurnCompleted(_) => self.completed = true,
+            ThreadEvent::TurnFailed(failed) => self.error = Some(failed.error.message.clone()),
+            ThreadEvent::Error(err) => self.error = Some(err.message.clone()),
+            ThreadEvent::ItemCompleted(ItemCompletedEvent { item }) => {
+                if let ThreadItemDetails::AgentMessage(message) = &item.details {
+                    self.last_agent_message = Some(message.text.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn error(&self) -> Option<String> {
+        match (&self.error, self.completed) {
+            (Some(error), _) => Some(error.clone()),
+            (None, true) => None,
+            (None, false) => Some("turn ended without a completion event".to_string()),
+        }
+    }
+}
+
+async fn interrupt(state: &AppState, conversation_id: &str) -> GatewayResult<()> {
+    let conversation_id = ConversationId::from_string(conversation_id)
+        .map_err(|e| GatewayError::Internal(format!("Invalid conversation id: {e}")))?;
+    let conversation = state
+        .codex_service
+        .conversation_manager()
+        .lock()
+        .await
+        .get_conversation(conversation_id)
+        .await
+        .map_err(|e| GatewayError::Internal(format!("Failed to get conversation: {e}")))?;
+    conversation
+        .submit(Op::Interrupt)
+        .await
+        .map_err(|e| GatewayError::Internal(format!("Failed to interrupt turn: {e}")))?;
+    Ok(())
+}
+
+fn job_path(dir: &Path, job_id: &str) -> PathBuf {
+    dir.join(format!("{job_id}.json"))
+}
+
+fn events_path(dir: &Path, job_id: &str) -> PathBuf {
+    dir.join(format!("{job_id}.jsonl"))
+}
+
+/// Atomically replace the metadata file of `job`
+async fn write_job(dir: &Path, job: &Job) -> io::Result<()> {
+    let path = job_path(dir, &job.id);
+    let tmp_path = path.with_extension("json.tmp");
+    let record = JobRecord {
+        job: job.clone(),
+        owner: job.owner.clone(),
+    };
+    tokio::fs::write(&tmp_path, serde_json::to_vec_pretty(&record)?).await?;
+    tokio::fs::rename(&tmp_path, &path).await
+}
+
+/// Read a JSONL event log, ignoring a trailing partial line
+async fn read_events(path: &Path) -> io::Result<Vec<ThreadEvent>> {
+    let contents = match tokio::fs::read_to_string(path).await {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Drop the oldest finished jobs once the retention cap is reached
+fn evict_finished(jobs: &mut HashMap<String, Job>) -> Vec<String> {
+    if jobs.len() < MAX_RETAINED_JOBS {
+        return Vec::new();
+    }
+    let mut finished: Vec<(DateTime<Utc>, String)> = jobs
+        .values()
+        .filter(|job| job.status.is_finished())
+        .map(|job| (job.created_at, job.id.clone()))
+        .collect();
+    finished.sort();
+    let excess = jobs.len() + 1 - MAX_RETAINED_JOBS;
+    finished
+        .into_iter()
+        .take(excess)
+        .map(|(_, id)| {
+            jobs.remove(&id);
+            id
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_exec::exec_events::ThreadErrorEvent;
+    use codex_exec::exec_events::TurnStartedEvent;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_evict_finished_keeps_running_jobs() {
+        let mut jobs = HashMap::new();
+        for i in 0..MAX_RETAINED_JOBS {
+            let mut job = Job::new("test".to_string(), None);
+            job.status = if i == 0 {
+                JobStatus::Running
+            } else {
+                JobStatus::Completed
+            };
+            jobs.insert(job.id.clone(), job);
+        }
+
+        let evicted = evict_finished(&mut jobs);
+
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(jobs.len(), MAX_RETAINED_JOBS - 1);
+        assert!(jobs.values().any(|job| job.status == JobStatus::Running));
+    }
+
+    #[test]
+    fn test_turn_outcome() {
+        let mut outcome = TurnOutcome::default();
+        outcome.observe(&ThreadEvent::TurnStarted(TurnStartedEvent::default()));
+        assert!(outcome.error().is_some());
+
+        outcome.observe(&ThreadEvent::Error(ThreadErrorEvent {
+            message: "boom".to_string(),
+        }));
+        assert_eq!(outcome.error().as_deref(), Some("boom"));
+    }
+
+    #[tokio::test]
+    async fn test_open_recovers_interrupted_jobs() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = TempDir::new()?;
+
+        let mut running = Job::new("api".to_string(), None);
+        running.status = JobStatus::Running;
+        write_job(dir.path(), &running).await?;
+        let event = serde_json::to_string(&ThreadEvent::TurnStarted(TurnStartedEvent::default()))?;
+        tokio::fs::write(
+            events_path(dir.path(), &running.id),
+            format!("{event}\n{{\"type\":"),
+        )
+        .await?;
+
+        let mut done = Job::new("api".to_string(), None);
+        done.status = JobStatus::Completed;
+        write_job(dir.path(), &done).await?;
+
+        let queue = JobQueue::open(dir.path().to_path_buf(), 1).await?;
+
+        let recovered = queue.get(&running.id, None).await.ok_or("missing job")?;
+        assert_eq!(recovered.status, JobStatus::Failed);
+        assert_eq!(recovered.event_count, 1);
+        assert!(recovered.error.is_some());
+        assert_eq!(
+            queue.get(&done.id, None).await.map(|job| job.status),
+            Some(JobStatus::Completed)
+        );
+
+        assert_eq!(queue.events(&running.id, None, 0).await?.len(), 1);
+        assert!(queue.events(&running.id, None, 1).await?.is_empty());
+        assert!(matches!(
+            queue.events("missing", None, 0).await,
+            Err(GatewayError::NotFound(_))
+        ));
+
+        // Recovery is persisted
+        let reopened = JobQueue::open(dir.path().to_path_buf(), 1).await?;
+        assert_eq!(
+            reopened.get(&running.id, None).await.map(|job| job.status),
+            Some(JobStatus::Failed)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_jobs_are_scoped_to_their_owner() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = TempDir::new()?;
+        let mut job = Job::new("api".to_string(), Some("key-a".to_string()));
+        job.status = JobStatus::Completed;
+        job.last_agent_message = Some("secret".to_string());
+        write_job(dir.path(), &job).await?;
+
+        // The owner survives a restart but is not part of the API view
+        let queue = JobQueue::open(dir.path().to_path_buf(), 1).await?;
+        let owned = queue
+            .get(&job.id, Some("key-a"))
+            .await
+            .ok_or("missing job")?;
+        assert!(serde_json::to_value(&owned)?.get("owner").is_none());
+
+        assert!(queue.get(&job.id, Some("key-b")).await.is_none());
+        assert!(queue.get(&job.id, None).await.is_none());
+        assert!(matches!(
+            queue.events(&job.id, Some("key-b"), 0).await,
+            Err(GatewayError::NotFound(_))
+        ));
+        assert_eq!(queue.events(&job.id, Some("key-a"), 0).await?.len(), 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,474 @@
+//! Sessions of the MCP Streamable HTTP transport
+//!
+//! Every MCP session owns one [`codex_mcp_server::serve_connection`] task.
+//! Streamable HTTP has no single long-lived pipe back to the client, so each
+//! session runs a router that hands server messages to whichever HTTP
+//! response can carry them:
+//!
+//! - responses go to the POST that sent the request;
+//! - `codex/event` notifications (`_meta.requestId`) and approval
+//!   elicitations (`codex_mcp_tool_call_id`) follow the `tools/call` they
+//!   belong to while its POST stream is open;
+//! - everything else, and anything whose POST went away, goes to the
+//!   session's standalone `GET` stream, or is dropped if there is none.
+//!
+//! Sessions end on `DELETE /mcp` or after [`SESSION_IDLE_TIMEOUT`] without
+//! requests or running tool calls.
+//!
+//! The router also maps every `tools/call` to the conversation it runs on,
+//! so the session's events reach [`EmbeddedTurns`] for metrics, audit and
+//! the shutdown drain. A call whose turn the shutdown interrupted gets one
+//! more `codex/event` carrying an "interrupted by shutdown" error.
+
+use crate::services::embedded_turns::EmbeddedTurns;
+use crate::services::embedded_turns::MCP_EVENT_METHOD;
+use crate::services::embedded_turns::mcp_event;
+use codex_core::ConversationManager;
+use codex_mcp_server::CodexToolCallParam;
+use codex_mcp_server::CodexToolCallReplyParam;
+use codex_protocol::ConversationId;
+use codex_protocol::protocol::EventMsg;
+use mcp_types::JSONRPC_VERSION;
+use mcp_types::JSONRPCMessage;
+use mcp_types::JSONRPCNotification;
+use mcp_types::JSONRPCRequest;
+use mcp_types::RequestId;
+use serde_json::Value;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+use tokio::sync::Mutex;
+use tokio::sync::mpsc;
+use tracing::debug;
+use tracing::info;
+use uuid::Uuid;
+
+/// Sessions unused for this long are closed when the next session starts
+pub const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// Buffered client messages per session before POSTs wait
+const INCOMING_CAPACITY: usize = 128;
+
+/// Server messages for one HTTP response
+pub type MessageStream = mpsc::UnboundedReceiver<JSONRPCMessage>;
+
+/// Registry of open MCP sessions
+#[derive(Debug, Clone, Default)]
+pub struct McpSessions {
+    sessions: Arc<Mutex<HashMap<String, Arc<McpSession>>>>,
+}
+
+/// One MCP client session
+#[derive(Debug)]
+pub struct McpSession {
+    id: String,
+    /// Key that opened the session; other keys cannot use it
+    owner: Option<String>,
+    incoming_tx: mpsc::Sender<JSONRPCMessage>,
+    routes: Arc<Mutex<Routes>>,
+    turns: Arc<Mutex<McpTurns>>,
+    last_used: std::sync::Mutex<Instant>,
+}
+
+/// Turns of one session, with the conversation each `tools/call` runs on
+#[derive(Debug)]
+struct McpTurns {
+    turns: EmbeddedTurns,
+    /// Prompts of `codex` calls whose conversation is not configured yet
+    prompts: HashMap<RequestId, String>,
+    /// Conversation of each running tool call
+    conversations: HashMap<RequestId, ConversationId>,
+}
+
+impl McpTurns {
+    fn new(turns: EmbeddedTurns) -> Self {
+        Self {
+            turns,
+            prompts: HashMap::new(),
+            conversations: HashMap::new(),
+        }
+    }
+
+    /// Note the prompt of a `codex` or `codex-reply` tool call
+    fn request(&mut self, request: &JSONRPCRequest) {
+        if request.method != "tools/call" {
+            return;
+        }
+        let Some(params) = &request.params else {
+            return;
+        };
+        let arguments = params.get("arguments").cloned().unwrap_or(Value::Null);
+        match params.get("name").and_then(Value::as_str) {
+            Some("codex") => {
+                if let Ok(param) = serde_json::from_value::<CodexToolCallParam>(arguments) {
+                    self.prompts.insert(request.id.clone(), param.prompt);
+                }
+            }
+            Some("codex-reply") => {
+                if let Ok(param) = serde_json::from_value::<CodexToolCallReplyParam>(arguments)
+                    && let Ok(conversation_id) = ConversationId::from_string(&param.conversation_id)
+                {
+                    self.conversations
+                        .insert(request.id.clone(), conversation_id);
+                    self.turns.submitted(conversation_id, param.prompt);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Pass an event of tool call `request_id` on to the turn bookkeeping
+    ///
+    /// Returns the notification to send after it when the event ends a turn
+    /// that the shutdown interrupted.
+    async fn event(&mut self, request_id: &RequestId, params: &Value) -> Option<JSONRPCMessage> {
+        let event = mcp_event(params)?;
+        if let EventMsg::SessionConfigured(configured) = &event.msg {
+            self.conversations
+                .insert(request_id.clone(), configured.session_id);
+            if let Some(prompt) = self.prompts.remove(request_id) {
+                self.turns.submitted(configured.session_id, prompt);
+            }
+        }
+        let conversation_id = self.conversations.get(request_id).copied()?;
+        let final_error = self.turns.observe(conversation_id, &event).await?;
+        let mut params = serde_json::to_value(final_error).ok()?;
+        params
+            .as_object_mut()?
+            .insert("_meta".to_string(), json!({ "requestId": request_id }));
+        Some(JSONRPCMessage::Notification(JSONRPCNotification {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            method: MCP_EVENT_METHOD.to_string(),
+            params: Some(params),
+        }))
+    }
+
+    /// Forget a tool call once it has been answered
+    fn finished(&mut self, request_id: &RequestId) {
+        self.prompts.remove(request_id);
+        self.conversations.remove(request_id);
+    }
+}
+
+#[derive(Debug, Default)]
+struct Routes {
+    /// Open POST streams by the client request id they wait on
+    pending: HashMap<RequestId, mpsc::UnboundedSender<JSONRPCMessage>>,
+    /// Standalone `GET` stream
+    standalone: Option<mpsc::UnboundedSender<JSONRPCMessage>>,
+}
+
+impl McpSessions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a session whose server keeps its conversations in
+    /// `conversations`, the manager `turns` tracks them with
+    pub async fn create(
+        &self,
+        owner: Option<String>,
+        codex_linux_sandbox_exe: Option<PathBuf>,
+        conversations: Arc<ConversationManager>,
+        turns: EmbeddedTurns,
+    ) -> Arc<McpSession> {
+        self.close_idle().await;
+
+        let (incoming_tx, incoming_rx) = mpsc::channel(INCOMING_CAPACITY);
+        let (outgoing_tx, outgoing_rx) = mpsc::unbounded_channel();
+        tokio::spawn(codex_mcp_server::serve_connection_with_manager(
+            incoming_rx,
+            outgoing_tx,
+            codex_linux_sandbox_exe,
+            conversations,
+        ));
+
+        let routes = Arc::new(Mutex::new(Routes::default()));
+        let turns = Arc::new(Mutex::new(McpTurns::new(turns)));
+        tokio::spawn(route_messages(
+            outgoing_rx,
+            Arc::clone(&routes),
+            Arc::clone(&turns),
+        ));
+
+        let session = Arc::new(McpSession {
+            id: Uuid::new_v4().simple().to_string(),
+            owner,
+            incoming_tx,
+            routes,
+            turns,
+            last_used: std::sync::Mutex::new(Instant::now()),
+        });
+        self.sessions
+            .lock()
+            .await
+            .insert(session.id.clone(), Arc::clone(&session));
+        info!("MCP session {} started", session.id);
+        session
+    }
+
+    /// Session `id`, if it exists and belongs to `owner`
+    pub async fn get(&self, id: &str, owner: Option<&str>) -> Option<Arc<McpSession>> {
+        let session = self.sessions.lock().await.get(id).cloned()?;
+        if session.owner.as_deref() != owner {
+            return None;
+        }
+        session.touch();
+        Some(session)
+    }
+
+    /// Close session `id`; returns whether it existed for `owner`
+    pub async fn remove(&self, id: &str, owner: Option<&str>) -> bool {
+        let mut sessions = self.sessions.lock().await;
+        let owned = sessions
+            .get(id)
+            .is_some_and(|session| session.owner.as_deref() == owner);
+        if owned {
+            sessions.remove(id);
+            info!("MCP session {id} closed");
+        }
+        owned
+    }
+
+    async fn close_idle(&self) {
+        self.sessions.lock().await.retain(|id, session| {
+            // A tool call still streaming keeps its session alive
+            let busy = !session
+                .routes
+                .try_lock()
+                .is_ok_and(|routes| routes.pending.is_empty());
+            let keep = busy || session.idle_for() < SESSION_IDLE_TIMEOUT;
+            if !keep {
+                info!("MCP session {id} expired");
+            }
+            keep
+        });
+    }
+}
+
+impl McpSession {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Forward client messages and return the stream that carries the
+    /// responses to `requests`, plus whatever belongs to them
+    ///
+    /// `early` messages (e.g. refusals produced by the gateway) are delivered
+    /// first. The stream ends once every request has been answered.
+    pub async fn send(
+        &self,
+        messages: Vec<JSONRPCMessage>,
+        requests: Vec<RequestId>,
+        early: Vec<JSONRPCMessage>,
+    ) -> Option<MessageStream> {
+        self.touch();
+        let stream = if requests.is_empty() && early.is_empty() {
+            None
+        } else {
+            let (tx, rx) = mpsc::unbounded_channel();
+            for message in early {
+                let _ = tx.send(message);
+            }
+            let mut routes = self.routes.lock().await;
+            for id in requests {
+                routes.pending.insert(id, tx.clone());
+            }
+            Some(rx)
+        };
+
+        for message in messages {
+            if let JSONRPCMessage::Request(request) = &message {
+                self.turns.lock().await.request(request);
+            }
+            if self.incoming_tx.send(message).await.is_err() {
+                debug!("MCP session {} is gone", self.id);
+                break;
+            }
+        }
+        stream
+    }
+
+    /// Attach the standalone stream, replacing any previous one
+    pub async fn listen(&self) -> MessageStream {
+        self.touch();
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.routes.lock().await.standalone = Some(tx);
+        rx
+    }
+
+    fn touch(&self) {
+        if let Ok(mut last_used) = self.last_used.lock() {
+            *last_used = Instant::now();
+        }
+    }
+
+    fn idle_for(&self) -> Duration {
+        self.last_used
+            .lock()
+            .map(|last_used| last_used.elapsed())
+            .unwrap_or_default()
+    }
+}
+
+/// Deliver server messages to the HTTP responses waiting for them
+async fn route_messages(
+    mut outgoing_rx: mpsc::UnboundedReceiver<JSONRPCMessage>,
+    routes: Arc<Mutex<Routes>>,
+    turns: Arc<Mutex<McpTurns>>,
+) {
+    while let Some(message) = outgoing_rx.recv().await {
+        let request_id = match &message {
+            JSONRPCMessage::Notification(notification) => notification
+                .params
+                .as_ref()
+                .and_then(|params| params.get("_meta")?.get("requestId").cloned())
+                .and_then(|id| serde_json::from_value::<RequestId>(id).ok()),
+            _ => None,
+        };
+        let final_error = match &message {
+            JSONRPCMessage::Notification(notification)
+                if notification.method == MCP_EVENT_METHOD =>
+            {
+                match (&request_id, &notification.params) {
+                    (Some(request_id), Some(params)) => {
+                        turns.lock().await.event(request_id, params).await
+                    }
+                    _ => None,
+                }
+            }
+            JSONRPCMessage::Response(response) => {
+                turns.lock().await.finished(&response.id);
+                None
+            }
+            JSONRPCMessage::Error(error) => {
+                turns.lock().await.finished(&error.id);
+                None
+            }
+            _ => None,
+        };
+
+        let mut routes = routes.lock().await;
+        deliver(&mut routes, message, request_id.as_ref());
+        if let Some(final_error) = final_error {
+            deliver(&mut routes, final_error, request_id.as_ref());
+        }
+    }
+}
+
+/// Hand one server message to the HTTP response that should carry it
+///
+/// `request_id` is the `tools/call` a notification belongs to.
+fn deliver(routes: &mut Routes, message: JSONRPCMessage, request_id: Option<&RequestId>) {
+    let target = match &message {
+        // The response ends that request's part of the POST stream
+        JSONRPCMessage::Response(response) => routes.pending.remove(&response.id),
+        JSONRPCMessage::Error(error) => routes.pending.remove(&error.id),
+        JSONRPCMessage::Notification(_) => {
+            request_id.and_then(|id| routes.pending.get(id).cloned())
+        }
+        JSONRPCMessage::Request(request) => request
+            .params
+            .as_ref()
+            .and_then(|params| params.get("codex_mcp_tool_call_id")?.as_str())
+            .and_then(|call_id| {
+                routes
+                    .pending
+                    .iter()
+                    .find(|(id, _)| request_id_string(id) == call_id)
+                    .map(|(_, tx)| tx.clone())
+            }),
+    };
+
+    // Fall back to the standalone stream when the POST went away
+    let message = match target {
+        Some(tx) => match tx.send(message) {
+            Ok(()) => return,
+            Err(mpsc::error::SendError(message)) => message,
+        },
+        None => message,
+    };
+    let delivered = routes
+        .standalone
+        .as_ref()
+        .is_some_and(|tx| tx.send(message).is_ok());
+    if !delivered {
+        routes.standalone = None;
+        debug!("Dropping MCP message with no open stream");
+    }
+}
+
+fn request_id_string(id: &RequestId) -> String {
+    match id {
+        RequestId::String(s) => s.clone(),
+        RequestId::Integer(n) => n.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::AuditLog;
+    use crate::services::Metrics;
+    use crate::services::drain::Drain;
+    use codex_core::CodexAuth;
+    use mcp_types::JSONRPCResponse;
+
+    #[tokio::test]
+    async fn test_messages_follow_their_request() {
+        let routes = Arc::new(Mutex::new(Routes::default()));
+        let (post_tx, mut post_rx) = mpsc::unbounded_channel();
+        let (get_tx, mut get_rx) = mpsc::unbounded_channel();
+        {
+            let mut routes = routes.lock().await;
+            routes.pending.insert(RequestId::Integer(7), post_tx);
+            routes.standalone = Some(get_tx);
+        }
+
+        let (outgoing_tx, outgoing_rx) = mpsc::unbounded_channel();
+        let turns = McpTurns::new(EmbeddedTurns::new(
+            AuditLog::disabled(),
+            Metrics::default(),
+            Drain::new(),
+            Arc::new(ConversationManager::with_auth(CodexAuth::from_api_key(
+                "test",
+            ))),
+            None,
+        ));
+        let router = tokio::spawn(route_messages(
+            outgoing_rx,
+            Arc::clone(&routes),
+            Arc::new(Mutex::new(turns)),
+        ));
+
+        let notification = |request_id: i64| {
+            JSONRPCMessage::Notification(JSONRPCNotification {
+                jsonrpc: "2.0".to_string(),
+                method: "codex/event".to_string(),
+                params: Some(json!({"_meta": {"requestId": request_id}, "msg": {}})),
+            })
+        };
+        outgoing_tx.send(notification(7)).unwrap();
+        outgoing_tx.send(notification(8)).unwrap();
+        outgoing_tx
+            .send(JSONRPCMessage::Response(JSONRPCResponse {
+                jsonrpc: "2.0".to_string(),
+                id: RequestId::Integer(7),
+                result: json!({}),
+            }))
+            .unwrap();
+        drop(outgoing_tx);
+        router.await.unwrap();
+
+        assert_eq!(post_rx.recv().await, Some(notification(7)));
+        assert!(matches!(
+            post_rx.recv().await,
+            Some(JSONRPCMessage::Response(_))
+        ));
+        assert_eq!(get_rx.recv().await, Some(notification(8)));
+        assert!(routes.lock().await.pending.is_empty());
+    }
+}
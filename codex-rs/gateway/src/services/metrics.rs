@@ -0,0 +1,483 @@
+//! In-process metrics exported in the Prometheus text format
+//!
+//! [`Metrics`] is a cheap-to-clone handle shared by the router (HTTP request
+//! counts and latencies), the auth middleware (rejected credentials), the
+//! WebSocket handler (open connections) and every event loop that drives a
+//! Codex turn (turn outcomes and token usage). `GET /metrics` renders a
+//! snapshot with [`Metrics::render`].
+
+use codex_protocol::ConversationId;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::TokenUsage;
+use codex_protocol::protocol::TokenUsageInfo;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::sync::atomic::AtomicI64;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+/// Conversations whose token totals are remembered before the map is reset
+const MAX_TRACKED_CONVERSATIONS: usize = 10_000;
+
+/// Upper bounds (seconds) of the request latency histogram buckets
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0,
+];
+
+/// Values sampled from other components at scrape time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MetricsSnapshot {
+    /// Sessions tracked by `CodexService`
+    pub active_conversations: usize,
+    /// `WebSocketConfig::max_connections`
+    pub max_websocket_connections: usize,
+}
+
+/// Shared metrics registry
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    inner: Arc<MetricsInner>,
+}
+
+#[derive(Debug, Default)]
+struct MetricsInner {
+    http: Mutex<BTreeMap<HttpSeriesKey, HttpSeries>>,
+    websocket_connections: AtomicI64,
+    turns_started: AtomicU64,
+    turns_finished: Mutex<BTreeMap<&'static str, u64>>,
+    tokens: Mutex<BTreeMap<&'static str, u64>>,
+    /// Last `total_token_usage` seen per conversation
+    token_totals: Mutex<HashMap<ConversationId, TokenUsage>>,
+    auth_rejections: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct HttpSeriesKey {
+    method: String,
+    route: String,
+    status: u16,
+}
+
+#[derive(Debug)]
+struct HttpSeries {
+    /// Cumulative count per entry of [`LATENCY_BUCKETS`]
+    buckets: Vec<u64>,
+    count: u64,
+    sum_seconds: f64,
+}
+
+impl Default for HttpSeries {
+    fn default() -> Self {
+        Self {
+            buckets: vec![0; LATENCY_BUCKETS.len()],
+            count: 0,
+            sum_seconds: 0.0,
+        }
+    }
+}
+
+/// Decrements the open WebSocket gauge when dropped
+#[derive(Debug)]
+pub struct WebSocketGuard {
+    metrics: Metrics,
+}
+
+impl Drop for WebSocketGuard {
+    fn drop(&mut self) {
+        self.metrics
+            .inner
+            .websocket_connections
+            .fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one HTTP request; `route` is the matched route template
+    pub fn record_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let key = HttpSeriesKey {
+            method: method.to_string(),
+            route: route.to_string(),
+            status,
+        };
+        let mut http = lock(&self.inner.http);
+        let series = http.entry(key).or_default();
+        series.count += 1;
+        series.sum_seconds += seconds;
+        for (bucket, bound) in series.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= *bound {
+                *bucket += 1;
+            }
+        }
+    }
+
+    /// Count a WebSocket connection until the returned guard is dropped
+    pub fn track_websocket(&self) -> WebSocketGuard {
+        self.inner
+            .websocket_connections
+            .fetch_add(1, Ordering::Relaxed);
+        WebSocketGuard {
+            metrics: self.clone(),
+        }
+    }
+
+    /// Like [`Metrics::track_websocket`], unless `max` connections are open
+    pub fn try_track_websocket(&self, max: usize) -> Option<WebSocketGuard> {
+        let max = i64::try_from(max).unwrap_or(i64::MAX);
+        self.inner
+            .websocket_connections
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |open| {
+                (open < max).then_some(open + 1)
+            })
+            .ok()?;
+        Some(WebSocketGuard {
+            metrics: self.clone(),
+        })
+    }
+
+    /// Count a rejected authentication attempt
+    pub fn record_auth_rejection(&self, reason: &'static str) {
+        *lock(&self.inner.auth_rejections).entry(reason).or_default() += 1;
+    }
+
+    /// Update turn and token counters from an event of `conversation_id`
+    ///
+    /// Returns the tokens the event added, so callers reporting per-turn
+    /// usage count exactly what the counters did. Core re-emits `TokenCount`
+    /// with an unchanged `last_token_usage` on every rate-limit update, so
+    /// each event contributes how far `total_token_usage` moved since the
+    /// previous event of the conversation instead.
+    pub fn observe_event(
+        &self,
+        conversation_id: ConversationId,
+        msg: &EventMsg,
+    ) -> Option<TokenUsage> {
+        let mut added = None;
+        let finished = match msg {
+            EventMsg::TaskStarted(_) => {
+                self.inner.turns_started.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            EventMsg::TaskComplete(_) => Some("completed"),
+            EventMsg::Error(_) => Some("failed"),
+            EventMsg::TurnAborted(_) => Some("aborted"),
+            EventMsg::TokenCount(event) => {
+                if let Some(info) = &event.info {
+                    added = self.token_delta(conversation_id, info);
+                }
+                if let Some(usage) = &added {
+                    let mut tokens = lock(&self.inner.tokens);
+                    for (kind, value) in [
+                        ("input", usage.input_tokens),
+                        ("cached_input", usage.cached_input_tokens),
+                        ("output", usage.output_tokens),
+                        ("reasoning_output", usage.reasoning_output_tokens),
+                    ] {
+                        *tokens.entry(kind).or_default() += u64::try_from(value).unwrap_or(0);
+                    }
+                }
+                None
+            }
+            _ => None,
+        };
+        if let Some(status) = finished {
+            *lock(&self.inner.turns_finished).entry(status).or_default() += 1;
+        }
+        added
+    }
+
+    /// Usage added since the previous total seen for `conversation_id`
+    ///
+    /// The first count seen for a conversation (e.g. one resumed after a
+    /// restart) falls back to the usage of its latest response.
+    fn token_delta(
+        &self,
+        conversation_id: ConversationId,
+        info: &TokenUsageInfo,
+    ) -> Option<TokenUsage> {
+        let total = &info.total_token_usage;
+        let mut totals = lock(&self.inner.token_totals);
+        let added = match totals.get(&conversation_id) {
+            Some(previous) => TokenUsage {
+                input_tokens: total.input_tokens - previous.input_tokens,
+                cached_input_tokens: total.cached_input_tokens - previous.cached_input_tokens,
+                output_tokens: total.output_tokens - previous.output_tokens,
+                reasoning_output_tokens: total.reasoning_output_tokens
+                    - previous.reasoning_output_tokens,
+                total_tokens: total.total_tokens - previous.total_tokens,
+            },
+            None => info.last_token_usage.clone(),
+        };
+        if totals.len() >= MAX_TRACKED_CONVERSATIONS && !totals.contains_key(&conversation_id) {
+            totals.clear();
+        }
+        totals.insert(conversation_id, total.clone());
+        (added.total_tokens > 0).then_some(added)
+    }
+
+    /// Render every metric in the Prometheus text exposition format
+    pub fn render(&self, snapshot: MetricsSnapshot) -> String {
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "codex_gateway_http_requests_total",
+            "counter",
+            "HTTP requests by method, route and status",
+        );
+        let http = lock(&self.inner.http);
+        for (key, series) in http.iter() {
+            let labels = http_labels(key);
+            let _ = writeln!(
+                out,
+                "codex_gateway_http_requests_total{{{labels}}} {}",
+                series.count
+            );
+        }
+
+        header(
+            &mut out,
+            "codex_gateway_http_request_duration_seconds",
+            "histogram",
+            "HTTP request latency by method, route and status",
+        );
+        for (key, series) in http.iter() {
+            let labels = http_labels(key);
+            for (bucket, bound) in series.buckets.iter().zip(LATENCY_BUCKETS) {
+                let _ = writeln!(
+                    out,
+                    "codex_gateway_http_request_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {bucket}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "codex_gateway_http_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}",
+                series.count
+            );
+            let _ = writeln!(
+                out,
+                "codex_gateway_http_request_duration_seconds_sum{{{labels}}} {}",
+                series.sum_seconds
+            );
+            let _ = writeln!(
+                out,
+                "codex_gateway_http_request_duration_seconds_count{{{labels}}} {}",
+                series.count
+            );
+        }
+        drop(http);
+
+        gauge(
+            &mut out,
+            "codex_gateway_websocket_connections",
+            "Open WebSocket connections",
+            self.inner.websocket_connections.load(Ordering::Relaxed),
+        );
+        gauge(
+            &mut out,
+            "codex_gateway_websocket_connections_max",
+            "Configured WebSocket connection limit",
+            snapshot.max_websocket_connections,
+        );
+        gauge(
+            &mut out,
+            "codex_gateway_active_conversations",
+            "Conversations tracked by the gateway",
+            snapshot.active_conversations,
+        );
+
+        header(
+            &mut out,
+            "codex_gateway_turns_started_total",
+            "counter",
+            "Agent turns started",
+        );
+        let _ = writeln!(
+            out,
+            "codex_gateway_turns_started_total {}",
+            self.inner.turns_started.load(Ordering::Relaxed)
+        );
+        labeled_counter(
+            &mut out,
+            "codex_gateway_turns_finished_total",
+            "Agent turns finished by outcome",
+            "status",
+            &lock(&self.inner.turns_finished),
+        );
+        labeled_counter(
+            &mut out,
+            "codex_gateway_tokens_total",
+            "Model tokens reported by TokenCount events",
+            "type",
+            &lock(&self.inner.tokens),
+        );
+        labeled_counter(
+            &mut out,
+            "codex_gateway_auth_rejections_total",
+            "Requests rejected by authentication",
+            "reason",
+            &lock(&self.inner.auth_rejections),
+        );
+
+        out
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: impl std::fmt::Display) {
+    header(out, name, "gauge", help);
+    let _ = writeln!(out, "{name} {value}");
+}
+
+fn labeled_counter(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    label: &str,
+    values: &BTreeMap<&'static str, u64>,
+) {
+    header(out, name, "counter", help);
+    for (value, count) in values {
+        let _ = writeln!(out, "{name}{{{label}=\"{value}\"}} {count}");
+    }
+}
+
+fn http_labels(key: &HttpSeriesKey) -> String {
+    format!(
+        "method=\"{}\",route=\"{}\",status=\"{}\"",
+        escape_label(&key.method),
+        escape_label(&key.route),
+        key.status
+    )
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::protocol::TaskCompleteEvent;
+    use codex_protocol::protocol::TokenCountEvent;
+
+    fn usage(input_tokens: i64, output_tokens: i64) -> TokenUsage {
+        TokenUsage {
+            input_tokens,
+            cached_input_tokens: 0,
+            output_tokens,
+            reasoning_output_tokens: 0,
+            total_tokens: input_tokens + output_tokens,
+        }
+    }
+
+    fn token_count(total: TokenUsage, last: TokenUsage) -> EventMsg {
+        EventMsg::TokenCount(TokenCountEvent {
+            info: Some(TokenUsageInfo {
+                total_token_usage: total,
+                last_token_usage: last,
+                model_context_window: None,
+            }),
+            rate_limits: None,
+        })
+    }
+
+    #[test]
+    fn test_render_http_histogram() {
+        let metrics = Metrics::new();
+        metrics.record_request("POST", "/exec", 200, Duration::from_millis(30));
+        metrics.record_request("POST", "/exec", 200, Duration::from_secs(3));
+
+        let output = metrics.render(MetricsSnapshot::default());
+        assert!(output.contains(
+            "codex_gateway_http_requests_total{method=\"POST\",route=\"/exec\",status=\"200\"} 2"
+        ));
+        assert!(output.contains(
+            "codex_gateway_http_request_duration_seconds_bucket{method=\"POST\",route=\"/exec\",status=\"200\",le=\"0.05\"} 1"
+        ));
+        assert!(output.contains(
+            "codex_gateway_http_request_duration_seconds_bucket{method=\"POST\",route=\"/exec\",status=\"200\",le=\"+Inf\"} 2"
+        ));
+    }
+
+    #[test]
+    fn test_websocket_guard_and_turns() {
+        let metrics = Metrics::new();
+        let guard = metrics.track_websocket();
+        metrics.observe_event(
+            ConversationId::new(),
+            &EventMsg::TaskComplete(TaskCompleteEvent {
+                last_agent_message: None,
+            }),
+        );
+        metrics.record_auth_rejection("invalid");
+
+        let output = metrics.render(MetricsSnapshot {
+            active_conversations: 3,
+            max_websocket_connections: 5000,
+        });
+        assert!(output.contains("codex_gateway_websocket_connections 1\n"));
+        assert!(output.contains("codex_gateway_websocket_connections_max 5000\n"));
+        assert!(output.contains("codex_gateway_active_conversations 3\n"));
+        assert!(output.contains("codex_gateway_turns_finished_total{status=\"completed\"} 1"));
+        assert!(output.contains("codex_gateway_auth_rejections_total{reason=\"invalid\"} 1"));
+
+        drop(guard);
+        let output = metrics.render(MetricsSnapshot::default());
+        assert!(output.contains("codex_gateway_websocket_connections 0\n"));
+
+        let first = metrics.try_track_websocket(1);
+        assert!(first.is_some());
+        assert!(metrics.try_track_websocket(1).is_none());
+        drop(first);
+        assert!(metrics.try_track_websocket(1).is_some());
+    }
+
+    #[test]
+    fn test_repeated_token_counts_are_counted_once() {
+        let metrics = Metrics::new();
+        let conversation_id = ConversationId::new();
+
+        // A rate-limit update before the first response carries no usage yet
+        let empty = EventMsg::TokenCount(TokenCountEvent {
+            info: None,
+            rate_limits: None,
+        });
+        assert!(metrics.observe_event(conversation_id, &empty).is_none());
+
+        let first =
+            metrics.observe_event(conversation_id, &token_count(usage(10, 5), usage(10, 5)));
+        assert_eq!(first.map(|usage| usage.total_tokens), Some(15));
+        // Rate-limit update of the next request repeats the previous usage
+        let repeated =
+            metrics.observe_event(conversation_id, &token_count(usage(10, 5), usage(10, 5)));
+        assert!(repeated.is_none());
+        let second =
+            metrics.observe_event(conversation_id, &token_count(usage(30, 7), usage(20, 2)));
+        assert_eq!(second.map(|usage| usage.input_tokens), Some(20));
+
+        let output = metrics.render(MetricsSnapshot::default());
+        assert!(output.contains("codex_gateway_tokens_total{type=\"input\"} 30\n"));
+        assert!(output.contains("codex_gateway_tokens_total{type=\"output\"} 7\n"));
+    }
+}
@@ -1,3 +1,23 @@
+pub mod artifacts;
+pub mod audit;
 pub mod codex_service;
+pub mod drain;
+pub mod embedded_turns;
+pub mod job_queue;
+pub mod mcp_sessions;
+pub mod metrics;
+pub mod readiness;
+pub mod session_registry;
+pub mod workspace;
 
+pub use artifacts::ArtifactStore;
+pub use audit::AuditLog;
 pub use codex_service::CodexService;
+pub use drain::Drain;
+pub use embedded_turns::EmbeddedTurns;
+pub use job_queue::JobQueue;
+pub use mcp_sessions::McpSessions;
+pub use metrics::Metrics;
+pub use readiness::Readiness;
+pub use session_registry::SessionRegistry;
+pub use workspace::WorkspacePolicy;
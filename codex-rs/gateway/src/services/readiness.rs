@@ -0,0 +1,300 @@
+//! Readiness checks behind `GET /health/ready`
+//!
+//! Each probe re-checks what a turn needs before it can succeed: the Codex
+//! config still loads, credentials for the model provider are present, the
+//! provider's host resolves, `CODEX_HOME` is writable and the configured MCP
+//! servers start. Every check reports its status and latency so orchestrators
+//! can stop routing traffic to a broken instance and operators can see why.
+//!
+//! Starting MCP servers spawns processes, so that result is cached for
+//! [`MCP_CHECK_TTL`]; the other checks run on every probe.
+
+use codex_app_server_protocol::AuthMode;
+use codex_core::auth::AuthManager;
+use codex_core::auth::CodexAuth;
+use codex_core::config::Config as CodexConfig;
+use codex_core::config::ConfigOverrides;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+use tokio::sync::Mutex;
+use url::Url;
+use uuid::Uuid;
+
+/// How long a successful or failed MCP start-up check is reused
+pub const MCP_CHECK_TTL: Duration = Duration::from_secs(60);
+
+/// Upper bound on resolving the model provider's host
+const DNS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Base URLs used when the provider does not set one (see `ModelProviderInfo`)
+const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const CHATGPT_BASE_URL: &str = "https://chatgpt.com/backend-api/codex";
+
+/// Outcome of one check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Failed,
+    /// Nothing to check (e.g. no MCP servers configured)
+    Skipped,
+}
+
+/// One dependency check of the readiness report
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl Check {
+    fn new(name: &'static str, started: Instant, (status, detail): Outcome) -> Self {
+        Self {
+            name,
+            status,
+            latency_ms: u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+            detail,
+        }
+    }
+}
+
+/// Result of every check
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessReport {
+    /// Whether no check failed
+    pub ready: bool,
+    pub checks: Vec<Check>,
+}
+
+type Outcome = (CheckStatus, Option<String>);
+
+fn ok(detail: impl Into<String>) -> Outcome {
+    (CheckStatus::Ok, Some(detail.into()))
+}
+
+fn failed(detail: impl Into<String>) -> Outcome {
+    (CheckStatus::Failed, Some(detail.into()))
+}
+
+/// Runs the readiness checks, caching the MCP start-up result
+#[derive(Debug, Clone, Default)]
+pub struct Readiness {
+    mcp: Arc<Mutex<Option<(Instant, Check)>>>,
+}
+
+impl Readiness {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run every check
+    ///
+    /// When the Codex config no longer loads, the remaining checks use
+    /// `fallback` (the config the gateway started with).
+    pub async fn check(&self, fallback: &CodexConfig) -> ReadinessReport {
+        let started = Instant::now();
+        let loaded =
+            CodexConfig::load_with_cli_overrides(Vec::new(), ConfigOverrides::default()).await;
+        let config_check = Check::new(
+            "config",
+            started,
+            match &loaded {
+                Ok(config) => ok(format!(
+                    "model {} via provider {}",
+                    config.model, config.model_provider_id
+                )),
+                Err(err) => failed(format!("failed to load Codex config: {err}")),
+            },
+        );
+        let config = loaded.as_ref().unwrap_or(fallback);
+
+        let started = Instant::now();
+        let (auth_outcome, auth) = check_auth(config);
+        let auth_check = Check::new("auth", started, auth_outcome);
+
+        let (provider_check, home_check, mcp_check) = tokio::join!(
+            async {
+                let started = Instant::now();
+                Check::new(
+                    "model_provider",
+                    started,
+                    check_provider(config, auth.as_ref()).await,
+                )
+            },
+            async {
+                let started = Instant::now();
+                Check::new(
+                    "codex_home",
+                    started,
+                    check_codex_home(&config.codex_home).await,
+                )
+            },
+            self.check_mcp(config),
+        );
+
+        let checks = vec![
+            config_check,
+            auth_check,
+            provider_check,
+            home_check,
+            mcp_check,
+        ];
+        ReadinessReport {
+            ready: checks
+                .iter()
+                .all(|check| check.status != CheckStatus::Failed),
+            checks,
+        }
+    }
+
+    /// Start the configured MCP servers, reusing a result younger than
+    /// [`MCP_CHECK_TTL`]
+    async fn check_mcp(&self, config: &CodexConfig) -> Check {
+        let mut cached = self.mcp.lock().await;
+        if let Some((checked_at, check)) = cached.as_ref()
+            && checked_at.elapsed() < MCP_CHECK_TTL
+        {
+            return check.clone();
+        }
+
+        let started = Instant::now();
+        let enabled = config
+            .mcp_servers
+            .values()
+            .filter(|server| server.enabled)
+            .count();
+        let outcome = if enabled == 0 {
+            (
+                CheckStatus::Skipped,
+                Some("no MCP servers configured".to_string()),
+            )
+        } else {
+            match codex_core::mcp::check_servers(
+                config.mcp_servers.clone(),
+                config.mcp_oauth_credentials_store_mode,
+            )
+            .await
+            {
+                Ok(errors) if errors.is_empty() => ok(format!("{enabled} server(s) started")),
+                Ok(errors) => {
+                    let mut errors: Vec<String> = errors
+                        .into_iter()
+                        .map(|(name, err)| format!("{name}: {err}"))
+                        .collect();
+                    errors.sort();
+                    failed(errors.join("; "))
+                }
+                Err(err) => failed(format!("failed to start MCP servers: {err:#}")),
+            }
+        };
+        let check = Check::new("mcp_servers", started, outcome);
+        *cached = Some((Instant::now(), check.clone()));
+        check
+    }
+}
+
+/// Credentials for the configured model provider
+fn check_auth(config: &CodexConfig) -> (Outcome, Option<CodexAuth>) {
+    let provider = &config.model_provider;
+    if provider.experimental_bearer_token.is_some() {
+        return (ok("bearer token from the provider config"), None);
+    }
+    let env_key = provider.api_key();
+    if let Ok(Some(_)) = env_key {
+        let name = provider.env_key.as_deref().unwrap_or_default();
+        return (ok(format!("API key from ${name}")), None);
+    }
+
+    let auth = AuthManager::shared(
+        config.codex_home.clone(),
+        false,
+        config.cli_auth_credentials_store_mode,
+    )
+    .auth();
+    let outcome = match (&auth, env_key) {
+        (Some(auth), _) if auth.mode == AuthMode::ChatGPT => ok("ChatGPT login"),
+        (Some(_), _) => ok("API key login"),
+        (None, Err(err)) => failed(err.to_string()),
+        (None, Ok(_)) if provider.requires_openai_auth => {
+            failed("not logged in; run `codex login` or set OPENAI_API_KEY")
+        }
+        (None, Ok(_)) => ok(format!("provider {} needs no credentials", provider.name)),
+    };
+    (outcome, auth)
+}
+
+/// Resolve the host of the provider's base URL
+async fn check_provider(config: &CodexConfig, auth: Option<&CodexAuth>) -> Outcome {
+    let default_base_url = match auth {
+        Some(auth) if auth.mode == AuthMode::ChatGPT => CHATGPT_BASE_URL,
+        _ => OPENAI_BASE_URL,
+    };
+    let base_url = config
+        .model_provider
+        .base_url
+        .as_deref()
+        .unwrap_or(default_base_url);
+    let url = match Url::parse(base_url) {
+        Ok(url) => url,
+        Err(err) => return failed(format!("invalid base URL {base_url}: {err}")),
+    };
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return failed(format!("base URL {base_url} has no host"));
+    };
+
+    match tokio::time::timeout(DNS_TIMEOUT, tokio::net::lookup_host((host, port))).await {
+        Ok(Ok(addresses)) => ok(format!(
+            "{host} resolved to {} address(es)",
+            addresses.count()
+        )),
+        Ok(Err(err)) => failed(format!("failed to resolve {host}: {err}")),
+        Err(_) => failed(format!(
+            "resolving {host} took more than {}s",
+            DNS_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+/// Create and remove a probe file in `codex_home`
+async fn check_codex_home(codex_home: &Path) -> Outcome {
+    let probe = codex_home.join(format!(".gateway-ready-{}", Uuid::new_v4().simple()));
+    if let Err(err) = tokio::fs::write(&probe, b"ok").await {
+        return failed(format!("{} is not writable: {err}", codex_home.display()));
+    }
+    let _ = tokio::fs::remove_file(&probe).await;
+    ok(codex_home.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_codex_home_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let (status, _) = check_codex_home(dir.path()).await;
+        assert_eq!(status, CheckStatus::Ok);
+        // The probe file is cleaned up
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        let (status, detail) = check_codex_home(&dir.path().join("missing")).await;
+        assert_eq!(status, CheckStatus::Failed);
+        assert!(detail.unwrap().contains("not writable"));
+    }
+
+    #[test]
+    fn test_check_serialization() {
+        let check = Check::new("mcp_servers", Instant::now(), (CheckStatus::Skipped, None));
+        let value = serde_json::to_value(&check).unwrap();
+        assert_eq!(value["name"], "mcp_servers");
+        assert_eq!(value["status"], "skipped");
+        assert!(value["latency_ms"].is_u64());
+        assert!(value.get("detail").is_none());
+    }
+}
@@ -0,0 +1,206 @@
+//! Durable `session_id` registry
+//!
+//! Maps each gateway `session_id` to its Codex [`ConversationId`], the rollout
+//! file the conversation is recorded in, the key that created it and when it
+//! was created and last used. The registry is written to
+//! `$CODEX_HOME/gateway/sessions.json` (or `GATEWAY_SESSIONS_FILE`) on every
+//! change, so after a restart [`crate::services::CodexService`] can rehydrate
+//! a session from its rollout the first time it is used again.
+
+use crate::middleware::api_key::write_private_file;
+use chrono::DateTime;
+use chrono::Utc;
+use codex_protocol::ConversationId;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::debug;
+use tracing::info;
+use tracing::warn;
+
+/// One registered session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub session_id: String,
+    pub conversation_id: ConversationId,
+    /// Rollout file of the conversation; looked up by id when unknown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollout_path: Option<PathBuf>,
+    /// `key_id` of the API key that created the session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SessionFile {
+    sessions: Vec<SessionRecord>,
+}
+
+/// Session registry, optionally backed by a file
+#[derive(Debug, Clone, Default)]
+pub struct SessionRegistry {
+    records: Arc<RwLock<HashMap<String, SessionRecord>>>,
+    path: Option<PathBuf>,
+}
+
+impl SessionRegistry {
+    /// Create an in-memory registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open a file-backed registry, loading existing sessions if the file exists
+    pub async fn open(path: PathBuf) -> io::Result<Self> {
+        let mut records = HashMap::new();
+        match tokio::fs::read(&path).await {
+            Ok(contents) => {
+                let file: SessionFile = serde_json::from_slice(&contents)?;
+                for record in file.sessions {
+                    records.insert(record.session_id.clone(), record);
+                }
+                info!("Loaded {} sessions from {}", records.len(), path.display());
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                debug!("Session file {} not found, starting empty", path.display());
+            }
+            Err(err) => return Err(err),
+        }
+
+        Ok(Self {
+            records: Arc::new(RwLock::new(records)),
+            path: Some(path),
+        })
+    }
+
+    pub async fn get(&self, session_id: &str) -> Option<SessionRecord> {
+        self.records.read().await.get(session_id).cloned()
+    }
+
+    /// Sessions created by `owner`, most recently used first
+    pub async fn list(&self, owner: Option<&str>) -> Vec<SessionRecord> {
+        let mut sessions: Vec<SessionRecord> = self
+            .records
+            .read()
+            .await
+            .values()
+            .filter(|record| record.owner.as_deref() == owner)
+            .cloned()
+            .collect();
+        sessions.sort_by(|a, b| b.last_used_at.cmp(&a.last_used_at));
+        sessions
+    }
+
+    /// Add or replace a session
+    pub async fn insert(&self, record: SessionRecord) {
+        let mut records = self.records.write().await;
+        records.insert(record.session_id.clone(), record);
+        self.save(&records).await;
+    }
+
+    /// Record that a session was used, and where its rollout lives if that
+    /// was not known yet
+    pub async fn touch(
+        &self,
+        session_id: &str,
+        conversation_id: ConversationId,
+        rollout_path: Option<PathBuf>,
+    ) {
+        let mut records = self.records.write().await;
+        let Some(record) = records.get_mut(session_id) else {
+            return;
+        };
+        record.conversation_id = conversation_id;
+        if rollout_path.is_some() {
+            record.rollout_path = rollout_path;
+        }
+        record.last_used_at = Utc::now();
+        self.save(&records).await;
+    }
+
+    pub async fn remove(&self, session_id: &str) -> Option<SessionRecord> {
+        let mut records = self.records.write().await;
+        let removed = records.remove(session_id);
+        if removed.is_some() {
+            self.save(&records).await;
+        }
+        removed
+    }
+
+    /// Write the registry back to disk (no-op for in-memory registries)
+    ///
+    /// A failed write only costs rehydration after a restart, so it is
+    /// logged rather than failing the request.
+    async fn save(&self, records: &HashMap<String, SessionRecord>) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let mut sessions: Vec<SessionRecord> = records.values().cloned().collect();
+        sessions.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+        let result = match serde_json::to_vec_pretty(&SessionFile { sessions }) {
+            Ok(contents) => write_private_file(path, &contents).await,
+            Err(err) => Err(err.into()),
+        };
+        if let Err(err) = result {
+            warn!("Failed to write sessions to {}: {err}", path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(session_id: &str, owner: Option<&str>) -> SessionRecord {
+        let now = Utc::now();
+        SessionRecord {
+            session_id: session_id.to_string(),
+            conversation_id: ConversationId::new(),
+            rollout_path: None,
+            owner: owner.map(str::to_string),
+            created_at: now,
+            last_used_at: now,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sessions_survive_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.json");
+
+        let registry = SessionRegistry::open(path.clone()).await.unwrap();
+        let first = record("alpha", Some("key_001"));
+        registry.insert(first.clone()).await;
+        registry.insert(record("beta", Some("key_002"))).await;
+        let rollout = dir.path().join("rollout.jsonl");
+        registry
+            .touch("alpha", first.conversation_id, Some(rollout.clone()))
+            .await;
+        registry.remove("beta").await;
+
+        let reopened = SessionRegistry::open(path).await.unwrap();
+        let alpha = reopened.get("alpha").await.unwrap();
+        assert_eq!(alpha.conversation_id, first.conversation_id);
+        assert_eq!(alpha.rollout_path, Some(rollout));
+        assert!(reopened.get("beta").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_is_scoped_to_owner() {
+        let registry = SessionRegistry::new();
+        registry.insert(record("alpha", Some("key_001"))).await;
+        registry.insert(record("beta", Some("key_002"))).await;
+        registry.insert(record("gamma", None)).await;
+
+        let listed = registry.list(Some("key_001")).await;
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].session_id, "alpha");
+        assert_eq!(registry.list(None).await[0].session_id, "gamma");
+    }
+}
@@ -0,0 +1,376 @@
+//! Per-tenant workspace isolation
+//!
+//! Every turn the gateway starts runs with [`TurnSettings`] resolved through a
+//! [`WorkspacePolicy`]. The policy comes from the caller's tenant
+//! ([`crate::config::TenantConfig`]) or, for callers without one, from the
+//! host's Codex config, and enforces three rules:
+//!
+//! - `cwd` (and local image paths) must canonicalize, symlinks included, to a
+//!   location inside the tenant's workspace root.
+//! - The requested sandbox mode may not be more permissive than the policy's
+//!   (`read-only` < `workspace-write` < `danger-full-access`).
+//! - The requested approval policy may not be more permissive than the
+//!   policy's (`untrusted` < `on-failure` < `on-request` < `never`).
+//!
+//! Requests may tighten either setting; anything else is a
+//! [`GatewayError::Forbidden`].
+
+use crate::config::GatewayConfig;
+use crate::error::GatewayError;
+use crate::error::GatewayResult;
+use crate::middleware::api_key::ApiKeyInfo;
+use codex_core::config::Config;
+use codex_protocol::config_types::SandboxMode;
+use codex_protocol::protocol::AskForApproval;
+use codex_protocol::protocol::SandboxPolicy;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Settings for one `Op::UserTurn`, already checked against the caller's policy
+#[derive(Debug, Clone)]
+pub struct TurnSettings {
+    pub cwd: PathBuf,
+    pub sandbox_policy: SandboxPolicy,
+    pub approval_policy: AskForApproval,
+}
+
+/// Limits that apply to one caller's turns
+#[derive(Debug, Clone)]
+pub struct WorkspacePolicy {
+    /// Tenant name, for error messages
+    tenant: Option<String>,
+    /// Canonical workspace root; `None` leaves paths unrestricted
+    root: Option<PathBuf>,
+    /// `cwd` used when the request does not set one
+    default_cwd: PathBuf,
+    /// Most permissive sandbox allowed, also the default
+    sandbox_policy: SandboxPolicy,
+    /// Most permissive approval policy allowed, also the default
+    approval_policy: AskForApproval,
+}
+
+impl WorkspacePolicy {
+    /// Policy for `caller`: its tenant, the default tenant, or the host config
+    pub fn for_caller(
+        config: &GatewayConfig,
+        codex_config: &Config,
+        caller: Option<&ApiKeyInfo>,
+    ) -> GatewayResult<Self> {
+        let tenants = &config.tenants;
+        let tenant = caller
+            .and_then(|key| key.tenant.as_ref())
+            .or(tenants.default_tenant.as_ref());
+
+        let Some(tenant) = tenant else {
+            return Ok(Self {
+                tenant: None,
+                root: None,
+                default_cwd: codex_config.cwd.clone(),
+                sandbox_policy: codex_config.sandbox_policy.clone(),
+                approval_policy: codex_config.approval_policy,
+            });
+        };
+
+        // A key bound to a tenant that no longer exists must not fall back to the host
+        let policy = tenants.tenants.get(tenant).ok_or_else(|| {
+            GatewayError::Forbidden(format!("Tenant '{tenant}' is not configured"))
+        })?;
+        let root = std::fs::canonicalize(&policy.workspace_root).map_err(|e| {
+            GatewayError::Config(format!(
+                "Workspace root {} of tenant '{tenant}' is unavailable: {e}",
+                policy.workspace_root.display()
+            ))
+        })?;
+
+        Ok(Self {
+            tenant: Some(tenant.clone()),
+            default_cwd: root.clone(),
+            root: Some(root),
+            sandbox_policy: sandbox_policy_for(policy.max_sandbox_mode, policy.network_access),
+            approval_policy: policy.max_approval_policy,
+        })
+    }
+
+    /// Check the request's overrides and build the settings for the turn
+    pub fn resolve(
+        &self,
+        cwd: Option<&Path>,
+        sandbox_mode: Option<&str>,
+        approval_policy: Option<AskForApproval>,
+    ) -> GatewayResult<TurnSettings> {
+        let cwd = match cwd {
+            Some(cwd) => self.check_path(cwd, true)?,
+            None => self.default_cwd.clone(),
+        };
+
+        let sandbox_policy = match sandbox_mode {
+            Some(mode) => {
+                let mode = parse_sandbox_mode(mode)?;
+                let allowed = sandbox_mode_of(&self.sandbox_policy);
+                if sandbox_rank(mode) > sandbox_rank(allowed) {
+                    return Err(self.forbidden(format!(
+                        "sandbox_mode '{mode}' exceeds the allowed '{allowed}'"
+                    )));
+                }
+                if mode == allowed {
+                    self.sandbox_policy.clone()
+                } else {
+                    sandbox_policy_for(mode, false)
+                }
+            }
+            None => self.sandbox_policy.clone(),
+        };
+
+        let approval_policy = match approval_policy {
+            Some(requested) if approval_rank(requested) > approval_rank(self.approval_policy) => {
+                return Err(self.forbidden(format!(
+                    "approval_policy '{requested}' exceeds the allowed '{}'",
+                    self.approval_policy
+                )));
+            }
+            Some(requested) => requested,
+            None => self.approval_policy,
+        };
+
+        Ok(TurnSettings {
+            cwd,
+            sandbox_policy,
+            approval_policy,
+        })
+    }
+
+    /// Check a complete sandbox policy supplied by a client
+    ///
+    /// On top of the mode ranking, extra writable roots must stay inside the
+    /// workspace root and network access must be allowed by the policy.
+    pub fn check_sandbox_policy(&self, requested: &SandboxPolicy) -> GatewayResult<()> {
+        let mode = sandbox_mode_of(requested).to_string();
+        let allowed = self.resolve(None, Some(&mode), None)?;
+        if let SandboxPolicy::WorkspaceWrite {
+            writable_roots,
+            network_access,
+            ..
+        } = requested
+        {
+            for root in writable_roots {
+                self.check_path(root, true)?;
+            }
+            let network_allowed = matches!(
+                allowed.sandbox_policy,
+                SandboxPolicy::WorkspaceWrite {
+                    network_access: true,
+                    ..
+                }
+            );
+            if *network_access && !network_allowed {
+                return Err(self.forbidden("network access is not allowed".to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolve `path` against the workspace root and make sure it stays inside
+    ///
+    /// Relative paths are taken from the root. Paths that do not exist are
+    /// rejected like paths outside the root, so callers cannot probe the host.
+    pub fn check_path(&self, path: &Path, expect_dir: bool) -> GatewayResult<PathBuf> {
+        let Some(root) = &self.root else {
+            return Ok(path.to_path_buf());
+        };
+
+        let resolved = std::fs::canonicalize(root.join(path))
+            .ok()
+            .filter(|resolved| resolved.starts_with(root))
+            .filter(|resolved| !expect_dir || resolved.is_dir());
+        resolved.ok_or_else(|| {
+            self.forbidden(format!(
+                "{} is not {} inside the workspace root",
+                path.display(),
+                if expect_dir { "a directory" } else { "a file" }
+            ))
+        })
+    }
+
+    fn forbidden(&self, message: String) -> GatewayError {
+        match &self.tenant {
+            Some(tenant) => GatewayError::Forbidden(format!("{message} (tenant '{tenant}')")),
+            None => GatewayError::Forbidden(message),
+        }
+    }
+}
+
+fn parse_sandbox_mode(mode: &str) -> GatewayResult<SandboxMode> {
+    serde_json::from_value(serde_json::Value::String(mode.to_string())).map_err(|_| {
+        GatewayError::InvalidRequest(format!(
+            "Invalid sandbox_mode '{mode}' (expected read-only, workspace-write or danger-full-access)"
+        ))
+    })
+}
+
+fn sandbox_policy_for(mode: SandboxMode, network_access: bool) -> SandboxPolicy {
+    match mode {
+        SandboxMode::ReadOnly => SandboxPolicy::ReadOnly,
+        SandboxMode::WorkspaceWrite => SandboxPolicy::WorkspaceWrite {
+            writable_roots: Vec::new(),
+            network_access,
+            exclude_tmpdir_env_var: false,
+            exclude_slash_tmp: false,
+        },
+        SandboxMode::DangerFullAccess => SandboxPolicy::DangerFullAccess,
+    }
+}
+
+fn sandbox_mode_of(policy: &SandboxPolicy) -> SandboxMode {
+    match policy {
+        SandboxPolicy::ReadOnly => SandboxMode::ReadOnly,
+        SandboxPolicy::WorkspaceWrite { .. } => SandboxMode::WorkspaceWrite,
+        SandboxPolicy::DangerFullAccess => SandboxMode::DangerFullAccess,
+    }
+}
+
+fn sandbox_rank(mode: SandboxMode) -> u8 {
+    match mode {
+        SandboxMode::ReadOnly => 0,
+        SandboxMode::WorkspaceWrite => 1,
+        SandboxMode::DangerFullAccess => 2,
+    }
+}
+
+/// How much the agent may do without a human saying yes
+fn approval_rank(policy: AskForApproval) -> u8 {
+    match policy {
+        AskForApproval::UnlessTrusted => 0,
+        AskForApproval::OnFailure => 1,
+        AskForApproval::OnRequest => 2,
+        AskForApproval::Never => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tenant_policy(root: &Path) -> WorkspacePolicy {
+        let root = std::fs::canonicalize(root).unwrap();
+        WorkspacePolicy {
+            tenant: Some("acme".to_string()),
+            default_cwd: root.clone(),
+            root: Some(root),
+            sandbox_policy: sandbox_policy_for(SandboxMode::WorkspaceWrite, false),
+            approval_policy: AskForApproval::OnRequest,
+        }
+    }
+
+    #[test]
+    fn test_cwd_must_stay_inside_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("acme");
+        std::fs::create_dir_all(root.join("repo")).unwrap();
+        std::fs::create_dir_all(dir.path().join("other")).unwrap();
+        let policy = tenant_policy(&root);
+        let root = std::fs::canonicalize(&root).unwrap();
+
+        let settings = policy.resolve(None, None, None).unwrap();
+        assert_eq!(settings.cwd, root);
+        let settings = policy.resolve(Some(Path::new("repo")), None, None).unwrap();
+        assert_eq!(settings.cwd, root.join("repo"));
+        let settings = policy
+            .resolve(Some(&root.join("repo")), None, None)
+            .unwrap();
+        assert_eq!(settings.cwd, root.join("repo"));
+
+        for cwd in [
+            dir.path().join("other"),
+            PathBuf::from("../other"),
+            PathBuf::from("missing"),
+        ] {
+            assert!(matches!(
+                policy.resolve(Some(&cwd), None, None),
+                Err(GatewayError::Forbidden(_))
+            ));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_escape_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("acme");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::create_dir_all(dir.path().join("secrets")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("secrets"), root.join("link")).unwrap();
+        let policy = tenant_policy(&root);
+
+        assert!(matches!(
+            policy.resolve(Some(Path::new("link")), None, None),
+            Err(GatewayError::Forbidden(_))
+        ));
+    }
+
+    #[test]
+    fn test_requests_can_tighten_but_not_loosen() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = tenant_policy(dir.path());
+
+        let settings = policy
+            .resolve(None, Some("read-only"), Some(AskForApproval::UnlessTrusted))
+            .unwrap();
+        assert_eq!(settings.sandbox_policy, SandboxPolicy::ReadOnly);
+        assert_eq!(settings.approval_policy, AskForApproval::UnlessTrusted);
+
+        let settings = policy.resolve(None, None, None).unwrap();
+        assert!(matches!(
+            settings.sandbox_policy,
+            SandboxPolicy::WorkspaceWrite { .. }
+        ));
+        assert_eq!(settings.approval_policy, AskForApproval::OnRequest);
+
+        assert!(matches!(
+            policy.resolve(None, Some("danger-full-access"), None),
+            Err(GatewayError::Forbidden(_))
+        ));
+        assert!(matches!(
+            policy.resolve(None, None, Some(AskForApproval::Never)),
+            Err(GatewayError::Forbidden(_))
+        ));
+        assert!(matches!(
+            policy.resolve(None, Some("yolo"), None),
+            Err(GatewayError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_sandbox_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("acme/cache")).unwrap();
+        let policy = tenant_policy(&dir.path().join("acme"));
+
+        let workspace_write =
+            |writable_roots: Vec<PathBuf>, network_access: bool| SandboxPolicy::WorkspaceWrite {
+                writable_roots,
+                network_access,
+                exclude_tmpdir_env_var: false,
+                exclude_slash_tmp: false,
+            };
+        assert!(
+            policy
+                .check_sandbox_policy(&SandboxPolicy::ReadOnly)
+                .is_ok()
+        );
+        assert!(
+            policy
+                .check_sandbox_policy(&workspace_write(vec![PathBuf::from("cache")], false))
+                .is_ok()
+        );
+        for requested in [
+            SandboxPolicy::DangerFullAccess,
+            workspace_write(vec![dir.path().to_path_buf()], false),
+            workspace_write(Vec::new(), true),
+        ] {
+            assert!(matches!(
+                policy.check_sandbox_policy(&requested),
+                Err(GatewayError::Forbidden(_))
+            ));
+        }
+    }
+}
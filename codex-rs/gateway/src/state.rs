@@ -2,7 +2,15 @@
 
 use crate::config::GatewayConfig;
 use crate::error::GatewayError;
+use crate::middleware::api_key::ApiKeyStore;
+use crate::middleware::oauth::OAuthStore;
+use crate::services::AuditLog;
 use crate::services::CodexService;
+use crate::services::JobQueue;
+use crate::services::McpSessions;
+use crate::services::Metrics;
+use crate::services::Readiness;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::runtime::Handle;
 use tokio::runtime::Runtime;
@@ -14,12 +22,23 @@ pub struct AppState {
     pub config: Arc<GatewayConfig>,
     /// Codex service for processing AI requests
     pub codex_service: Arc<CodexService>,
+    /// API keys accepted by the gateway (file-backed under CODEX_HOME)
+    pub api_keys: ApiKeyStore,
+    /// OAuth clients and tokens (file-backed under CODEX_HOME)
+    pub oauth: OAuthStore,
+    /// Durable background agent runs (`/jobs` and webhooks)
+    pub jobs: JobQueue,
+    /// Counters and gauges exported on `/metrics`
+    pub metrics: Metrics,
+    /// Open sessions of the `/mcp` Streamable HTTP transport
+    pub mcp_sessions: McpSessions,
+    /// Dependency checks behind `/health/ready`
+    pub readiness: Readiness,
     // Add more shared state here as needed in future iterations
     // Examples:
     // - Database connections
     // - Redis connections
     // - Service discovery clients
-    // - Metrics collectors
     // - Authentication services
 }
 
@@ -28,9 +47,76 @@ impl AppState {
     pub async fn new(config: GatewayConfig) -> Result<Self, GatewayError> {
         let codex_service = CodexService::new().await?;
         //                                            ^ propaga erro ao invés de panic
+        let audit = AuditLog::open(
+            &config.audit,
+            codex_service
+                .codex_config()
+                .codex_home
+                .join("gateway")
+                .join("audit")
+                .join("audit.jsonl"),
+        );
+        let codex_service = codex_service.with_audit_log(audit);
+
+        let api_keys_path = config.auth.api_keys_file.clone().unwrap_or_else(|| {
+            codex_service
+                .codex_config()
+                .codex_home
+                .join("gateway")
+                .join("api_keys.json")
+        });
+        let api_keys = ApiKeyStore::open_with_default_keys(api_keys_path.clone())
+            .await
+            .map_err(|err| {
+                GatewayError::Config(format!(
+                    "failed to load API keys from {}: {err}",
+                    api_keys_path.display()
+                ))
+            })?;
+
+        let oauth_path = config.auth.oauth_file.clone().unwrap_or_else(|| {
+            codex_service
+                .codex_config()
+                .codex_home
+                .join("gateway")
+                .join("oauth.json")
+        });
+        let oauth = OAuthStore::open_with_default_client(oauth_path.clone())
+            .await
+            .map_err(|err| {
+                GatewayError::Config(format!(
+                    "failed to load OAuth state from {}: {err}",
+                    oauth_path.display()
+                ))
+            })?;
+
+        let jobs_dir = std::env::var("GATEWAY_JOBS_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| {
+                codex_service
+                    .codex_config()
+                    .codex_home
+                    .join("gateway")
+                    .join("jobs")
+            });
+        let jobs = JobQueue::open(jobs_dir.clone(), config.max_concurrent_jobs)
+            .await
+            .map_err(|err| {
+                GatewayError::Config(format!(
+                    "failed to open job queue at {}: {err}",
+                    jobs_dir.display()
+                ))
+            })?;
+
         Ok(Self {
             config: Arc::new(config),
+            metrics: codex_service.metrics().clone(),
             codex_service: Arc::new(codex_service),
+            api_keys,
+            oauth,
+            jobs,
+            mcp_sessions: McpSessions::new(),
+            readiness: Readiness::new(),
         })
     }
 
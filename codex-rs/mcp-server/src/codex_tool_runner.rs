@@ -6,6 +6,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::exec_approval::handle_exec_approval_request;
+use crate::mcp_tool_call_approval::handle_mcp_tool_call_approval_request;
 use crate::outgoing_message::OutgoingMessageSender;
 use crate::outgoing_message::OutgoingNotificationMeta;
 use crate::patch_approval::handle_patch_approval_request;
@@ -18,6 +19,7 @@ use codex_core::protocol::ApplyPatchApprovalRequestEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
+use codex_core::protocol::McpToolCallApprovalRequestEvent;
 use codex_core::protocol::Op;
 use codex_core::protocol::Submission;
 use codex_core::protocol::TaskCompleteEvent;
@@ -180,6 +182,7 @@ async fn run_codex_tool_session_inner(
                         reason: _,
                         risk,
                         parsed_cmd,
+                        proposed_prefix: _,
                     }) => {
                         handle_exec_approval_request(
                             command,
@@ -227,6 +230,24 @@ async fn run_codex_tool_session_inner(
                         .await;
                         continue;
                     }
+                    EventMsg::McpToolCallApprovalRequest(McpToolCallApprovalRequestEvent {
+                        call_id,
+                        invocation,
+                        reason,
+                    }) => {
+                        handle_mcp_tool_call_approval_request(
+                            call_id,
+                            invocation,
+                            reason,
+                            outgoing.clone(),
+                            codex.clone(),
+                            request_id.clone(),
+                            request_id_str.clone(),
+                            event.id.clone(),
+                        )
+                        .await;
+                        continue;
+                    }
                     EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
                         let text = match last_agent_message {
                             Some(msg) => msg,
@@ -4,11 +4,15 @@
 use std::io::ErrorKind;
 use std::io::Result as IoResult;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use codex_common::CliConfigOverrides;
+use codex_core::AuthManager;
+use codex_core::ConversationManager;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
 
+use codex_protocol::protocol::SessionSource;
 use mcp_types::JSONRPCMessage;
 use tokio::io::AsyncBufReadExt;
 use tokio::io::AsyncWriteExt;
@@ -24,6 +28,7 @@ mod codex_tool_config;
 mod codex_tool_runner;
 mod error_code;
 mod exec_approval;
+mod mcp_tool_call_approval;
 pub(crate) mod message_processor;
 mod outgoing_message;
 mod patch_approval;
@@ -56,8 +61,8 @@ pub async fn run_main(
         .init();
 
     // Set up channels.
-    let (incoming_tx, mut incoming_rx) = mpsc::channel::<JSONRPCMessage>(CHANNEL_CAPACITY);
-    let (outgoing_tx, mut outgoing_rx) = mpsc::unbounded_channel::<OutgoingMessage>();
+    let (incoming_tx, incoming_rx) = mpsc::channel::<JSONRPCMessage>(CHANNEL_CAPACITY);
+    let (outgoing_tx, mut outgoing_rx) = mpsc::unbounded_channel::<JSONRPCMessage>();
 
     // Task: read from stdin, push to `incoming_tx`.
     let stdin_reader_handle = tokio::spawn({
@@ -97,32 +102,17 @@ pub async fn run_main(
         })?;
 
     // Task: process incoming messages.
-    let processor_handle = tokio::spawn({
-        let outgoing_message_sender = OutgoingMessageSender::new(outgoing_tx);
-        let mut processor = MessageProcessor::new(
-            outgoing_message_sender,
-            codex_linux_sandbox_exe,
-            std::sync::Arc::new(config),
-        );
-        async move {
-            while let Some(msg) = incoming_rx.recv().await {
-                match msg {
-                    JSONRPCMessage::Request(r) => processor.process_request(r).await,
-                    JSONRPCMessage::Response(r) => processor.process_response(r).await,
-                    JSONRPCMessage::Notification(n) => processor.process_notification(n).await,
-                    JSONRPCMessage::Error(e) => processor.process_error(e),
-                }
-            }
-
-            info!("processor task exited (channel closed)");
-        }
-    });
+    let processor_handle = tokio::spawn(serve_connection(
+        incoming_rx,
+        outgoing_tx,
+        codex_linux_sandbox_exe,
+        Arc::new(config),
+    ));
 
     // Task: write outgoing messages to stdout.
     let stdout_writer_handle = tokio::spawn(async move {
         let mut stdout = io::stdout();
-        while let Some(outgoing_message) = outgoing_rx.recv().await {
-            let msg: JSONRPCMessage = outgoing_message.into();
+        while let Some(msg) = outgoing_rx.recv().await {
             match serde_json::to_string(&msg) {
                 Ok(json) => {
                     if let Err(e) = stdout.write_all(json.as_bytes()).await {
@@ -148,3 +138,67 @@ pub async fn run_main(
 
     Ok(())
 }
+
+/// Serve one MCP client over an arbitrary transport.
+///
+/// Processes messages from `incoming_rx` until it closes and forwards every
+/// message for the client to `outgoing_tx`. [`run_main`] uses this over
+/// stdio; embedders such as the gateway run one per MCP session.
+pub async fn serve_connection(
+    incoming_rx: mpsc::Receiver<JSONRPCMessage>,
+    outgoing_tx: mpsc::UnboundedSender<JSONRPCMessage>,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+    config: Arc<Config>,
+) {
+    let auth_manager = AuthManager::shared(
+        config.codex_home.clone(),
+        false,
+        config.cli_auth_credentials_store_mode,
+    );
+    let conversation_manager = Arc::new(ConversationManager::new(auth_manager, SessionSource::Mcp));
+    serve_connection_with_manager(
+        incoming_rx,
+        outgoing_tx,
+        codex_linux_sandbox_exe,
+        conversation_manager,
+    )
+    .await;
+}
+
+/// Like [`serve_connection`], with conversations held by the caller's
+/// `conversation_manager` so an embedder can reach them directly (for
+/// example to shut them down).
+pub async fn serve_connection_with_manager(
+    mut incoming_rx: mpsc::Receiver<JSONRPCMessage>,
+    outgoing_tx: mpsc::UnboundedSender<JSONRPCMessage>,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+    conversation_manager: Arc<ConversationManager>,
+) {
+    let (message_tx, mut message_rx) = mpsc::unbounded_channel::<OutgoingMessage>();
+
+    // Task: convert outgoing messages to JSON-RPC for the transport.
+    tokio::spawn(async move {
+        while let Some(outgoing_message) = message_rx.recv().await {
+            if outgoing_tx.send(outgoing_message.into()).is_err() {
+                // Transport gone – nothing left to do.
+                break;
+            }
+        }
+    });
+
+    let mut processor = MessageProcessor::new(
+        OutgoingMessageSender::new(message_tx),
+        codex_linux_sandbox_exe,
+        conversation_manager,
+    );
+    while let Some(msg) = incoming_rx.recv().await {
+        match msg {
+            JSONRPCMessage::Request(r) => processor.process_request(r).await,
+            JSONRPCMessage::Response(r) => processor.process_response(r).await,
+            JSONRPCMessage::Notification(n) => processor.process_notification(n).await,
+            JSONRPCMessage::Error(e) => processor.process_error(e),
+        }
+    }
+
+    info!("processor task exited (channel closed)");
+}
@@ -6,6 +6,8 @@ use std::path::PathBuf;
 use codex_core::parse_command;
 use codex_core::protocol::FileChange;
 use codex_core::protocol::ReviewDecision;
+use codex_core::protocol::SandboxCommandAssessment;
+use codex_core::protocol::SandboxRiskLevel;
 use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
 use codex_mcp_server::CodexToolCallParam;
 use codex_mcp_server::ExecApprovalElicitRequestParams;
@@ -196,7 +198,11 @@ fn create_expected_elicitation_request(
             codex_cwd: workdir.to_path_buf(),
             codex_call_id: "call1234".to_string(),
             codex_parsed_cmd,
-            codex_risk: None,
+            codex_risk: Some(SandboxCommandAssessment {
+                description: "Runs code with `python3`; its effects cannot be predicted offline."
+                    .to_string(),
+                risk_level: SandboxRiskLevel::Medium,
+            }),
         })?),
     })
 }
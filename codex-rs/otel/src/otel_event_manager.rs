@@ -27,6 +27,10 @@ use tokio::time::error::Elapsed;
 pub enum ToolDecisionSource {
     Config,
     User,
+    /// A `codex-execpolicy` policy file allowed or forbade the command.
+    ExecPolicy,
+    /// The command guard refused a catastrophic command.
+    CommandGuard,
 }
 
 #[derive(Debug, Clone)]
@@ -3,12 +3,16 @@ use std::path::PathBuf;
 
 use crate::parse_command::ParsedCommand;
 use crate::protocol::FileChange;
+use crate::protocol::McpInvocation;
 use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use ts_rs::TS;
 
-#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash, JsonSchema, TS)]
+/// Ordered from least to most risky.
+#[derive(
+    Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash, JsonSchema, TS,
+)]
 #[serde(rename_all = "snake_case")]
 pub enum SandboxRiskLevel {
     Low,
@@ -47,6 +51,11 @@ pub struct ExecApprovalRequestEvent {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub risk: Option<SandboxCommandAssessment>,
     pub parsed_cmd: Vec<ParsedCommand>,
+    /// Command prefix that `ReviewDecision::ApprovedPrefix` and
+    /// `ReviewDecision::ApprovedPrefixForProject` would save. Absent when no
+    /// prefix can be safely offered for this command.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proposed_prefix: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, TS)]
@@ -61,3 +70,14 @@ pub struct ApplyPatchApprovalRequestEvent {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub grant_root: Option<PathBuf>,
 }
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, TS)]
+pub struct McpToolCallApprovalRequestEvent {
+    /// Identifier for the associated MCP tool call.
+    pub call_id: String,
+    /// The MCP tool call awaiting approval.
+    pub invocation: McpInvocation,
+    /// Optional human-readable reason for the approval (e.g. the tool is marked destructive).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
@@ -36,6 +36,7 @@ use ts_rs::TS;
 
 pub use crate::approvals::ApplyPatchApprovalRequestEvent;
 pub use crate::approvals::ExecApprovalRequestEvent;
+pub use crate::approvals::McpToolCallApprovalRequestEvent;
 pub use crate::approvals::SandboxCommandAssessment;
 pub use crate::approvals::SandboxRiskLevel;
 
@@ -153,6 +154,14 @@ pub enum Op {
         decision: ReviewDecision,
     },
 
+    /// Approve an MCP tool call
+    McpToolCallApproval {
+        /// The id of the submission we are approving
+        id: String,
+        /// The user's decision in response to the request.
+        decision: ReviewDecision,
+    },
+
     /// Append an entry to the persistent cross-session message history.
     ///
     /// Note the entry is not guaranteed to be logged if the user has
@@ -500,6 +509,8 @@ pub enum EventMsg {
 
     ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent),
 
+    McpToolCallApprovalRequest(McpToolCallApprovalRequestEvent),
+
     /// Notification advising the user that something they are using has been
     /// deprecated and should be phased out.
     DeprecationNotice(DeprecationNoticeEvent),
@@ -1420,6 +1431,15 @@ pub enum ReviewDecision {
     /// remainder of the session.
     ApprovedForSession,
 
+    /// User has approved this command and wants any future command starting
+    /// with the request's `proposed_prefix` to be approved automatically, in
+    /// this and later sessions.
+    ApprovedPrefix,
+
+    /// Like `ApprovedPrefix`, but only for commands run inside the current
+    /// project.
+    ApprovedPrefixForProject,
+
     /// User has denied this command and the agent should not execute it, but
     /// it should continue the session and try something else.
     #[default]
@@ -678,6 +678,19 @@ impl App {
                         "E X E C".to_string(),
                     ));
                 }
+                ApprovalRequest::McpToolCall { invocation, .. } => {
+                    let _ = tui.enter_alt_screen();
+                    let mut lines = vec![Line::from(format!(
+                        "{}.{}",
+                        invocation.server, invocation.tool
+                    ))];
+                    if let Some(arguments) = invocation.arguments.as_ref() {
+                        let pretty = serde_json::to_string_pretty(arguments)
+                            .unwrap_or_else(|_| arguments.to_string());
+                        lines.extend(pretty.lines().map(|line| Line::from(line.to_string())));
+                    }
+                    self.overlay = Some(Overlay::new_static_with_lines(lines, "M C P".to_string()));
+                }
             },
         }
         Ok(true)
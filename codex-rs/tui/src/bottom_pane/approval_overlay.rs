@@ -17,6 +17,7 @@ use crate::render::highlight::highlight_bash_to_lines;
 use crate::render::renderable::ColumnRenderable;
 use crate::render::renderable::Renderable;
 use codex_core::protocol::FileChange;
+use codex_core::protocol::McpInvocation;
 use codex_core::protocol::Op;
 use codex_core::protocol::ReviewDecision;
 use codex_core::protocol::SandboxCommandAssessment;
@@ -41,6 +42,7 @@ pub(crate) enum ApprovalRequest {
         command: Vec<String>,
         reason: Option<String>,
         risk: Option<SandboxCommandAssessment>,
+        proposed_prefix: Option<Vec<String>>,
     },
     ApplyPatch {
         id: String,
@@ -48,6 +50,11 @@ pub(crate) enum ApprovalRequest {
         cwd: PathBuf,
         changes: HashMap<PathBuf, FileChange>,
     },
+    McpToolCall {
+        id: String,
+        invocation: McpInvocation,
+        reason: Option<String>,
+    },
 }
 
 /// Modal overlay asking the user to approve or deny one or more requests.
@@ -97,14 +104,20 @@ impl ApprovalOverlay {
         header: Box<dyn Renderable>,
     ) -> (Vec<ApprovalOption>, SelectionViewParams) {
         let (options, title) = match &variant {
-            ApprovalVariant::Exec { .. } => (
-                exec_options(),
+            ApprovalVariant::Exec {
+                proposed_prefix, ..
+            } => (
+                exec_options(proposed_prefix.as_deref()),
                 "Would you like to run the following command?".to_string(),
             ),
             ApprovalVariant::ApplyPatch { .. } => (
                 patch_options(),
                 "Would you like to make the following edits?".to_string(),
             ),
+            ApprovalVariant::McpToolCall { .. } => (
+                mcp_tool_call_options(),
+                "Would you like to call the following MCP tool?".to_string(),
+            ),
         };
 
         let header = Box::new(ColumnRenderable::with([
@@ -150,12 +163,22 @@ impl ApprovalOverlay {
         };
         if let Some(variant) = self.current_variant.as_ref() {
             match (&variant, option.decision) {
-                (ApprovalVariant::Exec { id, command }, decision) => {
-                    self.handle_exec_decision(id, command, decision);
+                (
+                    ApprovalVariant::Exec {
+                        id,
+                        command,
+                        proposed_prefix,
+                    },
+                    decision,
+                ) => {
+                    self.handle_exec_decision(id, command, proposed_prefix.as_deref(), decision);
                 }
                 (ApprovalVariant::ApplyPatch { id, .. }, decision) => {
                     self.handle_patch_decision(id, decision);
                 }
+                (ApprovalVariant::McpToolCall { id }, decision) => {
+                    self.handle_mcp_tool_call_decision(id, decision);
+                }
             }
         }
 
@@ -163,8 +186,23 @@ impl ApprovalOverlay {
         self.advance_queue();
     }
 
-    fn handle_exec_decision(&self, id: &str, command: &[String], decision: ReviewDecision) {
-        let cell = history_cell::new_approval_decision_cell(command.to_vec(), decision);
+    fn handle_exec_decision(
+        &self,
+        id: &str,
+        command: &[String],
+        proposed_prefix: Option<&[String]>,
+        decision: ReviewDecision,
+    ) {
+        // Prefix decisions approve every command starting with the prefix, so
+        // the history shows the prefix rather than this one command.
+        let shown = match (decision, proposed_prefix) {
+            (
+                ReviewDecision::ApprovedPrefix | ReviewDecision::ApprovedPrefixForProject,
+                Some(prefix),
+            ) => prefix,
+            _ => command,
+        };
+        let cell = history_cell::new_approval_decision_cell(shown.to_vec(), decision);
         self.app_event_tx.send(AppEvent::InsertHistoryCell(cell));
         self.app_event_tx.send(AppEvent::CodexOp(Op::ExecApproval {
             id: id.to_string(),
@@ -179,6 +217,14 @@ impl ApprovalOverlay {
         }));
     }
 
+    fn handle_mcp_tool_call_decision(&self, id: &str, decision: ReviewDecision) {
+        self.app_event_tx
+            .send(AppEvent::CodexOp(Op::McpToolCallApproval {
+                id: id.to_string(),
+                decision,
+            }));
+    }
+
     fn advance_queue(&mut self) {
         if let Some(next) = self.queue.pop() {
             self.set_current(next);
@@ -238,12 +284,15 @@ impl BottomPaneView for ApprovalOverlay {
             && let Some(variant) = self.current_variant.as_ref()
         {
             match &variant {
-                ApprovalVariant::Exec { id, command } => {
-                    self.handle_exec_decision(id, command, ReviewDecision::Abort);
+                ApprovalVariant::Exec { id, command, .. } => {
+                    self.handle_exec_decision(id, command, None, ReviewDecision::Abort);
                 }
                 ApprovalVariant::ApplyPatch { id, .. } => {
                     self.handle_patch_decision(id, ReviewDecision::Abort);
                 }
+                ApprovalVariant::McpToolCall { id } => {
+                    self.handle_mcp_tool_call_decision(id, ReviewDecision::Abort);
+                }
             }
         }
         self.queue.clear();
@@ -291,6 +340,7 @@ impl From<ApprovalRequest> for ApprovalRequestState {
                 command,
                 reason,
                 risk,
+                proposed_prefix,
             } => {
                 let reason = reason.filter(|item| !item.is_empty());
                 let has_reason = reason.is_some();
@@ -310,7 +360,11 @@ impl From<ApprovalRequest> for ApprovalRequestState {
                 }
                 header.extend(full_cmd_lines);
                 Self {
-                    variant: ApprovalVariant::Exec { id, command },
+                    variant: ApprovalVariant::Exec {
+                        id,
+                        command,
+                        proposed_prefix,
+                    },
                     header: Box::new(Paragraph::new(header).wrap(Wrap { trim: false })),
                 }
             }
@@ -336,6 +390,24 @@ impl From<ApprovalRequest> for ApprovalRequestState {
                     header: Box::new(ColumnRenderable::with(header)),
                 }
             }
+            ApprovalRequest::McpToolCall {
+                id,
+                invocation,
+                reason,
+            } => {
+                let mut header: Vec<Line<'static>> = Vec::new();
+                if let Some(reason) = reason
+                    && !reason.is_empty()
+                {
+                    header.push(Line::from(vec!["Reason: ".into(), reason.italic()]));
+                    header.push(Line::from(""));
+                }
+                header.push(history_cell::format_mcp_invocation(invocation));
+                Self {
+                    variant: ApprovalVariant::McpToolCall { id },
+                    header: Box::new(Paragraph::new(header).wrap(Wrap { trim: false })),
+                }
+            }
         }
     }
 }
@@ -364,8 +436,17 @@ fn render_risk_lines(risk: &SandboxCommandAssessment) -> Vec<Line<'static>> {
 
 #[derive(Clone)]
 enum ApprovalVariant {
-    Exec { id: String, command: Vec<String> },
-    ApplyPatch { id: String },
+    Exec {
+        id: String,
+        command: Vec<String>,
+        proposed_prefix: Option<Vec<String>>,
+    },
+    ApplyPatch {
+        id: String,
+    },
+    McpToolCall {
+        id: String,
+    },
 }
 
 #[derive(Clone)]
@@ -384,8 +465,8 @@ impl ApprovalOption {
     }
 }
 
-fn exec_options() -> Vec<ApprovalOption> {
-    vec![
+fn exec_options(proposed_prefix: Option<&[String]>) -> Vec<ApprovalOption> {
+    let mut options = vec![
         ApprovalOption {
             label: "Yes, proceed".to_string(),
             decision: ReviewDecision::Approved,
@@ -398,6 +479,39 @@ fn exec_options() -> Vec<ApprovalOption> {
             display_shortcut: None,
             additional_shortcuts: vec![key_hint::plain(KeyCode::Char('a'))],
         },
+    ];
+    if let Some(prefix) = proposed_prefix {
+        let prefix = strip_bash_lc_and_escape(prefix);
+        options.push(ApprovalOption {
+            label: format!("Yes, and always allow `{prefix} …` in this project"),
+            decision: ReviewDecision::ApprovedPrefixForProject,
+            display_shortcut: None,
+            additional_shortcuts: vec![key_hint::plain(KeyCode::Char('p'))],
+        });
+        options.push(ApprovalOption {
+            label: format!("Yes, and always allow `{prefix} …` everywhere"),
+            decision: ReviewDecision::ApprovedPrefix,
+            display_shortcut: None,
+            additional_shortcuts: vec![key_hint::plain(KeyCode::Char('e'))],
+        });
+    }
+    options.push(ApprovalOption {
+        label: "No, and tell Codex what to do differently".to_string(),
+        decision: ReviewDecision::Abort,
+        display_shortcut: Some(key_hint::plain(KeyCode::Esc)),
+        additional_shortcuts: vec![key_hint::plain(KeyCode::Char('n'))],
+    });
+    options
+}
+
+fn patch_options() -> Vec<ApprovalOption> {
+    vec![
+        ApprovalOption {
+            label: "Yes, proceed".to_string(),
+            decision: ReviewDecision::Approved,
+            display_shortcut: None,
+            additional_shortcuts: vec![key_hint::plain(KeyCode::Char('y'))],
+        },
         ApprovalOption {
             label: "No, and tell Codex what to do differently".to_string(),
             decision: ReviewDecision::Abort,
@@ -407,7 +521,7 @@ fn exec_options() -> Vec<ApprovalOption> {
     ]
 }
 
-fn patch_options() -> Vec<ApprovalOption> {
+fn mcp_tool_call_options() -> Vec<ApprovalOption> {
     vec![
         ApprovalOption {
             label: "Yes, proceed".to_string(),
@@ -415,6 +529,12 @@ fn patch_options() -> Vec<ApprovalOption> {
             display_shortcut: None,
             additional_shortcuts: vec![key_hint::plain(KeyCode::Char('y'))],
         },
+        ApprovalOption {
+            label: "Yes, and don't ask again for this tool".to_string(),
+            decision: ReviewDecision::ApprovedForSession,
+            display_shortcut: None,
+            additional_shortcuts: vec![key_hint::plain(KeyCode::Char('a'))],
+        },
         ApprovalOption {
             label: "No, and tell Codex what to do differently".to_string(),
             decision: ReviewDecision::Abort,
@@ -437,6 +557,7 @@ mod tests {
             command: vec!["echo".to_string(), "hi".to_string()],
             reason: Some("reason".to_string()),
             risk: None,
+            proposed_prefix: None,
         }
     }
 
@@ -479,6 +600,7 @@ mod tests {
             command,
             reason: None,
             risk: None,
+            proposed_prefix: None,
         };
 
         let view = ApprovalOverlay::new(exec_request, tx);
@@ -549,4 +671,60 @@ mod tests {
         }
         assert_eq!(decision, Some(ReviewDecision::ApprovedForSession));
     }
+
+    #[test]
+    fn prefix_shortcut_approves_prefix_for_project() {
+        let (tx_raw, mut rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx_raw);
+        let request = ApprovalRequest::Exec {
+            id: "test".to_string(),
+            command: vec!["cargo".into(), "test".into(), "-p".into(), "core".into()],
+            reason: None,
+            risk: None,
+            proposed_prefix: Some(vec!["cargo".into(), "test".into()]),
+        };
+        let mut view = ApprovalOverlay::new(request, tx);
+        assert!(
+            view.options
+                .iter()
+                .any(|opt| opt.label == "Yes, and always allow `cargo test …` everywhere")
+        );
+        view.handle_key_event(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::NONE));
+
+        let mut decision = None;
+        while let Ok(ev) = rx.try_recv() {
+            if let AppEvent::CodexOp(Op::ExecApproval { decision: d, .. }) = ev {
+                decision = Some(d);
+                break;
+            }
+        }
+        assert_eq!(decision, Some(ReviewDecision::ApprovedPrefixForProject));
+    }
+
+    #[test]
+    fn mcp_tool_call_decision_emits_mcp_op() {
+        let (tx_raw, mut rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx_raw);
+        let request = ApprovalRequest::McpToolCall {
+            id: "sub-1".to_string(),
+            invocation: McpInvocation {
+                server: "db".to_string(),
+                tool: "drop_table".to_string(),
+                arguments: Some(serde_json::json!({ "table": "users" })),
+            },
+            reason: Some("tool is marked destructive".to_string()),
+        };
+        let mut view = ApprovalOverlay::new(request, tx);
+        view.handle_key_event(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE));
+
+        let mut decision = None;
+        while let Ok(ev) = rx.try_recv() {
+            if let AppEvent::CodexOp(Op::McpToolCallApproval { id, decision: d }) = ev {
+                assert_eq!(id, "sub-1");
+                decision = Some(d);
+                break;
+            }
+        }
+        assert_eq!(decision, Some(ReviewDecision::ApprovedForSession));
+    }
 }
@@ -532,6 +532,7 @@ mod tests {
             command: vec!["echo".into(), "ok".into()],
             reason: None,
             risk: None,
+            proposed_prefix: None,
         }
     }
 
@@ -26,6 +26,7 @@ use codex_core::protocol::ExecCommandEndEvent;
 use codex_core::protocol::ExitedReviewModeEvent;
 use codex_core::protocol::ListCustomPromptsResponseEvent;
 use codex_core::protocol::McpListToolsResponseEvent;
+use codex_core::protocol::McpToolCallApprovalRequestEvent;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
 use codex_core::protocol::Op;
@@ -630,6 +631,19 @@ impl ChatWidget {
         );
     }
 
+    fn on_mcp_tool_call_approval_request(
+        &mut self,
+        id: String,
+        ev: McpToolCallApprovalRequestEvent,
+    ) {
+        let id2 = id.clone();
+        let ev2 = ev.clone();
+        self.defer_or_handle(
+            |q| q.push_mcp_tool_call_approval(id, ev),
+            |s| s.handle_mcp_tool_call_approval_now(id2, ev2),
+        );
+    }
+
     fn on_exec_command_begin(&mut self, ev: ExecCommandBeginEvent) {
         self.flush_answer_stream_with_separator();
         let ev2 = ev.clone();
@@ -894,6 +908,7 @@ impl ChatWidget {
             command: ev.command,
             reason: ev.reason,
             risk: ev.risk,
+            proposed_prefix: ev.proposed_prefix,
         };
         self.bottom_pane.push_approval_request(request);
         self.request_redraw();
@@ -920,6 +935,25 @@ impl ChatWidget {
         });
     }
 
+    pub(crate) fn handle_mcp_tool_call_approval_now(
+        &mut self,
+        id: String,
+        ev: McpToolCallApprovalRequestEvent,
+    ) {
+        self.flush_answer_stream_with_separator();
+        self.notify(Notification::McpToolCallApprovalRequested {
+            tool: format!("{}.{}", ev.invocation.server, ev.invocation.tool),
+        });
+
+        let request = ApprovalRequest::McpToolCall {
+            id,
+            invocation: ev.invocation,
+            reason: ev.reason,
+        };
+        self.bottom_pane.push_approval_request(request);
+        self.request_redraw();
+    }
+
     pub(crate) fn handle_exec_begin_now(&mut self, ev: ExecCommandBeginEvent) {
         // Ensure the status indicator is visible while the command runs.
         self.running_commands.insert(
@@ -1539,6 +1573,9 @@ impl ChatWidget {
             EventMsg::ApplyPatchApprovalRequest(ev) => {
                 self.on_apply_patch_approval_request(id.unwrap_or_default(), ev)
             }
+            EventMsg::McpToolCallApprovalRequest(ev) => {
+                self.on_mcp_tool_call_approval_request(id.unwrap_or_default(), ev)
+            }
             EventMsg::ExecCommandBegin(ev) => self.on_exec_command_begin(ev),
             EventMsg::ExecCommandOutputDelta(delta) => self.on_exec_command_output_delta(delta),
             EventMsg::PatchApplyBegin(ev) => self.on_patch_apply_begin(ev),
@@ -2768,6 +2805,7 @@ enum Notification {
     AgentTurnComplete { response: String },
     ExecApprovalRequested { command: String },
     EditApprovalRequested { cwd: PathBuf, changes: Vec<PathBuf> },
+    McpToolCallApprovalRequested { tool: String },
 }
 
 impl Notification {
@@ -2780,6 +2818,9 @@ impl Notification {
             Notification::ExecApprovalRequested { command } => {
                 format!("Approval requested: {}", truncate_text(command, 30))
             }
+            Notification::McpToolCallApprovalRequested { tool } => {
+                format!("Approval requested: {}", truncate_text(tool, 30))
+            }
             Notification::EditApprovalRequested { cwd, changes } => {
                 format!(
                     "Codex wants to edit {}",
@@ -2798,7 +2839,8 @@ impl Notification {
         match self {
             Notification::AgentTurnComplete { .. } => "agent-turn-complete",
             Notification::ExecApprovalRequested { .. }
-            | Notification::EditApprovalRequested { .. } => "approval-requested",
+            | Notification::EditApprovalRequested { .. }
+            | Notification::McpToolCallApprovalRequested { .. } => "approval-requested",
         }
     }
 
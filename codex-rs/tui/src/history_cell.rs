@@ -421,6 +421,32 @@ pub fn new_approval_decision_cell(
                 ],
             )
         }
+        ApprovedPrefix => {
+            let snippet = Span::from(exec_snippet(&command)).dim();
+            (
+                "✔ ".green(),
+                vec![
+                    "You ".into(),
+                    "approved".bold(),
+                    " codex to run commands starting with ".into(),
+                    snippet,
+                    " from now on".bold(),
+                ],
+            )
+        }
+        ApprovedPrefixForProject => {
+            let snippet = Span::from(exec_snippet(&command)).dim();
+            (
+                "✔ ".green(),
+                vec![
+                    "You ".into(),
+                    "approved".bold(),
+                    " codex to run commands starting with ".into(),
+                    snippet,
+                    " in this project from now on".bold(),
+                ],
+            )
+        }
         Denied => {
             let snippet = Span::from(exec_snippet(&command)).dim();
             (
@@ -1439,7 +1465,7 @@ impl HistoryCell for FinalMessageSeparator {
     }
 }
 
-fn format_mcp_invocation<'a>(invocation: McpInvocation) -> Line<'a> {
+pub(crate) fn format_mcp_invocation<'a>(invocation: McpInvocation) -> Line<'a> {
     let args_str = invocation
         .arguments
         .as_ref()
@@ -1527,6 +1553,8 @@ mod tests {
             tool_timeout_sec: None,
             enabled_tools: None,
             disabled_tools: None,
+            approval: None,
+            tools: HashMap::new(),
         };
         config.mcp_servers.insert("docs".to_string(), stdio_config);
 
@@ -1546,6 +1574,8 @@ mod tests {
             tool_timeout_sec: None,
             enabled_tools: None,
             disabled_tools: None,
+            approval: None,
+            tools: HashMap::new(),
         };
         config.mcp_servers.insert("http".to_string(), http_config);
 